@@ -35,6 +35,10 @@ fn main() {
     let workspace_root = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).to_path_buf();
 
     commit_info(&workspace_root);
+
+    #[allow(clippy::disallowed_methods)]
+    let target = std::env::var("TARGET").unwrap();
+    println!("cargo:rustc-env=PREK_BUILD_TARGET={target}");
 }
 
 fn commit_info(workspace_root: &Path) {
@@ -9,17 +9,44 @@ impl EnvVars {
 
     pub const SKIP: &'static str = "SKIP";
 
+    pub const FORCE_COLOR: &'static str = "FORCE_COLOR";
+
     // PREK specific environment variables, public for users
     pub const PREK_HOME: &'static str = "PREK_HOME";
     pub const PREK_COLOR: &'static str = "PREK_COLOR";
     pub const PREK_ALLOW_NO_CONFIG: &'static str = "PREK_ALLOW_NO_CONFIG";
     pub const PREK_NO_CONCURRENCY: &'static str = "PREK_NO_CONCURRENCY";
+    pub const PREK_CONCURRENCY: &'static str = "PREK_CONCURRENCY";
     pub const PREK_NO_FAST_PATH: &'static str = "PREK_NO_FAST_PATH";
+    pub const PREK_DETERMINISTIC_ENVS: &'static str = "PREK_DETERMINISTIC_ENVS";
+    pub const PREK_DISABLE: &'static str = "PREK_DISABLE";
+    pub const PREK_LOCK_TIMEOUT: &'static str = "PREK_LOCK_TIMEOUT";
+    pub const PREK_STRICT: &'static str = "PREK_STRICT";
+    pub const PREK_DETERMINISTIC_OUTPUT: &'static str = "PREK_DETERMINISTIC_OUTPUT";
+    pub const PREK_KEEP_TEMP: &'static str = "PREK_KEEP_TEMP";
+    pub const PREK_LOG_FILE: &'static str = "PREK_LOG_FILE";
+    pub const PREK_NO_INSTALL: &'static str = "PREK_NO_INSTALL";
+    pub const PREK_GO_DOWNLOAD_MIRROR: &'static str = "PREK_GO_DOWNLOAD_MIRROR";
+    pub const PREK_CLONE_TIMEOUT: &'static str = "PREK_CLONE_TIMEOUT";
+
+    // PREK specific environment variables, set for hooks of the commit message stages
+    pub const PREK_COMMIT_AUTHOR_NAME: &'static str = "PREK_COMMIT_AUTHOR_NAME";
+    pub const PREK_COMMIT_AUTHOR_EMAIL: &'static str = "PREK_COMMIT_AUTHOR_EMAIL";
+    pub const PREK_COMMIT_IS_MERGE: &'static str = "PREK_COMMIT_IS_MERGE";
+    pub const PREK_COMMIT_IS_AMEND: &'static str = "PREK_COMMIT_IS_AMEND";
+    pub const PREK_COMMIT_MSG_FILE: &'static str = "PREK_COMMIT_MSG_FILE";
+
+    // PREK specific environment variables, set for all hooks
+    pub const PREK_STAGED_FILES: &'static str = "PREK_STAGED_FILES";
 
     // PREK internal environment variables
     pub const PREK_INTERNAL__TEST_DIR: &'static str = "PREK_INTERNAL__TEST_DIR";
-    pub const PREK_INTERNAL__SORT_FILENAMES: &'static str = "PREK_INTERNAL__SORT_FILENAMES";
     pub const PREK_INTERNAL__SKIP_POST_CHECKOUT: &'static str = "PREK_INTERNAL__SKIP_POST_CHECKOUT";
+    pub const PREK_INTERNAL__RUNNING_HOOKS: &'static str = "PREK_INTERNAL__RUNNING_HOOKS";
+
+    // Git related
+    pub const GIT_AUTHOR_NAME: &'static str = "GIT_AUTHOR_NAME";
+    pub const GIT_AUTHOR_EMAIL: &'static str = "GIT_AUTHOR_EMAIL";
 
     // UV related
     pub const UV_CACHE_DIR: &'static str = "UV_CACHE_DIR";
@@ -29,6 +56,8 @@ impl EnvVars {
     pub const NPM_CONFIG_USERCONFIG: &'static str = "NPM_CONFIG_USERCONFIG";
     pub const NPM_CONFIG_PREFIX: &'static str = "NPM_CONFIG_PREFIX";
     pub const NODE_PATH: &'static str = "NODE_PATH";
+    pub const COREPACK_ENABLE_DOWNLOAD_PROMPT: &'static str = "COREPACK_ENABLE_DOWNLOAD_PROMPT";
+    pub const YARN_NODE_LINKER: &'static str = "YARN_NODE_LINKER";
 
     // Go related
     pub const GOTOOLCHAIN: &'static str = "GOTOOLCHAIN";
@@ -0,0 +1,67 @@
+#!/usr/bin/env python3
+"""Update the pinned checksums of the toolchain archives prek downloads.
+
+Writes `src/languages/checksums.txt`, with the SHA-256 of the Go and Node.js release
+archives of the versions prek may install, as published by `go.dev` and `nodejs.org`.
+Review the diff before committing it, the table is what downloads are verified against.
+"""
+
+import json
+import urllib.request
+from pathlib import Path
+
+OUTPUT = Path(__file__).parent.parent / "src" / "languages" / "checksums.txt"
+
+# The oldest versions to pin, older ones fall back to the published checksums.
+MIN_GO = (1, 20)
+MIN_NODE = 18
+
+
+def fetch(url: str) -> bytes:
+    with urllib.request.urlopen(url) as response:
+        return response.read()
+
+
+def go_checksums() -> list[tuple[str, str]]:
+    releases = json.loads(fetch("https://go.dev/dl/?mode=json&include=all"))
+    checksums = []
+    for release in releases:
+        version = release["version"].removeprefix("go")
+        try:
+            major, minor = (int(part) for part in version.split(".")[:2])
+        except ValueError:
+            continue
+        if (major, minor) < MIN_GO or not release["stable"]:
+            continue
+        for file in release["files"]:
+            if file["kind"] == "archive" and file["sha256"]:
+                checksums.append((file["filename"], file["sha256"]))
+    return checksums
+
+
+def node_checksums() -> list[tuple[str, str]]:
+    releases = json.loads(fetch("https://nodejs.org/dist/index.json"))
+    checksums = []
+    for release in releases:
+        version = release["version"]
+        if int(version.removeprefix("v").split(".")[0]) < MIN_NODE:
+            continue
+        shasums = fetch(f"https://nodejs.org/dist/{version}/SHASUMS256.txt").decode()
+        for line in shasums.splitlines():
+            sha256, filename = line.split("  ", 1)
+            if filename.endswith((".tar.xz", ".zip")) and filename.startswith("node-v"):
+                checksums.append((filename, sha256))
+    return checksums
+
+
+def main() -> None:
+    checksums = sorted(set(go_checksums() + node_checksums()))
+    with OUTPUT.open("w") as f:
+        f.write("# Generated by `scripts/update-checksums.py`, do not edit.\n")
+        for filename, sha256 in checksums:
+            f.write(f"{sha256}  {filename}\n")
+    print(f"Wrote {len(checksums)} checksums to {OUTPUT}")
+
+
+if __name__ == "__main__":
+    main()
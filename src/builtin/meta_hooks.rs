@@ -6,20 +6,21 @@ use fancy_regex::Regex;
 use itertools::Itertools;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::cli::run::{CollectOptions, FileFilter, collect_files};
+use crate::cli::run::{CollectOptions, FileFilter, collect_files, ignore_case};
 use crate::config::{HookOptions, Language, Repo, read_config};
+use crate::git::GitBackend;
 use crate::hook::Hook;
 use crate::store::Store;
 use crate::workspace::Project;
 
 /// Ensures that the configured hooks apply to at least one file in the repository.
 pub(crate) async fn check_hooks_apply(
-    _hook: &Hook,
+    git: &impl GitBackend,
     filenames: &[&String],
 ) -> Result<(i32, Vec<u8>)> {
     let store = Store::from_settings()?.init()?;
 
-    let input = collect_files(CollectOptions::default().with_all_files(true)).await?;
+    let input = collect_files(git, CollectOptions::default().with_all_files(true)).await?;
 
     let mut code = 0;
     let mut output = Vec::new();
@@ -80,10 +81,10 @@ fn excludes_any<T: AsRef<str> + Sync>(
 
 /// Ensures that exclude directives apply to any file in the repository.
 pub(crate) async fn check_useless_excludes(
-    _hook: &Hook,
+    git: &impl GitBackend,
     filenames: &[&String],
 ) -> Result<(i32, Vec<u8>)> {
-    let input = collect_files(CollectOptions::default().with_all_files(true)).await?;
+    let input = collect_files(git, CollectOptions::default().with_all_files(true)).await?;
 
     let mut code = 0;
     let mut output = Vec::new();
@@ -119,10 +120,11 @@ pub(crate) async fn check_useless_excludes(
                 opts.types_or.as_deref().unwrap_or(&[]),
                 opts.exclude_types.as_deref().unwrap_or(&[]),
             );
+            let ignore = opts.files_case_insensitive.unwrap_or(false);
             if !excludes_any(
                 &filtered_files,
-                opts.files.as_deref(),
-                opts.exclude.as_deref(),
+                ignore_case(opts.files.as_deref(), ignore).as_deref(),
+                ignore_case(opts.exclude.as_deref(), ignore).as_deref(),
             )? {
                 code = 1;
                 writeln!(
@@ -146,6 +148,8 @@ pub fn identity(_hook: &Hook, filenames: &[&String]) -> (i32, Vec<u8>) {
 mod tests {
     use super::*;
 
+    use crate::git::FakeGit;
+
     #[test]
     fn test_excludes_any() -> Result<()> {
         let files = vec!["file1.txt", "file2.txt", "file3.txt"];
@@ -157,4 +161,51 @@ mod tests {
         assert!(excludes_any(&files, None, Some("^html/"))?);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_check_useless_excludes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        fs_err::create_dir(dir.path().join("html"))?;
+        fs_err::write(path("html/index.html"), "<html></html>")?;
+        fs_err::write(path("main.py"), "print()")?;
+
+        let config = path(".pre-commit-config.yaml");
+        fs_err::write(
+            &config,
+            indoc::formatdoc! {r"
+                exclude: ^{root}/vendor/
+                repos:
+                  - repo: local
+                    hooks:
+                      - id: html
+                        name: html
+                        language: system
+                        entry: echo
+                        exclude: ^{root}/html/
+                      - id: python
+                        name: python
+                        language: system
+                        entry: echo
+                        types: [python]
+                        exclude: ^{root}/html/
+            ", root = dir.path().display()},
+        )?;
+
+        let git = FakeGit {
+            tracked: vec![path("html/index.html"), path("main.py")],
+            ..FakeGit::default()
+        };
+        let (code, output) = check_useless_excludes(&git, &[&config]).await?;
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8(output)?,
+            indoc::formatdoc! {"
+                The global exclude pattern \"^{root}/vendor/\" does not match any files
+                The exclude pattern `^{root}/html/` for `python` does not match any files
+            ", root = dir.path().display()}
+        );
+
+        Ok(())
+    }
 }
@@ -1,9 +1,12 @@
 use std::str::FromStr;
 use std::sync::LazyLock;
 
+use tracing::debug;
+
 use constants::env_vars::EnvVars;
 
 use crate::builtin::pre_commit_hooks::{Implemented, is_pre_commit_hooks};
+use crate::git::GitBackend;
 use crate::hook::{Hook, Repo};
 
 mod meta_hooks;
@@ -19,29 +22,59 @@ pub fn check_fast_path(hook: &Hook) -> bool {
             if *NO_FAST_PATH {
                 return false;
             }
-            Implemented::from_str(hook.id.as_str()).is_ok()
+            let Ok(implemented) = Implemented::from_str(hook.id.as_str()) else {
+                return false;
+            };
+            // An overridden entry may run something else than the upstream hook.
+            let manifest_entry = hook.repo().get_hook(&hook.id).map(|h| h.entry.as_str());
+            if manifest_entry != Some(hook.entry.entry()) {
+                debug!("Hook `{hook}` overrides `entry`, not running the builtin implementation");
+                return false;
+            }
+            // The builtin implementations run from the repository root.
+            if hook.working_dir.is_some() {
+                debug!("Hook `{hook}` sets `working_dir`, not running the builtin implementation");
+                return false;
+            }
+            if !implemented.supports_args(hook) {
+                debug!(
+                    "Hook `{hook}` has `args` the builtin implementation does not support, not running it"
+                );
+                return false;
+            }
+            true
         }
         _ => false,
     }
 }
 
-pub async fn run_fast_path(hook: &Hook, filenames: &[&String]) -> anyhow::Result<(i32, Vec<u8>)> {
+/// Run a hook with a builtin implementation, `verbose` asks for more context in the output.
+pub async fn run_fast_path(
+    git: &impl GitBackend,
+    hook: &Hook,
+    filenames: &[&String],
+    verbose: bool,
+) -> anyhow::Result<(i32, Vec<u8>)> {
     match hook.repo() {
-        Repo::Meta { .. } => run_meta_hook(hook, filenames).await,
+        Repo::Meta { .. } => run_meta_hook(git, hook, filenames).await,
         Repo::Remote { url, .. } if is_pre_commit_hooks(url) => {
             Implemented::from_str(hook.id.as_str())
                 .unwrap()
-                .run(hook, filenames)
+                .run(git, hook, filenames, verbose)
                 .await
         }
         _ => unreachable!(),
     }
 }
 
-async fn run_meta_hook(hook: &Hook, filenames: &[&String]) -> anyhow::Result<(i32, Vec<u8>)> {
+async fn run_meta_hook(
+    git: &impl GitBackend,
+    hook: &Hook,
+    filenames: &[&String],
+) -> anyhow::Result<(i32, Vec<u8>)> {
     match hook.id.as_str() {
-        "check-hooks-apply" => meta_hooks::check_hooks_apply(hook, filenames).await,
-        "check-useless-excludes" => meta_hooks::check_useless_excludes(hook, filenames).await,
+        "check-hooks-apply" => meta_hooks::check_hooks_apply(git, filenames).await,
+        "check-useless-excludes" => meta_hooks::check_useless_excludes(git, filenames).await,
         "identity" => Ok(meta_hooks::identity(hook, filenames)),
         _ => unreachable!(),
     }
@@ -1,27 +1,15 @@
+use std::io;
+
 use clap::Parser;
 use futures::StreamExt;
 use rustc_hash::FxHashSet;
 
-use crate::git::{get_staged_files, lfs_files};
+use crate::git::GitBackend;
 use crate::hook::Hook;
 use crate::run::CONCURRENCY;
 
-enum FileFilter {
-    NoFilter,
-    Files(FxHashSet<String>),
-}
-
-impl FileFilter {
-    fn contains(&self, path: &str) -> bool {
-        match self {
-            FileFilter::NoFilter => true,
-            FileFilter::Files(files) => files.contains(path),
-        }
-    }
-}
-
 #[derive(Parser)]
-struct Args {
+pub(crate) struct Args {
     #[arg(long)]
     enforce_all: bool,
     #[arg(long = "maxkb", default_value = "500")]
@@ -29,38 +17,54 @@ struct Args {
 }
 
 pub(crate) async fn check_added_large_files(
+    git: &impl GitBackend,
     hook: &Hook,
     filenames: &[&String],
 ) -> anyhow::Result<(i32, Vec<u8>)> {
-    let args = Args::try_parse_from(hook.entry.parsed()?.iter().chain(&hook.args))?;
+    let args = Args::try_parse_from(hook.entry.parsed()?.iter().chain(&hook.expanded_args()))?;
 
-    let filter = if args.enforce_all {
-        FileFilter::NoFilter
-    } else {
-        let add_files: FxHashSet<_> = get_staged_files().await?.into_iter().collect();
-        FileFilter::Files(add_files)
-    };
+    let staged_files: FxHashSet<_> = git.staged_files().await?.into_iter().collect();
 
-    let lfs_files = lfs_files::<FxHashSet<String>>(filenames).await?;
-    let mut tasks = futures::stream::iter(
-        filenames
-            .iter()
-            .filter(|f| filter.contains(f))
-            .filter(|f| !lfs_files.contains(f.as_str())),
-    )
-    .map(async |filename| {
-        let size = fs_err::tokio::metadata(filename).await?.len();
-        let size = size / 1024;
-        if size > args.max_kb {
-            anyhow::Ok(Some(format!(
-                "{filename} ({size} KB) exceeds {} KB\n",
-                args.max_kb
-            )))
-        } else {
-            anyhow::Ok(None)
-        }
-    })
-    .buffered(*CONCURRENCY);
+    let lfs_files = git.lfs_files(filenames).await?;
+    let filenames: Vec<_> = filenames
+        .iter()
+        .filter(|f| args.enforce_all || staged_files.contains(f.as_str()))
+        .filter(|f| !lfs_files.contains(f.as_str()))
+        .copied()
+        .collect();
+
+    // Files being added are checked by the size of the staged content, which is what will be
+    // committed. Other files, only checked with `--enforce-all`, are checked on disk.
+    let staged: Vec<_> = filenames
+        .iter()
+        .filter(|f| staged_files.contains(f.as_str()))
+        .copied()
+        .collect();
+    let staged_sizes = git.staged_blob_sizes(&staged).await?;
+
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| {
+            let size = if let Some(size) = staged_sizes.get(filename) {
+                *size
+            } else {
+                match fs_err::tokio::metadata(filename).await {
+                    Ok(metadata) => metadata.len(),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => return anyhow::Ok(None),
+                    Err(err) => return Err(err.into()),
+                }
+            };
+
+            let size = size / 1024;
+            if size > args.max_kb {
+                anyhow::Ok(Some(format!(
+                    "{filename} ({size} KB) exceeds {} KB\n",
+                    args.max_kb
+                )))
+            } else {
+                anyhow::Ok(None)
+            }
+        })
+        .buffered(*CONCURRENCY);
 
     let mut code = 0;
     let mut output = Vec::new();
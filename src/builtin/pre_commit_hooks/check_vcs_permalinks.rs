@@ -0,0 +1,119 @@
+use clap::Parser;
+use fancy_regex::Regex;
+use futures::StreamExt;
+
+use crate::builtin::pre_commit_hooks::location::{column, report};
+use crate::hook::Hook;
+use crate::run::CONCURRENCY;
+
+#[derive(Parser)]
+pub(crate) struct Args {
+    #[arg(long = "additional-github-domain")]
+    additional_github_domains: Vec<String>,
+}
+
+/// A blob link with a line anchor that references a branch instead of a commit.
+fn pattern(domain: &str) -> Regex {
+    Regex::new(&format!(
+        r"https://{}/[^/ ]+/[^/ ]+/blob/(?![a-fA-F0-9]{{4,64}}/)([^/. ]+)/[^# ]+#L\d+",
+        fancy_regex::escape(domain)
+    ))
+    .expect("Failed to build permalink pattern")
+}
+
+pub(crate) async fn check_vcs_permalinks(
+    hook: &Hook,
+    filenames: &[&String],
+    verbose: bool,
+) -> anyhow::Result<(i32, Vec<u8>)> {
+    let args = Args::try_parse_from(hook.entry.parsed()?.iter().chain(&hook.expanded_args()))?;
+
+    let patterns = std::iter::once("github.com")
+        .chain(args.additional_github_domains.iter().map(String::as_str))
+        .map(pattern)
+        .collect::<Vec<_>>();
+
+    let mut tasks = futures::stream::iter(filenames)
+        .map(async |filename| check_file(filename, &patterns, verbose).await)
+        .buffered(*CONCURRENCY);
+
+    let mut code = 0;
+    let mut output = Vec::new();
+
+    while let Some(result) = tasks.next().await {
+        let o = result?;
+        if !o.is_empty() {
+            code = 1;
+            output.extend(o.into_bytes());
+        }
+    }
+
+    if code != 0 {
+        output.extend(b"\nNon-permanent github link detected.\n");
+        output.extend(b"On any page on github press [y] to load a permalink.\n");
+    }
+
+    Ok((code, output))
+}
+
+async fn check_file(filename: &str, patterns: &[Regex], verbose: bool) -> anyhow::Result<String> {
+    let content = fs_err::tokio::read(filename).await?;
+    let content = String::from_utf8_lossy(&content);
+    let lines = content.lines().collect::<Vec<_>>();
+
+    let mut output = String::new();
+    for (idx, line) in lines.iter().enumerate() {
+        for pattern in patterns {
+            if let Some(link) = pattern.find(line)? {
+                output.push_str(&report(
+                    filename,
+                    &lines,
+                    idx + 1,
+                    column(line, link.start()),
+                    link.as_str(),
+                    verbose,
+                ));
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permalink_pattern() {
+        let github = pattern("github.com");
+        assert!(
+            github
+                .is_match("https://github.com/owner/repo/blob/main/src/lib.rs#L10")
+                .unwrap()
+        );
+        assert!(
+            !github
+                .is_match("https://github.com/owner/repo/blob/0a1b2c3d/src/lib.rs#L10")
+                .unwrap()
+        );
+        // Only links to a line are checked.
+        assert!(
+            !github
+                .is_match("https://github.com/owner/repo/blob/main/src/lib.rs")
+                .unwrap()
+        );
+
+        let enterprise = pattern("git.example.com");
+        assert!(
+            enterprise
+                .is_match("https://git.example.com/owner/repo/blob/dev/a.py#L1")
+                .unwrap()
+        );
+        assert!(
+            !enterprise
+                .is_match("https://gitXexample.com/owner/repo/blob/dev/a.py#L1")
+                .unwrap()
+        );
+    }
+}
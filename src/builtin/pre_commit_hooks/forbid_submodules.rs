@@ -0,0 +1,66 @@
+use crate::git::GitBackend;
+use crate::hook::Hook;
+
+/// Fail if any of the files is a submodule, like the `fail` hook of `pre-commit-hooks` does.
+pub(crate) async fn forbid_submodules(
+    git: &impl GitBackend,
+    hook: &Hook,
+    filenames: &[&String],
+) -> anyhow::Result<(i32, Vec<u8>)> {
+    let submodules = git.submodule_files(filenames).await?;
+    if submodules.is_empty() {
+        return Ok((0, Vec::new()));
+    }
+
+    let mut output = format!("{}\n\n", hook.entry.entry());
+    for filename in filenames
+        .iter()
+        .filter(|filename| submodules.contains(filename.as_str()))
+    {
+        output.push_str(filename);
+        output.push('\n');
+    }
+    output.push('\n');
+
+    Ok((1, output.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::config::{HookOptions, Language, ManifestHook};
+    use crate::git::FakeGit;
+    use crate::hook::{HookBuilder, Repo};
+
+    #[tokio::test]
+    async fn submodules() -> anyhow::Result<()> {
+        let config = ManifestHook {
+            id: "forbid-submodules".to_string(),
+            name: "forbid submodules".to_string(),
+            entry: "submodules are not allowed in this repository:".to_string(),
+            language: Language::Fail,
+            options: HookOptions::default(),
+        };
+        let hook = HookBuilder::new(Arc::new(Repo::local(vec![])), config, 0).build()?;
+        let git = FakeGit {
+            submodules: vec!["vendor/lib".to_string()],
+            ..FakeGit::default()
+        };
+        let files = ["src/main.rs".to_string(), "vendor/lib".to_string()];
+        let files: Vec<_> = files.iter().collect();
+
+        let (code, output) = forbid_submodules(&git, &hook, &files).await?;
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8(output)?,
+            "submodules are not allowed in this repository:\n\nvendor/lib\n\n"
+        );
+
+        let (code, _) = forbid_submodules(&git, &hook, &files[..1]).await?;
+        assert_eq!(code, 0);
+
+        Ok(())
+    }
+}
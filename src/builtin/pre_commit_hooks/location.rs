@@ -0,0 +1,72 @@
+use std::fmt::Write;
+
+/// The number of lines shown before and after a reported line.
+const CONTEXT_LINES: usize = 2;
+
+/// Format a problem found at `line` and `col` (1-based) of `filename` as
+/// `filename:line:col: message`.
+///
+/// With `verbose`, the surrounding lines are shown under it, with the reported one marked.
+pub(crate) fn report(
+    filename: &str,
+    lines: &[&str],
+    line: usize,
+    col: usize,
+    message: &str,
+    verbose: bool,
+) -> String {
+    let mut out = format!("{filename}:{line}:{col}: {message}\n");
+    if !verbose {
+        return out;
+    }
+
+    let start = line.saturating_sub(CONTEXT_LINES + 1);
+    let end = (line + CONTEXT_LINES).min(lines.len());
+    let width = end.to_string().len();
+    for (number, text) in (start + 1..).zip(&lines[start..end]) {
+        let marker = if number == line { '>' } else { ' ' };
+        let _ = writeln!(out, "{marker} {number:>width$} | {text}");
+    }
+    out
+}
+
+/// The 1-based column of the byte `offset` in `line`, counted in characters.
+pub(crate) fn column(line: &str, offset: usize) -> usize {
+    line[..offset].chars().count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_with_context() {
+        let lines = (1..=12).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+
+        assert_eq!(
+            report("a.txt", &lines, 10, 3, "problem", false),
+            "a.txt:10:3: problem\n"
+        );
+        insta::assert_snapshot!(report("a.txt", &lines, 10, 3, "problem", true), @r"
+        a.txt:10:3: problem
+           8 | line 8
+           9 | line 9
+        > 10 | line 10
+          11 | line 11
+          12 | line 12
+        ");
+        insta::assert_snapshot!(report("a.txt", &lines, 1, 1, "problem", true), @r"
+        a.txt:1:1: problem
+        > 1 | line 1
+          2 | line 2
+          3 | line 3
+        ");
+    }
+
+    #[test]
+    fn column_in_characters() {
+        assert_eq!(column("abc", 0), 1);
+        assert_eq!(column("héllo", "hé".len()), 3);
+    }
+}
@@ -1,18 +1,25 @@
 use std::str::FromStr;
 
 use anyhow::Result;
+use clap::Parser;
 use url::Url;
 
+use crate::git::GitBackend;
 use crate::hook::Hook;
 
 mod check_added_large_files;
+mod check_vcs_permalinks;
 mod fix_end_of_file;
 mod fix_trailing_whitespace;
+mod forbid_submodules;
+mod location;
 
 pub(crate) enum Implemented {
     TrailingWhitespace,
     CheckAddedLargeFiles,
     EndOfFileFixer,
+    CheckVcsPermalinks,
+    ForbidSubmodules,
 }
 
 impl FromStr for Implemented {
@@ -23,25 +30,57 @@ impl FromStr for Implemented {
             "trailing-whitespace" => Ok(Self::TrailingWhitespace),
             "check-added-large-files" => Ok(Self::CheckAddedLargeFiles),
             "end-of-file-fixer" => Ok(Self::EndOfFileFixer),
+            "check-vcs-permalinks" => Ok(Self::CheckVcsPermalinks),
+            "forbid-submodules" => Ok(Self::ForbidSubmodules),
             _ => Err(()),
         }
     }
 }
 
 impl Implemented {
-    pub(crate) async fn run(self, hook: &Hook, filenames: &[&String]) -> Result<(i32, Vec<u8>)> {
+    /// Whether the builtin implementation understands the `args` of the hook, otherwise the
+    /// hook must run in its own environment to behave the same as with `pre-commit`.
+    pub(crate) fn supports_args(&self, hook: &Hook) -> bool {
+        match self {
+            Self::TrailingWhitespace => parses::<fix_trailing_whitespace::Args>(hook),
+            Self::CheckAddedLargeFiles => parses::<check_added_large_files::Args>(hook),
+            Self::CheckVcsPermalinks => parses::<check_vcs_permalinks::Args>(hook),
+            Self::EndOfFileFixer | Self::ForbidSubmodules => hook.args.is_empty(),
+        }
+    }
+
+    pub(crate) async fn run(
+        self,
+        git: &impl GitBackend,
+        hook: &Hook,
+        filenames: &[&String],
+        verbose: bool,
+    ) -> Result<(i32, Vec<u8>)> {
         match self {
             Self::TrailingWhitespace => {
                 fix_trailing_whitespace::fix_trailing_whitespace(hook, filenames).await
             }
             Self::CheckAddedLargeFiles => {
-                check_added_large_files::check_added_large_files(hook, filenames).await
+                check_added_large_files::check_added_large_files(git, hook, filenames).await
             }
             Self::EndOfFileFixer => fix_end_of_file::fix_end_of_file(hook, filenames).await,
+            Self::CheckVcsPermalinks => {
+                check_vcs_permalinks::check_vcs_permalinks(hook, filenames, verbose).await
+            }
+            Self::ForbidSubmodules => {
+                forbid_submodules::forbid_submodules(git, hook, filenames).await
+            }
         }
     }
 }
 
+/// Whether `A` parses the `entry` and `args` of the hook, like the implementation does.
+fn parses<A: Parser>(hook: &Hook) -> bool {
+    hook.entry
+        .parsed()
+        .is_ok_and(|entry| A::try_parse_from(entry.iter().chain(&hook.expanded_args())).is_ok())
+}
+
 // TODO: compare rev
 pub(crate) fn is_pre_commit_hooks(url: &Url) -> bool {
     url.host_str() == Some("github.com") && url.path() == "/pre-commit/pre-commit-hooks"
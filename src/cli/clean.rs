@@ -4,16 +4,33 @@ use std::path::Path;
 
 use anyhow::Result;
 use owo_colors::OwoColorize;
+#[cfg(feature = "docker")]
+use rustc_hash::FxHashSet;
 use tracing::error;
 
 use crate::cli::ExitStatus;
 use crate::fs::Simplified;
+#[cfg(feature = "docker")]
+use crate::languages::remove_docker_images;
 use crate::printer::Printer;
 use crate::store::{CacheBucket, Store};
 
-pub(crate) fn clean(printer: Printer) -> Result<ExitStatus> {
+pub(crate) async fn clean(docker: bool, printer: Printer) -> Result<ExitStatus> {
     let store = Store::from_settings()?;
 
+    if docker {
+        #[cfg(feature = "docker")]
+        {
+            // The whole store goes away below, so no environment keeps any image.
+            let removed = remove_docker_images(&FxHashSet::default()).await?;
+            writeln!(printer.stdout(), "Removed {removed} docker images")?;
+        }
+        #[cfg(not(feature = "docker"))]
+        anyhow::bail!(
+            "`--docker` is not available in this build of prek, rebuild it with the `docker` feature"
+        );
+    }
+
     if !store.path().exists() {
         writeln!(printer.stdout(), "Nothing to clean")?;
         return Ok(ExitStatus::Success);
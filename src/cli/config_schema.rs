@@ -0,0 +1,346 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+
+use crate::cli::ExitStatus;
+use crate::config::{HookType, Language, MetaHookID, Stage};
+use crate::printer::Printer;
+
+/// Print a JSON Schema of the configuration file, for editors to validate and complete it.
+pub(crate) fn config_schema(printer: Printer) -> Result<ExitStatus> {
+    writeln!(
+        printer.stdout(),
+        "{}",
+        serde_json::to_string_pretty(&schema())?
+    )?;
+    Ok(ExitStatus::Success)
+}
+
+fn string(description: &str) -> Value {
+    json!({ "type": "string", "description": description })
+}
+
+fn boolean(description: &str) -> Value {
+    json!({ "type": "boolean", "description": description })
+}
+
+fn strings(description: &str) -> Value {
+    json!({ "type": "array", "items": { "type": "string" }, "description": description })
+}
+
+/// A regex, or a list of regexes matching any of them.
+fn pattern(description: &str) -> Value {
+    json!({
+        "oneOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } },
+        ],
+        "description": description,
+    })
+}
+
+fn reference(definition: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{definition}") })
+}
+
+fn array_of(definition: &str, description: &str) -> Value {
+    json!({ "type": "array", "items": reference(definition), "description": description })
+}
+
+/// The properties of `HookOptions`, common to all hooks.
+fn hook_options() -> Map<String, Value> {
+    let mut options = Map::new();
+    for (key, value) in [
+        ("alias", string("An additional id to run the hook by.")),
+        ("files", pattern("The pattern of files to run on.")),
+        (
+            "exclude",
+            pattern("Exclude files that were matched by `files`."),
+        ),
+        (
+            "files_case_insensitive",
+            boolean("Match `files` and `exclude` ignoring case."),
+        ),
+        ("types", strings("List of file types to run on (AND).")),
+        ("types_or", strings("List of file types to run on (OR).")),
+        ("exclude_types", strings("List of file types to exclude.")),
+        (
+            "additional_dependencies",
+            strings("Additional dependencies to install in the hook environment."),
+        ),
+        ("args", strings("Additional arguments to pass to the hook.")),
+        (
+            "always_run",
+            boolean("Run the hook even if there are no matching files."),
+        ),
+        (
+            "fail_fast",
+            boolean("If this hook fails, don't run any more hooks."),
+        ),
+        (
+            "pass_filenames",
+            boolean("Append the filenames to check to the hook entry as arguments."),
+        ),
+        ("description", string("A description of the hook.")),
+        (
+            "language_version",
+            string("Run the hook on a specific version of the language."),
+        ),
+        (
+            "log_file",
+            string("Write the output of the hook to this file when it fails or is verbose."),
+        ),
+        (
+            "require_serial",
+            boolean("Run the hook in a single process instead of in parallel."),
+        ),
+        (
+            "stdin",
+            json!({
+                "enum": ["filename"],
+                "description": "Pipe the content of each file to the hook on stdin instead of passing the filenames.",
+            }),
+        ),
+        (
+            "working_dir",
+            string("Run the hook from this directory, relative to the repository root."),
+        ),
+        (
+            "priority",
+            json!({
+                "type": "integer",
+                "description": "Hooks with a higher priority run first. Default is 0.",
+            }),
+        ),
+        (
+            "stages",
+            array_of("stage", "Select which git hooks to run the hook for."),
+        ),
+        (
+            "post_checkout_on",
+            array_of(
+                "checkout_type",
+                "The kinds of checkout the hook runs for at the `post-checkout` stage. Default is `[branch]`.",
+            ),
+        ),
+        (
+            "verbose",
+            boolean("Print the output of the hook even if it passes."),
+        ),
+        (
+            "minimum_pre_commit_version",
+            string("The minimum version of pre-commit required by the hook."),
+        ),
+        (
+            "minimum_prek_version",
+            string("The minimum version of prek required by the hook."),
+        ),
+    ] {
+        options.insert(key.to_string(), value);
+    }
+    options
+}
+
+/// A hook object with the common options, the given `properties` and `required` keys.
+fn hook(properties: Value, required: &[&str]) -> Value {
+    let mut all = hook_options();
+    if let Value::Object(properties) = properties {
+        all.extend(properties);
+    }
+    json!({
+        "type": "object",
+        "required": required,
+        "properties": all,
+    })
+}
+
+/// Build the JSON Schema of `Config`.
+pub(crate) fn schema() -> Value {
+    let languages = Language::ALL.map(|language| language.to_string());
+    let stages = Stage::value_variants()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let hook_types = HookType::value_variants()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let meta_hook_ids = [
+        MetaHookID::CheckHooksApply,
+        MetaHookID::CheckUselessExcludes,
+        MetaHookID::Identity,
+    ]
+    .map(|id| id.to_string());
+
+    let remote_hook = hook(
+        json!({
+            "id": string("The id of the hook in the repository."),
+            "name": string("Override the name of the hook."),
+            "entry": string("Override the entrypoint of the hook."),
+            "language": reference("language"),
+        }),
+        &["id"],
+    );
+    let local_hook = hook(
+        json!({
+            "id": string("The id of the hook."),
+            "name": string("The name of the hook."),
+            "entry": string("The command to run. It can contain arguments that will not be overridden."),
+            "language": reference("language"),
+        }),
+        &["id", "name", "entry", "language"],
+    );
+    let meta_hook = hook(
+        json!({
+            "id": { "enum": meta_hook_ids },
+            "name": string("Override the name of the hook."),
+            "language": { "const": "system" },
+        }),
+        &["id"],
+    );
+
+    let properties = json!({
+        "repos": array_of("repo", "The repositories of hooks to run."),
+        "default_install_hook_types": array_of(
+            "hook_type",
+            "The hook types installed by `install` by default.",
+        ),
+        "default_language_version": {
+            "type": "object",
+            "description": "A mapping from language to the default `language_version`.",
+            "propertyNames": reference("language"),
+            "additionalProperties": { "type": "string" },
+        },
+        "default_stages": array_of(
+            "stage",
+            "A configuration-wide default for the `stages` of hooks.",
+        ),
+        "files": pattern("Global file include pattern."),
+        "exclude": pattern("Global file exclude pattern."),
+        "fail_fast": boolean("Stop running hooks after the first failure."),
+        "default_max_output_lines": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Truncate hook output to this many lines, unless `--max-output-lines` is given.",
+        },
+        "use_ignore_file": boolean("Exclude the files matched by the `.prekignore` file at the repository root."),
+        "minimum_pre_commit_version": string("The minimum version of pre-commit required by the configuration."),
+        "minimum_prek_version": string("The minimum version of prek required by the configuration."),
+        "ci": {
+            "type": "object",
+            "description": "Configuration for the pre-commit.ci service.",
+        },
+    });
+
+    let remote_repo = json!({
+        "type": "object",
+        "required": ["repo", "rev", "hooks"],
+        "properties": {
+            "repo": {
+                "type": "string",
+                "not": { "enum": ["local", "meta"] },
+                "description": "The URL of the repository to clone.",
+            },
+            "rev": string("The revision or tag to clone at."),
+            "hooks": array_of("remote_hook", "The hooks of the repository to run."),
+        },
+    });
+    let local_repo = json!({
+        "type": "object",
+        "required": ["repo", "hooks"],
+        "additionalProperties": false,
+        "properties": {
+            "repo": { "const": "local" },
+            "hooks": array_of("local_hook", "Hooks defined in the configuration."),
+        },
+    });
+    let meta_repo = json!({
+        "type": "object",
+        "required": ["repo", "hooks"],
+        "additionalProperties": false,
+        "properties": {
+            "repo": { "const": "meta" },
+            "hooks": array_of("meta_hook", "Hooks checking the configuration itself."),
+        },
+    });
+
+    let definitions = json!({
+        "language": { "enum": languages },
+        "stage": { "enum": stages },
+        "hook_type": { "enum": hook_types },
+        "checkout_type": { "enum": ["branch", "file"] },
+        "repo": {
+            "oneOf": [
+                reference("remote_repo"),
+                reference("local_repo"),
+                reference("meta_repo"),
+            ],
+        },
+        "remote_repo": remote_repo,
+        "local_repo": local_repo,
+        "meta_repo": meta_repo,
+        "remote_hook": remote_hook,
+        "local_hook": local_hook,
+        "meta_hook": meta_hook,
+    });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "pre-commit configuration",
+        "description": "The configuration of prek and pre-commit, `.pre-commit-config.yaml`.",
+        "type": "object",
+        "required": ["repos"],
+        "properties": properties,
+        "definitions": definitions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_KEYS;
+
+    #[test]
+    fn schema_covers_config_keys() {
+        let schema = schema();
+        let mut properties = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let mut keys = CONFIG_KEYS.to_vec();
+        properties.sort_unstable();
+        keys.sort_unstable();
+        assert_eq!(properties, keys);
+    }
+
+    #[test]
+    fn schema_definitions_resolve() {
+        fn refs<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+            match value {
+                Value::Object(map) => {
+                    if let Some(Value::String(reference)) = map.get("$ref") {
+                        out.push(reference);
+                    }
+                    map.values().for_each(|value| refs(value, out));
+                }
+                Value::Array(values) => values.iter().for_each(|value| refs(value, out)),
+                _ => {}
+            }
+        }
+
+        let schema = schema();
+        let mut found = Vec::new();
+        refs(&schema, &mut found);
+        for reference in found {
+            let name = reference.strip_prefix("#/definitions/").unwrap();
+            assert!(
+                schema["definitions"].get(name).is_some(),
+                "unresolved {reference}"
+            );
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::cli::ExitStatus;
+use crate::cli::export_hooks::join;
+use crate::cli::reporter::HookInitReporter;
+use crate::config::Language;
+use crate::fs::Simplified;
+use crate::hook::{Hook, InstallInfo};
+use crate::printer::Printer;
+use crate::store::Store;
+use crate::workspace::Project;
+
+/// The environment a hook runs in.
+#[derive(Debug, Serialize)]
+struct EnvInfo {
+    id: String,
+    repo: String,
+    language: Language,
+    /// The requested `language_version`.
+    language_request: String,
+    /// Whether the language installs an environment at all.
+    needs_env: bool,
+    installed: bool,
+    env_path: Option<PathBuf>,
+    toolchain: Option<PathBuf>,
+    language_version: Option<String>,
+    /// The dependencies of the environment, or the ones that would be installed.
+    dependencies: Vec<String>,
+    /// When the environment was installed, in seconds since the Unix epoch.
+    installed_at: Option<u64>,
+    /// The size of the environment on disk, in bytes.
+    size: Option<u64>,
+    /// The command run for the hook, without the filenames.
+    command: String,
+    pass_filenames: bool,
+}
+
+impl EnvInfo {
+    fn new(hook: &Hook, installed: &[InstallInfo]) -> Result<Self> {
+        let entry = hook.entry.parsed()?;
+        let needs_env = hook.language.supports_install_env();
+        // The same lookup as `run`, without installing anything.
+        let info = installed
+            .iter()
+            .find(|info| needs_env && info.matches(hook));
+
+        let mut dependencies = info
+            .map_or(hook.dependencies(), |info| &info.dependencies)
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        dependencies.sort_unstable();
+
+        Ok(Self {
+            id: hook.id.clone(),
+            repo: hook.repo().to_string(),
+            language: hook.language,
+            language_request: hook.language_version.clone(),
+            needs_env,
+            installed: info.is_some(),
+            env_path: info.map(|info| info.env_path.clone()),
+            toolchain: info
+                .map(|info| info.toolchain.clone())
+                .filter(|toolchain| !toolchain.as_os_str().is_empty()),
+            language_version: info.map(|info| info.language_version.to_string()),
+            dependencies,
+            installed_at: info
+                .and_then(|info| installed_at(&info.env_path))
+                .map(|time| time.as_secs()),
+            size: info.map(|info| dir_size(&info.env_path)),
+            command: join(&entry, &hook.expanded_args()),
+            pass_filenames: hook.pass_filenames,
+        })
+    }
+
+    fn write_text(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "{} ({})", self.id.bold(), self.repo)?;
+        writeln!(out, "  language: {}", self.language)?;
+
+        if !self.needs_env {
+            writeln!(
+                out,
+                "  environment: none, `{}` hooks run with the tools on `PATH`",
+                self.language
+            )?;
+        } else if let Some(env_path) = &self.env_path {
+            writeln!(out, "  environment: {}", env_path.user_display())?;
+            if let Some(toolchain) = &self.toolchain {
+                writeln!(out, "  toolchain: {}", toolchain.user_display())?;
+            }
+            if let Some(version) = &self.language_version {
+                writeln!(out, "  language version: {version}")?;
+            }
+            if !self.dependencies.is_empty() {
+                writeln!(
+                    out,
+                    "  dependencies: {}",
+                    self.dependencies.iter().join(", ")
+                )?;
+            }
+            if let Some(installed_at) = self.installed_at {
+                let age = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .saturating_sub(Duration::from_secs(installed_at));
+                writeln!(out, "  installed: {} ago", human_duration(age))?;
+            }
+            if let Some(size) = self.size {
+                writeln!(out, "  size: {}", human_size(size))?;
+            }
+        } else {
+            writeln!(out, "  environment: {}", "not installed".yellow())?;
+            writeln!(
+                out,
+                "  would install: language version `{}`",
+                self.language_request
+            )?;
+            if !self.dependencies.is_empty() {
+                writeln!(
+                    out,
+                    "  with dependencies: {}",
+                    self.dependencies.iter().join(", ")
+                )?;
+            }
+        }
+
+        if self.pass_filenames {
+            writeln!(out, "  command: {} [FILENAMES]...", self.command)?;
+        } else {
+            writeln!(out, "  command: {}", self.command)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// When the environment was marked as installed, since the Unix epoch.
+fn installed_at(env_path: &Path) -> Option<Duration> {
+    fs_err::metadata(env_path.join(".prek-hook.json"))
+        .and_then(|metadata| metadata.modified())
+        .ok()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()
+}
+
+/// The size of the files in `path`, recursively, without following symlinks.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs_err::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map_or(0, |metadata| metadata.len()),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes}B"),
+        1024..1_048_576 => format!("{:.1}KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1}MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn human_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// Show the environment the hooks with the given id run in, without installing it.
+pub(crate) async fn env_info(
+    config: Option<PathBuf>,
+    hook_id: &str,
+    json: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let reporter = HookInitReporter::from(printer);
+    let hooks = {
+        let _lock = store.lock_async().await?;
+        project.init_hooks(&store, Some(&reporter)).await?
+    };
+
+    let installed = store.installed_hooks().collect::<Vec<_>>();
+    let infos = hooks
+        .iter()
+        .filter(|hook| hook.id == hook_id || hook.alias == hook_id)
+        .map(|hook| EnvInfo::new(hook, &installed))
+        .collect::<Result<Vec<_>>>()?;
+
+    if infos.is_empty() {
+        writeln!(
+            printer.stderr_important(),
+            "No hook found for id `{}`",
+            hook_id.cyan()
+        )?;
+        return Ok(ExitStatus::Failure);
+    }
+
+    if json {
+        writeln!(
+            printer.stdout(),
+            "{}",
+            serde_json::to_string_pretty(&infos)?
+        )?;
+    } else {
+        let mut out = String::new();
+        for (idx, info) in infos.iter().enumerate() {
+            if idx > 0 {
+                writeln!(out)?;
+            }
+            info.write_text(&mut out)?;
+        }
+        write!(printer.stdout(), "{out}")?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_units() {
+        assert_eq!(human_size(512), "512B");
+        assert_eq!(human_size(1536), "1.5KiB");
+        assert_eq!(human_size(3 * 1_048_576), "3.0MiB");
+        assert_eq!(human_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(human_duration(Duration::from_secs(7200)), "2h");
+        assert_eq!(human_duration(Duration::from_secs(3 * 86400)), "3d");
+    }
+}
@@ -0,0 +1,462 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+
+use crate::builtin;
+use crate::cli::reporter::HookInitReporter;
+use crate::cli::{ExitStatus, ExportFormat};
+use crate::config::{Language, Stage};
+use crate::fs::Simplified;
+use crate::hook::{EnvSegment, Hook, Repo, env_segments};
+use crate::printer::Printer;
+use crate::store::Store;
+use crate::workspace::Project;
+
+/// Write the hooks of the configuration as a standalone script, for environments that can't
+/// run prek.
+///
+/// The script is meant to be reviewed and adapted: file patterns are translated to `grep -E`,
+/// file types are not checked, and hooks that need a managed toolchain are left as `TODO`s.
+pub(crate) async fn export_hooks(
+    config: Option<PathBuf>,
+    format: ExportFormat,
+    hook_stage: Stage,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let reporter = HookInitReporter::from(printer);
+    let hooks = {
+        let _lock = store.lock_async().await?;
+        project.init_hooks(&store, Some(&reporter)).await?
+    };
+
+    let mut out = String::new();
+    if matches!(format, ExportFormat::Shell) {
+        writeln!(out, "#!/bin/sh")?;
+    }
+    writeln!(
+        out,
+        "# Generated by `prek export-hooks` from `{}`.",
+        project.config_file().user_display()
+    )?;
+    writeln!(
+        out,
+        "# File patterns are matched with `grep -E` and file types are not checked,"
+    )?;
+    writeln!(out, "# review the commands before relying on them.")?;
+
+    let global_filters = FileFilters {
+        files: project.config().files.as_deref(),
+        exclude: project.config().exclude.as_deref(),
+        case_insensitive: false,
+    };
+
+    if matches!(format, ExportFormat::Shell) {
+        writeln!(out)?;
+        writeln!(out, "cd \"$(git rev-parse --show-toplevel)\" || exit 1")?;
+        writeln!(out, "status=0")?;
+    }
+
+    for hook in hooks.iter().filter(|h| h.stages.contains(&hook_stage)) {
+        writeln!(out)?;
+        let exported = export_hook(hook, hook_stage, &global_filters, format)
+            .with_context(|| format!("Failed to export hook `{}`", hook.id))?;
+        match exported {
+            Exported::Commands(lines) => match format {
+                ExportFormat::Shell => {
+                    writeln!(out, "# {}", hook.id)?;
+                    for line in lines {
+                        writeln!(out, "{line}")?;
+                    }
+                }
+                ExportFormat::GithubActions => {
+                    writeln!(out, "- name: {}", serde_json::to_string(&hook.name)?)?;
+                    writeln!(out, "  run: |")?;
+                    for line in lines {
+                        writeln!(out, "    {line}")?;
+                    }
+                }
+            },
+            Exported::Todo(lines) => {
+                for line in lines {
+                    writeln!(out, "# {line}")?;
+                }
+            }
+        }
+    }
+
+    if matches!(format, ExportFormat::Shell) {
+        writeln!(out)?;
+        writeln!(out, "exit $status")?;
+    }
+
+    write!(printer.stdout(), "{out}")?;
+
+    Ok(ExitStatus::Success)
+}
+
+enum Exported {
+    /// Shell commands reproducing the hook.
+    Commands(Vec<String>),
+    /// A note on what the hook needs, for hooks that can't be reproduced with plain commands.
+    Todo(Vec<String>),
+}
+
+struct FileFilters<'a> {
+    files: Option<&'a str>,
+    exclude: Option<&'a str>,
+    case_insensitive: bool,
+}
+
+impl FileFilters<'_> {
+    /// Append `grep` commands applying the filters to a pipeline.
+    fn append_to(&self, pipeline: &mut String) -> Result<()> {
+        for (key, pattern, invert) in [("files", self.files, ""), ("exclude", self.exclude, "v")] {
+            let Some(pattern) = pattern else {
+                continue;
+            };
+            let (ere, ignore_case) = to_ere(pattern).with_context(|| {
+                format!("Failed to translate the `{key}` pattern `{pattern}` for `grep -E`")
+            })?;
+            let flags = if self.case_insensitive || ignore_case {
+                "iE"
+            } else {
+                "E"
+            };
+            write!(pipeline, " | grep -{invert}{flags} -- {}", quote(&ere))?;
+        }
+        Ok(())
+    }
+}
+
+/// Translate a `files` or `exclude` pattern to a POSIX extended regex, returning whether it
+/// ignores case.
+///
+/// Only the syntax with an equivalent is translated, patterns using anything else, like
+/// lookarounds or backreferences, are refused rather than exported with another meaning.
+fn to_ere(pattern: &str) -> Result<(String, bool)> {
+    let mut rest = pattern;
+    let mut ignore_case = false;
+    let mut verbose = false;
+    // Global flags, like `(?ix)`, at the start of the pattern.
+    while let Some(flags) = rest
+        .strip_prefix("(?")
+        .and_then(|r| r.split_once(')'))
+        .filter(|(flags, _)| !flags.is_empty() && flags.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        for flag in flags.0.chars() {
+            match flag {
+                'i' => ignore_case = true,
+                'x' => verbose = true,
+                _ => bail!("The flag `(?{flag})` has no `grep -E` equivalent"),
+            }
+        }
+        rest = flags.1;
+    }
+
+    let mut ere = String::with_capacity(rest.len());
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('d') => ere.push_str("[0-9]"),
+                Some('D') => ere.push_str("[^0-9]"),
+                Some('w') => ere.push_str("[[:alnum:]_]"),
+                Some('W') => ere.push_str("[^[:alnum:]_]"),
+                Some('s') => ere.push_str("[[:space:]]"),
+                Some('S') => ere.push_str("[^[:space:]]"),
+                Some('A') => ere.push('^'),
+                Some('Z' | 'z') => ere.push('$'),
+                Some(c) if c.is_ascii_digit() => {
+                    bail!("Backreferences have no `grep -E` equivalent")
+                }
+                Some(c) if c.is_ascii_punctuation() || c == ' ' => {
+                    if ".[]()*+?{}|^$\\".contains(c) {
+                        ere.push('\\');
+                    }
+                    ere.push(c);
+                }
+                Some(c) => bail!("The escape `\\{c}` has no `grep -E` equivalent"),
+                None => bail!("The pattern ends with a lone `\\`"),
+            },
+            '[' => {
+                ere.push('[');
+                if chars.next_if_eq(&'^').is_some() {
+                    ere.push('^');
+                }
+                if chars.next_if_eq(&']').is_some() {
+                    ere.push(']');
+                }
+                // A literal `-` must come last in POSIX brackets.
+                let mut dash = false;
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        // Backslashes are literal in POSIX brackets.
+                        Some('\\') => match chars.next() {
+                            Some('d') => ere.push_str("0-9"),
+                            Some('w') => ere.push_str("[:alnum:]_"),
+                            Some('s') => ere.push_str("[:space:]"),
+                            Some('-') => dash = true,
+                            Some(c)
+                                if (c.is_ascii_punctuation() || c == ' ') && !"]^".contains(c) =>
+                            {
+                                ere.push(c);
+                            }
+                            Some(c) => {
+                                bail!("The escape `\\{c}` in brackets has no `grep -E` equivalent")
+                            }
+                            None => bail!("The pattern ends with a lone `\\`"),
+                        },
+                        // `[:`, `[.` and `[=` start POSIX classes, unlike in Python.
+                        Some('[') if matches!(chars.peek(), Some(':' | '.' | '=')) => {
+                            bail!("`[:`, `[.` and `[=` in brackets have no `grep -E` equivalent")
+                        }
+                        Some(c) => ere.push(c),
+                        None => bail!("The pattern has an unclosed `[`"),
+                    }
+                }
+                if dash {
+                    ere.push('-');
+                }
+                ere.push(']');
+            }
+            '(' if chars.next_if_eq(&'?').is_some() => match chars.next() {
+                Some(':') => ere.push('('),
+                // Named groups, `(?P<name>...)` or `(?<name>...)`.
+                Some('P') if chars.next_if_eq(&'<').is_some() => {
+                    chars.by_ref().find(|c| *c == '>');
+                    ere.push('(');
+                }
+                Some('<') if chars.peek().is_some_and(|c| !"=!".contains(*c)) => {
+                    chars.by_ref().find(|c| *c == '>');
+                    ere.push('(');
+                }
+                Some('=' | '!' | '<') => bail!("Lookarounds have no `grep -E` equivalent"),
+                Some(c) => bail!("The group `(?{c}` has no `grep -E` equivalent"),
+                None => bail!("The pattern ends with a lone `(?`"),
+            },
+            '*' | '+' | '?' | '}' => {
+                ere.push(c);
+                // Lazy quantifiers match the same lines.
+                chars.next_if_eq(&'?');
+                if chars.peek() == Some(&'+') {
+                    bail!("Possessive quantifiers have no `grep -E` equivalent");
+                }
+            }
+            c if verbose && c.is_whitespace() => {}
+            '#' if verbose => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            c => ere.push(c),
+        }
+    }
+
+    Ok((ere, ignore_case))
+}
+
+fn export_hook(
+    hook: &Hook,
+    hook_stage: Stage,
+    global_filters: &FileFilters,
+    format: ExportFormat,
+) -> Result<Exported> {
+    let on_failure = match format {
+        ExportFormat::Shell => " || status=1",
+        ExportFormat::GithubActions => "",
+    };
+
+    // prek does the file matching itself.
+    if builtin::check_fast_path(hook) {
+        let mut command = format!("prek run {} --all-files", quote(&hook.id));
+        if hook_stage != Stage::PreCommit {
+            write!(command, " --hook-stage {hook_stage}")?;
+        }
+        return Ok(Exported::Commands(vec![format!("{command}{on_failure}")]));
+    }
+
+    // Leave the environment variables to the shell running the script, the values of this
+    // machine may not fit another one.
+    let mut entry = hook.entry.split()?;
+    let quote_arg = if hook.entry.expands() {
+        quote_expanding
+    } else {
+        quote
+    };
+    match (hook.language, hook.repo()) {
+        (Language::System | Language::Fail, _) => {}
+        // Local scripts are relative to the repository root.
+        (Language::Script, Repo::Local { .. }) => {
+            if !entry[0].contains('/') {
+                entry[0] = format!("./{}", entry[0]);
+            }
+        }
+        (Language::Script, Repo::Remote { url, rev, .. }) => {
+            return Ok(Exported::Todo(vec![
+                format!(
+                    "TODO: `{}` is a script from `{url}@{rev}`, check out the repository and run:",
+                    hook.id
+                ),
+                format!("  {}", join_quoted(&entry, &hook.args, quote_arg)),
+            ]));
+        }
+        (language, _) => {
+            return Ok(Exported::Todo(vec![
+                format!(
+                    "TODO: `{}` is a `{language}` hook, install its toolchain and run:",
+                    hook.id
+                ),
+                format!("  {}", join_quoted(&entry, &hook.args, quote_arg)),
+            ]));
+        }
+    }
+
+    let mut lines = Vec::new();
+
+    let mut types = Vec::new();
+    if hook.types != ["file"] {
+        types.push(format!("of types `{}`", hook.types.join(", ")));
+    }
+    if !hook.types_or.is_empty() {
+        types.push(format!("of any of types `{}`", hook.types_or.join(", ")));
+    }
+    if !hook.exclude_types.is_empty() {
+        types.push(format!("not of types `{}`", hook.exclude_types.join(", ")));
+    }
+    if !types.is_empty() {
+        lines.push(format!(
+            "# Only files {} should be checked.",
+            types.join(" and ")
+        ));
+    }
+
+    let command = join_quoted(&entry, &hook.args, quote_arg);
+    if hook.always_run && !hook.pass_filenames {
+        lines.push(format!("{command}{on_failure}"));
+        return Ok(Exported::Commands(lines));
+    }
+
+    let mut list_files = "git ls-files".to_string();
+    global_filters.append_to(&mut list_files)?;
+    FileFilters {
+        files: hook.files.as_deref(),
+        exclude: hook.exclude.as_deref(),
+        case_insensitive: hook.files_case_insensitive,
+    }
+    .append_to(&mut list_files)?;
+    // `grep` fails when no file matches, which aborts scripts run with `-e`, like GitHub steps.
+    if list_files.contains(" | grep ") {
+        list_files.push_str(" || true");
+    }
+    lines.push(format!("files=$({list_files})"));
+
+    if hook.language == Language::Fail {
+        lines.push("if [ -n \"$files\" ]; then".to_string());
+        lines.push(format!(
+            "  printf '%s\\n' {} \"$files\"",
+            quote(hook.entry.entry())
+        ));
+        lines.push(match format {
+            ExportFormat::Shell => "  status=1".to_string(),
+            ExportFormat::GithubActions => "  exit 1".to_string(),
+        });
+        lines.push("fi".to_string());
+        return Ok(Exported::Commands(lines));
+    }
+
+    let run_with_files = if hook.pass_filenames {
+        format!("printf '%s\\n' \"$files\" | tr '\\n' '\\0' | xargs -0 {command}{on_failure}")
+    } else {
+        format!("{command}{on_failure}")
+    };
+
+    lines.push("if [ -n \"$files\" ]; then".to_string());
+    lines.push(format!("  {run_with_files}"));
+    if hook.always_run {
+        lines.push("else".to_string());
+        lines.push(format!("  {command}{on_failure}"));
+    }
+    lines.push("fi".to_string());
+
+    Ok(Exported::Commands(lines))
+}
+
+/// Quote `s` for a POSIX shell, if needed.
+fn quote(s: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(format!("'{}'", s.replace('\'', r"'\''")))
+    }
+}
+
+/// Quote `s` for a POSIX shell, leaving its `${VAR}` references for the shell to expand, like
+/// prek does for the entry and `args` of hooks.
+fn quote_expanding(s: &str) -> Cow<'_, str> {
+    let segments = env_segments(s);
+    if let [EnvSegment::Text(text)] = segments.as_slice() {
+        return quote(*text);
+    }
+    Cow::Owned(
+        segments
+            .iter()
+            .filter(|segment| **segment != EnvSegment::Text(""))
+            .map(|segment| match segment {
+                EnvSegment::Text(text) => quote(text),
+                EnvSegment::Var(name) => Cow::Owned(format!("\"${{{name}}}\"")),
+            })
+            .collect(),
+    )
+}
+
+/// Join the entry and the hook `args` into a shell command, like [`crate::run::build_command`].
+pub(crate) fn join(entry: &[String], args: &[String]) -> String {
+    join_quoted(entry, args, quote)
+}
+
+/// Join the entry and the hook `args` into a shell command, quoting each with `quote_arg`.
+fn join_quoted(entry: &[String], args: &[String], quote_arg: fn(&str) -> Cow<'_, str>) -> String {
+    entry
+        .iter()
+        .chain(args)
+        .map(|arg| quote_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_ere;
+
+    #[test]
+    fn translate_patterns() {
+        let ere = |pattern| to_ere(pattern).unwrap();
+        assert_eq!(ere(r"^src/.*\.rs$"), (r"^src/.*\.rs$".to_string(), false));
+        assert_eq!(ere(r"(?i)\d+\.TXT\Z"), (r"[0-9]+\.TXT$".to_string(), true));
+        assert_eq!(
+            ere(r"^(?:docs|(?P<name>tests))/[\w\-.]*?\.md$"),
+            (r"^(docs|(tests))/[[:alnum:]_.-]*\.md$".to_string(), false)
+        );
+        assert_eq!(
+            ere("(?x)^(\n    foo/  # the foo\n  | bar\\ baz/\n)"),
+            ("^(foo/|bar baz/)".to_string(), false)
+        );
+        assert_eq!(ere(r"[]\\/]"), (r"[]\/]".to_string(), false));
+
+        for pattern in [
+            r"foo(?=bar)",
+            r"(a)\1",
+            r"(?s)a.b",
+            r"a\bb",
+            r"[\]]",
+            r"a++",
+        ] {
+            assert!(to_ere(pattern).is_err(), "{pattern}");
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+
+use crate::cli::ExitStatus;
+#[cfg(feature = "docker")]
+use crate::config::Language;
+#[cfg(feature = "docker")]
+use crate::languages::{remove_docker_images, used_docker_images};
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Hook output logs older than this are removed, see `--max-output-lines`.
+const MAX_LOG_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Remove what no hook environment of the store uses anymore, and old hook output logs.
+pub(crate) async fn gc(printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    if !store.path().exists() {
+        writeln!(printer.stdout(), "Nothing to clean")?;
+        return Ok(ExitStatus::Success);
+    }
+    let _lock = store.lock_async().await?;
+
+    #[cfg(feature = "docker")]
+    {
+        // Without `docker` environments there is nothing to keep, nor maybe a docker to ask.
+        if store
+            .installed_hooks()
+            .any(|info| info.language == Language::Docker)
+        {
+            let removed = remove_docker_images(&used_docker_images(&store)).await?;
+            writeln!(printer.stdout(), "Removed {removed} unused docker images")?;
+        }
+    }
+
+    let removed = remove_old_logs(&store.logs_dir(), MAX_LOG_AGE)?;
+    if removed > 0 {
+        writeln!(printer.stdout(), "Removed {removed} old hook output logs")?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+/// Remove the files of `logs_dir` not modified for `max_age`, returning how many were removed.
+fn remove_old_logs(logs_dir: &Path, max_age: Duration) -> Result<usize> {
+    let Ok(entries) = fs_err::read_dir(logs_dir) else {
+        return Ok(0);
+    };
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok());
+        if metadata.is_file() && age.is_some_and(|age| age > max_age) {
+            fs_err::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
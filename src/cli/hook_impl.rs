@@ -1,14 +1,23 @@
 use anyhow::Result;
 use std::ffi::OsString;
+use std::io;
 use std::path::PathBuf;
 
 use anstream::eprintln;
+use tracing::debug;
 
 use constants::env_vars::EnvVars;
 
-use crate::cli::{self, ExitStatus, RunArgs};
-use crate::config::HookType;
+use crate::cli::{self, ExitStatus, RunArgs, RunOptions};
+use crate::config::{
+    ALTER_CONFIG_FILE, CONFIG_FILE, HookType, MANIFEST_FILE, Repo, TOML_CONFIG_FILE, read_config,
+    read_manifest,
+};
+use crate::fs::CWD;
+use crate::git;
 use crate::printer::Printer;
+use crate::store::Store;
+use crate::workspace::Project;
 
 pub(crate) async fn hook_impl(
     config: Option<PathBuf>,
@@ -20,23 +29,37 @@ pub(crate) async fn hook_impl(
 ) -> Result<ExitStatus> {
     // TODO: run in legacy mode
 
-    if let Some(ref config_file) = config {
-        if !config_file.try_exists()? {
-            return if skip_on_missing_config || EnvVars::is_set(EnvVars::PREK_ALLOW_NO_CONFIG) {
-                Ok(ExitStatus::Success)
-            } else {
-                eprintln!("Config file not found: {}", config_file.display());
-                eprintln!(
-                    "- To temporarily silence this, run `{}=1 git ...`",
-                    EnvVars::PREK_ALLOW_NO_CONFIG
-                );
-                eprintln!(
-                    "- To permanently silence this, install hooks with the `--allow-missing-config` flag"
-                );
-                eprintln!("- To uninstall hooks, run `PREK uninstall`");
-                Ok(ExitStatus::Failure)
-            };
-        }
+    if let Some(source) = disabled_by(hook_type).await? {
+        eprintln!("prek disabled via {source}");
+        return Ok(ExitStatus::Success);
+    }
+
+    // Hooks installed without a project, e.g. by `init-template-dir`, look for the config in
+    // the repository root, which is the working directory of git hooks.
+    let config_file = match &config {
+        Some(config_file) => config_file.clone(),
+        None => [CONFIG_FILE, ALTER_CONFIG_FILE, TOML_CONFIG_FILE]
+            .into_iter()
+            .map(|file| CWD.join(file))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE)),
+    };
+    if !config_file.try_exists()? {
+        return if skip_on_missing_config || EnvVars::is_set(EnvVars::PREK_ALLOW_NO_CONFIG) {
+            debug!("Config file not found, skipping: {}", config_file.display());
+            Ok(ExitStatus::Success)
+        } else {
+            eprintln!("Config file not found: {}", config_file.display());
+            eprintln!(
+                "- To temporarily silence this, run `{}=1 git ...`",
+                EnvVars::PREK_ALLOW_NO_CONFIG
+            );
+            eprintln!(
+                "- To permanently silence this, install hooks with the `--allow-missing-config` flag"
+            );
+            eprintln!("- To uninstall hooks, run `prek uninstall`");
+            Ok(ExitStatus::Failure)
+        };
     }
 
     if !hook_type.num_args().contains(&args.len()) {
@@ -46,22 +69,120 @@ pub(crate) async fn hook_impl(
 
     let run_args = to_run_args(hook_type, &args);
 
-    cli::run(
+    if matches!(hook_type, HookType::PreCommit | HookType::PreMergeCommit)
+        && nothing_to_run(config.clone()).await?
+    {
+        debug!("No files are staged and no hook runs without files, skipping");
+        return Ok(ExitStatus::Success);
+    }
+
+    let options = RunOptions {
         config,
-        run_args.hook_id,
-        hook_type.into(),
-        run_args.from_ref,
-        run_args.to_ref,
-        run_args.all_files,
-        vec![],
-        vec![],
-        false, // last_commit is always false in hook implementation context
-        false,
-        run_args.extra,
-        false,
-        printer,
-    )
-    .await
+        hook_id: run_args.hook_id,
+        hook_stages: vec![hook_type.into()],
+        from_ref: run_args.from_ref,
+        to_ref: run_args.to_ref,
+        all_files: run_args.all_files,
+        extra_args: run_args.extra,
+        ..RunOptions::default()
+    };
+    cli::run(options, &CWD, printer).await
+}
+
+/// Check whether a commit has nothing for the hooks to run on: no file is staged and no hook
+/// of the config is `always_run`.
+///
+/// This only reads the config, repos are not cloned. A remote hook that doesn't set
+/// `always_run` in the config is looked up in its manifest if the repo is already in the store,
+/// and otherwise assumed to be `always_run`. Anything `run` would report, like an unstaged
+/// config or unmerged paths, is left to it.
+async fn nothing_to_run(config: Option<PathBuf>) -> Result<bool> {
+    if !git::get_staged_files().await?.is_empty()
+        || git::is_in_merge_conflict().await?
+        || git::has_unmerged_paths().await?
+    {
+        return Ok(false);
+    }
+
+    let Ok(config_file) = Project::find_config_file(config) else {
+        return Ok(false);
+    };
+    if git::file_not_staged(&config_file).await? {
+        return Ok(false);
+    }
+    let Ok(config) = read_config(&config_file) else {
+        return Ok(false);
+    };
+
+    let store = Store::from_settings()?;
+    let always_run = config.repos.iter().any(|repo| match repo {
+        Repo::Local(repo) => repo
+            .hooks
+            .iter()
+            .any(|hook| hook.options.always_run.unwrap_or(false)),
+        Repo::Meta(repo) => repo
+            .hooks
+            .iter()
+            .any(|hook| hook.0.options.always_run.unwrap_or(false)),
+        Repo::Remote(repo) => {
+            let manifest = store
+                .cloned_repo(repo)
+                .and_then(|path| read_manifest(&path.join(MANIFEST_FILE)).ok());
+            repo.hooks.iter().any(|hook| {
+                hook.options.always_run.unwrap_or_else(|| {
+                    manifest.as_ref().is_none_or(|manifest| {
+                        manifest
+                            .hooks
+                            .iter()
+                            .find(|h| h.id == hook.id)
+                            .is_none_or(|h| h.options.always_run.unwrap_or(false))
+                    })
+                })
+            })
+        }
+    });
+
+    Ok(!always_run)
+}
+
+/// Name of the marker file in the git directory that disables hooks.
+const DISABLE_MARKER: &str = "prek-disabled";
+
+/// Check whether hooks of `hook_type` are disabled, returning the source that disabled them.
+///
+/// `PREK_DISABLE` takes precedence over the `.git/prek-disabled` marker file:
+/// - `PREK_DISABLE=1` (or `true`) disables all hooks.
+/// - `PREK_DISABLE=0` (or `false`) enables all hooks, even if the marker file exists.
+/// - Otherwise, it's a comma-separated list of hook types to disable, e.g. `PREK_DISABLE=pre-push`.
+///
+/// The marker file uses the same format, except that an empty file disables all hooks.
+async fn disabled_by(hook_type: HookType) -> Result<Option<String>> {
+    if let Some(value) = EnvVars::var_os(EnvVars::PREK_DISABLE)
+        && !value.is_empty()
+    {
+        let disabled = is_disabled(&value.to_string_lossy(), hook_type);
+        return Ok(disabled.then(|| EnvVars::PREK_DISABLE.to_string()));
+    }
+
+    let marker = git::get_git_common_dir().await?.join(DISABLE_MARKER);
+    match fs_err::read_to_string(&marker) {
+        Ok(content) => {
+            let disabled = content.trim().is_empty() || is_disabled(&content, hook_type);
+            Ok(disabled.then(|| format!(".git/{DISABLE_MARKER}")))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn is_disabled(value: &str, hook_type: HookType) -> bool {
+    match value.trim() {
+        "1" | "true" => true,
+        "0" | "false" => false,
+        value => value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .any(|name| name == hook_type.as_str()),
+    }
 }
 
 fn to_run_args(hook_type: HookType, args: &[OsString]) -> RunArgs {
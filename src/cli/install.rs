@@ -3,23 +3,28 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use clap::ValueEnum;
 use indoc::indoc;
+use itertools::Itertools;
 use owo_colors::OwoColorize;
 use same_file::is_same_file;
 
 use crate::cli::reporter::{HookInitReporter, HookInstallReporter};
 use crate::cli::run;
 use crate::cli::{ExitStatus, HookType};
+use crate::config::{self, Stage};
 use crate::fs::Simplified;
 use crate::git;
 use crate::git::git_cmd;
 use crate::printer::Printer;
 use crate::store::Store;
+use crate::warn_user;
 use crate::workspace::Project;
 
 pub(crate) async fn install(
     config: Option<PathBuf>,
     hook_types: Vec<HookType>,
+    auto_hook_types: bool,
     install_hook_environments: bool,
     overwrite: bool,
     allow_missing_config: bool,
@@ -28,7 +33,7 @@ pub(crate) async fn install(
 ) -> Result<ExitStatus> {
     if git_dir.is_none() && git::has_hooks_path_set().await? {
         writeln!(
-            printer.stderr(),
+            printer.stderr_important(),
             indoc::indoc! {"
                 Cowardly refusing to install hooks with `core.hooksPath` set.
                 hint: `git config --unset-all core.hooksPath` to fix this.
@@ -37,8 +42,20 @@ pub(crate) async fn install(
         return Ok(ExitStatus::Failure);
     }
 
-    let project = Project::from_config_file(config.clone()).ok();
-    let hook_types = get_hook_types(project.as_ref(), hook_types);
+    let mut project = Project::from_config_file(config.clone()).ok();
+    let hook_types = if auto_hook_types {
+        resolve_auto_hook_types(project.as_mut(), printer).await?
+    } else {
+        // Unused `default_install_hook_types` are already reported when loading the config.
+        if let Some(project) = &project {
+            for hook_type in unused_hook_types(project, &hook_types) {
+                warn_user!(
+                    "No hook runs at `{hook_type}`, the installed `{hook_type}` hook will do nothing"
+                );
+            }
+        }
+        get_hook_types(project.as_ref(), hook_types)
+    };
 
     let hooks_path = if let Some(dir) = git_dir {
         dir.join("hooks")
@@ -73,7 +90,7 @@ pub(crate) async fn install_hooks(config: Option<PathBuf>, printer: Printer) ->
     let reporter = HookInitReporter::from(printer);
     let hooks = project.init_hooks(&store, Some(&reporter)).await?;
     let reporter = HookInstallReporter::from(printer);
-    run::install_hooks(hooks, &store, &reporter).await?;
+    run::install_hooks(hooks, &store, &reporter, false).await?;
 
     Ok(ExitStatus::Success)
 }
@@ -99,6 +116,162 @@ fn get_hook_types(project: Option<&Project>, hook_types: Vec<HookType>) -> Vec<H
     hook_types
 }
 
+/// The hook types the hooks of the project run at, with the ids of the hooks running at each.
+///
+/// Like when the hooks are built, hooks of remote repositories without `stages` in the config
+/// use the `stages` of their manifest, so the repositories must be initialized. Hooks without
+/// `stages` run at `default_stages`, or at `pre-commit` if it's not set. When
+/// `default_install_hook_types` is set, only those hook types are considered.
+fn auto_hook_types(project: &Project) -> Vec<(HookType, Vec<&str>)> {
+    let config = project.config();
+    let default_stages = config
+        .default_stages
+        .as_deref()
+        .unwrap_or(&[Stage::PreCommit]);
+    let hooks = config
+        .repos
+        .iter()
+        .zip_eq(project.repos())
+        .flat_map(|(repo_config, repo)| -> Vec<(&str, Option<&[Stage]>)> {
+            match repo_config {
+                config::Repo::Remote(repo_config) => repo_config
+                    .hooks
+                    .iter()
+                    .map(|h| {
+                        let manifest_stages = || {
+                            repo.get_hook(&h.id)
+                                .and_then(|hook| hook.options.stages.as_deref())
+                        };
+                        (
+                            h.id.as_str(),
+                            h.options.stages.as_deref().or_else(manifest_stages),
+                        )
+                    })
+                    .collect(),
+                config::Repo::Local(repo_config) => repo_config
+                    .hooks
+                    .iter()
+                    .map(|h| (h.id.as_str(), h.options.stages.as_deref()))
+                    .collect(),
+                config::Repo::Meta(repo_config) => repo_config
+                    .hooks
+                    .iter()
+                    .map(|h| (h.0.id.as_str(), h.0.options.stages.as_deref()))
+                    .collect(),
+            }
+        })
+        .map(|(id, stages)| (id, stages.unwrap_or(default_stages)))
+        .collect::<Vec<_>>();
+
+    HookType::value_variants()
+        .iter()
+        .filter(|hook_type| {
+            config
+                .default_install_hook_types
+                .as_ref()
+                .is_none_or(|types| types.contains(hook_type))
+        })
+        .filter_map(|hook_type| {
+            let ids = hooks
+                .iter()
+                .filter(|(_, stages)| {
+                    stages
+                        .iter()
+                        .any(|stage| stage.hook_type() == Some(*hook_type))
+                })
+                .map(|(id, _)| *id)
+                .unique()
+                .collect::<Vec<_>>();
+            (!ids.is_empty()).then_some((*hook_type, ids))
+        })
+        .collect()
+}
+
+/// The hook types in `hook_types` that no hook of the project runs at.
+///
+/// Like [`config::Locations::check_config_stages`], remote hooks without `stages` in the
+/// config are assumed to run at any stage, since their manifest is not read.
+fn unused_hook_types(project: &Project, hook_types: &[HookType]) -> Vec<HookType> {
+    let config = project.config();
+    let default_stages = config.default_stages.as_deref();
+    let stages = config
+        .repos
+        .iter()
+        .flat_map(|repo| -> Vec<Option<&[Stage]>> {
+            match repo {
+                config::Repo::Remote(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|h| h.options.stages.as_deref())
+                    .collect(),
+                config::Repo::Local(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|h| h.options.stages.as_deref().or(default_stages))
+                    .collect(),
+                config::Repo::Meta(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|h| h.0.options.stages.as_deref().or(default_stages))
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    hook_types
+        .iter()
+        .filter(|hook_type| {
+            let stage = Stage::from(**hook_type);
+            !stages
+                .iter()
+                .any(|stages| stages.is_none_or(|stages| stages.contains(&stage)))
+        })
+        .copied()
+        .collect()
+}
+
+/// Pick the hook types to (un)install from the stages of the hooks, and tell the user why.
+///
+/// Remote repositories are cloned if needed, to read the stages of their manifest.
+async fn resolve_auto_hook_types(
+    project: Option<&mut Project>,
+    printer: Printer,
+) -> Result<Vec<HookType>> {
+    let Some(project) = project else {
+        anyhow::bail!("`--auto-hook-types` requires a valid configuration file");
+    };
+
+    let store = Store::from_settings()?.init()?;
+    let reporter = HookInitReporter::from(printer);
+    {
+        let _lock = store.lock_async().await?;
+        project.init_repos(&store, Some(&reporter)).await?;
+    }
+
+    let hook_types = auto_hook_types(project);
+    if hook_types.is_empty() {
+        writeln!(
+            printer.stdout(),
+            "No hook runs at a hook type to install, using the defaults"
+        )?;
+        return Ok(get_hook_types(Some(project), vec![]));
+    }
+
+    for (hook_type, ids) in &hook_types {
+        writeln!(
+            printer.stdout(),
+            "Using {} for {}",
+            hook_type.as_str().cyan(),
+            ids.iter().map(|id| format!("`{id}`")).join(", ")
+        )?;
+    }
+
+    Ok(hook_types
+        .into_iter()
+        .map(|(hook_type, _)| hook_type)
+        .collect())
+}
+
 fn install_hook_script(
     project: Option<&Project>,
     hook_type: HookType,
@@ -118,7 +291,7 @@ fn install_hook_script(
             )?;
         } else {
             if !is_our_script(&hook_path)? {
-                let legacy_path = format!("{}.legacy", hook_path.display());
+                let legacy_path = legacy_path(&hook_path);
                 fs_err::rename(&hook_path, &legacy_path)?;
                 writeln!(
                     printer.stdout(),
@@ -144,8 +317,7 @@ fn install_hook_script(
         args.push("--skip-on-missing-config".to_string());
     }
 
-    let prek = std::env::current_exe()?;
-    let prek = prek.simplified().display().to_string();
+    let prek = script_prek_path(&std::env::current_exe()?);
     let hook_script = HOOK_TMPL
         .replace("ARGS=(hook-impl)", &format!("ARGS=({})", args.join(" ")))
         .replace(r#"PREK="prek""#, &format!(r#"PREK="{prek}""#));
@@ -198,21 +370,63 @@ static CURRENT_HASH: &str = "182c10f181da4464a3eec51b83331688";
 /// Checks if the script contains any of the hashes that `prek` has used in the past.
 fn is_our_script(hook_path: &Path) -> Result<bool> {
     let content = fs_err::read_to_string(hook_path)?;
-    Ok(std::iter::once(CURRENT_HASH)
+    Ok(is_our_script_content(&content))
+}
+
+fn is_our_script_content(content: &str) -> bool {
+    std::iter::once(CURRENT_HASH)
         .chain(PRIOR_HASHES.iter().copied())
-        .any(|hash| content.contains(hash)))
+        .any(|hash| content.contains(hash))
+}
+
+/// The path of the prek binary, as written into hook scripts.
+fn script_prek_path(prek: &Path) -> String {
+    prek.simplified().display().to_string()
+}
+
+/// Checks if the script is ours and runs the given prek binary.
+pub(crate) fn is_script_for(hook_path: &Path, prek: &Path) -> Result<bool> {
+    let content = fs_err::read_to_string(hook_path)?;
+    Ok(is_our_script_content(&content)
+        && content.contains(&format!(r#"PREK="{}""#, script_prek_path(prek))))
+}
+
+/// The path an existing hook at `hook_path` is moved aside to, `<hook_path>.legacy`.
+fn legacy_path(hook_path: &Path) -> PathBuf {
+    let mut path = hook_path.as_os_str().to_os_string();
+    path.push(".legacy");
+    PathBuf::from(path)
+}
+
+/// Restore the hook that was moved aside when installing the script at `hook_path`.
+pub(crate) fn restore_legacy_hook(hook_path: &Path, printer: Printer) -> Result<()> {
+    let legacy_path = legacy_path(hook_path);
+    if legacy_path.try_exists()? {
+        fs_err::rename(&legacy_path, hook_path)?;
+        writeln!(
+            printer.stdout(),
+            "Restored previous hook to {}",
+            hook_path.user_display().cyan()
+        )?;
+    }
+    Ok(())
 }
 
 pub(crate) async fn uninstall(
     config: Option<PathBuf>,
     hook_types: Vec<HookType>,
+    auto_hook_types: bool,
     printer: Printer,
 ) -> Result<ExitStatus> {
-    let project = Project::from_config_file(config).ok();
-    for hook_type in get_hook_types(project.as_ref(), hook_types) {
+    let mut project = Project::from_config_file(config).ok();
+    let hook_types = if auto_hook_types {
+        resolve_auto_hook_types(project.as_mut(), printer).await?
+    } else {
+        get_hook_types(project.as_ref(), hook_types)
+    };
+    for hook_type in hook_types {
         let hooks_path = git::get_git_common_dir().await?.join("hooks");
         let hook_path = hooks_path.join(hook_type.as_str());
-        let legacy_path = hooks_path.join(format!("{}.legacy", hook_type.as_str()));
 
         if !hook_path.try_exists()? {
             writeln!(
@@ -234,14 +448,7 @@ pub(crate) async fn uninstall(
                 hook_type.as_str().cyan()
             )?;
 
-            if legacy_path.try_exists()? {
-                fs_err::rename(&legacy_path, &hook_path)?;
-                writeln!(
-                    printer.stdout(),
-                    "Restored previous hook to {}",
-                    hook_path.user_display().cyan()
-                )?;
-            }
+            restore_legacy_hook(&hook_path, printer)?;
         }
     }
 
@@ -259,6 +466,7 @@ pub(crate) async fn init_template_dir(
         config,
         hook_types,
         false,
+        false,
         true,
         !requires_config,
         printer,
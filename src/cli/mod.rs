@@ -9,24 +9,38 @@ use clap_complete::engine::{ArgValueCompleter, CompletionCandidate};
 
 use constants::env_vars::EnvVars;
 
-use crate::config::{self, CONFIG_FILE, HookType, Stage};
+use crate::config::{self, CONFIG_FILE, HookType, MANIFEST_FILE, Stage};
 use crate::workspace::Project;
 
 mod clean;
+mod config_schema;
+mod env_info;
+mod export_hooks;
+mod gc;
 mod hook_impl;
 mod install;
-mod reporter;
+mod new_hook;
+pub(crate) mod reporter;
 pub mod run;
 mod sample_config;
+mod self_uninstall;
 mod self_update;
+mod self_version;
 mod validate;
 
 pub(crate) use clean::clean;
+pub(crate) use config_schema::config_schema;
+pub(crate) use env_info::env_info;
+pub(crate) use export_hooks::export_hooks;
+pub(crate) use gc::gc;
 pub(crate) use hook_impl::hook_impl;
 pub(crate) use install::{init_template_dir, install, install_hooks, uninstall};
-pub(crate) use run::run;
+pub(crate) use new_hook::new_hook;
+pub(crate) use run::{RunOptions, run};
 pub(crate) use sample_config::sample_config;
+pub(crate) use self_uninstall::self_uninstall;
 pub(crate) use self_update::self_update;
+pub(crate) use self_version::self_version;
 pub(crate) use validate::{validate_configs, validate_manifest};
 
 // Parses hook ids from .pre-commit-config.yaml
@@ -96,15 +110,33 @@ pub(crate) enum ExitStatus {
     External(u8),
 }
 
+impl ExitStatus {
+    fn code(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::Error => 2,
+            Self::Interrupted => 130,
+            Self::External(code) => code,
+        }
+    }
+
+    /// Exit the process right away, e.g. from the Ctrl-C handler.
+    ///
+    /// On Windows, an interrupted process exits with `STATUS_CONTROL_C_EXIT` like the
+    /// processes killed by Ctrl-C.
+    #[allow(clippy::exit, clippy::cast_possible_wrap)]
+    pub(crate) fn exit_process(self) -> ! {
+        match self {
+            Self::Interrupted if cfg!(windows) => std::process::exit(0xC000_013A_u32 as i32),
+            status => std::process::exit(i32::from(status.code())),
+        }
+    }
+}
+
 impl From<ExitStatus> for ExitCode {
     fn from(status: ExitStatus) -> Self {
-        match status {
-            ExitStatus::Success => Self::from(0),
-            ExitStatus::Failure => Self::from(1),
-            ExitStatus::Error => Self::from(2),
-            ExitStatus::Interrupted => Self::from(130),
-            ExitStatus::External(code) => Self::from(code),
-        }
+        Self::from(status.code())
     }
 }
 
@@ -130,6 +162,49 @@ impl From<ColorChoice> for anstream::ColorChoice {
     }
 }
 
+#[derive(Debug, Copy, Clone, Default, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable output.
+    #[default]
+    Text,
+
+    /// Machine-readable JSON output.
+    Json,
+}
+
+/// How the filenames passed to hooks are written.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum HookPaths {
+    /// Relative to the repository root.
+    #[default]
+    Root,
+    /// Relative to the current working directory, the hooks are run from there.
+    Cwd,
+    /// Absolute paths.
+    Absolute,
+}
+
+/// The format of a configuration file.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum ConfigFormat {
+    /// `.pre-commit-config.yaml`, compatible with `pre-commit`.
+    #[default]
+    Yaml,
+
+    /// `.pre-commit-config.toml`, only understood by prek.
+    Toml,
+}
+
+#[derive(Debug, Copy, Clone, Default, clap::ValueEnum)]
+pub(crate) enum ExportFormat {
+    /// A POSIX shell script.
+    #[default]
+    Shell,
+
+    /// Steps of a GitHub Actions job.
+    GithubActions,
+}
+
 const STYLES: Styles = Styles::styled()
     .header(AnsiColor::Green.on_default().effects(Effects::BOLD))
     .usage(AnsiColor::Green.on_default().effects(Effects::BOLD))
@@ -165,6 +240,7 @@ pub(crate) struct Cli {
 #[derive(Debug, Parser)]
 #[command(next_help_heading = "Global options", next_display_order = 1000)]
 #[command(disable_help_flag = true, disable_version_flag = true)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct GlobalArgs {
     /// Path to alternate config file.
     #[arg(global = true, short, long, value_parser)]
@@ -190,14 +266,40 @@ pub(crate) struct GlobalArgs {
     #[arg(global = true, long)]
     pub no_progress: bool,
 
-    /// Do not print any output.
+    /// Only print failures, warnings and errors.
     #[arg(global = true, long, short, conflicts_with = "verbose")]
     pub quiet: bool,
 
+    /// Do not print any output, not even failures.
+    #[arg(global = true, long, conflicts_with_all = ["verbose", "quiet"])]
+    pub silent: bool,
+
     /// Use verbose output.
     #[arg(global = true, short, long, action = ArgAction::Count)]
     pub(crate) verbose: u8,
 
+    /// Exit with a non-zero status if any warnings are emitted, even if all hooks passed.
+    ///
+    /// The warnings are listed again at the end of the run.
+    #[arg(global = true, long, env = EnvVars::PREK_STRICT, value_parser = clap::builder::BoolishValueParser::new())]
+    pub(crate) strict: bool,
+
+    /// Also write the debug logs, with timestamps, to this file, whatever the verbosity.
+    ///
+    /// This helps to capture diagnostics for bug reports without cluttering the terminal.
+    #[arg(global = true, long, env = EnvVars::PREK_LOG_FILE, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub(crate) log_file: Option<PathBuf>,
+
+    /// Keep the temporary directory of a failed archive extraction, and print its path.
+    ///
+    /// This helps to debug corrupt downloads of language toolchains.
+    #[arg(global = true, long, env = EnvVars::PREK_KEEP_TEMP, value_parser = clap::builder::BoolishValueParser::new())]
+    pub(crate) keep_temp: bool,
+
+    /// Rebuild the images of `docker` hooks, even if an image of the same source exists.
+    #[arg(global = true, long)]
+    pub(crate) refresh: bool,
+
     /// Display the prek version.
     #[arg(global = true, short = 'V', long, action = clap::ArgAction::Version)]
     version: Option<bool>,
@@ -227,13 +329,20 @@ pub(crate) enum Command {
     ValidateManifest(ValidateManifestArgs),
     /// Produce a sample `.pre-commit-config.yaml` file.
     SampleConfig(SampleConfigArgs),
+    /// Add a hook to the `.pre-commit-hooks.yaml` manifest of a hook repository.
+    NewHook(NewHookArgs),
+    /// Print a JSON Schema of the configuration file, for editor validation and completion.
+    ConfigSchema,
+    /// Export the hooks as a standalone script, for environments that can't run prek.
+    ExportHooks(ExportHooksArgs),
     /// Auto-update pre-commit config to the latest repos' versions.
     #[command(name = "auto-update", alias = "autoupdate")]
     AutoUpdate(AutoUpdateArgs),
-    /// Clean unused cached repos.
+    /// Remove the docker images no hook environment uses anymore, and hook output logs older
+    /// than a week.
     GC,
     /// Clean out pre-commit files.
-    Clean,
+    Clean(CleanArgs),
     /// Install hook script in a directory intended for use with `git config init.templateDir`.
     #[command(name = "init-template-dir", alias = "init-templatedir")]
     InitTemplateDir(InitTemplateDirArgs),
@@ -248,6 +357,9 @@ pub(crate) enum Command {
     #[command(name = "self")]
     Self_(SelfNamespace),
 
+    /// Inspect the environments hooks run in.
+    Env(EnvNamespace),
+
     /// Generate shell completion scripts.
     #[command(hide = true)]
     GenerateShellCompletion(GenerateShellCompletionArgs),
@@ -263,9 +375,18 @@ pub(crate) struct InstallArgs {
     #[arg(long)]
     pub(crate) install_hooks: bool,
 
+    /// Which hook type to install.
+    ///
+    /// Defaults to the `default_install_hook_types` of the config, or `pre-commit`.
     #[arg(short = 't', long = "hook-type", value_name = "HOOK_TYPE", value_enum)]
     pub(crate) hook_types: Vec<HookType>,
 
+    /// Install the hook types the hooks in the config file run at, from their `stages`.
+    ///
+    /// Only hook types in `default_install_hook_types` are installed, when it is set.
+    #[arg(long, conflicts_with = "hook_types")]
+    pub(crate) auto_hook_types: bool,
+
     /// Allow a missing `pre-commit` configuration file.
     #[arg(long)]
     pub(crate) allow_missing_config: bool,
@@ -275,6 +396,10 @@ pub(crate) struct InstallArgs {
 pub(crate) struct UninstallArgs {
     #[arg(short = 't', long = "hook-type", value_name = "HOOK_TYPE", value_enum)]
     pub(crate) hook_types: Vec<HookType>,
+
+    /// Uninstall the hook types the hooks in the config file run at, like `install --auto-hook-types`.
+    #[arg(long, conflicts_with = "hook_types")]
+    pub(crate) auto_hook_types: bool,
 }
 
 #[derive(Debug, Clone, Default, Args)]
@@ -306,11 +431,17 @@ pub(crate) struct RunExtraArgs {
 }
 
 #[derive(Debug, Clone, Default, Args)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct RunArgs {
     /// The hook ID to run.
+    ///
+    /// Use `--` to pass a hook ID that starts with `-`, e.g. `prek run -- -my-hook`.
     #[arg(value_name = "HOOK", value_hint = ValueHint::Other, add = ArgValueCompleter::new(hook_id_completer))]
     pub(crate) hook_id: Option<String>,
     /// Run on all files in the repo.
+    ///
+    /// Use with `--directory` to only run on the files under some directories, e.g.
+    /// `prek run --all-files --directory src/`.
     #[arg(short, long, conflicts_with_all = ["files", "from_ref", "to_ref"])]
     pub(crate) all_files: bool,
     /// Specific filenames to run hooks on.
@@ -319,8 +450,15 @@ pub(crate) struct RunArgs {
     /// Run hooks on all files in the specified directories.
     ///
     /// You can specify multiple directories. It can be used in conjunction with `--files`.
-    #[arg(short, long, value_name = "DIR", conflicts_with_all = ["all_files", "from_ref", "to_ref"], value_hint = ValueHint::DirPath)]
+    #[arg(short, long, value_name = "DIR", conflicts_with_all = ["from_ref", "to_ref"], value_hint = ValueHint::DirPath)]
     pub(crate) directory: Vec<String>,
+    /// Also run hooks on untracked files that are not ignored by git.
+    ///
+    /// Applies to the staged files, `--all-files` and `--directory`, but not to the files changed
+    /// between refs. Files ignored by `.gitignore` files, `.git/info/exclude` or the global
+    /// excludes file are skipped.
+    #[arg(long, conflicts_with_all = ["from_ref", "to_ref", "last_commit"])]
+    pub(crate) include_untracked: bool,
     /// The original ref in a `from_ref...to_ref` diff expression.
     /// Files changed in this diff will be run through the hooks.
     #[arg(short = 's', long, alias = "source", requires = "to_ref", value_hint = ValueHint::Other)]
@@ -333,11 +471,71 @@ pub(crate) struct RunArgs {
     #[arg(long, conflicts_with_all = ["all_files", "files", "directory", "from_ref", "to_ref"])]
     pub(crate) last_commit: bool,
     /// The stage during which the hook is fired.
-    #[arg(long, default_value_t = Stage::PreCommit, value_enum)]
-    pub(crate) hook_stage: Stage,
-    /// When hooks fail, run `git diff` directly afterward.
+    ///
+    /// Repeat it to run the hooks of several stages at once, e.g.
+    /// `--hook-stage pre-commit --hook-stage pre-push`. The files are selected by the first
+    /// stage that runs on files.
+    #[arg(long, default_values_t = [Stage::PreCommit], value_enum)]
+    pub(crate) hook_stage: Vec<Stage>,
+    /// When hooks fail, run `git diff` on the files they modified.
     #[arg(long)]
     pub(crate) show_diff_on_failure: bool,
+    /// Truncate the output of each hook to at most this many lines.
+    ///
+    /// The full output is saved to a file in the store, which `prek gc` removes after a week.
+    /// Overrides `default_max_output_lines` in the configuration file.
+    #[arg(long, value_name = "N")]
+    pub(crate) max_output_lines: Option<usize>,
+    /// Abort the run when a hook can't be run, instead of reporting it as errored and
+    /// continuing with the other hooks.
+    #[arg(long)]
+    pub(crate) abort_on_error: bool,
+    /// When a single hook is selected by its ID and fails, exit with the exit code of the hook
+    /// instead of 1.
+    #[arg(long, requires = "hook_id")]
+    pub(crate) propagate_exit_code: bool,
+    /// Run hooks of languages that are not supported yet as `system` hooks, using the
+    /// interpreter found on `PATH`, instead of skipping them.
+    ///
+    /// Additional dependencies of those hooks are not installed.
+    #[arg(long)]
+    pub(crate) allow_system_fallback: bool,
+    /// Install a fresh environment for hooks whose matching environment has dependencies no
+    /// hook asks for, instead of reusing it.
+    ///
+    /// This happens after removing some `additional_dependencies` of a hook, the old
+    /// environment with all of them still matches the hook.
+    #[arg(long)]
+    pub(crate) reinstall_stale_envs: bool,
+    /// Fail instead of installing the environments of hooks that have none installed.
+    ///
+    /// Every hook without an environment is listed with the environment it needs, and no hook
+    /// is run. Useful in CI images with the environments installed ahead of time.
+    #[arg(long, env = EnvVars::PREK_NO_INSTALL, value_parser = clap::builder::BoolishValueParser::new())]
+    pub(crate) no_install: bool,
+    /// Run hooks with `fail_fast: true` before the others, keeping the configured order within
+    /// each group, so their failures stop the run as early as possible.
+    #[arg(long)]
+    pub(crate) fail_fast_first: bool,
+    /// A file with the ids of hooks to skip, one per line, in addition to the `SKIP`
+    /// environment variable.
+    ///
+    /// Defaults to `.prek-skip` at the root of the repository, if it exists.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub(crate) skip_file: Option<PathBuf>,
+    /// Read the configuration from a file outside of the repository, or from stdin with `-`.
+    ///
+    /// Unlike `--config`, the configuration is not required to be staged, which suits
+    /// configurations generated on the fly, e.g. `generate-config | prek run --from-config -`.
+    #[arg(long, value_name = "CONFIG", value_hint = ValueHint::FilePath)]
+    pub(crate) from_config: Option<PathBuf>,
+    /// How to write the filenames passed to hooks.
+    ///
+    /// Hooks of the `docker` and `docker_image` languages always get paths relative to the
+    /// repository root, which is mounted in the container, and so do builtin hooks, which run
+    /// in process.
+    #[arg(long, value_enum, default_value_t = HookPaths::Root)]
+    pub(crate) paths: HookPaths,
 
     #[command(flatten)]
     pub(crate) extra: RunExtraArgs,
@@ -346,8 +544,16 @@ pub(crate) struct RunArgs {
 #[derive(Debug, Args)]
 pub(crate) struct ValidateConfigArgs {
     /// The path to the configuration file.
+    ///
+    /// Directories are searched recursively for `.pre-commit-config.yaml`,
+    /// `.pre-commit-config.yml` and `.pre-commit-config.toml` files. Use `-` to read a
+    /// configuration from stdin.
     #[arg(value_name = "CONFIG")]
     pub(crate) configs: Vec<PathBuf>,
+
+    /// The format of the validation report.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) output_format: OutputFormat,
 }
 
 #[derive(Debug, Args)]
@@ -360,6 +566,8 @@ pub(crate) struct ValidateManifestArgs {
 #[derive(Debug, Args)]
 pub(crate) struct SampleConfigArgs {
     /// Write the sample config to a file (`.pre-commit-config.yaml` by default).
+    ///
+    /// With `--format toml`, the default file is `.pre-commit-config.toml`.
     #[arg(
         short,
         long,
@@ -367,6 +575,56 @@ pub(crate) struct SampleConfigArgs {
         default_missing_value = CONFIG_FILE,
     )]
     pub(crate) file: Option<PathBuf>,
+
+    /// The format of the sample config.
+    #[arg(long, value_enum, default_value_t = ConfigFormat::Yaml)]
+    pub(crate) format: ConfigFormat,
+
+    /// Overwrite the file if it already exists.
+    #[arg(long, requires = "file", conflicts_with = "append")]
+    pub(crate) force: bool,
+
+    /// Append the sample repos to the file if it already exists.
+    #[arg(long, requires = "file")]
+    pub(crate) append: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct NewHookArgs {
+    /// The id of the hook.
+    #[arg(long)]
+    pub(crate) id: String,
+
+    /// The name of the hook, defaults to the id.
+    #[arg(long)]
+    pub(crate) name: Option<String>,
+
+    /// The language of the hook.
+    #[arg(long)]
+    pub(crate) language: String,
+
+    /// The command to run.
+    #[arg(long)]
+    pub(crate) entry: String,
+
+    /// The file types the hook runs on, e.g. `--types python,pyi`.
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) types: Vec<String>,
+
+    /// The manifest file to add the hook to. It is created if it doesn't exist.
+    #[arg(short, long, default_value = MANIFEST_FILE, value_hint = ValueHint::FilePath)]
+    pub(crate) file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ExportHooksArgs {
+    /// The format of the exported hooks.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Shell)]
+    pub(crate) format: ExportFormat,
+
+    /// Export the hooks of this stage.
+    #[arg(long, default_value_t = Stage::PreCommit, value_enum)]
+    pub(crate) hook_stage: Stage,
 }
 
 #[derive(Debug, Args)]
@@ -403,6 +661,36 @@ pub struct SelfNamespace {
 pub enum SelfCommand {
     /// Update prek.
     Update(SelfUpdateArgs),
+    /// Display prek's version and build information.
+    Version(SelfVersionArgs),
+    /// Uninstall prek.
+    Uninstall(SelfUninstallArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct EnvNamespace {
+    #[command(subcommand)]
+    pub command: EnvCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EnvCommand {
+    /// Show the environment a hook runs in, without installing it.
+    ///
+    /// Prints the environment path, toolchain, language version, dependencies and the command
+    /// run for the hook, or what would be installed if there is no matching environment yet.
+    Info(EnvInfoArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct EnvInfoArgs {
+    /// The hook ID.
+    #[arg(value_name = "HOOK", value_hint = ValueHint::Other, add = ArgValueCompleter::new(hook_id_completer))]
+    pub hook_id: String,
+
+    /// Output the environment information as JSON.
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Args)]
@@ -417,6 +705,30 @@ pub struct SelfUpdateArgs {
     pub token: Option<String>,
 }
 
+#[derive(Debug, Args)]
+pub struct SelfVersionArgs {
+    /// Output the version information as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SelfUninstallArgs {
+    /// Uninstall without asking for confirmation.
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Also remove the git hook scripts installed in these repositories.
+    ///
+    /// Hook scripts of the current repository are always removed.
+    #[arg(long, value_name = "REPO", num_args = 1.., value_hint = ValueHint::DirPath)]
+    pub all_repos: Vec<PathBuf>,
+
+    /// Also remove the prek cache directory.
+    #[arg(long)]
+    pub purge_cache: bool,
+}
+
 #[derive(Debug, Args)]
 pub(crate) struct GenerateShellCompletionArgs {
     /// The shell to generate the completion script for
@@ -424,6 +736,13 @@ pub(crate) struct GenerateShellCompletionArgs {
     pub shell: clap_complete::Shell,
 }
 
+#[derive(Debug, Args)]
+pub(crate) struct CleanArgs {
+    /// Also remove the docker images built for `docker` hooks.
+    #[arg(long)]
+    pub(crate) docker: bool,
+}
+
 #[derive(Debug, Args)]
 pub(crate) struct InitTemplateDirArgs {
     /// The directory in which to write the hook script.
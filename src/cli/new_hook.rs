@@ -0,0 +1,144 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::config::parse_manifest;
+use crate::fs::Simplified;
+use crate::printer::Printer;
+
+pub(crate) fn new_hook(
+    file: &Path,
+    id: String,
+    name: Option<String>,
+    language: String,
+    entry: String,
+    types: Vec<String>,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let existing = match fs_err::read_to_string(file) {
+        Ok(content) if !content.trim().is_empty() => Some(content),
+        Ok(_) => None,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(content) = &existing {
+        let manifest = parse_manifest(file, content)?;
+        if manifest.hooks.iter().any(|hook| hook.id == id) {
+            writeln!(
+                printer.stderr_important(),
+                "Hook `{}` already exists in `{}`",
+                id.cyan(),
+                file.user_display().cyan()
+            )?;
+            return Ok(ExitStatus::Failure);
+        }
+    }
+
+    let hook = render_hook(&id, name.as_deref(), &language, &entry, &types)?;
+    let manifest = match &existing {
+        Some(content) => append_hook(content, &hook),
+        None => hook,
+    };
+
+    // Validate the result the same way `validate-manifest` does before writing it.
+    parse_manifest(file, &manifest).with_context(|| format!("Invalid hook `{id}`"))?;
+
+    fs_err::create_dir_all(file.parent().unwrap_or(Path::new(".")))?;
+    fs_err::write(file, manifest)?;
+
+    writeln!(
+        printer.stdout(),
+        "{} hook `{}` to `{}`",
+        if existing.is_some() {
+            "Appended"
+        } else {
+            "Added"
+        },
+        id.cyan(),
+        file.user_display().cyan()
+    )?;
+    writeln!(
+        printer.stdout(),
+        "{}{} Try it by copying it to a `{}` repo of your config, then run `{}`",
+        "hint".yellow().bold(),
+        ":".bold(),
+        "repo: local".cyan(),
+        format!("prek run {id} --verbose --all-files").cyan()
+    )?;
+
+    Ok(ExitStatus::Success)
+}
+
+/// Render a single hook as a one-item YAML list.
+fn render_hook(
+    id: &str,
+    name: Option<&str>,
+    language: &str,
+    entry: &str,
+    types: &[String],
+) -> Result<String> {
+    let mut hook = serde_yaml::Mapping::new();
+    hook.insert("id".into(), id.into());
+    hook.insert("name".into(), name.unwrap_or(id).into());
+    hook.insert("entry".into(), entry.into());
+    hook.insert("language".into(), language.into());
+    if !types.is_empty() {
+        hook.insert(
+            "types".into(),
+            types
+                .iter()
+                .map(|tag| serde_yaml::Value::from(tag.as_str()))
+                .collect(),
+        );
+    }
+
+    Ok(serde_yaml::to_string(&[hook])?)
+}
+
+/// Append `hook` to the end of `manifest`, keeping the existing content untouched.
+fn append_hook(manifest: &str, hook: &str) -> String {
+    // Match the indentation of the existing list items.
+    let indent = manifest
+        .lines()
+        .find(|line| line.trim_start().starts_with('-'))
+        .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+
+    let mut result = manifest.to_string();
+    if !result.ends_with('\n') {
+        result.push('\n');
+    }
+    for line in hook.lines() {
+        result.push_str(indent);
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_hook_matches_indent() {
+        let manifest = "  - id: first\n    name: first\n    entry: first\n    language: system\n";
+        let hook =
+            render_hook("second", None, "python", "second", &["python".to_string()]).unwrap();
+        insta::assert_snapshot!(append_hook(manifest, &hook), @r"
+          - id: first
+            name: first
+            entry: first
+            language: system
+          - id: second
+            name: second
+            entry: second
+            language: python
+            types:
+            - python
+        ");
+    }
+}
@@ -1,8 +1,12 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use constants::env_vars::EnvVars;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use itertools::Itertools;
 use owo_colors::OwoColorize;
 use rustc_hash::FxHashMap;
 
@@ -59,6 +63,12 @@ impl ProgressReporter {
         id
     }
 
+    fn on_update(&self, id: usize, msg: impl Into<Cow<'static, str>>) {
+        if let Some(progress) = self.state.lock().unwrap().bars.get(&id) {
+            progress.set_message(msg);
+        }
+    }
+
     fn on_progress(&self, id: usize) {
         let progress = {
             let mut state = self.state.lock().unwrap();
@@ -102,6 +112,18 @@ impl workspace::HookInitReporter for HookInitReporter {
             .on_start(format!("{} {}", "Cloning".bold().cyan(), repo.dimmed()))
     }
 
+    fn on_clone_progress(&self, id: usize, repo: &str, progress: &str) {
+        self.reporter.on_update(
+            id,
+            format!(
+                "{} {} {}",
+                "Cloning".bold().cyan(),
+                repo.dimmed(),
+                progress.dimmed()
+            ),
+        );
+    }
+
     fn on_clone_complete(&self, id: usize) {
         self.reporter.on_progress(id);
     }
@@ -132,6 +154,55 @@ impl From<Printer> for HookInstallReporter {
     }
 }
 
+/// What installing the environments of the hooks did, printed once it's done.
+#[derive(Debug, Default)]
+pub(crate) struct InstallSummary {
+    /// The number of newly installed environments, by language and version.
+    pub(crate) installed: BTreeMap<String, usize>,
+    /// The number of environments installed by a previous run that were reused.
+    pub(crate) reused: usize,
+    pub(crate) duration: Duration,
+}
+
+fn environments(count: usize) -> &'static str {
+    if count == 1 {
+        "environment"
+    } else {
+        "environments"
+    }
+}
+
+impl Display for InstallSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let installed = self.installed.values().sum::<usize>();
+        // Durations differ between runs, print a placeholder for a deterministic output.
+        let duration = if EnvVars::is_set(EnvVars::PREK_DETERMINISTIC_OUTPUT) {
+            "[TIME]".to_string()
+        } else {
+            format!("{:.2}s", self.duration.as_secs_f64())
+        };
+        write!(
+            f,
+            "{} {installed} {} ({}) in {duration}",
+            "Installed".bold().cyan(),
+            environments(installed),
+            self.installed
+                .iter()
+                .map(|(label, count)| format!("{label}: {count}"))
+                .join(", "),
+        )?;
+        if self.reused > 0 {
+            write!(
+                f,
+                "; reused {} cached {}",
+                self.reused,
+                environments(self.reused)
+            )?;
+        }
+        Ok(())
+    }
+}
+
 impl HookInstallReporter {
     pub fn on_install_start(&self, hook: &Hook) -> usize {
         self.reporter.on_start(format!(
@@ -145,7 +216,28 @@ impl HookInstallReporter {
         self.reporter.on_progress(id);
     }
 
-    pub fn on_complete(&self) {
+    pub fn on_build_start(&self, hook: &Hook) -> usize {
+        self.reporter.on_start(format!(
+            "{} {}",
+            "Building image for".bold().cyan(),
+            hook.id.dimmed(),
+        ))
+    }
+
+    pub fn on_build_complete(&self, id: usize) {
+        self.reporter.on_progress(id);
+    }
+
+    /// Finish the progress bars, and print the summary if environments were installed, unless
+    /// progress output is suppressed.
+    pub(crate) fn on_complete(&self, summary: &InstallSummary) {
         self.reporter.on_complete();
+
+        if summary.installed.is_empty()
+            || !matches!(self.reporter.printer, Printer::Default | Printer::Verbose)
+        {
+            return;
+        }
+        let _ = writeln!(self.reporter.printer.stderr(), "{summary}");
     }
 }
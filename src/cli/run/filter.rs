@@ -1,8 +1,10 @@
-use std::path::Path;
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
 
 use anyhow::Result;
 use fancy_regex as regex;
 use fancy_regex::Regex;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use itertools::{Either, Itertools};
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use rustc_hash::FxHashSet;
@@ -12,9 +14,50 @@ use constants::env_vars::EnvVars;
 
 use crate::config::Stage;
 use crate::fs::normalize_path;
+use crate::git::GitBackend;
 use crate::hook::Hook;
 use crate::identify::tags_from_path;
-use crate::{git, warn_user};
+use crate::warn_user;
+
+/// A file of gitignore patterns at the repository root, excluding files from all hooks.
+pub(crate) const IGNORE_FILE: &str = ".prekignore";
+
+/// The patterns of the [`IGNORE_FILE`] in a directory.
+pub(crate) struct IgnoreFile {
+    matcher: Gitignore,
+    /// The lines that could not be parsed, with their 1-based line number.
+    pub(crate) errors: Vec<(usize, ignore::Error)>,
+}
+
+impl IgnoreFile {
+    /// Read the ignore file in `root`, `None` if there is none.
+    pub(crate) fn read(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(IGNORE_FILE);
+        let content = match fs_err::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut builder = GitignoreBuilder::new(root);
+        let mut errors = Vec::new();
+        for (idx, line) in content.lines().enumerate() {
+            if let Err(err) = builder.add_line(Some(path.clone()), line) {
+                errors.push((idx + 1, err));
+            }
+        }
+        let matcher = builder.build()?;
+
+        Ok(Some(Self { matcher, errors }))
+    }
+
+    /// Whether the file at `filename`, relative to the root, is ignored.
+    pub(crate) fn is_ignored(&self, filename: &str) -> bool {
+        self.matcher
+            .matched_path_or_any_parents(filename, false)
+            .is_ignore()
+    }
+}
 
 /// Filter filenames by include/exclude patterns.
 pub(crate) struct FilenameFilter {
@@ -48,7 +91,20 @@ impl FilenameFilter {
     }
 
     pub(crate) fn for_hook(hook: &Hook) -> Result<Self, Box<regex::Error>> {
-        Self::new(hook.files.as_deref(), hook.exclude.as_deref())
+        let files = ignore_case(hook.files.as_deref(), hook.files_case_insensitive);
+        let exclude = ignore_case(hook.exclude.as_deref(), hook.files_case_insensitive);
+        Self::new(files.as_deref(), exclude.as_deref())
+    }
+}
+
+/// Make a `files` or `exclude` pattern match ignoring case, for `files_case_insensitive`.
+///
+/// Patterns support the inline flags of `fancy_regex`, a leading `(?i)` applies to the whole
+/// pattern.
+pub(crate) fn ignore_case(pattern: Option<&str>, ignore: bool) -> Option<Cow<'_, str>> {
+    match pattern {
+        Some(pattern) if ignore => Some(Cow::Owned(format!("(?i){pattern}"))),
+        pattern => pattern.map(Cow::Borrowed),
     }
 }
 
@@ -176,7 +232,10 @@ pub(crate) struct CollectOptions {
     pub(crate) all_files: bool,
     pub(crate) files: Vec<String>,
     pub(crate) directories: Vec<String>,
+    pub(crate) include_untracked: bool,
     pub(crate) commit_msg_filename: Option<String>,
+    /// Exclude the files matched by the [`IGNORE_FILE`] of the current directory.
+    pub(crate) use_ignore_file: bool,
 }
 
 impl CollectOptions {
@@ -188,7 +247,10 @@ impl CollectOptions {
 
 /// Get all filenames to run hooks on.
 #[allow(clippy::too_many_arguments)]
-pub(crate) async fn collect_files(opts: CollectOptions) -> Result<Vec<String>> {
+pub(crate) async fn collect_files(
+    git: &impl GitBackend,
+    opts: CollectOptions,
+) -> Result<Vec<String>> {
     let CollectOptions {
         hook_stage,
         from_ref,
@@ -196,39 +258,56 @@ pub(crate) async fn collect_files(opts: CollectOptions) -> Result<Vec<String>> {
         all_files,
         files,
         directories,
+        include_untracked,
         commit_msg_filename,
+        use_ignore_file,
     } = opts;
 
     let mut filenames = collect_files_from_args(
+        git,
         hook_stage,
         from_ref,
         to_ref,
         all_files,
         files,
         directories,
+        include_untracked,
         commit_msg_filename,
     )
     .await?;
 
-    // Sort filenames if in tests to make the order consistent.
-    if EnvVars::is_set(EnvVars::PREK_INTERNAL__SORT_FILENAMES) {
+    // Sort filenames for a deterministic output, the order of git is not guaranteed.
+    if EnvVars::is_set(EnvVars::PREK_DETERMINISTIC_OUTPUT) {
         filenames.sort_unstable();
     }
 
     for filename in &mut filenames {
         normalize_path(filename);
     }
+
+    if use_ignore_file && let Some(ignore_file) = IgnoreFile::read(&std::env::current_dir()?)? {
+        filenames.retain(|filename| {
+            let ignored = ignore_file.is_ignored(filename);
+            if ignored {
+                debug!("`{filename}` is excluded by `{IGNORE_FILE}`");
+            }
+            !ignored
+        });
+    }
+
     Ok(filenames)
 }
 
 #[allow(clippy::too_many_arguments)]
 async fn collect_files_from_args(
+    git: &impl GitBackend,
     hook_stage: Stage,
     from_ref: Option<String>,
     to_ref: Option<String>,
     all_files: bool,
     mut files: Vec<String>,
     mut directories: Vec<String>,
+    include_untracked: bool,
     commit_msg_filename: Option<String>,
 ) -> Result<Vec<String>> {
     if !hook_stage.operate_on_files() {
@@ -241,7 +320,13 @@ async fn collect_files_from_args(
     }
 
     if let (Some(from_ref), Some(to_ref)) = (from_ref, to_ref) {
-        let files = git::get_changed_files(&from_ref, &to_ref).await?;
+        // In a freshly initialized repository there are no refs to compare.
+        if !git.has_head().await? {
+            debug!("Repository has no commits yet, no files changed");
+            return Ok(vec![]);
+        }
+
+        let files = git.changed_files(&from_ref, &to_ref).await?;
         debug!(
             "Files changed between {} and {}: {}",
             from_ref,
@@ -268,9 +353,25 @@ async fn collect_files_from_args(
             normalize_path(filename);
         }
         for dir in &mut directories {
+            *dir = resolve_dots(Path::new(dir)).to_string_lossy().to_string();
             normalize_path(dir);
         }
 
+        // Paths are relative to the repository root at this point, `git ls-files` can't list
+        // files outside of it.
+        let is_outside = |path: &str| {
+            let path = resolve_dots(Path::new(path));
+            path.is_absolute() || path.components().next() == Some(Component::ParentDir)
+        };
+        if let Some(dir) = directories.iter().find(|dir| is_outside(dir)) {
+            anyhow::bail!("Directory `{dir}` is outside the repository");
+        }
+
+        let (files, outside): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|filename| !is_outside(filename));
+        warn_outside(&outside, "file", "files");
+
         let (mut exists, non_exists): (FxHashSet<_>, Vec<_>) =
             files.into_iter().partition_map(|filename| {
                 if Path::new(&filename).exists() {
@@ -279,22 +380,27 @@ async fn collect_files_from_args(
                     Either::Right(filename)
                 }
             });
-        if !non_exists.is_empty() {
-            if non_exists.len() == 1 {
-                warn_user!(
-                    "This file does not exist, it will be ignored: `{}`",
-                    non_exists[0]
-                );
-            } else if non_exists.len() == 2 {
-                warn_user!(
-                    "These files do not exist, they will be ignored: `{}`",
-                    non_exists.join(", ")
-                );
-            }
-        }
-
+        warn_non_existent(&non_exists, "file", "files");
+
+        let (directories, non_exists): (Vec<_>, Vec<_>) = directories
+            .into_iter()
+            .partition(|dir| Path::new(dir).is_dir());
+        warn_non_existent(&non_exists, "directory", "directories");
+
+        // Match directories like git matches paths on case-insensitive file systems.
+        let ignore_case = git
+            .config_get("core.ignorecase")
+            .await?
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"));
         for dir in directories {
-            let dir_files = git::git_ls_files(Some(Path::new(&dir))).await?;
+            let pathspec = if ignore_case {
+                format!(":(icase){dir}")
+            } else {
+                dir
+            };
+            let dir_files = git
+                .ls_files(Some(Path::new(&pathspec)), include_untracked)
+                .await?;
             for file in dir_files {
                 exists.insert(file);
             }
@@ -305,19 +411,258 @@ async fn collect_files_from_args(
     }
 
     if all_files {
-        let files = git::git_ls_files(None).await?;
+        let files = git.ls_files(None, include_untracked).await?;
         debug!("All files in the repo: {}", files.len());
         return Ok(files);
     }
 
-    if git::is_in_merge_conflict().await? {
-        let files = git::get_conflicted_files().await?;
+    if git.is_in_merge_conflict().await? {
+        let files = git.conflicted_files().await?;
         debug!("Conflicted files: {}", files.len());
         return Ok(files);
     }
 
-    let files = git::get_staged_files().await?;
+    let mut files = git.staged_files().await?;
     debug!("Staged files: {}", files.len());
 
+    if include_untracked {
+        let untracked = git.untracked_files().await?;
+        debug!("Untracked files: {}", untracked.len());
+        files.extend(untracked);
+    }
+
     Ok(files)
 }
+
+/// Resolve `.` and `..` components lexically, this also drops trailing separators.
+///
+/// `..` components that would go above the start of a relative path are kept.
+fn resolve_dots(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    resolved.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    if resolved.as_os_str().is_empty() {
+        resolved.push(".");
+    }
+    resolved
+}
+
+fn warn_non_existent(paths: &[String], singular: &str, plural: &str) {
+    match paths {
+        [] => {}
+        [path] => {
+            warn_user!("This {singular} does not exist, it will be ignored: `{path}`");
+        }
+        paths => {
+            warn_user!(
+                "These {plural} do not exist, they will be ignored: `{}`",
+                paths.join(", ")
+            );
+        }
+    }
+}
+
+fn warn_outside(paths: &[String], singular: &str, plural: &str) {
+    match paths {
+        [] => {}
+        [path] => {
+            warn_user!("This {singular} is outside the repository, it will be ignored: `{path}`");
+        }
+        paths => {
+            warn_user!(
+                "These {plural} are outside the repository, they will be ignored: `{}`",
+                paths.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::FakeGit;
+
+    fn strings(files: &[&str]) -> Vec<String> {
+        files.iter().map(ToString::to_string).collect()
+    }
+
+    async fn collect(git: &FakeGit, opts: CollectOptions) -> Vec<String> {
+        let mut files = collect_files(git, opts).await.unwrap();
+        files.sort_unstable();
+        files
+    }
+
+    #[tokio::test]
+    async fn test_staged_files_by_default() {
+        let git = FakeGit {
+            head: true,
+            tracked: strings(&["a.txt", "b.txt"]),
+            staged: strings(&["b.txt"]),
+            ..FakeGit::default()
+        };
+        assert_eq!(collect(&git, CollectOptions::default()).await, ["b.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_conflicted_files_during_merge() {
+        let git = FakeGit {
+            head: true,
+            staged: strings(&["a.txt"]),
+            conflicted: Some(strings(&["b.txt", "c.txt"])),
+            ..FakeGit::default()
+        };
+        assert_eq!(
+            collect(&git, CollectOptions::default()).await,
+            ["b.txt", "c.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_all_files() {
+        let git = FakeGit {
+            head: true,
+            tracked: strings(&["a.txt", "b.txt"]),
+            untracked: strings(&["new.txt"]),
+            staged: strings(&["b.txt"]),
+            ..FakeGit::default()
+        };
+        assert_eq!(
+            collect(&git, CollectOptions::default().with_all_files(true)).await,
+            ["a.txt", "b.txt"]
+        );
+        assert_eq!(
+            collect(
+                &git,
+                CollectOptions {
+                    include_untracked: true,
+                    ..CollectOptions::default().with_all_files(true)
+                }
+            )
+            .await,
+            ["a.txt", "b.txt", "new.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_staged_files_with_untracked() {
+        let git = FakeGit {
+            head: true,
+            tracked: strings(&["a.txt", "b.txt"]),
+            untracked: strings(&["new.txt"]),
+            staged: strings(&["b.txt"]),
+            ..FakeGit::default()
+        };
+        assert_eq!(collect(&git, CollectOptions::default()).await, ["b.txt"]);
+        assert_eq!(
+            collect(
+                &git,
+                CollectOptions {
+                    include_untracked: true,
+                    ..CollectOptions::default()
+                }
+            )
+            .await,
+            ["b.txt", "new.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changed_files_between_refs() {
+        let mut git = FakeGit {
+            head: true,
+            tracked: strings(&["a.txt", "b.txt"]),
+            staged: strings(&["a.txt"]),
+            ..FakeGit::default()
+        };
+        git.changed.insert(
+            ("HEAD~1".to_string(), "HEAD".to_string()),
+            strings(&["b.txt"]),
+        );
+        let opts = || CollectOptions {
+            from_ref: Some("HEAD~1".to_string()),
+            to_ref: Some("HEAD".to_string()),
+            ..CollectOptions::default()
+        };
+        assert_eq!(collect(&git, opts()).await, ["b.txt"]);
+
+        // A repository without commits has no refs to compare.
+        git.head = false;
+        assert!(collect(&git, opts()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_directories() {
+        let git = FakeGit {
+            head: true,
+            tracked: strings(&["src/a.rs", "src/nested/b.rs", "src2/c.rs", "README.md"]),
+            untracked: strings(&["src/new.rs"]),
+            ..FakeGit::default()
+        };
+        assert_eq!(
+            collect(
+                &git,
+                CollectOptions {
+                    directories: strings(&["src/"]),
+                    ..CollectOptions::default()
+                }
+            )
+            .await,
+            ["src/a.rs", "src/nested/b.rs"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stages() {
+        let git = FakeGit {
+            head: true,
+            staged: strings(&["a.txt"]),
+            ..FakeGit::default()
+        };
+        assert_eq!(
+            collect(
+                &git,
+                CollectOptions {
+                    hook_stage: Stage::CommitMsg,
+                    commit_msg_filename: Some(".git/COMMIT_EDITMSG".to_string()),
+                    ..CollectOptions::default()
+                }
+            )
+            .await,
+            [".git/COMMIT_EDITMSG"]
+        );
+        assert!(
+            collect(
+                &git,
+                CollectOptions {
+                    hook_stage: Stage::PostCheckout,
+                    ..CollectOptions::default()
+                }
+            )
+            .await
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_resolve_dots() {
+        let resolve = |path: &str| resolve_dots(Path::new(path)).to_string_lossy().to_string();
+        assert_eq!(resolve("src/"), "src");
+        assert_eq!(resolve("./src/./nested/"), "src/nested");
+        assert_eq!(resolve("src/../docs"), "docs");
+        assert_eq!(resolve("src/.."), ".");
+        assert_eq!(resolve("dir1/../.."), "..");
+        assert_eq!(resolve("../other"), "../other");
+    }
+}
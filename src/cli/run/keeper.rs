@@ -11,7 +11,7 @@ use constants::env_vars::EnvVars;
 
 use crate::cleanup::add_cleanup;
 use crate::fs::Simplified;
-use crate::git::{self, GIT, git_cmd};
+use crate::git::{GIT, GitBackend, git_cmd};
 use crate::store::Store;
 
 static RESTORE_WORKTREE: Mutex<Option<WorkTreeKeeper>> = Mutex::new(None);
@@ -20,8 +20,8 @@ struct IntentToAddKeeper(Vec<PathBuf>);
 struct WorkingTreeKeeper(Option<PathBuf>);
 
 impl IntentToAddKeeper {
-    async fn clean() -> Result<Self> {
-        let files = git::intent_to_add_files().await?;
+    async fn clean(git: &impl GitBackend) -> Result<Self> {
+        let files = git.intent_to_add_files().await?;
         if files.is_empty() {
             return Ok(Self(vec![]));
         }
@@ -70,9 +70,11 @@ impl Drop for IntentToAddKeeper {
 }
 
 impl WorkingTreeKeeper {
-    async fn clean(patch_dir: &Path) -> Result<Self> {
-        let tree = git::write_tree().await?;
+    async fn clean(git: &impl GitBackend, patch_dir: &Path) -> Result<Self> {
+        let tree = git.write_tree().await?;
 
+        // Only tracked files are stashed, untracked files stay in place and are seen by hooks
+        // run with `--include-untracked`.
         let mut cmd = git_cmd("git diff-index")?;
         let output = cmd
             .arg("diff-index")
@@ -224,10 +226,13 @@ impl Drop for RestoreGuard {
 impl WorkTreeKeeper {
     /// Clear intent-to-add changes from the index and clear the non-staged changes from the working directory.
     /// Restore them when the instance is dropped.
-    pub async fn clean(store: &Store) -> Result<RestoreGuard> {
+    ///
+    /// The index is read through `git`, it and the working tree are still changed by running
+    /// the `git` executable.
+    pub async fn clean(git: &impl GitBackend, store: &Store) -> Result<RestoreGuard> {
         let cleaner = Self {
-            intent_to_add: Some(IntentToAddKeeper::clean().await?),
-            working_tree: Some(WorkingTreeKeeper::clean(&store.patches_dir()).await?),
+            intent_to_add: Some(IntentToAddKeeper::clean(git).await?),
+            working_tree: Some(WorkingTreeKeeper::clean(git, &store.patches_dir()).await?),
         };
 
         // Set to the global for the cleanup hook.
@@ -1,5 +1,7 @@
-pub(crate) use filter::{CollectOptions, FileFilter, collect_files};
-pub(crate) use run::{install_hooks, run};
+pub(crate) use filter::{
+    CollectOptions, FileFilter, IGNORE_FILE, IgnoreFile, collect_files, ignore_case,
+};
+pub(crate) use run::{RunOptions, install_hooks, run};
 
 mod filter;
 mod keeper;
@@ -1,18 +1,21 @@
+use std::cell::{Cell, RefCell};
 use std::cmp::{Reverse, max};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Write as _;
-use std::hash::Hash;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::Write;
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anstream::ColorChoice;
 use anyhow::{Context, Result};
 use futures::StreamExt;
 use futures::stream::FuturesUnordered;
 use indoc::indoc;
+use itertools::Itertools;
 use owo_colors::{OwoColorize, Style};
 use rand::SeedableRng;
 use rand::prelude::{SliceRandom, StdRng};
@@ -23,20 +26,25 @@ use unicode_width::UnicodeWidthStr;
 
 use constants::env_vars::EnvVars;
 
-use crate::cli::reporter::{HookInitReporter, HookInstallReporter};
+use crate::builtin;
+use crate::cli::reporter::{HookInitReporter, HookInstallReporter, InstallSummary};
 use crate::cli::run::keeper::WorkTreeKeeper;
 use crate::cli::run::{CollectOptions, FileFilter, collect_files};
-use crate::cli::{ExitStatus, RunExtraArgs};
-use crate::config::{Language, Stage};
-use crate::fs::Simplified;
-use crate::git;
-use crate::hook::{Hook, InstalledHook};
+use crate::cli::{ExitStatus, HookPaths, RunExtraArgs};
+use crate::config::{CheckoutType, Language, Stage, check_stages};
+use crate::fs::{self, Simplified};
+use crate::git::{self, CommitInfo, Git, GitBackend};
+use crate::hook::{Hook, InstallInfo, InstalledHook};
+use crate::languages::{Health, HealthChecks};
 use crate::printer::{Printer, Stdout};
+use crate::run::is_partitioned;
 use crate::store::Store;
 use crate::workspace::Project;
+use crate::{warn_user, warnings};
 
 enum HookToRun {
-    Skipped(Arc<Hook>),
+    /// Skipped, with the reason shown in the hook status.
+    Skipped(Arc<Hook>, &'static str),
     ToRun(Arc<InstalledHook>),
 }
 
@@ -45,28 +53,72 @@ impl Deref for HookToRun {
 
     fn deref(&self) -> &Self::Target {
         match self {
-            HookToRun::Skipped(hook) => hook,
+            HookToRun::Skipped(hook, _) => hook,
             HookToRun::ToRun(hook) => hook,
         }
     }
 }
 
-#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+/// The options of a run, from `prek run` or from a git hook.
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct RunOptions {
+    pub(crate) config: Option<PathBuf>,
+    pub(crate) from_config: Option<PathBuf>,
+    pub(crate) hook_id: Option<String>,
+    pub(crate) hook_stages: Vec<Stage>,
+    pub(crate) from_ref: Option<String>,
+    pub(crate) to_ref: Option<String>,
+    pub(crate) all_files: bool,
+    pub(crate) files: Vec<String>,
+    pub(crate) directories: Vec<String>,
+    pub(crate) include_untracked: bool,
+    pub(crate) last_commit: bool,
+    pub(crate) show_diff_on_failure: bool,
+    pub(crate) max_output_lines: Option<usize>,
+    pub(crate) abort_on_error: bool,
+    pub(crate) propagate_exit_code: bool,
+    pub(crate) allow_system_fallback: bool,
+    pub(crate) reinstall_stale_envs: bool,
+    pub(crate) no_install: bool,
+    pub(crate) fail_fast_first: bool,
+    pub(crate) skip_file: Option<PathBuf>,
+    pub(crate) paths: HookPaths,
+    pub(crate) extra_args: RunExtraArgs,
+    pub(crate) verbose: bool,
+}
+
 pub(crate) async fn run(
-    config: Option<PathBuf>,
-    hook_id: Option<String>,
-    hook_stage: Stage,
-    from_ref: Option<String>,
-    to_ref: Option<String>,
-    all_files: bool,
-    files: Vec<String>,
-    directories: Vec<String>,
-    last_commit: bool,
-    show_diff_on_failure: bool,
-    extra_args: RunExtraArgs,
-    verbose: bool,
+    options: RunOptions,
+    invocation_dir: &Path,
     printer: Printer,
 ) -> Result<ExitStatus> {
+    let RunOptions {
+        config,
+        from_config,
+        hook_id,
+        hook_stages,
+        from_ref,
+        to_ref,
+        all_files,
+        files,
+        directories,
+        include_untracked,
+        last_commit,
+        show_diff_on_failure,
+        max_output_lines,
+        abort_on_error,
+        propagate_exit_code,
+        allow_system_fallback,
+        reinstall_stale_envs,
+        no_install,
+        fail_fast_first,
+        skip_file,
+        paths,
+        extra_args,
+        verbose,
+    } = options;
+
     // Convert `--last-commit` to `HEAD~1..HEAD`
     let (from_ref, to_ref) = if last_commit {
         (Some("HEAD~1".to_string()), Some("HEAD".to_string()))
@@ -75,27 +127,71 @@ pub(crate) async fn run(
     };
 
     // Prevent recursive post-checkout hooks.
-    if hook_stage == Stage::PostCheckout
+    if hook_stages.contains(&Stage::PostCheckout)
         && EnvVars::is_set(EnvVars::PREK_INTERNAL__SKIP_POST_CHECKOUT)
     {
         return Ok(ExitStatus::Success);
     }
 
+    // Prevent hooks from running the hooks of this repository again, with prek or `git commit`.
+    // Running prek in another repository, e.g. a fixture of a test suite, is fine.
+    let running = running_hooks(&std::env::current_dir()?, &hook_stages);
+    if let Ok(parents) = EnvVars::var(EnvVars::PREK_INTERNAL__RUNNING_HOOKS)
+        && let Some(stage) = hook_stages
+            .iter()
+            .zip(&running)
+            .find_map(|(stage, entry)| parents.lines().any(|line| line == entry).then_some(stage))
+    {
+        anyhow::bail!(
+            "prek is already running the `{stage}` hooks of this repository, refusing to run them from within a hook (does a hook run `prek` or `git commit`?)"
+        );
+    }
+    let running = match EnvVars::var(EnvVars::PREK_INTERNAL__RUNNING_HOOKS) {
+        Ok(parents) => format!("{parents}\n{}", running.join("\n")),
+        Err(_) => running.join("\n"),
+    };
+
+    // Files are collected once for all the stages.
+    let is_commit_msg = |stage: &Stage| matches!(stage, Stage::CommitMsg | Stage::PrepareCommitMsg);
+    if let Some(msg_stage) = hook_stages.iter().find(|s| is_commit_msg(s))
+        && let Some(file_stage) = hook_stages
+            .iter()
+            .find(|s| s.operate_on_files() && !is_commit_msg(s))
+    {
+        anyhow::bail!(
+            "`--hook-stage {msg_stage}` can't be combined with `--hook-stage {file_stage}`, they run on different files"
+        );
+    }
+    let files_stage = hook_stages
+        .iter()
+        .copied()
+        .find(|s| s.operate_on_files())
+        .unwrap_or(hook_stages[0]);
+
     let should_stash = !all_files && files.is_empty() && directories.is_empty();
 
+    let git = Git;
+
     // Check if we have unresolved merge conflict files and fail fast.
-    if should_stash && git::has_unmerged_paths().await? {
+    if should_stash && git.has_unmerged_paths().await? {
         writeln!(
-            printer.stderr(),
+            printer.stderr_important(),
             "You have unmerged paths. Resolve them before running prek."
         )?;
         return Ok(ExitStatus::Failure);
     }
 
-    let config_file = Project::find_config_file(config)?;
-    if should_stash && git::file_not_staged(&config_file).await? {
+    // A config from `--from-config` is not a file of the repository, it can't be staged.
+    let (config_file, in_repo) = match from_config {
+        Some(_) if config.is_some() => {
+            anyhow::bail!("`--from-config` can't be combined with `--config`");
+        }
+        Some(from_config) => (from_config, false),
+        None => (Project::find_config_file(config)?, true),
+    };
+    if should_stash && in_repo && git::file_not_staged(&config_file).await? {
         writeln!(
-            printer.stderr(),
+            printer.stderr_important(),
             indoc!(
                 "Your pre-commit configuration file is not staged.
                 Run `git add {}` to fix this."
@@ -113,35 +209,58 @@ pub(crate) async fn run(
     let lock = store.lock_async().await?;
     let hooks = project.init_hooks(&store, Some(&reporter)).await?;
 
-    let hooks: Vec<_> = hooks
+    let stages = hooks
+        .iter()
+        .map(|h| (h.id.as_str(), Some(h.stages.as_slice())));
+    for mismatch in check_stages(project.config(), stages) {
+        debug!("{mismatch}");
+    }
+
+    let mut hooks: Vec<_> = hooks
         .into_iter()
         .filter(|h| {
             hook_id
                 .as_deref()
                 .is_none_or(|hook_id| h.id == hook_id || h.alias == hook_id)
         })
-        .filter(|h| h.stages.contains(&hook_stage))
+        .filter(|h| h.stages.iter().any(|stage| hook_stages.contains(stage)))
         .collect();
 
     if hooks.is_empty() && hook_id.is_some() {
         writeln!(
-            printer.stderr(),
+            printer.stderr_important(),
             "No hook found for id `{}` and stage `{}`",
             hook_id.unwrap().cyan(),
-            hook_stage.cyan()
+            hook_stages.iter().join(", ").cyan()
         )?;
         return Ok(ExitStatus::Failure);
     }
 
-    let skips = get_skips();
+    let skips = get_skips(skip_file.as_deref())?;
+    // Git runs `post-checkout` for file checkouts too, most hooks only want branch checkouts.
+    let checkout_type = extra_args
+        .checkout_type
+        .as_deref()
+        .filter(|_| hook_stages.contains(&Stage::PostCheckout))
+        .and_then(CheckoutType::from_flag);
     let skips = hooks
         .iter()
-        .filter(|h| skips.contains(&h.id) || skips.contains(&h.alias))
-        .map(|h| h.idx)
-        .collect::<HashSet<_>>();
+        .filter_map(|h| {
+            if skips.contains(&h.id) || skips.contains(&h.alias) {
+                Some((h.idx, StatusPrinter::SKIPPED_BY_ENV))
+            } else if checkout_type == Some(CheckoutType::File)
+                && !h.post_checkout_on.contains(&CheckoutType::File)
+            {
+                Some((h.idx, StatusPrinter::FILE_CHECKOUT))
+            } else {
+                None
+            }
+        })
+        .collect::<HashMap<_, _>>();
+    order_by_priority(&mut hooks);
     let to_run = hooks
         .iter()
-        .filter(|h| !skips.contains(&h.idx))
+        .filter(|h| !skips.contains_key(&h.idx))
         .cloned()
         .collect::<Vec<_>>();
 
@@ -149,17 +268,46 @@ pub(crate) async fn run(
         "Hooks going to run: {:?}",
         to_run.iter().map(|h| &h.id).collect::<Vec<_>>()
     );
+    if no_install {
+        let missing = missing_environments(&to_run, &store);
+        if !missing.is_empty() {
+            writeln!(
+                printer.stderr_important(),
+                "No environment is installed for these hooks, and `--no-install` is set:"
+            )?;
+            for hook in missing {
+                let mut dependencies = hook.dependencies().iter().collect::<Vec<_>>();
+                dependencies.sort_unstable();
+                let language_version = if hook.language_version.is_empty() {
+                    "default"
+                } else {
+                    &hook.language_version
+                };
+                writeln!(
+                    printer.stderr_important(),
+                    "- {}: language `{}`, language_version `{}`, dependencies [{}]",
+                    hook.id.cyan(),
+                    hook.language,
+                    language_version,
+                    dependencies.iter().join(", ")
+                )?;
+            }
+            return Ok(ExitStatus::Error);
+        }
+    }
+
     let reporter = HookInstallReporter::from(printer);
-    let mut installed_hooks = install_hooks(to_run, &store, &reporter).await?;
+    let mut installed_hooks =
+        install_hooks(to_run, &store, &reporter, reinstall_stale_envs).await?;
 
     // Release the store lock.
     drop(lock);
 
-    let hooks = hooks
+    let mut hooks = hooks
         .into_iter()
         .map(|h| {
-            if skips.contains(&h.idx) {
-                HookToRun::Skipped(Arc::new(h))
+            if let Some(&reason) = skips.get(&h.idx) {
+                HookToRun::Skipped(Arc::new(h), reason)
             } else {
                 // Find and remove the matching resolved hook
                 let idx = installed_hooks
@@ -171,25 +319,65 @@ pub(crate) async fn run(
         })
         .collect::<Vec<_>>();
 
+    // The sort is stable, hooks keep their order within each group.
+    if fail_fast_first {
+        hooks.sort_by_key(|hook| !hook.fail_fast);
+    }
+
     // Clear any unstaged changes from the git working directory.
     let mut _guard = None;
     if should_stash {
-        _guard = Some(WorkTreeKeeper::clean(&store).await?);
+        // Restoring the stash could conflict with the state the operation keeps, so hooks
+        // see the unstaged changes instead. This is expected for every commit of a rebase,
+        // not worth a warning failing `--strict` runs.
+        if let Some(operation) = git.operation_in_progress().await? {
+            debug!("A {operation} is in progress, unstaged changes are not stashed");
+        } else {
+            _guard = Some(WorkTreeKeeper::clean(&git, &store).await?);
+        }
     }
 
-    set_env_vars(from_ref.as_ref(), to_ref.as_ref(), &extra_args);
+    let commit_info = if hook_stages.iter().any(is_commit_msg) {
+        Some(
+            git::commit_info(
+                extra_args.prepare_commit_message_source.as_deref(),
+                extra_args.commit_object_name.as_deref(),
+                extra_args.commit_msg_filename.as_deref().map(Path::new),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
 
-    let filenames = collect_files(CollectOptions {
-        hook_stage,
-        from_ref,
-        to_ref,
+    set_env_vars(
+        &running,
+        from_ref.as_ref(),
+        to_ref.as_ref(),
         all_files,
-        files,
-        directories,
-        commit_msg_filename: extra_args.commit_msg_filename.clone(),
-    })
+        &extra_args,
+        commit_info.as_ref(),
+    );
+
+    let filenames = collect_files(
+        &git,
+        CollectOptions {
+            hook_stage: files_stage,
+            from_ref,
+            to_ref,
+            all_files,
+            files,
+            directories,
+            include_untracked,
+            commit_msg_filename: extra_args.commit_msg_filename.clone(),
+            use_ignore_file: project.config().use_ignore_file.unwrap_or(true),
+        },
+    )
     .await?;
 
+    // Kept until the hooks are done.
+    let _staged_files = set_staged_files(&git.staged_files().await?)?;
+
     let filter = FileFilter::new(
         &filenames,
         project.config().files.as_deref(),
@@ -197,22 +385,86 @@ pub(crate) async fn run(
     )?;
     trace!("Files after filtered: {}", filter.len());
 
+    // With `--propagate-exit-code`, a single hook selected by its id exits with the code of the
+    // hook, for scripts running it.
+    let propagate_exit_code = propagate_exit_code && hook_id.is_some() && hooks.len() == 1;
+
     run_hooks(
+        &git,
         &hooks,
         &filter,
         &store,
         project.config().fail_fast.unwrap_or(false),
         show_diff_on_failure,
+        include_untracked,
+        max_output_lines.or(project.config().default_max_output_lines),
+        extra_args.commit_msg_filename.as_deref().map(Path::new),
+        abort_on_error,
+        allow_system_fallback,
+        paths,
+        invocation_dir,
         verbose,
+        propagate_exit_code,
         printer,
     )
     .await
 }
 
+/// Write the staged files to a temporary file, and point `PREK_STAGED_FILES` to it.
+///
+/// Hooks can read the full list of staged files from there, whatever the files of the run, e.g.
+/// with `--all-files`, and their own filters. The list may
+/// not fit in the environment of the hooks, so it is not passed directly. The files are
+/// relative to the repository root, each followed by a NUL byte since filenames can contain
+/// newlines, like `git ls-files -z`.
+///
+/// The file is in the temporary directory of the host, which is not mounted in the container of
+/// docker hooks.
+///
+/// Like [`set_env_vars`], this must be called while no other task is running.
+fn set_staged_files(filenames: &[String]) -> Result<tempfile::NamedTempFile> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    for filename in filenames {
+        write!(file, "{filename}\0")?;
+    }
+    file.flush()?;
+
+    unsafe {
+        std::env::set_var(EnvVars::PREK_STAGED_FILES, file.path());
+    }
+
+    Ok(file)
+}
+
+/// The entries of `PREK_INTERNAL__RUNNING_HOOKS` for a run of the `stages` hooks of the
+/// repository at `root`, one `<stage> <root>` line per stage.
+fn running_hooks(root: &Path, stages: &[Stage]) -> Vec<String> {
+    stages
+        .iter()
+        .map(|stage| format!("{stage} {}", root.display()))
+        .collect()
+}
+
 // `pre-commit` sets these environment variables for other git hooks.
-fn set_env_vars(from_ref: Option<&String>, to_ref: Option<&String>, args: &RunExtraArgs) {
+//
+// The environment is process-global: this must be called while no other task is running,
+// which is the case between installing the hooks and running them.
+fn set_env_vars(
+    running_hooks: &str,
+    from_ref: Option<&String>,
+    to_ref: Option<&String>,
+    all_files: bool,
+    args: &RunExtraArgs,
+    commit_info: Option<&CommitInfo>,
+) {
     unsafe {
         std::env::set_var("PRE_COMMIT", "1");
+        // Detected by nested `prek run` invocations, see `run`.
+        std::env::set_var(EnvVars::PREK_INTERNAL__RUNNING_HOOKS, running_hooks);
+
+        if all_files {
+            std::env::set_var("PRE_COMMIT_ALL_FILES", "1");
+        }
 
         if let Some(ref source) = args.prepare_commit_message_source {
             std::env::set_var("PRE_COMMIT_COMMIT_MSG_SOURCE", source.clone());
@@ -250,16 +502,52 @@ fn set_env_vars(from_ref: Option<&String>, to_ref: Option<&String>, args: &RunEx
             std::env::set_var("PRE_COMMIT_CHECKOUT_TYPE", checkout.clone());
         }
         if args.is_squash_merge {
-            std::env::set_var("PRE_COMMIT_SQUASH_MERGE", "1");
+            std::env::set_var("PRE_COMMIT_IS_SQUASH_MERGE", "1");
         }
         if let Some(ref command) = args.rewrite_command {
             std::env::set_var("PRE_COMMIT_REWRITE_COMMAND", command.clone());
         }
+
+        // `pre-commit` has no equivalent of these.
+        if let Some(info) = commit_info {
+            if let Some(ref name) = info.author_name {
+                std::env::set_var(EnvVars::PREK_COMMIT_AUTHOR_NAME, name);
+            }
+            if let Some(ref email) = info.author_email {
+                std::env::set_var(EnvVars::PREK_COMMIT_AUTHOR_EMAIL, email);
+            }
+            if info.is_merge {
+                std::env::set_var(EnvVars::PREK_COMMIT_IS_MERGE, "1");
+            }
+            if info.is_amend {
+                std::env::set_var(EnvVars::PREK_COMMIT_IS_AMEND, "1");
+            }
+            if let Some(ref file) = info.message_file {
+                std::env::set_var(EnvVars::PREK_COMMIT_MSG_FILE, file);
+            }
+        }
+
+        // Hooks write to a pipe, so they disable colors unless told otherwise.
+        // With `--color=auto`, colors from hooks are stripped along with ours when
+        // stdout is not a terminal.
+        if matches!(
+            ColorChoice::global(),
+            ColorChoice::Always | ColorChoice::AlwaysAnsi
+        ) {
+            std::env::set_var(EnvVars::FORCE_COLOR, "1");
+        }
     }
 }
 
-fn get_skips() -> Vec<String> {
-    match EnvVars::var_os(EnvVars::SKIP) {
+/// The file listing hook ids to skip, read from the repository root by default.
+const SKIP_FILE: &str = ".prek-skip";
+
+/// The hook ids to skip, from the `SKIP` environment variable and the skip file.
+///
+/// The skip file has one hook id per line, blank lines and lines starting with `#` are
+/// ignored. `skip_file` must exist, the default one is only read if it exists.
+fn get_skips(skip_file: Option<&Path>) -> Result<Vec<String>> {
+    let mut skips = match EnvVars::var_os(EnvVars::SKIP) {
         Some(s) if !s.is_empty() => s
             .to_string_lossy()
             .split(',')
@@ -267,14 +555,54 @@ fn get_skips() -> Vec<String> {
             .filter(|s| !s.is_empty())
             .collect(),
         _ => vec![],
+    };
+
+    let content = match skip_file {
+        Some(path) => Some(fs_err::read_to_string(path)?),
+        None => match fs_err::read_to_string(SKIP_FILE) {
+            Ok(content) => Some(content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        },
+    };
+    if let Some(content) = content {
+        skips.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(ToString::to_string),
+        );
     }
+
+    Ok(skips)
+}
+
+/// Dependencies of `info` that none of the `wanted` dependencies asks for.
+///
+/// An environment with such dependencies still matches hooks with fewer dependencies, so a
+/// hook whose `additional_dependencies` were trimmed keeps running with the old ones.
+fn stale_dependencies<'a>(info: &'a InstallInfo, wanted: &FxHashSet<String>) -> Vec<&'a String> {
+    let mut stale = info
+        .dependencies
+        .iter()
+        .filter(|dep| !wanted.contains(*dep))
+        .collect::<Vec<_>>();
+    stale.sort_unstable();
+    stale
 }
 
+/// Install the environments of `hooks`, reusing the matching installed ones.
+///
+/// If `reinstall_stale_envs` is set, environments with dependencies no hook asks for are not
+/// reused, a fresh environment with the exact dependencies is installed instead.
 pub async fn install_hooks(
     hooks: Vec<Hook>,
     store: &Store,
     reporter: &HookInstallReporter,
+    reinstall_stale_envs: bool,
 ) -> Result<Vec<InstalledHook>> {
+    let start = Instant::now();
     let num_hooks = hooks.len();
     let mut new_installed = Vec::with_capacity(hooks.len());
     let mut group_futures = FuturesUnordered::new();
@@ -291,6 +619,14 @@ pub async fn install_hooks(
 
     // Group hooks by language to enable parallel installation across different languages.
     for (_, hooks) in hooks_by_language {
+        // The dependencies all hooks of the language ask for, the environments may have more.
+        let wanted = Rc::new(
+            hooks
+                .iter()
+                .flat_map(|hook| hook.dependencies().iter().cloned())
+                .collect::<FxHashSet<_>>(),
+        );
+
         // Partition hooks into non-overlapping sets based on their dependencies.
         // This allows us to install hooks that have no overlapping dependencies in parallel,
         // while ensuring that hooks with overlapping dependencies are installed sequentially.
@@ -298,6 +634,7 @@ pub async fn install_hooks(
 
         for mut hooks in partitions {
             let installed_hooks = installed_hooks.clone();
+            let wanted = wanted.clone();
 
             // Install hooks from the one with most dependencies to the least dependencies,
             // the later hooks can reuse the environment of the earlier ones.
@@ -308,7 +645,8 @@ pub async fn install_hooks(
                 let mut newly_installed = Vec::new();
 
                 for hook in hooks {
-                    // Find a matching installed hook environment.
+                    // Find a matching installed hook environment, preferring the ones without
+                    // stale dependencies, then the smallest.
                     if let Some(info) = installed_hooks
                         .iter()
                         .chain(newly_installed.iter().filter_map(|h| {
@@ -318,18 +656,40 @@ pub async fn install_hooks(
                                 None
                             }
                         }))
-                        .find(|info| info.matches(&hook))
+                        .filter(|info| info.matches(&hook))
+                        .min_by_key(|info| {
+                            (
+                                stale_dependencies(info, &wanted).len(),
+                                info.dependencies.len(),
+                            )
+                        })
                     {
+                        let stale = stale_dependencies(info, &wanted);
+                        if stale.is_empty() || !reinstall_stale_envs {
+                            debug!(
+                                "Found installed environment for hook `{}` at `{}`",
+                                &hook,
+                                info.env_path.display()
+                            );
+                            // Extra packages are harmless most of the time, and warnings
+                            // would fail every run with `--strict`.
+                            if !stale.is_empty() {
+                                debug!(
+                                    "Hook `{hook}` reuses the environment at `{}`, which also has `{}` installed, use `--reinstall-stale-envs` to install only the dependencies of the hook",
+                                    info.env_path.display(),
+                                    stale.iter().join("`, `"),
+                                );
+                            }
+                            hook_envs.push(InstalledHook::Installed {
+                                hook: Arc::new(hook),
+                                info: Arc::new(info.clone()),
+                            });
+                            continue;
+                        }
                         debug!(
-                            "Found installed environment for hook `{}` at `{}`",
-                            &hook,
+                            "Environment at `{}` has stale dependencies, not reusing it for hook `{hook}`",
                             info.env_path.display()
                         );
-                        hook_envs.push(InstalledHook::Installed {
-                            hook: Arc::new(hook),
-                            info: Arc::new(info.clone()),
-                        });
-                        continue;
                     }
 
                     let hook = Arc::new(hook);
@@ -339,7 +699,7 @@ pub async fn install_hooks(
 
                     let installed_hook = hook
                         .language
-                        .install(hook.clone(), store)
+                        .install(hook.clone(), store, reporter)
                         .await
                         .context(format!("Failed to install hook `{hook}`"))?;
 
@@ -372,7 +732,11 @@ pub async fn install_hooks(
     while let Some(result) = group_futures.next().await {
         new_installed.extend(result?);
     }
-    reporter.on_complete();
+    reporter.on_complete(&install_summary(
+        &installed_hooks,
+        &new_installed,
+        start.elapsed(),
+    ));
 
     debug_assert_eq!(
         num_hooks,
@@ -383,6 +747,58 @@ pub async fn install_hooks(
     Ok(new_installed)
 }
 
+/// The hooks that install an environment and have no matching one in the store.
+fn missing_environments<'a>(hooks: &'a [Hook], store: &Store) -> Vec<&'a Hook> {
+    let installed = store.installed_hooks().collect::<Vec<_>>();
+    hooks
+        .iter()
+        .filter(|hook| {
+            Language::supported(hook.language)
+                && hook.language.supports_install_env()
+                && !installed.iter().any(|info| info.matches(hook))
+        })
+        .collect()
+}
+
+/// Count the environments the hooks use, by whether they were installed by this run.
+fn install_summary(
+    previously_installed: &[InstallInfo],
+    hooks: &[InstalledHook],
+    duration: Duration,
+) -> InstallSummary {
+    let previous = previously_installed
+        .iter()
+        .map(|info| &info.env_path)
+        .collect::<FxHashSet<_>>();
+
+    let mut summary = InstallSummary {
+        duration,
+        ..InstallSummary::default()
+    };
+    let envs = hooks
+        .iter()
+        .filter_map(|hook| match hook {
+            InstalledHook::Installed { info, .. } => Some(info),
+            InstalledHook::NoNeedInstall(_) => None,
+        })
+        .unique_by(|info| &info.env_path);
+    for info in envs {
+        if previous.contains(&info.env_path) {
+            summary.reused += 1;
+            continue;
+        }
+        let version = &info.language_version;
+        let label = if *version == semver::Version::new(0, 0, 0) {
+            info.language.to_string()
+        } else {
+            format!("{} {}.{}", info.language, version.major, version.minor)
+        };
+        *summary.installed.entry(label).or_default() += 1;
+    }
+
+    summary
+}
+
 fn sets_disjoint<T>(set1: &FxHashSet<T>, set2: &FxHashSet<T>) -> bool
 where
     T: Eq + Hash,
@@ -436,21 +852,43 @@ fn partition_overlapping_sets(sets: &[Hook]) -> Vec<Vec<Hook>> {
     groups
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookStatus {
+    /// The hook passed, or was skipped.
+    Passed,
+    /// The hook exited with a non-zero code, or modified files.
+    Failed { exit_code: i32 },
+    /// The hook could not be run.
+    Errored,
+}
+
 struct StatusPrinter {
     printer: Printer,
     columns: usize,
+    /// In quiet mode, the status line of the running hook is held back until it fails.
+    running: RefCell<Option<String>>,
+    /// Whether the running hook failed, its details are shown in quiet mode.
+    failing: Cell<bool>,
 }
 
 impl StatusPrinter {
     const PASSED: &'static str = "Passed";
     const FAILED: &'static str = "Failed";
+    const ERRORED: &'static str = "Errored";
     const SKIPPED: &'static str = "Skipped";
     const NO_FILES: &'static str = "(no files to check)";
+    const SKIPPED_BY_ENV: &'static str = "(skipped via SKIP)";
+    const FILE_CHECKOUT: &'static str = "(file checkout)";
     const UNIMPLEMENTED: &'static str = "(unimplemented yet)";
 
     fn for_hooks(hooks: &[HookToRun], printer: Printer) -> Self {
         let columns = Self::calculate_columns(hooks);
-        Self { printer, columns }
+        Self {
+            printer,
+            columns,
+            running: RefCell::new(None),
+            failing: Cell::new(false),
+        }
     }
 
     fn calculate_columns(hooks: &[HookToRun]) -> usize {
@@ -465,12 +903,19 @@ impl StatusPrinter {
         )
     }
 
+    fn is_quiet(&self) -> bool {
+        self.printer == Printer::Quiet
+    }
+
     fn write_skipped(
         &self,
         hook_name: &str,
         reason: &str,
         style: Style,
     ) -> Result<(), std::fmt::Error> {
+        if self.is_quiet() {
+            return Ok(());
+        }
         let dots = self.columns - hook_name.width_cjk() - Self::SKIPPED.len() - reason.len() - 1;
         let line = format!(
             "{hook_name}{}{}{}",
@@ -482,51 +927,131 @@ impl StatusPrinter {
     }
 
     fn write_running(&self, hook_name: &str) -> Result<(), std::fmt::Error> {
-        write!(
-            self.printer.stdout(),
+        self.failing.set(false);
+        let line = format!(
             "{}{}",
             hook_name,
             ".".repeat(self.columns - hook_name.width_cjk() - Self::PASSED.len() - 1)
-        )
+        );
+        if self.is_quiet() {
+            *self.running.borrow_mut() = Some(line);
+            Ok(())
+        } else {
+            write!(self.printer.stdout(), "{line}")
+        }
     }
 
     fn write_passed(&self) -> Result<(), std::fmt::Error> {
+        if self.running.take().is_some() {
+            return Ok(());
+        }
         writeln!(self.printer.stdout(), "{}", Self::PASSED.on_green())
     }
 
     fn write_failed(&self) -> Result<(), std::fmt::Error> {
-        writeln!(self.printer.stdout(), "{}", Self::FAILED.on_red())
+        self.write_failure(Self::FAILED)
+    }
+
+    fn write_errored(&self) -> Result<(), std::fmt::Error> {
+        self.write_failure(Self::ERRORED)
+    }
+
+    fn write_failure(&self, status: &str) -> Result<(), std::fmt::Error> {
+        self.failing.set(true);
+        let running = self.running.take().unwrap_or_default();
+        writeln!(self.failure_stdout(), "{running}{}", status.on_red())
     }
 
+    /// Where the details of the running hook are written, only failures are shown in quiet
+    /// mode.
     fn stdout(&self) -> Stdout {
-        self.printer.stdout()
+        if self.failing.get() {
+            self.failure_stdout()
+        } else {
+            self.printer.stdout()
+        }
+    }
+
+    /// Where failures are written, they are shown in quiet mode too.
+    fn failure_stdout(&self) -> Stdout {
+        if self.is_quiet() {
+            Stdout::Enabled
+        } else {
+            self.printer.stdout()
+        }
     }
 }
 
+/// Order hooks by their `priority`, highest first, then by their position in the config.
+fn order_by_priority(hooks: &mut [Hook]) {
+    hooks.sort_by_key(|hook| (Reverse(hook.priority), hook.idx));
+}
+
 /// Run all hooks.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 async fn run_hooks(
+    git: &impl GitBackend,
     hooks: &[HookToRun],
     filter: &FileFilter<'_>,
     store: &Store,
     fail_fast: bool,
     show_diff_on_failure: bool,
+    include_untracked: bool,
+    max_output_lines: Option<usize>,
+    commit_msg_filename: Option<&Path>,
+    abort_on_error: bool,
+    allow_system_fallback: bool,
+    paths: HookPaths,
+    invocation_dir: &Path,
     verbose: bool,
+    propagate_exit_code: bool,
     printer: Printer,
 ) -> Result<ExitStatus> {
     let printer = StatusPrinter::for_hooks(hooks, printer);
+    let health = HealthChecks::new(hooks.iter().filter_map(|hook| match hook {
+        HookToRun::Skipped(..) => None,
+        HookToRun::ToRun(hook) => Some(hook.language),
+    }));
     let mut success = true;
+    // The exit code of the hook, propagated when it's the only hook run.
+    let mut exit_code = None;
 
-    let mut diff = git::get_diff().await?;
+    let mut diff = WorkTree::snapshot(git, include_untracked).await?;
+    // The files changed by hooks, to show only their changes with `--show-diff-on-failure`.
+    let mut modified = BTreeSet::new();
     // Hooks might modify the files, so they must be run sequentially.
     for hook in hooks {
-        let (hook_success, new_diff) =
-            run_hook(hook, filter, store, diff, verbose, &printer).await?;
+        let (status, new_diff) = run_hook(
+            git,
+            hook,
+            filter,
+            store,
+            &diff,
+            max_output_lines,
+            commit_msg_filename,
+            abort_on_error,
+            allow_system_fallback,
+            paths,
+            invocation_dir,
+            verbose,
+            &health,
+            &printer,
+        )
+        .await?;
 
-        success &= hook_success;
-        diff = new_diff;
+        success &= status == HookStatus::Passed;
+        if let HookStatus::Failed { exit_code: code } = status {
+            exit_code = u8::try_from(code).ok().filter(|&code| code != 0);
+        }
+        if let Some(new_diff) = new_diff {
+            if show_diff_on_failure && new_diff != diff {
+                modified.extend(diff.changed_files(&new_diff));
+            }
+            diff = new_diff;
+        }
         let fail_fast = fail_fast
             || match hook {
-                HookToRun::Skipped(_) => false,
+                HookToRun::Skipped(..) => false,
                 HookToRun::ToRun(hook) => hook.fail_fast,
             };
         if !success && fail_fast {
@@ -534,8 +1059,12 @@ async fn run_hooks(
         }
     }
 
-    if !success && show_diff_on_failure {
-        writeln!(printer.stdout(), "All changes made by hooks:")?;
+    if !success
+        && show_diff_on_failure
+        && !modified.is_empty()
+        && printer.failure_stdout() == Stdout::Enabled
+    {
+        writeln!(printer.failure_stdout(), "All changes made by hooks:")?;
         let color = match ColorChoice::global() {
             ColorChoice::Auto => "--color=auto",
             ColorChoice::Always | ColorChoice::AlwaysAnsi => "--color=always",
@@ -543,9 +1072,12 @@ async fn run_hooks(
         };
         git::git_cmd("git diff")?
             .arg("--no-pager")
+            .arg("--literal-pathspecs")
             .arg("diff")
             .arg("--no-ext-diff")
             .arg(color)
+            .arg("--")
+            .args(&modified)
             .check(true)
             .spawn()?
             .wait()
@@ -554,31 +1086,176 @@ async fn run_hooks(
 
     if success {
         Ok(ExitStatus::Success)
+    } else if let (true, Some(code)) = (propagate_exit_code, exit_code) {
+        Ok(ExitStatus::External(code))
     } else {
         Ok(ExitStatus::Failure)
     }
 }
 
+/// The state of the working tree, to find the files changed by a hook.
+#[derive(PartialEq, Eq)]
+struct WorkTree {
+    diff: Vec<u8>,
+    /// The files deleted from the index, which `git diff` doesn't show.
+    deleted: BTreeSet<String>,
+    /// The untracked files with a hash of their content, tracked with `--include-untracked`
+    /// since `git diff` doesn't see them.
+    untracked: Option<BTreeMap<String, u64>>,
+}
+
+impl WorkTree {
+    async fn snapshot(git: &impl GitBackend, include_untracked: bool) -> Result<Self> {
+        let untracked = if include_untracked {
+            Some(hash_untracked(git).await?)
+        } else {
+            None
+        };
+        Ok(Self {
+            diff: git.diff().await?,
+            deleted: git.staged_deletions().await?.into_iter().collect(),
+            untracked,
+        })
+    }
+
+    /// Take a new snapshot, in the same mode as this one.
+    async fn refresh(&self, git: &impl GitBackend) -> Result<Self> {
+        Self::snapshot(git, self.untracked.is_some()).await
+    }
+
+    /// The files that differ between this snapshot and `after`.
+    fn changed_files(&self, after: &Self) -> Vec<String> {
+        let mut files = git::changed_between(&self.diff, &after.diff);
+        files.extend(self.deleted.symmetric_difference(&after.deleted).cloned());
+        if let (Some(before), Some(after)) = (&self.untracked, &after.untracked) {
+            files.extend(
+                before
+                    .keys()
+                    .chain(after.keys())
+                    .filter(|file| before.get(*file) != after.get(*file))
+                    .cloned(),
+            );
+        }
+        files
+    }
+}
+
+async fn hash_untracked(git: &impl GitBackend) -> Result<BTreeMap<String, u64>> {
+    let mut hashes = BTreeMap::new();
+    for file in git.untracked_files().await? {
+        // The file may be a broken symlink or be removed concurrently.
+        let Ok(content) = fs_err::tokio::read(&file).await else {
+            continue;
+        };
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        hashes.insert(file, hasher.finish());
+    }
+    Ok(hashes)
+}
+
+async fn read_commit_msg(commit_msg_filename: Option<&Path>) -> Result<Option<Vec<u8>>> {
+    match commit_msg_filename {
+        Some(path) => Ok(Some(fs_err::tokio::read(path).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Truncate `output` to `max_lines` lines, keeping the first and last halves.
+///
+/// The full output is saved to a file in the store. Returns `None` if the output fits.
+async fn truncate_output(
+    output: &str,
+    max_lines: usize,
+    store: &Store,
+    hook_id: &str,
+) -> Result<Option<String>> {
+    let lines: Vec<_> = output.lines().collect();
+    if lines.len() <= max_lines {
+        return Ok(None);
+    }
+
+    let logs_dir = store.logs_dir();
+    fs_err::tokio::create_dir_all(&logs_dir).await?;
+    let now = std::time::SystemTime::now();
+    let log_path = logs_dir.join(format!(
+        "{hook_id}-{}-{}.log",
+        now.duration_since(std::time::UNIX_EPOCH)?.as_millis(),
+        std::process::id()
+    ));
+    fs_err::tokio::write(&log_path, output).await?;
+
+    let head = max_lines / 2;
+    let tail = max_lines - head;
+    let marker = format!(
+        "... {} lines truncated (full output: {})",
+        lines.len() - max_lines,
+        log_path.user_display()
+    );
+
+    Ok(Some(
+        lines[..head]
+            .iter()
+            .copied()
+            .chain(std::iter::once(marker.as_str()))
+            .chain(lines[lines.len() - tail..].iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ))
+}
+
+/// Rewrite the filenames, which are relative to the repository `root`, as requested by `paths`.
+fn hook_filenames(
+    filenames: &[&String],
+    paths: HookPaths,
+    root: &Path,
+    invocation_dir: &Path,
+) -> Result<Vec<String>> {
+    filenames
+        .iter()
+        .map(|filename| match paths {
+            HookPaths::Root => Ok((*filename).clone()),
+            HookPaths::Absolute => Ok(root.join(filename).to_string_lossy().to_string()),
+            HookPaths::Cwd => Ok(fs::relative_to(root.join(filename), invocation_dir)?
+                .to_string_lossy()
+                .to_string()),
+        })
+        .collect()
+}
+
 /// Shuffle the files so that they more evenly fill out the xargs
 /// partitions, but do it deterministically in case a hook cares about ordering.
+///
+/// Only used when the files are split into several invocations of a hook without
+/// `require_serial`, otherwise hooks get the files sorted.
 fn shuffle<T>(filenames: &mut [T]) {
     const SEED: u64 = 1_542_676_187;
     let mut rng = StdRng::seed_from_u64(SEED);
     filenames.shuffle(&mut rng);
 }
 
+/// Run a hook, returning its status and a new snapshot of the working tree if it was run.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 async fn run_hook(
+    git: &impl GitBackend,
     hook: &HookToRun,
     filter: &FileFilter<'_>,
     store: &Store,
-    diff: Vec<u8>,
+    diff: &WorkTree,
+    max_output_lines: Option<usize>,
+    commit_msg_filename: Option<&Path>,
+    abort_on_error: bool,
+    allow_system_fallback: bool,
+    paths: HookPaths,
+    invocation_dir: &Path,
     verbose: bool,
+    health: &HealthChecks,
     printer: &StatusPrinter,
-) -> Result<(bool, Vec<u8>)> {
+) -> Result<(HookStatus, Option<WorkTree>)> {
     let hook = match hook {
-        HookToRun::Skipped(hook) => {
-            printer.write_skipped(&hook.name, "", Style::new().black().on_yellow())?;
-            return Ok((true, diff));
+        HookToRun::Skipped(hook, reason) => {
+            printer.write_skipped(&hook.name, reason, Style::new().black().on_yellow())?;
+            return Ok((HookStatus::Passed, None));
         }
         HookToRun::ToRun(hook) => hook,
     };
@@ -591,17 +1268,31 @@ async fn run_hook(
             StatusPrinter::NO_FILES,
             Style::new().black().on_cyan(),
         )?;
-        return Ok((true, diff));
+        return Ok((HookStatus::Passed, None));
     }
 
-    if !Language::supported(hook.language) {
+    let language = if Language::supported(hook.language) {
+        hook.language
+    } else if allow_system_fallback && hook.language.supports_system_fallback() {
+        warn_user!(
+            "Language `{}` is not supported yet, running hook `{}` as a `system` hook",
+            hook.language,
+            hook.id
+        );
+        Language::System
+    } else {
+        // Already visible in the hook status, only recorded for `--strict`.
+        warnings::record(&format!(
+            "Hook `{}` was skipped, language `{}` is not supported yet",
+            hook.id, hook.language
+        ));
         printer.write_skipped(
             &hook.name,
             StatusPrinter::UNIMPLEMENTED,
             Style::new().black().on_yellow(),
         )?;
-        return Ok((true, diff));
-    }
+        return Ok((HookStatus::Passed, None));
+    };
 
     printer.write_running(&hook.name)?;
     std::io::stdout().flush()?;
@@ -609,23 +1300,96 @@ async fn run_hook(
     let start = std::time::Instant::now();
 
     let filenames = if hook.pass_filenames {
-        shuffle(&mut filenames);
+        filenames.sort_unstable();
         filenames
     } else {
         vec![]
     };
 
-    let (status, output) = hook
-        .language
-        .run(hook, &filenames, store)
+    let root = std::env::current_dir()?;
+    // The repository root is mounted in the container of docker hooks, and builtin hooks run
+    // in process, from the repository root.
+    let (paths, run_dir) = if matches!(language, Language::Docker | Language::DockerImage)
+        || builtin::check_fast_path(hook)
+    {
+        (HookPaths::Root, root.clone())
+    } else {
+        // Hooks with a `working_dir` are run from there, with the filenames relative to it
+        // unless they are absolute.
+        match hook.working_dir.as_deref() {
+            Some(working_dir) if paths == HookPaths::Absolute => (paths, root.join(working_dir)),
+            Some(working_dir) => (HookPaths::Cwd, root.join(working_dir)),
+            None if paths == HookPaths::Cwd => (paths, invocation_dir.to_path_buf()),
+            None => (paths, root.clone()),
+        }
+    };
+    if !run_dir.is_dir() {
+        anyhow::bail!(
+            "Failed to run hook `{hook}` from `{}`: not a directory",
+            run_dir.simplified_display()
+        );
+    }
+    let filenames = hook_filenames(&filenames, paths, &root, &run_dir)?;
+    let mut filenames = filenames.iter().collect::<Vec<_>>();
+    if !hook.require_serial && is_partitioned(hook, &filenames) {
+        shuffle(&mut filenames);
+    }
+
+    // `git diff` doesn't see changes to the commit message file, which lives in `.git`.
+    let commit_msg = read_commit_msg(commit_msg_filename).await?;
+
+    // Hooks are run from the directory their filenames are relative to.
+    let result = language
+        .run(hook, &filenames, store, &run_dir, verbose || hook.verbose)
         .await
-        .context(format!("Failed to run hook `{hook}`"))?;
+        .context(format!("Failed to run hook `{hook}`"));
+    let (status, output) = match result {
+        Ok(result) => result,
+        Err(err) if !abort_on_error => {
+            printer.write_errored()?;
+            writeln!(
+                printer.stdout(),
+                "{}",
+                format!("- hook id: {}", hook.id).dimmed()
+            )?;
+            let mut causes = err.chain();
+            if let Some(err) = causes.next() {
+                writeln!(printer.stdout(), "  {}: {err}", "error".red().bold())?;
+            }
+            for err in causes {
+                writeln!(printer.stdout(), "    {}: {err}", "caused by".red().bold())?;
+            }
+            // The runtime of the language may be the cause rather than the hook itself.
+            match health.get(language).await {
+                Ok(Health {
+                    version: Some(version),
+                }) => {
+                    writeln!(
+                        printer.stdout(),
+                        "  {}: {language} {version}",
+                        "runtime".cyan().bold()
+                    )?;
+                }
+                Ok(Health { version: None }) => {}
+                Err(reason) => {
+                    writeln!(printer.stdout(), "  {}: {reason}", "runtime".cyan().bold())?;
+                }
+            }
+
+            // The runner may have changed files before failing.
+            return Ok((HookStatus::Errored, Some(diff.refresh(git).await?)));
+        }
+        Err(err) => return Err(err),
+    };
 
     let duration = start.elapsed();
 
-    let new_diff = git::get_diff().await?;
-    let file_modified = diff != new_diff;
-    let success = status == 0 && !file_modified;
+    let new_diff = diff.refresh(git).await?;
+    let file_modified = *diff != new_diff;
+    // A rewritten message fails the hook like modified files do, so the new message is
+    // reviewed before committing.
+    let msg_modified = commit_msg != read_commit_msg(commit_msg_filename).await?;
+    let success = status == 0 && !file_modified && !msg_modified;
     if success {
         printer.write_passed()?;
     } else {
@@ -639,10 +1403,16 @@ async fn run_hook(
             format!("- hook id: {}", hook.id).dimmed()
         )?;
         if verbose || hook.verbose {
+            // Durations differ between runs, print a placeholder for a deterministic output.
+            let duration = if EnvVars::is_set(EnvVars::PREK_DETERMINISTIC_OUTPUT) {
+                "[TIME]".to_string()
+            } else {
+                format!("{:.2?}s", duration.as_secs_f64())
+            };
             writeln!(
                 printer.stdout(),
                 "{}",
-                format!("- duration: {:.2?}s", duration.as_secs_f64()).dimmed()
+                format!("- duration: {duration}").dimmed()
             )?;
         }
         if status != 0 {
@@ -659,6 +1429,13 @@ async fn run_hook(
                 "- files were modified by this hook".dimmed()
             )?;
         }
+        if msg_modified {
+            writeln!(
+                printer.stdout(),
+                "{}",
+                "- commit message was modified by this hook".dimmed()
+            )?;
+        }
 
         // To be consistent with pre-commit, merge stderr into stdout.
         let stdout = output.trim_ascii();
@@ -672,14 +1449,78 @@ async fn run_hook(
                 file.write_all(stdout).await?;
                 file.sync_all().await?;
             } else {
+                let mut stdout = String::from_utf8_lossy(stdout);
+                if let Some(max_lines) = max_output_lines {
+                    if let Some(truncated) =
+                        truncate_output(&stdout, max_lines, store, &hook.id).await?
+                    {
+                        stdout = truncated.into();
+                    }
+                }
                 writeln!(
                     printer.stdout(),
                     "{}",
-                    textwrap::indent(&String::from_utf8_lossy(stdout), "  ").dimmed()
+                    textwrap::indent(&stdout, "  ").dimmed()
                 )?;
             }
         }
     }
 
-    Ok((success, new_diff))
+    let status = if success {
+        HookStatus::Passed
+    } else {
+        HookStatus::Failed { exit_code: status }
+    };
+    Ok((status, Some(new_diff)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HookOptions, ManifestHook};
+    use crate::hook::{HookBuilder, Repo};
+
+    fn build_hook(id: &str, priority: Option<i64>, idx: usize) -> Hook {
+        let config = ManifestHook {
+            id: id.to_string(),
+            name: id.to_string(),
+            entry: "true".to_string(),
+            language: Language::System,
+            options: HookOptions {
+                priority,
+                ..Default::default()
+            },
+        };
+        HookBuilder::new(Arc::new(Repo::local(vec![])), config, idx)
+            .build()
+            .expect("hook should be valid")
+    }
+
+    fn ids(hooks: &[Hook]) -> Vec<&str> {
+        hooks.iter().map(|hook| hook.id.as_str()).collect()
+    }
+
+    #[test]
+    fn priority_order() {
+        let mut hooks = vec![
+            build_hook("slow", None, 0),
+            build_hook("fast", Some(10), 1),
+            build_hook("last", Some(-1), 2),
+            build_hook("other", None, 3),
+            build_hook("syntax", Some(10), 4),
+        ];
+        order_by_priority(&mut hooks);
+        assert_eq!(ids(&hooks), ["fast", "syntax", "slow", "other", "last"]);
+    }
+
+    #[test]
+    fn priority_keeps_config_order() {
+        let mut hooks = vec![
+            build_hook("a", None, 0),
+            build_hook("b", Some(0), 1),
+            build_hook("c", None, 2),
+        ];
+        order_by_priority(&mut hooks);
+        assert_eq!(ids(&hooks), ["a", "b", "c"]);
+    }
 }
@@ -4,7 +4,8 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use owo_colors::OwoColorize;
 
-use crate::cli::ExitStatus;
+use crate::cli::{ConfigFormat, ExitStatus};
+use crate::config::{CONFIG_FILE, TOML_CONFIG_FILE};
 use crate::fs::Simplified;
 use crate::printer::Printer;
 
@@ -21,14 +22,86 @@ repos:
       - id: check-added-large-files
 ";
 
+static SAMPLE_TOML_CONFIG: &str = r#"# See https://pre-commit.com for more information
+# See https://pre-commit.com/hooks.html for more hooks
+[[repos]]
+repo = "https://github.com/pre-commit/pre-commit-hooks"
+rev = "v5.0.0"
+hooks = [
+  { id = "trailing-whitespace" },
+  { id = "end-of-file-fixer" },
+  { id = "check-yaml" },
+  { id = "check-added-large-files" },
+]
+"#;
+
 #[allow(clippy::print_stdout)]
-pub(crate) fn sample_config(file: Option<PathBuf>, printer: Printer) -> Result<ExitStatus> {
-    if let Some(file) = file {
+pub(crate) fn sample_config(
+    file: Option<PathBuf>,
+    format: ConfigFormat,
+    force: bool,
+    append: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let sample = match format {
+        ConfigFormat::Yaml => SAMPLE_CONFIG,
+        ConfigFormat::Toml => SAMPLE_TOML_CONFIG,
+    };
+
+    if let Some(mut file) = file {
+        // `--file` without a value defaults to the YAML name.
+        if format == ConfigFormat::Toml && file == Path::new(CONFIG_FILE) {
+            file = PathBuf::from(TOML_CONFIG_FILE);
+        }
         fs_err::create_dir_all(file.parent().unwrap_or(Path::new(".")))?;
-        if file.exists() {
-            anyhow::bail!("File `{}` already exists", file.simplified_display().cyan());
+
+        let existing = match fs_err::read_to_string(&file) {
+            Ok(content) if !content.trim().is_empty() => Some(content),
+            Ok(_) => None,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        match existing {
+            Some(content) if append => {
+                let config = match format {
+                    ConfigFormat::Yaml => append_sample_repos(&content),
+                    ConfigFormat::Toml => append_sample_toml_repos(&content),
+                };
+                let Some(config) = config else {
+                    let expected = match format {
+                        ConfigFormat::Yaml => "a block-style `repos` list",
+                        ConfigFormat::Toml => "a `[[repos]]` array of tables",
+                    };
+                    anyhow::bail!(
+                        "Cannot append to `{}`, it has no {expected}",
+                        file.simplified_display().cyan()
+                    );
+                };
+                fs_err::write(&file, config)?;
+
+                writeln!(
+                    printer.stdout(),
+                    "Appended sample repos to `{}`",
+                    file.simplified_display().cyan()
+                )?;
+                return Ok(ExitStatus::Success);
+            }
+            Some(_) if !force => {
+                writeln!(
+                    printer.stderr_important(),
+                    indoc::indoc! {"
+                        File `{}` already exists, refusing to overwrite it.
+                        hint: Use `--force` to overwrite it, or `--append` to add the sample repos to it."
+                    },
+                    file.simplified_display().cyan()
+                )?;
+                return Ok(ExitStatus::Failure);
+            }
+            _ => {}
         }
-        fs_err::write(&file, SAMPLE_CONFIG)?;
+
+        fs_err::write(&file, sample)?;
 
         writeln!(
             printer.stdout(),
@@ -39,6 +112,177 @@ pub(crate) fn sample_config(file: Option<PathBuf>, printer: Printer) -> Result<E
         return Ok(ExitStatus::Success);
     }
 
-    print!("{SAMPLE_CONFIG}");
+    print!("{sample}");
     Ok(ExitStatus::Success)
 }
+
+/// Insert the sample repos at the end of the top-level `repos` list of `config`.
+///
+/// Returns `None` if `repos` is not a block sequence we can extend.
+fn append_sample_repos(config: &str) -> Option<String> {
+    let (_, sample_repos) = SAMPLE_CONFIG.split_once("repos:\n")?;
+
+    let lines: Vec<_> = config.lines().collect();
+    let Some(start) = lines.iter().position(|line| line.starts_with("repos:")) else {
+        let mut config = config.to_string();
+        if !config.ends_with('\n') {
+            config.push('\n');
+        }
+        config.push_str("repos:\n");
+        config.push_str(sample_repos);
+        return Some(config);
+    };
+
+    // Something like `repos: []`
+    let value = lines[start]["repos:".len()..].trim();
+    if !value.is_empty() && !value.starts_with('#') {
+        return None;
+    }
+
+    // The list ends at the next top-level key.
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| {
+            !line.is_empty()
+                && !line.starts_with(char::is_whitespace)
+                && !line.starts_with('#')
+                && !line.starts_with('-')
+        })
+        .map_or(lines.len(), |pos| start + 1 + pos);
+    // Keep trailing blank lines after the inserted repos.
+    let end = lines[start + 1..end]
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(start + 1, |pos| start + 2 + pos);
+
+    // Match the indentation of the existing list items.
+    let indent = lines[start + 1..end]
+        .iter()
+        .find(|line| line.trim_start().starts_with('-'))
+        .map_or("  ", |line| &line[..line.len() - line.trim_start().len()]);
+
+    let mut result = String::new();
+    for line in &lines[..end] {
+        result.push_str(line);
+        result.push('\n');
+    }
+    for line in sample_repos.lines() {
+        result.push_str(indent);
+        result.push_str(line.strip_prefix("  ").unwrap_or(line));
+        result.push('\n');
+    }
+    for line in &lines[end..] {
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    // Make sure we produced valid YAML.
+    serde_yaml::from_str::<serde_yaml::Value>(&result).ok()?;
+    Some(result)
+}
+
+/// Add the sample repos as `[[repos]]` tables at the end of the TOML `config`.
+///
+/// Returns `None` if `repos` is defined in a way that can't be extended, like `repos = []`.
+fn append_sample_toml_repos(config: &str) -> Option<String> {
+    let (_, sample_repos) = SAMPLE_TOML_CONFIG.split_once("[[repos]]\n")?;
+
+    let mut result = config.trim_end().to_string();
+    result.push_str("\n\n[[repos]]\n");
+    result.push_str(sample_repos);
+
+    // Make sure we produced valid TOML.
+    result.parse::<toml::Table>().ok()?;
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_sample_repos, append_sample_toml_repos};
+
+    #[test]
+    fn append_to_repos() {
+        let config = indoc::indoc! {"
+            repos:
+            - repo: local
+              hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+
+            default_stages: [pre-commit]
+        "};
+        insta::assert_snapshot!(append_sample_repos(config).unwrap(), @r"
+        repos:
+        - repo: local
+          hooks:
+          - id: echo
+            name: echo
+            language: system
+            entry: echo
+        - repo: 'https://github.com/pre-commit/pre-commit-hooks'
+          rev: v5.0.0
+          hooks:
+            - id: trailing-whitespace
+            - id: end-of-file-fixer
+            - id: check-yaml
+            - id: check-added-large-files
+
+        default_stages: [pre-commit]
+        ");
+    }
+
+    #[test]
+    fn append_without_repos() {
+        insta::assert_snapshot!(append_sample_repos("fail_fast: true").unwrap(), @r"
+        fail_fast: true
+        repos:
+          - repo: 'https://github.com/pre-commit/pre-commit-hooks'
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+              - id: end-of-file-fixer
+              - id: check-yaml
+              - id: check-added-large-files
+        ");
+    }
+
+    #[test]
+    fn append_to_flow_repos() {
+        assert_eq!(append_sample_repos("repos: []\n"), None);
+    }
+
+    #[test]
+    fn append_to_toml_repos() {
+        let config = indoc::indoc! {r#"
+            fail_fast = true
+
+            [[repos]]
+            repo = "local"
+            hooks = [{ id = "echo", name = "echo", language = "system", entry = "echo" }]
+        "#};
+        insta::assert_snapshot!(append_sample_toml_repos(config).unwrap(), @r#"
+        fail_fast = true
+
+        [[repos]]
+        repo = "local"
+        hooks = [{ id = "echo", name = "echo", language = "system", entry = "echo" }]
+
+        [[repos]]
+        repo = "https://github.com/pre-commit/pre-commit-hooks"
+        rev = "v5.0.0"
+        hooks = [
+          { id = "trailing-whitespace" },
+          { id = "end-of-file-fixer" },
+          { id = "check-yaml" },
+          { id = "check-added-large-files" },
+        ]
+        "#);
+    }
+
+    #[test]
+    fn append_to_inline_toml_repos() {
+        assert_eq!(append_sample_toml_repos("repos = []\n"), None);
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Write;
+use std::io::{BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+use tracing::debug;
+
+use crate::cli::install::{is_script_for, restore_legacy_hook};
+use crate::cli::{ExitStatus, clean};
+use crate::config::HookType;
+use crate::fs::Simplified;
+use crate::git;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Remove the prek binary, along with the hook scripts that run it.
+pub(crate) async fn self_uninstall(
+    yes: bool,
+    repos: Vec<PathBuf>,
+    purge_cache: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let prek = std::env::current_exe()?;
+
+    let mut hooks_dirs = Vec::new();
+    match git::get_git_common_dir_of(Path::new(".")).await {
+        Ok(dir) => hooks_dirs.push(dir.join("hooks")),
+        Err(err) => debug!("Not in a git repository: {err}"),
+    }
+    for repo in &repos {
+        let dir = git::get_git_common_dir_of(repo)
+            .await
+            .with_context(|| format!("`{}` is not a git repository", repo.user_display()))?;
+        hooks_dirs.push(dir.join("hooks"));
+    }
+    hooks_dirs.sort();
+    hooks_dirs.dedup();
+
+    // Only remove the scripts that run this binary, others may belong to another installation.
+    let mut hook_scripts = Vec::new();
+    for hooks_dir in &hooks_dirs {
+        for hook_type in HookType::value_variants() {
+            let hook_path = hooks_dir.join(hook_type.as_str());
+            if hook_path.try_exists()? && is_script_for(&hook_path, &prek)? {
+                hook_scripts.push(hook_path);
+            }
+        }
+    }
+
+    let store = Store::from_settings()?;
+
+    writeln!(printer.stdout(), "The following will be removed:")?;
+    writeln!(printer.stdout(), "  {}", prek.user_display().cyan())?;
+    for hook_path in &hook_scripts {
+        writeln!(printer.stdout(), "  {}", hook_path.user_display().cyan())?;
+    }
+    if purge_cache && store.path().exists() {
+        writeln!(printer.stdout(), "  {}", store.path().user_display().cyan())?;
+    }
+
+    if !yes && !confirm(printer)? {
+        return Ok(ExitStatus::Failure);
+    }
+
+    for hook_path in &hook_scripts {
+        fs_err::remove_file(hook_path)?;
+        writeln!(
+            printer.stdout(),
+            "Removed hook {}",
+            hook_path.user_display().cyan()
+        )?;
+        restore_legacy_hook(hook_path, printer)?;
+    }
+
+    if purge_cache {
+        clean(false, printer).await?;
+    }
+
+    remove_exe(&prek)?;
+    writeln!(printer.stdout(), "Uninstalled prek")?;
+
+    Ok(ExitStatus::Success)
+}
+
+/// Ask the user to confirm, refusing when there is no terminal to ask on.
+fn confirm(printer: Printer) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        writeln!(
+            printer.stderr_important(),
+            "Refusing to uninstall without confirmation, pass `{}` to proceed",
+            "--yes".green()
+        )?;
+        return Ok(false);
+    }
+
+    write!(printer.stderr(), "Proceed? [y/N] ")?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    if matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+        Ok(true)
+    } else {
+        writeln!(printer.stderr(), "Aborted")?;
+        Ok(false)
+    }
+}
+
+#[cfg(not(windows))]
+fn remove_exe(prek: &Path) -> Result<()> {
+    fs_err::remove_file(prek)?;
+    Ok(())
+}
+
+/// A running executable can't delete itself on Windows, so leave it to a detached
+/// process that waits for us to exit.
+#[cfg(windows)]
+fn remove_exe(prek: &Path) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Stdio;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    std::process::Command::new("cmd")
+        .arg("/C")
+        .raw_arg(format!(
+            r#"ping -n 3 127.0.0.1 >NUL & del /F /Q "{}""#,
+            prek.display()
+        ))
+        .creation_flags(DETACHED_PROCESS | CREATE_NO_WINDOW)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
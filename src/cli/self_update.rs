@@ -54,7 +54,7 @@ pub(crate) async fn self_update(
     let Ok(updater) = updater.load_receipt() else {
         debug!("no receipt found; assuming prek was installed via a package manager");
         writeln!(
-            printer.stderr(),
+            printer.stderr_important(),
             "{}",
             format_args!(
                 concat!(
@@ -78,7 +78,7 @@ pub(crate) async fn self_update(
             "receipt is not for this executable; assuming prek was installed via a package manager"
         );
         writeln!(
-            printer.stderr(),
+            printer.stderr_important(),
             "{}",
             format_args!(
                 concat!(
@@ -158,7 +158,7 @@ pub(crate) async fn self_update(
             return if let AxoupdateError::Reqwest(err) = err {
                 if err.status() == Some(http::StatusCode::FORBIDDEN) && token.is_none() {
                     writeln!(
-                        printer.stderr(),
+                        printer.stderr_important(),
                         "{}",
                         format_args!(
                             "{}{} GitHub API rate limit exceeded. Please provide a GitHub token via the {} option.",
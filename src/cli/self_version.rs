@@ -0,0 +1,25 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::cli::ExitStatus;
+use crate::printer::Printer;
+use crate::version;
+
+/// Display prek's version and build information.
+pub(crate) fn self_version(json: bool, printer: Printer) -> Result<ExitStatus> {
+    let version = version::version();
+
+    if json {
+        writeln!(
+            printer.stdout(),
+            "{}",
+            serde_json::to_string_pretty(&version)?
+        )?;
+    } else {
+        writeln!(printer.stdout(), "prek {version}")?;
+        writeln!(printer.stdout(), "target: {}", version.target())?;
+    }
+
+    Ok(ExitStatus::Success)
+}
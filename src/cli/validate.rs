@@ -1,27 +1,197 @@
 use std::error::Error;
+use std::fmt::Write;
 use std::iter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anstream::eprintln;
+use anyhow::Result;
 use owo_colors::OwoColorize;
+use serde::Serialize;
 
-use crate::cli::ExitStatus;
-use crate::config::{read_config, read_manifest};
+use crate::cli::run::{IGNORE_FILE, IgnoreFile};
+use crate::cli::{ExitStatus, OutputFormat};
+use crate::config::{
+    self, ALTER_CONFIG_FILE, CONFIG_FILE, Locations, TOML_CONFIG_FILE, Warning, parse_config,
+    read_config_content, read_manifest,
+};
+use crate::fs::Simplified;
+use crate::printer::Printer;
 
-pub(crate) fn validate_configs(configs: Vec<PathBuf>) -> ExitStatus {
-    let mut status = ExitStatus::Success;
+#[derive(Debug, Serialize)]
+struct ConfigError {
+    message: String,
+    line: Option<usize>,
+    column: Option<usize>,
+}
 
-    for config in configs {
-        if let Err(err) = read_config(&config) {
-            eprintln!("{}: {}", "error".red().bold(), err);
-            for source in iter::successors(err.source(), |&err| err.source()) {
-                eprintln!("  {}: {}", "caused by".red().bold(), source);
+impl From<&config::Error> for ConfigError {
+    fn from(err: &config::Error) -> Self {
+        match err {
+            config::Error::Yaml(_, err) => {
+                let location = err.location();
+                Self {
+                    message: err.to_string(),
+                    line: location.as_ref().map(serde_yaml::Location::line),
+                    column: location.as_ref().map(serde_yaml::Location::column),
+                }
             }
-            status = ExitStatus::Failure;
+            // TOML errors locate the problem by a byte span of the content, not by line.
+            config::Error::Toml(_, err) => Self {
+                message: err.message().to_string(),
+                line: None,
+                column: None,
+            },
+            err => Self {
+                message: err.to_string(),
+                line: None,
+                column: None,
+            },
         }
     }
+}
 
-    status
+#[derive(Debug, Serialize)]
+struct ConfigReport {
+    file: String,
+    errors: Vec<ConfigError>,
+    warnings: Vec<Warning>,
+}
+
+/// Expand directories into the configuration files found in them, recursively.
+fn find_config_files(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in fs_err::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                if entry.file_name() != ".git" {
+                    walk(&path, files)?;
+                }
+            } else if [CONFIG_FILE, ALTER_CONFIG_FILE, TOML_CONFIG_FILE]
+                .iter()
+                .any(|name| entry.file_name() == *name)
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        if path.is_dir() {
+            let mut found = Vec::new();
+            walk(&path, &mut found)?;
+            found.sort_unstable();
+            files.extend(found);
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Warn about the lines of the ignore file next to the config that can't be parsed.
+fn check_ignore_file(config_path: &Path) -> Vec<Warning> {
+    let root = config_path.parent().unwrap_or(Path::new("."));
+    let Ok(Some(ignore_file)) = IgnoreFile::read(root) else {
+        return vec![];
+    };
+    ignore_file
+        .errors
+        .into_iter()
+        .map(|(line, err)| Warning {
+            message: format!("`{IGNORE_FILE}` line {line} is invalid and ignored: {err}"),
+            line: None,
+            column: None,
+        })
+        .collect()
+}
+
+fn validate_config(path: &Path, output_format: OutputFormat) -> ConfigReport {
+    let file = path.user_display().to_string();
+
+    // The config is read once, it may come from stdin.
+    match read_config_content(path)
+        .and_then(|content| parse_config(path, &content).map(|config| (config, content)))
+    {
+        Ok((config, content)) => {
+            let locations = Locations::find(&config, &content);
+            let mut warnings = locations.check_config(path, &content);
+            warnings.extend(locations.check_config_stages(&config));
+            if config.use_ignore_file.unwrap_or(true) {
+                warnings.extend(check_ignore_file(path));
+            }
+            ConfigReport {
+                file,
+                errors: vec![],
+                warnings,
+            }
+        }
+        Err(err) => {
+            if matches!(output_format, OutputFormat::Text) {
+                eprintln!("{}: {}", "error".red().bold(), err);
+                for source in iter::successors(err.source(), |&err| err.source()) {
+                    eprintln!("  {}: {}", "caused by".red().bold(), source);
+                }
+            }
+            ConfigReport {
+                file,
+                errors: vec![ConfigError::from(&err)],
+                warnings: vec![],
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_configs(
+    configs: Vec<PathBuf>,
+    output_format: OutputFormat,
+    strict: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let configs = find_config_files(configs)?;
+    let reports = configs
+        .iter()
+        .map(|config| validate_config(config, output_format))
+        .collect::<Vec<_>>();
+
+    match output_format {
+        OutputFormat::Text => {
+            for report in &reports {
+                for warning in &report.warnings {
+                    let location = match (warning.line, warning.column) {
+                        (Some(line), Some(column)) => format!("{}:{line}:{column}", report.file),
+                        _ => report.file.clone(),
+                    };
+                    writeln!(
+                        printer.stderr_important(),
+                        "{}{} {}: {}",
+                        "warning".yellow().bold(),
+                        ":".bold(),
+                        location,
+                        warning.message
+                    )?;
+                }
+            }
+        }
+        OutputFormat::Json => {
+            writeln!(
+                printer.stdout(),
+                "{}",
+                serde_json::to_string_pretty(&reports)?
+            )?;
+        }
+    }
+
+    if reports.iter().any(|report| !report.errors.is_empty()) {
+        Ok(ExitStatus::Error)
+    } else if strict && reports.iter().any(|report| !report.warnings.is_empty()) {
+        Ok(ExitStatus::Failure)
+    } else {
+        Ok(ExitStatus::Success)
+    }
 }
 
 pub(crate) fn validate_manifest(configs: Vec<PathBuf>) -> ExitStatus {
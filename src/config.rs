@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::io::Read;
 use std::ops::RangeInclusive;
 use std::path::Path;
 use std::str::FromStr;
 
 use anyhow::Result;
+use clap::ValueEnum;
 use fancy_regex as regex;
+use itertools::Itertools;
 use serde::{Deserialize, Deserializer, Serialize};
 use url::Url;
 
@@ -13,9 +16,10 @@ use crate::fs::Simplified;
 
 pub const CONFIG_FILE: &str = ".pre-commit-config.yaml";
 pub const ALTER_CONFIG_FILE: &str = ".pre-commit-config.yml";
+pub const TOML_CONFIG_FILE: &str = ".pre-commit-config.toml";
 pub const MANIFEST_FILE: &str = ".pre-commit-hooks.yaml";
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Language {
     Conda,
@@ -41,6 +45,29 @@ pub enum Language {
 }
 
 impl Language {
+    pub const ALL: [Self; 20] = [
+        Self::Conda,
+        Self::Coursier,
+        Self::Dart,
+        Self::Docker,
+        Self::DockerImage,
+        Self::Dotnet,
+        Self::Fail,
+        Self::Golang,
+        Self::Haskell,
+        Self::Lua,
+        Self::Node,
+        Self::Perl,
+        Self::Python,
+        Self::R,
+        Self::Ruby,
+        Self::Rust,
+        Self::Swift,
+        Self::Pygrep,
+        Self::Script,
+        Self::System,
+    ];
+
     pub fn as_str(&self) -> &str {
         match self {
             Self::Conda => "conda",
@@ -73,7 +100,34 @@ impl Display for Language {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default, Deserialize, clap::ValueEnum)]
+impl FromStr for Language {
+    type Err = UnknownVariant;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.as_str() == s)
+            .ok_or_else(|| UnknownVariant {
+                kind: "language",
+                value: s.to_string(),
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(|err| {
+            let supported = Self::ALL.map(|language| language.as_str().to_string());
+            serde::de::Error::custom(format!("{err}, expected one of: {}", supported.join(", ")))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
 #[serde(rename_all = "kebab-case")]
 pub enum HookType {
     CommitMsg,
@@ -128,6 +182,37 @@ impl Display for HookType {
     }
 }
 
+/// The error returned when parsing a [`HookType`] or a [`Stage`] fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown {kind}: `{value}`")]
+pub struct UnknownVariant {
+    kind: &'static str,
+    value: String,
+}
+
+impl FromStr for HookType {
+    type Err = UnknownVariant;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "commit-msg" => Ok(Self::CommitMsg),
+            "post-checkout" => Ok(Self::PostCheckout),
+            "post-commit" => Ok(Self::PostCommit),
+            "post-merge" => Ok(Self::PostMerge),
+            "post-rewrite" => Ok(Self::PostRewrite),
+            "pre-commit" => Ok(Self::PreCommit),
+            "pre-merge-commit" => Ok(Self::PreMergeCommit),
+            "pre-push" => Ok(Self::PrePush),
+            "pre-rebase" => Ok(Self::PreRebase),
+            "prepare-commit-msg" => Ok(Self::PrepareCommitMsg),
+            _ => Err(UnknownVariant {
+                kind: "hook type",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, clap::ValueEnum)]
 #[serde(rename_all = "kebab-case")]
 pub enum Stage {
@@ -181,6 +266,23 @@ impl Stage {
             Self::PrepareCommitMsg => "prepare-commit-msg",
         }
     }
+
+    /// The hook type that runs hooks at this stage, `None` for `manual`.
+    pub fn hook_type(self) -> Option<HookType> {
+        match self {
+            Self::Manual => None,
+            Self::CommitMsg => Some(HookType::CommitMsg),
+            Self::PostCheckout => Some(HookType::PostCheckout),
+            Self::PostCommit => Some(HookType::PostCommit),
+            Self::PostMerge => Some(HookType::PostMerge),
+            Self::PostRewrite => Some(HookType::PostRewrite),
+            Self::PreCommit => Some(HookType::PreCommit),
+            Self::PreMergeCommit => Some(HookType::PreMergeCommit),
+            Self::PrePush => Some(HookType::PrePush),
+            Self::PreRebase => Some(HookType::PreRebase),
+            Self::PrepareCommitMsg => Some(HookType::PrepareCommitMsg),
+        }
+    }
 }
 
 impl Display for Stage {
@@ -189,6 +291,31 @@ impl Display for Stage {
     }
 }
 
+impl FromStr for Stage {
+    type Err = UnknownVariant;
+
+    /// Parse a stage, accepting the same legacy aliases as the config file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manual" => Ok(Self::Manual),
+            "commit-msg" => Ok(Self::CommitMsg),
+            "post-checkout" => Ok(Self::PostCheckout),
+            "post-commit" => Ok(Self::PostCommit),
+            "post-merge" => Ok(Self::PostMerge),
+            "post-rewrite" => Ok(Self::PostRewrite),
+            "pre-commit" | "commit" => Ok(Self::PreCommit),
+            "pre-merge-commit" | "merge-commit" => Ok(Self::PreMergeCommit),
+            "pre-push" | "push" => Ok(Self::PrePush),
+            "pre-rebase" => Ok(Self::PreRebase),
+            "prepare-commit-msg" => Ok(Self::PrepareCommitMsg),
+            _ => Err(UnknownVariant {
+                kind: "stage",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
 impl Stage {
     pub fn operate_on_files(self) -> bool {
         matches!(
@@ -206,7 +333,6 @@ impl Stage {
 // TODO: warn unexpected keys
 // TODO: warn deprecated stage
 // TODO: warn sensible regex
-// TODO: check minimum_pre_commit_version
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Config {
@@ -220,13 +346,24 @@ pub struct Config {
     /// Default to all stages.
     pub default_stages: Option<Vec<Stage>>,
     /// Global file include pattern.
+    #[serde(default, deserialize_with = "deserialize_pattern")]
     pub files: Option<String>,
     /// Global file exclude pattern.
+    #[serde(default, deserialize_with = "deserialize_pattern")]
     pub exclude: Option<String>,
     /// Set to true to have pre-commit stop running hooks after the first failure.
     /// Default is false.
     pub fail_fast: Option<bool>,
+    /// Truncate hook output to this many lines, unless `--max-output-lines` is given.
+    /// Default is unlimited.
+    pub default_max_output_lines: Option<usize>,
+    /// Exclude the files matched by the `.prekignore` file at the repository root.
+    /// Default is true.
+    pub use_ignore_file: Option<bool>,
     pub minimum_pre_commit_version: Option<String>,
+    /// The minimum version of prek required by the configuration.
+    /// `pre-commit` only warns about this key.
+    pub minimum_prek_version: Option<String>,
     /// Configuration for pre-commit.ci service.
     pub ci: Option<HashMap<String, serde_yaml::Value>>,
 }
@@ -277,16 +414,80 @@ impl Display for RepoLocation {
     }
 }
 
+/// Deserialize a `files` or `exclude` pattern, given as a regex or a list of regexes.
+///
+/// A list is combined into a single alternation of its patterns.
+fn deserialize_pattern<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Pattern {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let pattern = match Option::<Pattern>::deserialize(deserializer)? {
+        None => None,
+        Some(Pattern::One(pattern)) => Some(pattern),
+        Some(Pattern::Many(patterns)) if patterns.is_empty() => None,
+        // Each pattern is grouped, so that its inline flags don't leak into the others.
+        Some(Pattern::Many(patterns)) => Some(
+            patterns
+                .iter()
+                .map(|pattern| format!("(?:{pattern})"))
+                .join("|"),
+        ),
+    };
+    Ok(pattern)
+}
+
+/// What a hook reads from stdin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStdin {
+    /// The content of the file the hook is run for.
+    Filename,
+}
+
+/// The kind of checkout a `post-checkout` hook runs for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckoutType {
+    /// Switching branches, or checking out a commit.
+    Branch,
+    /// Checking out files, e.g. `git checkout -- <path>`.
+    File,
+}
+
+impl CheckoutType {
+    /// Parse the checkout flag git passes to `post-checkout`, `1` for a branch checkout and `0`
+    /// for a file checkout.
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "1" => Some(Self::Branch),
+            "0" => Some(Self::File),
+            _ => None,
+        }
+    }
+}
+
 /// Common hook options.
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
 pub struct HookOptions {
     /// Not documented in the official docs.
     pub alias: Option<String>,
     /// The pattern of files to run on.
+    #[serde(default, deserialize_with = "deserialize_pattern")]
     pub files: Option<String>,
     /// Exclude files that were matched by `files`.
     /// Default is `$^`, which matches nothing.
+    #[serde(default, deserialize_with = "deserialize_pattern")]
     pub exclude: Option<String>,
+    /// Match `files` and `exclude` ignoring case, like adding `(?i)` to both patterns.
+    /// Default is false.
+    pub files_case_insensitive: Option<bool>,
     /// List of file types to run on (AND).
     /// Default is `[file]`, which matches all files.
     pub types: Option<Vec<String>>,
@@ -317,17 +518,33 @@ pub struct HookOptions {
     pub language_version: Option<String>,
     /// Write the output of the hook to a file when the hook fails or verbose is enabled.
     pub log_file: Option<String>,
-    /// This hook will execute using a single process instead of in parallel.
+    /// This hook will execute using a single process instead of in parallel, with the files
+    /// passed in sorted order.
     /// Default is false.
     pub require_serial: Option<bool>,
+    /// Pipe the content of each file to the hook on stdin, one file at a time, instead of
+    /// passing the filenames as arguments. Only supported by `system` and `script` hooks.
+    pub stdin: Option<HookStdin>,
+    /// Run the hook from this directory, relative to the repository root, with the filenames
+    /// relative to it.
+    pub working_dir: Option<String>,
+    /// Hooks with a higher priority run first, hooks with the same priority run in the order
+    /// they are configured.
+    /// Default is 0.
+    pub priority: Option<i64>,
     /// Select which git hook(s) to run for.
     /// Default all stages are selected.
     /// See <https://pre-commit.com/#confining-hooks-to-run-at-certain-stages>.
     pub stages: Option<Vec<Stage>>,
+    /// The kinds of checkout the hook runs for at the `post-checkout` stage.
+    /// Default is `[branch]`, unlike `pre-commit` which also runs them for file checkouts.
+    pub post_checkout_on: Option<Vec<CheckoutType>>,
     /// Print the output of the hook even if it passes.
     /// Default is false.
     pub verbose: Option<bool>,
     pub minimum_pre_commit_version: Option<String>,
+    /// The minimum version of prek required by the hook.
+    pub minimum_prek_version: Option<String>,
 }
 
 impl HookOptions {
@@ -346,6 +563,7 @@ impl HookOptions {
             alias,
             files,
             exclude,
+            files_case_insensitive,
             types,
             types_or,
             exclude_types,
@@ -358,9 +576,14 @@ impl HookOptions {
             language_version,
             log_file,
             require_serial,
+            stdin,
+            working_dir,
+            priority,
             stages,
+            post_checkout_on,
             verbose,
             minimum_pre_commit_version,
+            minimum_prek_version,
         );
     }
 }
@@ -454,9 +677,10 @@ impl<'de> Deserialize<'de> for MetaHook {
                 entry: String::new(),
                 options: HookOptions {
                     files: Some(format!(
-                        "^{}|{}$",
+                        "^{}|{}|{}$",
                         regex::escape(CONFIG_FILE),
-                        regex::escape(ALTER_CONFIG_FILE)
+                        regex::escape(ALTER_CONFIG_FILE),
+                        regex::escape(TOML_CONFIG_FILE)
                     )),
                     ..Default::default()
                 },
@@ -468,9 +692,10 @@ impl<'de> Deserialize<'de> for MetaHook {
                 entry: String::new(),
                 options: HookOptions {
                     files: Some(format!(
-                        "^{}|{}$",
+                        "^{}|{}|{}$",
                         regex::escape(CONFIG_FILE),
-                        regex::escape(ALTER_CONFIG_FILE)
+                        regex::escape(ALTER_CONFIG_FILE),
+                        regex::escape(TOML_CONFIG_FILE)
                     )),
                     ..Default::default()
                 },
@@ -611,7 +836,7 @@ impl<'de> Deserialize<'de> for Repo {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct ManifestHook {
     /// The id of the hook.
@@ -644,32 +869,631 @@ pub enum Error {
     #[error("Failed to parse `{0}`")]
     Yaml(String, #[source] serde_yaml::Error),
 
+    #[error("Failed to parse `{0}`")]
+    Toml(String, #[source] Box<toml::de::Error>),
+
     #[error("Invalid repo URL: {0}")]
     RepoUrl(#[from] url::ParseError),
+
+    #[error(
+        "Invalid `{key}` regex of {scope} in `{path}`:{}",
+        display_pattern(pattern)
+    )]
+    Regex {
+        path: String,
+        scope: String,
+        key: &'static str,
+        pattern: String,
+        #[source]
+        error: Box<regex::Error>,
+    },
+
+    #[error("Invalid `minimum_prek_version` `{0}`")]
+    InvalidPrekVersion(String, #[source] semver::Error),
+
+    #[error(
+        "Required minimum prek version `{0}` is greater than current version `{current}`, run `prek self update` to upgrade",
+        current = env!("CARGO_PKG_VERSION")
+    )]
+    PrekTooOld(String),
 }
 
-/// Read the configuration file from the given path.
-pub fn read_config(path: &Path) -> Result<Config, Error> {
-    let content = match fs_err::read_to_string(path) {
-        Ok(content) => content,
+/// Check that the running prek is at least the `minimum_prek_version` `required`.
+///
+/// Partial versions like `0.2` are accepted, the missing components are zeros.
+pub fn check_prek_version(required: &str) -> Result<(), Error> {
+    let trimmed = required.trim();
+    let padded = match trimmed.matches('.').count() {
+        0 => format!("{trimmed}.0.0"),
+        1 => format!("{trimmed}.0"),
+        _ => trimmed.to_string(),
+    };
+    let minimum = semver::Version::parse(&padded)
+        .map_err(|e| Error::InvalidPrekVersion(required.to_string(), e))?;
+    let current = semver::Version::parse(env!("CARGO_PKG_VERSION"))
+        .expect("the package version is valid semver");
+    if current < minimum {
+        return Err(Error::PrekTooOld(required.to_string()));
+    }
+    Ok(())
+}
+
+/// Show multiline patterns as an indented block, and others inline.
+fn display_pattern(pattern: &str) -> String {
+    let pattern = pattern.trim_end();
+    if pattern.contains('\n') {
+        pattern.lines().fold(String::new(), |mut acc, line| {
+            acc.push_str("\n    ");
+            acc.push_str(line);
+            acc
+        })
+    } else {
+        format!(" `{pattern}`")
+    }
+}
+
+/// Check that the `files` and `exclude` patterns compile.
+///
+/// Patterns are passed verbatim to the regex engine, so multiline verbose patterns
+/// (`(?x)` with `# comments`) behave the same as in `pre-commit`.
+fn check_patterns(
+    path: &Path,
+    scope: impl Fn() -> String,
+    files: Option<&str>,
+    exclude: Option<&str>,
+) -> Result<(), Error> {
+    for (key, pattern) in [("files", files), ("exclude", exclude)] {
+        let Some(pattern) = pattern else {
+            continue;
+        };
+        if let Err(error) = regex::Regex::new(pattern) {
+            return Err(Error::Regex {
+                path: path.user_display().to_string(),
+                scope: scope(),
+                key,
+                pattern: pattern.to_string(),
+                error: Box::new(error),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_hook_patterns(path: &Path, id: &str, options: &HookOptions) -> Result<(), Error> {
+    check_patterns(
+        path,
+        || format!("hook `{id}`"),
+        options.files.as_deref(),
+        options.exclude.as_deref(),
+    )
+}
+
+/// Whether the configuration file at `path` is written in TOML rather than YAML.
+pub fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+/// The config path that reads the configuration from stdin.
+pub const STDIN_CONFIG: &str = "-";
+
+/// Read the content of a configuration file, or of stdin if the path is [`STDIN_CONFIG`].
+///
+/// Stdin and named pipes can only be read once, the content should be kept around and parsed
+/// with [`parse_config`].
+pub fn read_config_content(path: &Path) -> Result<String, Error> {
+    if path == Path::new(STDIN_CONFIG) {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    match fs_err::read_to_string(path) {
+        Ok(content) => Ok(content),
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            return Err(Error::NotFound(path.user_display().to_string()));
+            Err(Error::NotFound(path.user_display().to_string()))
         }
-        Err(e) => return Err(e.into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Read the configuration file from the given path.
+///
+/// Files with a `.toml` extension are parsed as TOML, with the same schema as the YAML config.
+pub fn read_config(path: &Path) -> Result<Config, Error> {
+    let content = read_config_content(path)?;
+    parse_config(path, &content)
+}
+
+/// Parse the content of the configuration file at `path`.
+pub fn parse_config(path: &Path, content: &str) -> Result<Config, Error> {
+    let config: Config = if is_toml(path) {
+        toml::from_str(content)
+            .map_err(|e| Error::Toml(path.user_display().to_string(), Box::new(e)))?
+    } else {
+        serde_yaml::from_str(content)
+            .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?
     };
-    let config = serde_yaml::from_str(&content)
-        .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+
+    if let Some(required) = &config.minimum_prek_version {
+        check_prek_version(required)?;
+    }
+    check_patterns(
+        path,
+        || "top level".to_string(),
+        config.files.as_deref(),
+        config.exclude.as_deref(),
+    )?;
+    for repo in &config.repos {
+        match repo {
+            Repo::Remote(repo) => {
+                for hook in &repo.hooks {
+                    check_hook_patterns(path, &hook.id, &hook.options)?;
+                }
+            }
+            Repo::Local(repo) => {
+                for hook in &repo.hooks {
+                    check_hook_patterns(path, &hook.id, &hook.options)?;
+                }
+            }
+            Repo::Meta(repo) => {
+                for hook in &repo.hooks {
+                    check_hook_patterns(path, &hook.0.id, &hook.0.options)?;
+                }
+            }
+        }
+    }
+
     Ok(config)
 }
 
 /// Read the manifest file from the given path.
 pub fn read_manifest(path: &Path) -> Result<Manifest, Error> {
     let content = fs_err::read_to_string(path)?;
-    let manifest = serde_yaml::from_str(&content)
+    parse_manifest(path, &content)
+}
+
+/// Parse the content of the manifest file at the given path.
+pub fn parse_manifest(path: &Path, content: &str) -> Result<Manifest, Error> {
+    let manifest: Manifest = serde_yaml::from_str(content)
         .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+
+    for hook in &manifest.hooks {
+        check_hook_patterns(path, &hook.id, &hook.options)?;
+    }
+
     Ok(manifest)
 }
 
+/// A problem in the configuration file that doesn't prevent it from being loaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct Warning {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Warning {
+    fn new(message: String, location: Option<Location>) -> Self {
+        Self {
+            message,
+            line: location.map(|location| location.line),
+            column: location.map(|location| location.column),
+        }
+    }
+}
+
+/// Top-level keys recognized in the configuration file.
+pub const CONFIG_KEYS: &[&str] = &[
+    "repos",
+    "default_install_hook_types",
+    "default_language_version",
+    "default_stages",
+    "files",
+    "exclude",
+    "fail_fast",
+    "default_max_output_lines",
+    "use_ignore_file",
+    "minimum_pre_commit_version",
+    "minimum_prek_version",
+    "ci",
+];
+
+/// A 1-based line and column in the configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// The location of the byte `offset` of `content`.
+    fn from_offset(content: &str, offset: usize) -> Self {
+        let before = &content[..offset];
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Prefix an error message with where the problem is, given as `file:line:column`.
+pub(crate) fn location_prefix(location: Option<&str>) -> String {
+    location.map_or_else(String::new, |location| format!("{location}: "))
+}
+
+/// Where the top-level keys, the repos and the hooks of a configuration are defined.
+#[derive(Debug, Clone, Default)]
+pub struct Locations {
+    /// The top-level keys, in the order they are defined.
+    keys: Vec<(String, Location)>,
+    /// The location of the `repo` key of each repo, and of the `id` key of each of its hooks.
+    repos: Vec<(Option<Location>, Vec<Option<Location>>)>,
+}
+
+impl Locations {
+    /// Find the repos and hooks of `config` in its source `content`.
+    ///
+    /// The keys are searched in the order they are defined, so a location is missing rather
+    /// than wrong when the search doesn't recognize how a key is written.
+    pub fn find(config: &Config, content: &str) -> Self {
+        let mut cursor = 0;
+        let mut find = |key: &str, value: Option<&str>| {
+            let offset = find_key(content, cursor, key, value)?;
+            cursor = offset + key.len();
+            Some(Location::from_offset(content, offset))
+        };
+
+        let repos = config
+            .repos
+            .iter()
+            .map(|repo| {
+                let ids: Vec<&String> = match repo {
+                    Repo::Remote(repo) => repo.hooks.iter().map(|hook| &hook.id).collect(),
+                    Repo::Local(repo) => repo.hooks.iter().map(|hook| &hook.id).collect(),
+                    Repo::Meta(repo) => repo.hooks.iter().map(|hook| &hook.0.id).collect(),
+                };
+                let repo = find("repo", None);
+                let hooks = ids.into_iter().map(|id| find("id", Some(id))).collect();
+                (repo, hooks)
+            })
+            .collect();
+
+        Self {
+            keys: top_level_keys(content),
+            repos,
+        }
+    }
+
+    /// The location of the top-level `key` in the configuration.
+    pub fn key(&self, key: &str) -> Option<Location> {
+        self.keys
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, location)| *location)
+    }
+
+    /// The location of the repo at `repo_idx` in the configuration.
+    pub fn repo(&self, repo_idx: usize) -> Option<Location> {
+        self.repos.get(repo_idx)?.0
+    }
+
+    /// The location of the hook at `hook_idx` of the repo at `repo_idx`.
+    pub fn hook(&self, repo_idx: usize, hook_idx: usize) -> Option<Location> {
+        *self.repos.get(repo_idx)?.1.get(hook_idx)?
+    }
+
+    /// Check the configuration `content` for unexpected keys and deprecated stage names.
+    ///
+    /// The content is expected to be a valid configuration, problems that prevent it from
+    /// being loaded are reported by [`read_config`]. `path` tells whether the content is TOML.
+    pub fn check_config(&self, path: &Path, content: &str) -> Vec<Warning> {
+        let root: Option<serde_yaml::Value> = if is_toml(path) {
+            toml::from_str(content).ok()
+        } else {
+            serde_yaml::from_str(content).ok()
+        };
+        let Some(serde_yaml::Value::Mapping(root)) = root else {
+            return vec![];
+        };
+
+        let mut warnings = Vec::new();
+
+        for key in root.keys().filter_map(serde_yaml::Value::as_str) {
+            if !CONFIG_KEYS.contains(&key) {
+                warnings.push(Warning::new(
+                    format!("Unexpected key `{key}`"),
+                    self.key(key),
+                ));
+            }
+        }
+
+        let deprecated_stages = |stages: Option<&serde_yaml::Value>| {
+            stages
+                .and_then(serde_yaml::Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(serde_yaml::Value::as_str)
+                .filter_map(|stage| {
+                    let replacement = match stage {
+                        "commit" => Stage::PreCommit,
+                        "merge-commit" => Stage::PreMergeCommit,
+                        "push" => Stage::PrePush,
+                        _ => return None,
+                    };
+                    Some((stage.to_string(), replacement))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (stage, replacement) in deprecated_stages(root.get("default_stages")) {
+            warnings.push(Warning::new(
+                format!(
+                    "`default_stages` uses deprecated stage `{stage}`, use `{replacement}` instead"
+                ),
+                self.key("default_stages"),
+            ));
+        }
+
+        let repos = root
+            .get("repos")
+            .and_then(serde_yaml::Value::as_sequence)
+            .into_iter()
+            .flatten();
+        for (repo_idx, repo) in repos.enumerate() {
+            let hooks = repo
+                .get("hooks")
+                .and_then(serde_yaml::Value::as_sequence)
+                .into_iter()
+                .flatten();
+            for (hook_idx, hook) in hooks.enumerate() {
+                let Some(id) = hook.get("id").and_then(serde_yaml::Value::as_str) else {
+                    continue;
+                };
+                for (stage, replacement) in deprecated_stages(hook.get("stages")) {
+                    warnings.push(Warning::new(
+                        format!(
+                            "Hook `{id}` uses deprecated stage `{stage}`, use `{replacement}` instead"
+                        ),
+                        self.hook(repo_idx, hook_idx),
+                    ));
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// Check the stages of the hooks in the configuration, see [`check_stages`].
+    ///
+    /// The stages of remote hooks that are not set in the configuration come from their
+    /// manifest, these hooks are assumed to run at any stage.
+    pub fn check_config_stages(&self, config: &Config) -> Vec<Warning> {
+        let default_stages = config
+            .default_stages
+            .as_deref()
+            .unwrap_or(Stage::value_variants());
+        // The id, stages and location of each hook.
+        let hooks: Vec<_> = config
+            .repos
+            .iter()
+            .enumerate()
+            .flat_map(|(repo_idx, repo)| {
+                let hooks: Vec<(&str, Option<&[Stage]>)> = match repo {
+                    Repo::Remote(repo) => repo
+                        .hooks
+                        .iter()
+                        .map(|h| (h.id.as_str(), h.options.stages.as_deref()))
+                        .collect(),
+                    Repo::Local(repo) => repo
+                        .hooks
+                        .iter()
+                        .map(|h| {
+                            (
+                                h.id.as_str(),
+                                Some(h.options.stages.as_deref().unwrap_or(default_stages)),
+                            )
+                        })
+                        .collect(),
+                    Repo::Meta(repo) => repo
+                        .hooks
+                        .iter()
+                        .map(|h| {
+                            (
+                                h.0.id.as_str(),
+                                Some(h.0.options.stages.as_deref().unwrap_or(default_stages)),
+                            )
+                        })
+                        .collect(),
+                };
+                hooks
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(hook_idx, (id, stages))| (id, stages, (repo_idx, hook_idx)))
+            })
+            .collect();
+
+        check_stages(config, hooks.iter().map(|(id, stages, _)| (*id, *stages)))
+            .into_iter()
+            .map(|mismatch| {
+                let location = match &mismatch {
+                    StageMismatch::Hook { id, .. } => hooks
+                        .iter()
+                        .find(|(hook_id, ..)| *hook_id == id.as_str())
+                        .and_then(|(.., (repo_idx, hook_idx))| self.hook(*repo_idx, *hook_idx)),
+                    StageMismatch::DefaultStage(_) => self.key("default_stages"),
+                    StageMismatch::InstallHookType(_) => self.key("default_install_hook_types"),
+                };
+                Warning::new(mismatch.to_string(), location)
+            })
+            .collect()
+    }
+}
+
+/// Find the top-level keys of YAML or TOML `content`, without the keys of TOML tables.
+fn top_level_keys(content: &str) -> Vec<(String, Location)> {
+    let mut keys = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        // The keys after a TOML table header belong to the table.
+        if line.starts_with('[') {
+            break;
+        }
+        // Nested keys, comments, list items and YAML document markers.
+        if line.starts_with([' ', '\t', '\r', '\n', '#', '-']) {
+            continue;
+        }
+        let Some(end) = line.find([':', '=']) else {
+            continue;
+        };
+        let key = line[..end].trim().trim_matches(['"', '\'']);
+        if !key.is_empty() {
+            keys.push((key.to_string(), Location::from_offset(content, start)));
+        }
+    }
+    keys
+}
+
+/// Find the byte offset of the next `key`, from `start`, in YAML or TOML `content`.
+///
+/// With `value`, the key must be set to it, quoted or not.
+fn find_key(content: &str, start: usize, key: &str, value: Option<&str>) -> Option<usize> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut from = start;
+    while let Some(found) = content[from..].find(key) {
+        let offset = from + found;
+        from = offset + key.len();
+
+        let line = &content[content[..offset].rfind('\n').map_or(0, |idx| idx + 1)..offset];
+        if line.trim_start().starts_with('#') || line.contains(" #") {
+            continue;
+        }
+        if line.chars().next_back().is_some_and(is_word) {
+            continue;
+        }
+        let rest = content[from..].trim_start_matches(['"', '\'']).trim_start();
+        let Some(rest) = rest.strip_prefix([':', '=']) else {
+            continue;
+        };
+        let Some(value) = value else {
+            return Some(offset);
+        };
+        let Some(rest) = rest
+            .trim_start()
+            .trim_start_matches(['"', '\''])
+            .strip_prefix(value)
+        else {
+            continue;
+        };
+        if !rest.chars().next().is_some_and(is_word) {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// A mismatch between the stages hooks run at and the configured stages and hook types.
+#[derive(Debug)]
+pub enum StageMismatch {
+    /// The hook doesn't run at any of the installed hook types.
+    Hook {
+        id: String,
+        stages: Vec<Stage>,
+        installed: Vec<HookType>,
+    },
+    /// No hook runs at this stage of `default_stages`.
+    DefaultStage(Stage),
+    /// No hook runs at this hook type of `default_install_hook_types`.
+    InstallHookType(HookType),
+}
+
+impl Display for StageMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hook {
+                id,
+                stages,
+                installed,
+            } => write!(
+                f,
+                "Hook `{id}` runs at `{}`, but only `{}` hooks are installed",
+                stages.iter().join(", "),
+                installed.iter().join(", "),
+            ),
+            Self::DefaultStage(stage) => write!(
+                f,
+                "`default_stages` contains `{stage}`, but no hook runs at it"
+            ),
+            Self::InstallHookType(hook_type) => write!(
+                f,
+                "`default_install_hook_types` contains `{hook_type}`, but no hook runs at it"
+            ),
+        }
+    }
+}
+
+/// Check that every hook runs at one of the installed hook types, and that every stage of
+/// `default_stages` and `default_install_hook_types` has a hook running at it.
+///
+/// `hooks` yields the id and the effective stages of each hook, `None` if they can't be known
+/// without the hook's manifest. Hooks that can run at `manual` are expected to be run with
+/// `--hook-stage`.
+pub fn check_stages<'a>(
+    config: &Config,
+    hooks: impl IntoIterator<Item = (&'a str, Option<&'a [Stage]>)>,
+) -> Vec<StageMismatch> {
+    let hooks = hooks.into_iter().collect::<Vec<_>>();
+    let installed = config
+        .default_install_hook_types
+        .clone()
+        .unwrap_or_else(|| vec![HookType::PreCommit]);
+    let runs_at = |stage: Stage| {
+        hooks
+            .iter()
+            .any(|(_, stages)| stages.is_none_or(|stages| stages.contains(&stage)))
+    };
+
+    let mut mismatches = Vec::new();
+    for (id, stages) in &hooks {
+        let Some(stages) = stages else {
+            continue;
+        };
+        if !stages.contains(&Stage::Manual)
+            && !installed
+                .iter()
+                .any(|hook_type| stages.contains(&Stage::from(*hook_type)))
+        {
+            mismatches.push(StageMismatch::Hook {
+                id: (*id).to_string(),
+                stages: stages.to_vec(),
+                installed: installed.clone(),
+            });
+        }
+    }
+    for stage in config.default_stages.iter().flatten() {
+        if !runs_at(*stage) {
+            mismatches.push(StageMismatch::DefaultStage(*stage));
+        }
+    }
+    // Only hook types that were asked for explicitly, the default `pre-commit` is covered by
+    // the hook check above.
+    for hook_type in config.default_install_hook_types.iter().flatten() {
+        if !runs_at(Stage::from(*hook_type)) {
+            mismatches.push(StageMismatch::InstallHookType(*hook_type));
+        }
+    }
+
+    mismatches
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -703,6 +1527,7 @@ mod tests {
                                         alias: None,
                                         files: None,
                                         exclude: None,
+                                        files_case_insensitive: None,
                                         types: None,
                                         types_or: None,
                                         exclude_types: None,
@@ -715,9 +1540,14 @@ mod tests {
                                         language_version: None,
                                         log_file: None,
                                         require_serial: None,
+                                        stdin: None,
+                                        working_dir: None,
+                                        priority: None,
                                         stages: None,
+                                        post_checkout_on: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
+                                        minimum_prek_version: None,
                                     },
                                 },
                             ],
@@ -730,7 +1560,10 @@ mod tests {
                 files: None,
                 exclude: None,
                 fail_fast: None,
+                default_max_output_lines: None,
+                use_ignore_file: None,
                 minimum_pre_commit_version: None,
+                minimum_prek_version: None,
                 ci: None,
             },
         )
@@ -794,6 +1627,7 @@ mod tests {
                                         alias: None,
                                         files: None,
                                         exclude: None,
+                                        files_case_insensitive: None,
                                         types: None,
                                         types_or: None,
                                         exclude_types: None,
@@ -806,9 +1640,14 @@ mod tests {
                                         language_version: None,
                                         log_file: None,
                                         require_serial: None,
+                                        stdin: None,
+                                        working_dir: None,
+                                        priority: None,
                                         stages: None,
+                                        post_checkout_on: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
+                                        minimum_prek_version: None,
                                     },
                                 },
                             ],
@@ -821,7 +1660,10 @@ mod tests {
                 files: None,
                 exclude: None,
                 fail_fast: None,
+                default_max_output_lines: None,
+                use_ignore_file: None,
                 minimum_pre_commit_version: None,
+                minimum_prek_version: None,
                 ci: None,
             },
         )
@@ -903,6 +1745,7 @@ mod tests {
                                         alias: None,
                                         files: None,
                                         exclude: None,
+                                        files_case_insensitive: None,
                                         types: None,
                                         types_or: None,
                                         exclude_types: None,
@@ -915,9 +1758,14 @@ mod tests {
                                         language_version: None,
                                         log_file: None,
                                         require_serial: None,
+                                        stdin: None,
+                                        working_dir: None,
+                                        priority: None,
                                         stages: None,
+                                        post_checkout_on: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
+                                        minimum_prek_version: None,
                                     },
                                 },
                             ],
@@ -930,7 +1778,10 @@ mod tests {
                 files: None,
                 exclude: None,
                 fail_fast: None,
+                default_max_output_lines: None,
+                use_ignore_file: None,
                 minimum_pre_commit_version: None,
+                minimum_prek_version: None,
                 ci: None,
             },
         )
@@ -1028,6 +1879,7 @@ mod tests {
                                                 "^\\.pre-commit-config\\.yaml|\\.pre-commit-config\\.yml$",
                                             ),
                                             exclude: None,
+                                            files_case_insensitive: None,
                                             types: None,
                                             types_or: None,
                                             exclude_types: None,
@@ -1040,9 +1892,14 @@ mod tests {
                                             language_version: None,
                                             log_file: None,
                                             require_serial: None,
+                                            stdin: None,
+                                            working_dir: None,
+                                            priority: None,
                                             stages: None,
+                                            post_checkout_on: None,
                                             verbose: None,
                                             minimum_pre_commit_version: None,
+                                            minimum_prek_version: None,
                                         },
                                     },
                                 ),
@@ -1058,6 +1915,7 @@ mod tests {
                                                 "^\\.pre-commit-config\\.yaml|\\.pre-commit-config\\.yml$",
                                             ),
                                             exclude: None,
+                                            files_case_insensitive: None,
                                             types: None,
                                             types_or: None,
                                             exclude_types: None,
@@ -1070,9 +1928,14 @@ mod tests {
                                             language_version: None,
                                             log_file: None,
                                             require_serial: None,
+                                            stdin: None,
+                                            working_dir: None,
+                                            priority: None,
                                             stages: None,
+                                            post_checkout_on: None,
                                             verbose: None,
                                             minimum_pre_commit_version: None,
+                                            minimum_prek_version: None,
                                         },
                                     },
                                 ),
@@ -1086,6 +1949,7 @@ mod tests {
                                             alias: None,
                                             files: None,
                                             exclude: None,
+                                            files_case_insensitive: None,
                                             types: None,
                                             types_or: None,
                                             exclude_types: None,
@@ -1098,11 +1962,16 @@ mod tests {
                                             language_version: None,
                                             log_file: None,
                                             require_serial: None,
+                                            stdin: None,
+                                            working_dir: None,
+                                            priority: None,
                                             stages: None,
+                                            post_checkout_on: None,
                                             verbose: Some(
                                                 true,
                                             ),
                                             minimum_pre_commit_version: None,
+                                            minimum_prek_version: None,
                                         },
                                     },
                                 ),
@@ -1116,7 +1985,10 @@ mod tests {
                 files: None,
                 exclude: None,
                 fail_fast: None,
+                default_max_output_lines: None,
+                use_ignore_file: None,
                 minimum_pre_commit_version: None,
+                minimum_prek_version: None,
                 ci: None,
             },
         )
@@ -1162,6 +2034,7 @@ mod tests {
                                         alias: None,
                                         files: None,
                                         exclude: None,
+                                        files_case_insensitive: None,
                                         types: None,
                                         types_or: None,
                                         exclude_types: None,
@@ -1176,9 +2049,14 @@ mod tests {
                                         ),
                                         log_file: None,
                                         require_serial: None,
+                                        stdin: None,
+                                        working_dir: None,
+                                        priority: None,
                                         stages: None,
+                                        post_checkout_on: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
+                                        minimum_prek_version: None,
                                     },
                                 },
                                 ManifestHook {
@@ -1190,6 +2068,7 @@ mod tests {
                                         alias: None,
                                         files: None,
                                         exclude: None,
+                                        files_case_insensitive: None,
                                         types: None,
                                         types_or: None,
                                         exclude_types: None,
@@ -1204,9 +2083,14 @@ mod tests {
                                         ),
                                         log_file: None,
                                         require_serial: None,
+                                        stdin: None,
+                                        working_dir: None,
+                                        priority: None,
                                         stages: None,
+                                        post_checkout_on: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
+                                        minimum_prek_version: None,
                                     },
                                 },
                                 ManifestHook {
@@ -1218,6 +2102,7 @@ mod tests {
                                         alias: None,
                                         files: None,
                                         exclude: None,
+                                        files_case_insensitive: None,
                                         types: None,
                                         types_or: None,
                                         exclude_types: None,
@@ -1232,9 +2117,14 @@ mod tests {
                                         ),
                                         log_file: None,
                                         require_serial: None,
+                                        stdin: None,
+                                        working_dir: None,
+                                        priority: None,
                                         stages: None,
+                                        post_checkout_on: None,
                                         verbose: None,
                                         minimum_pre_commit_version: None,
+                                        minimum_prek_version: None,
                                     },
                                 },
                             ],
@@ -1247,13 +2137,198 @@ mod tests {
                 files: None,
                 exclude: None,
                 fail_fast: None,
+                default_max_output_lines: None,
+                use_ignore_file: None,
                 minimum_pre_commit_version: None,
+                minimum_prek_version: None,
                 ci: None,
             },
         )
         "#);
     }
 
+    #[test]
+    fn verbose_regex() -> Result<()> {
+        let yaml = indoc::indoc! { r"
+            exclude: |
+              (?x)^(
+                  third_party/.*|
+                  generated/.*  # codegen
+              )$
+            repos:
+              - repo: local
+                hooks:
+                  - id: ruff
+                    name: ruff
+                    entry: ruff
+                    language: system
+                    files: |
+                      (?x)^(
+                          path/to/file1.py|
+                          path/to/file2.py|
+                          path/to/file3.py
+                      )$
+        "};
+        let config = serde_yaml::from_str::<Config>(yaml)?;
+
+        // Block scalars are passed through verbatim.
+        let exclude = config.exclude.as_deref().unwrap();
+        assert_eq!(
+            exclude,
+            "(?x)^(\n    third_party/.*|\n    generated/.*  # codegen\n)$\n"
+        );
+        let exclude = regex::Regex::new(exclude)?;
+        assert!(exclude.is_match("third_party/lib.rs")?);
+        assert!(exclude.is_match("generated/api.rs")?);
+        assert!(!exclude.is_match("src/main.rs")?);
+
+        let Repo::Local(repo) = &config.repos[0] else {
+            unreachable!()
+        };
+        let files = regex::Regex::new(repo.hooks[0].options.files.as_deref().unwrap())?;
+        assert!(files.is_match("path/to/file2.py")?);
+        assert!(!files.is_match("path/to/file4.py")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_regex() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(CONFIG_FILE);
+        fs_err::write(
+            &path,
+            indoc::indoc! { r"
+                repos:
+                  - repo: local
+                    hooks:
+                      - id: ruff
+                        name: ruff
+                        entry: ruff
+                        language: system
+                        exclude: |
+                          (?x)^(
+                              generated/.*  # codegen
+                          $
+            "},
+        )?;
+
+        let err = read_config(&path).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Regex {
+                key: "exclude",
+                ref scope,
+                ref pattern,
+                ..
+            } if scope == "hook `ruff`" && pattern.starts_with("(?x)^(")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_toml() {
+        // Same schema as the YAML config.
+        let yaml = indoc::indoc! {r"
+            default_stages: [pre-commit]
+            repos:
+              - repo: local
+                hooks:
+                  - id: cargo-fmt
+                    name: cargo fmt
+                    entry: cargo fmt --
+                    language: system
+                    types: [rust]
+              - repo: https://github.com/crate-ci/typos
+                rev: v1.0.0
+                hooks:
+                  - id: typos
+                    args: [--force-exclude]
+              - repo: meta
+                hooks:
+                  - id: check-hooks-apply
+        "};
+        let toml = indoc::indoc! {r#"
+            default_stages = ["pre-commit"]
+
+            [[repos]]
+            repo = "local"
+            hooks = [
+              { id = "cargo-fmt", name = "cargo fmt", entry = "cargo fmt --", language = "system", types = ["rust"] },
+            ]
+
+            [[repos]]
+            repo = "https://github.com/crate-ci/typos"
+            rev = "v1.0.0"
+
+            [[repos.hooks]]
+            id = "typos"
+            args = ["--force-exclude"]
+
+            [[repos]]
+            repo = "meta"
+            hooks = [{ id = "check-hooks-apply" }]
+        "#};
+        let from_yaml = serde_yaml::from_str::<Config>(yaml).unwrap();
+        let from_toml = toml::from_str::<Config>(toml).unwrap();
+        assert_eq!(format!("{from_toml:?}"), format!("{from_yaml:?}"));
+
+        // Remote hook should have `rev`.
+        let toml = indoc::indoc! {r#"
+            [[repos]]
+            repo = "https://github.com/crate-ci/typos"
+            hooks = [{ id = "typos" }]
+        "#};
+        let err = toml::from_str::<Config>(toml).unwrap_err();
+        assert_eq!(err.message(), "Invalid remote repo: missing field `rev`");
+
+        // Local hook should not have `rev`.
+        let toml = indoc::indoc! {r#"
+            [[repos]]
+            repo = "local"
+            rev = "v1.0.0"
+            hooks = [{ id = "cargo-fmt", name = "cargo fmt", entry = "cargo fmt", language = "system" }]
+        "#};
+        let err = toml::from_str::<Config>(toml).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Invalid local repo: unknown field `rev`, expected `hooks`"
+        );
+
+        // Invalid meta hook id
+        let toml = indoc::indoc! {r#"
+            [[repos]]
+            repo = "meta"
+            hooks = [{ id = "hello" }]
+        "#};
+        let err = toml::from_str::<Config>(toml).unwrap_err();
+        assert_eq!(err.message(), "Invalid meta repo: Unknown meta hook id");
+    }
+
+    #[test]
+    fn read_toml_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(TOML_CONFIG_FILE);
+        fs_err::write(
+            &path,
+            indoc::indoc! {r#"
+                [[repos]]
+                repo = "local"
+                hooks = [{ id = "echo", name = "echo", entry = "echo", language = "system", files = "(" }]
+            "#},
+        )?;
+
+        let err = read_config(&path).unwrap_err();
+        assert!(matches!(err, Error::Regex { key: "files", .. }));
+
+        fs_err::write(&path, "repos = [")?;
+        let err = read_config(&path).unwrap_err();
+        assert!(matches!(err, Error::Toml(..)));
+
+        Ok(())
+    }
+
     #[test]
     fn test_read_config() -> Result<()> {
         let config = read_config(Path::new("tests/fixtures/uv-pre-commit-config.yaml"))?;
@@ -1267,4 +2342,110 @@ mod tests {
         insta::assert_debug_snapshot!(manifest);
         Ok(())
     }
+
+    #[test]
+    fn hook_type_from_str() {
+        for hook_type in HookType::value_variants() {
+            assert_eq!(
+                hook_type.as_str().parse::<HookType>().ok(),
+                Some(*hook_type)
+            );
+        }
+        assert!("commit".parse::<HookType>().is_err());
+        assert_eq!(
+            "pre_commit".parse::<HookType>().unwrap_err().to_string(),
+            "Unknown hook type: `pre_commit`"
+        );
+    }
+
+    #[test]
+    fn stage_from_str() {
+        for stage in Stage::value_variants() {
+            assert_eq!(stage.as_str().parse::<Stage>().ok(), Some(*stage));
+        }
+        assert_eq!("commit".parse::<Stage>().ok(), Some(Stage::PreCommit));
+        assert_eq!(
+            "merge-commit".parse::<Stage>().ok(),
+            Some(Stage::PreMergeCommit)
+        );
+        assert_eq!("push".parse::<Stage>().ok(), Some(Stage::PrePush));
+        assert_eq!(
+            "unknown".parse::<Stage>().unwrap_err().to_string(),
+            "Unknown stage: `unknown`"
+        );
+    }
+
+    #[test]
+    fn unknown_language() -> Result<()> {
+        assert_eq!("python".parse::<Language>().ok(), Some(Language::Python));
+        assert_eq!(
+            "docker_image".parse::<Language>().ok(),
+            Some(Language::DockerImage)
+        );
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(CONFIG_FILE);
+        fs_err::write(
+            &path,
+            indoc::indoc! { r"
+                repos:
+                  - repo: local
+                    hooks:
+                      - id: ruff
+                        name: ruff
+                        entry: ruff
+                        language: pyton
+            "},
+        )?;
+
+        let err = read_config(&path).unwrap_err();
+        let err = anyhow::Error::from(err);
+        insta::assert_snapshot!(err.root_cause(), @"repos: Invalid local repo: Unknown language: `pyton`, expected one of: conda, coursier, dart, docker, docker_image, dotnet, fail, golang, haskell, lua, node, perl, python, r, ruby, rust, swift, pygrep, script, system at line 2 column 3");
+
+        Ok(())
+    }
+
+    #[test]
+    fn locations() -> Result<()> {
+        let yaml = indoc::indoc! {r"
+            repos:
+              - repo: https://github.com/pre-commit/pre-commit-hooks
+                rev: v5.0.0
+                hooks:
+                  - id: trailing-whitespace
+                  # - id: end-of-file-fixer
+                  - id: end-of-file-fixer
+                    args: [--fix=lf]
+                  - id: trailing-whitespace
+              - repo: local
+                hooks:
+                  - {id: ruff, name: ruff, entry: ruff, language: system}
+                  - name: ruff format
+                    id: 'ruff-format'
+                    entry: ruff format
+                    language: system
+              - repo: meta
+                hooks:
+                  - id: identity
+        "};
+        let config = serde_yaml::from_str::<Config>(yaml)?;
+        let locations = Locations::find(&config, yaml);
+
+        let location = |line, column| Some(Location { line, column });
+        assert_eq!(locations.repo(0), location(2, 5));
+        assert_eq!(locations.hook(0, 0), location(5, 9));
+        assert_eq!(locations.hook(0, 1), location(7, 9));
+        assert_eq!(locations.hook(0, 2), location(9, 9));
+        assert_eq!(locations.repo(1), location(10, 5));
+        assert_eq!(locations.hook(1, 0), location(12, 10));
+        assert_eq!(locations.hook(1, 1), location(14, 9));
+        assert_eq!(locations.repo(2), location(17, 5));
+        assert_eq!(locations.hook(2, 0), location(19, 9));
+        assert_eq!(locations.hook(2, 1), None);
+        assert_eq!(locations.repo(3), None);
+        assert_eq!(locations.key("repos"), location(1, 1));
+        assert_eq!(locations.key("rev"), None);
+
+        Ok(())
+    }
 }
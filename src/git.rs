@@ -1,13 +1,17 @@
 use std::collections::HashSet;
+use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::LazyLock;
 
 use anyhow::Result;
 use itertools::Itertools;
+use rustc_hash::{FxHashMap, FxHashSet};
 use tokio::io::AsyncWriteExt;
 use tracing::warn;
 
+use constants::env_vars::EnvVars;
+
 use crate::process::Cmd;
 use crate::{git, process};
 
@@ -23,6 +27,252 @@ pub enum Error {
 
 pub static GIT: LazyLock<Result<PathBuf, which::Error>> = LazyLock::new(|| which::which("git"));
 
+/// The git operations used to select and run hooks.
+///
+/// [`Git`] runs the `git` executable, tests can use an in-memory implementation instead of
+/// setting up a repository.
+pub(crate) trait GitBackend {
+    /// Files staged for commit, except deleted ones.
+    async fn staged_files(&self) -> Result<Vec<String>, Error>;
+    /// Files changed between `old` and `new`.
+    async fn changed_files(&self, old: &str, new: &str) -> Result<Vec<String>, Error>;
+    /// Files tracked by git under `path`, and untracked files that are not ignored if
+    /// `include_untracked` is set.
+    async fn ls_files(
+        &self,
+        path: Option<&Path>,
+        include_untracked: bool,
+    ) -> Result<Vec<String>, Error>;
+    /// Untracked files that are not ignored.
+    async fn untracked_files(&self) -> Result<Vec<String>, Error>;
+    /// Files added with `git add --intent-to-add`.
+    async fn intent_to_add_files(&self) -> Result<Vec<String>, Error>;
+    /// The diff of the working tree against the index.
+    async fn diff(&self) -> Result<Vec<u8>, Error>;
+    /// Files deleted from the index.
+    async fn staged_deletions(&self) -> Result<Vec<String>, Error>;
+    /// Write the index to a tree object, returning its id.
+    async fn write_tree(&self) -> Result<String, Error>;
+    /// The `paths` that are submodules in the index.
+    async fn submodule_files(&self, paths: &[&String]) -> Result<FxHashSet<String>, Error>;
+    /// The `paths` stored with git LFS.
+    async fn lfs_files(&self, paths: &[&String]) -> Result<FxHashSet<String>, Error>;
+    /// The size of the staged content of `paths`.
+    async fn staged_blob_sizes(&self, paths: &[&String]) -> Result<FxHashMap<String, u64>, Error>;
+    /// Whether the repository has any commits.
+    async fn has_head(&self) -> Result<bool, Error>;
+    async fn has_unmerged_paths(&self) -> Result<bool, Error>;
+    async fn is_in_merge_conflict(&self) -> Result<bool, Error>;
+    /// The operation paused halfway in the repository, like an interactive rebase.
+    async fn operation_in_progress(&self) -> Result<Option<Operation>, Error>;
+    async fn conflicted_files(&self) -> Result<Vec<String>, Error>;
+    /// The value of a git config `key`, if set.
+    async fn config_get(&self, key: &str) -> Result<Option<String>, Error>;
+}
+
+/// The [`GitBackend`] running the `git` executable.
+pub(crate) struct Git;
+
+impl GitBackend for Git {
+    async fn staged_files(&self) -> Result<Vec<String>, Error> {
+        get_staged_files().await
+    }
+
+    async fn changed_files(&self, old: &str, new: &str) -> Result<Vec<String>, Error> {
+        get_changed_files(old, new).await
+    }
+
+    async fn ls_files(
+        &self,
+        path: Option<&Path>,
+        include_untracked: bool,
+    ) -> Result<Vec<String>, Error> {
+        git_ls_files(path, include_untracked).await
+    }
+
+    async fn untracked_files(&self) -> Result<Vec<String>, Error> {
+        get_untracked_files().await
+    }
+
+    async fn intent_to_add_files(&self) -> Result<Vec<String>, Error> {
+        intent_to_add_files().await
+    }
+
+    async fn diff(&self) -> Result<Vec<u8>, Error> {
+        get_diff().await
+    }
+
+    async fn staged_deletions(&self) -> Result<Vec<String>, Error> {
+        get_staged_deletions().await
+    }
+
+    async fn write_tree(&self) -> Result<String, Error> {
+        write_tree().await
+    }
+
+    async fn submodule_files(&self, paths: &[&String]) -> Result<FxHashSet<String>, Error> {
+        submodule_files(paths).await
+    }
+
+    async fn lfs_files(&self, paths: &[&String]) -> Result<FxHashSet<String>, Error> {
+        lfs_files(paths).await
+    }
+
+    async fn staged_blob_sizes(&self, paths: &[&String]) -> Result<FxHashMap<String, u64>, Error> {
+        staged_blob_sizes(paths).await
+    }
+
+    async fn has_head(&self) -> Result<bool, Error> {
+        has_head().await
+    }
+
+    async fn has_unmerged_paths(&self) -> Result<bool, Error> {
+        has_unmerged_paths().await
+    }
+
+    async fn is_in_merge_conflict(&self) -> Result<bool, Error> {
+        is_in_merge_conflict().await
+    }
+
+    async fn operation_in_progress(&self) -> Result<Option<Operation>, Error> {
+        operation_in_progress().await
+    }
+
+    async fn conflicted_files(&self) -> Result<Vec<String>, Error> {
+        get_conflicted_files().await
+    }
+
+    async fn config_get(&self, key: &str) -> Result<Option<String>, Error> {
+        get_config(key).await
+    }
+}
+
+/// An in-memory [`GitBackend`] for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct FakeGit {
+    pub(crate) head: bool,
+    pub(crate) tracked: Vec<String>,
+    pub(crate) untracked: Vec<String>,
+    pub(crate) staged: Vec<String>,
+    /// Files changed between two refs, keyed by `(old, new)`.
+    pub(crate) changed: FxHashMap<(String, String), Vec<String>>,
+    pub(crate) intent_to_add: Vec<String>,
+    pub(crate) diff: Vec<u8>,
+    pub(crate) deleted: Vec<String>,
+    pub(crate) tree: String,
+    pub(crate) submodules: Vec<String>,
+    pub(crate) lfs: Vec<String>,
+    /// The size of the staged content of files.
+    pub(crate) blob_sizes: FxHashMap<String, u64>,
+    pub(crate) unmerged: bool,
+    /// The conflicted files, `Some` while in a merge conflict.
+    pub(crate) conflicted: Option<Vec<String>>,
+    pub(crate) operation: Option<Operation>,
+    pub(crate) config: FxHashMap<String, String>,
+}
+
+#[cfg(test)]
+impl GitBackend for FakeGit {
+    async fn staged_files(&self) -> Result<Vec<String>, Error> {
+        Ok(self.staged.clone())
+    }
+
+    async fn changed_files(&self, old: &str, new: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .changed
+            .get(&(old.to_string(), new.to_string()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn ls_files(
+        &self,
+        path: Option<&Path>,
+        include_untracked: bool,
+    ) -> Result<Vec<String>, Error> {
+        let untracked = if include_untracked {
+            self.untracked.as_slice()
+        } else {
+            &[]
+        };
+        Ok(self
+            .tracked
+            .iter()
+            .chain(untracked)
+            .filter(|file| path.is_none_or(|path| Path::new(file).starts_with(path)))
+            .cloned()
+            .collect())
+    }
+
+    async fn untracked_files(&self) -> Result<Vec<String>, Error> {
+        Ok(self.untracked.clone())
+    }
+
+    async fn intent_to_add_files(&self) -> Result<Vec<String>, Error> {
+        Ok(self.intent_to_add.clone())
+    }
+
+    async fn diff(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.diff.clone())
+    }
+
+    async fn staged_deletions(&self) -> Result<Vec<String>, Error> {
+        Ok(self.deleted.clone())
+    }
+
+    async fn write_tree(&self) -> Result<String, Error> {
+        Ok(self.tree.clone())
+    }
+
+    async fn submodule_files(&self, paths: &[&String]) -> Result<FxHashSet<String>, Error> {
+        Ok(paths
+            .iter()
+            .filter(|path| self.submodules.contains(**path))
+            .map(|path| (*path).clone())
+            .collect())
+    }
+
+    async fn lfs_files(&self, paths: &[&String]) -> Result<FxHashSet<String>, Error> {
+        Ok(paths
+            .iter()
+            .filter(|path| self.lfs.contains(**path))
+            .map(|path| (*path).clone())
+            .collect())
+    }
+
+    async fn staged_blob_sizes(&self, paths: &[&String]) -> Result<FxHashMap<String, u64>, Error> {
+        Ok(paths
+            .iter()
+            .filter_map(|path| Some(((*path).clone(), *self.blob_sizes.get(*path)?)))
+            .collect())
+    }
+
+    async fn has_head(&self) -> Result<bool, Error> {
+        Ok(self.head)
+    }
+
+    async fn has_unmerged_paths(&self) -> Result<bool, Error> {
+        Ok(self.unmerged)
+    }
+
+    async fn is_in_merge_conflict(&self) -> Result<bool, Error> {
+        Ok(self.conflicted.is_some())
+    }
+
+    async fn operation_in_progress(&self) -> Result<Option<Operation>, Error> {
+        Ok(self.operation)
+    }
+
+    async fn conflicted_files(&self) -> Result<Vec<String>, Error> {
+        Ok(self.conflicted.clone().unwrap_or_default())
+    }
+
+    async fn config_get(&self, key: &str) -> Result<Option<String>, Error> {
+        Ok(self.config.get(key).cloned())
+    }
+}
+
 static GIT_ENV_REMOVE: LazyLock<()> = LazyLock::new(|| {
     let keep = &[
         "GIT_EXEC_PATH",
@@ -48,11 +298,16 @@ static GIT_ENV_REMOVE: LazyLock<()> = LazyLock::new(|| {
     }
 });
 
+/// Remove the inherited git environment variables that would change how our git commands behave.
+pub(crate) fn clear_git_env() {
+    LazyLock::force(&GIT_ENV_REMOVE);
+}
+
 pub fn git_cmd(summary: &str) -> Result<Cmd, Error> {
     let mut cmd = Cmd::new(GIT.as_ref().map_err(|&e| Error::GitNotFound(e))?, summary);
     cmd.arg("-c").arg("core.useBuiltinFSMonitor=false");
 
-    LazyLock::force(&GIT_ENV_REMOVE);
+    clear_git_env();
 
     Ok(cmd)
 }
@@ -97,9 +352,21 @@ pub async fn get_changed_files(old: &str, new: &str) -> Result<Vec<String>, Erro
     Ok(zsplit(&output.stdout))
 }
 
-pub async fn git_ls_files(path: Option<&Path>) -> Result<Vec<String>, Error> {
+/// List files tracked by git, and untracked files that are not ignored if `include_untracked` is set.
+///
+/// Ignore rules are delegated to git (`--exclude-standard`), which respects `.gitignore` files in
+/// all directories, `.git/info/exclude` and the global excludes file.
+pub async fn git_ls_files(
+    path: Option<&Path>,
+    include_untracked: bool,
+) -> Result<Vec<String>, Error> {
     let mut cmd = git_cmd("get git all files")?;
     cmd.arg("ls-files").arg("-z").check(true);
+    if include_untracked {
+        cmd.arg("--cached")
+            .arg("--others")
+            .arg("--exclude-standard");
+    }
 
     if let Some(p) = path {
         cmd.arg("--").arg(p);
@@ -108,6 +375,25 @@ pub async fn git_ls_files(path: Option<&Path>) -> Result<Vec<String>, Error> {
     Ok(zsplit(&output.stdout))
 }
 
+/// List untracked files that are not ignored, relative to the repository root.
+///
+/// Like [`git_ls_files`], ignore rules are delegated to git.
+pub async fn get_untracked_files() -> Result<Vec<String>, Error> {
+    let output = git_cmd("get untracked files")?
+        .arg("status")
+        .arg("--porcelain")
+        .arg("-z")
+        .arg("--untracked-files=all")
+        .arg("--no-renames")
+        .check(true)
+        .output()
+        .await?;
+    Ok(zsplit(&output.stdout)
+        .into_iter()
+        .filter_map(|entry| entry.strip_prefix("?? ").map(ToString::to_string))
+        .collect())
+}
+
 pub async fn get_git_dir() -> Result<PathBuf, Error> {
     let output = git_cmd("get git dir")?
         .arg("rev-parse")
@@ -120,6 +406,21 @@ pub async fn get_git_dir() -> Result<PathBuf, Error> {
     ))
 }
 
+/// Get the absolute path of the git common directory of the repository containing `path`.
+pub async fn get_git_common_dir_of(path: &Path) -> Result<PathBuf, Error> {
+    let output = git_cmd("get git common dir")?
+        .arg("rev-parse")
+        .arg("--path-format=absolute")
+        .arg("--git-common-dir")
+        .current_dir(path)
+        .check(true)
+        .output()
+        .await?;
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
 pub async fn get_git_common_dir() -> Result<PathBuf, Error> {
     let output = git_cmd("get git common dir")?
         .arg("rev-parse")
@@ -165,6 +466,22 @@ pub async fn file_not_staged(file: &Path) -> Result<bool> {
     Ok(status.code().is_some_and(|code| code == 1))
 }
 
+/// Check if the repository has any commits, i.e. `HEAD` points to a valid commit.
+pub async fn has_head() -> Result<bool, Error> {
+    let status = git_cmd("check HEAD exists")?
+        .arg("rev-parse")
+        .arg("--verify")
+        .arg("--quiet")
+        .arg("HEAD")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .check(false)
+        .status()
+        .await?;
+
+    Ok(status.success())
+}
+
 pub async fn has_unmerged_paths() -> Result<bool, Error> {
     let output = git_cmd("check has unmerged paths")?
         .arg("ls-files")
@@ -180,6 +497,54 @@ pub async fn is_in_merge_conflict() -> Result<bool, Error> {
     Ok(git_dir.join("MERGE_HEAD").try_exists()? && git_dir.join("MERGE_MSG").try_exists()?)
 }
 
+/// A git operation that can stop halfway, leaving its state in the git directory until it's
+/// continued or aborted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rebase => write!(f, "rebase"),
+            Self::CherryPick => write!(f, "cherry-pick"),
+            Self::Revert => write!(f, "revert"),
+            Self::Bisect => write!(f, "bisect"),
+        }
+    }
+}
+
+/// Find the operation in progress from the state files git keeps in the git directory.
+///
+/// `rebase-apply` is shared with `git am`, which is reported as a rebase.
+pub async fn operation_in_progress() -> Result<Option<Operation>, Error> {
+    let git_dir = get_git_dir().await?;
+    let states = [
+        ("rebase-merge", Operation::Rebase),
+        ("rebase-apply", Operation::Rebase),
+        ("CHERRY_PICK_HEAD", Operation::CherryPick),
+        ("REVERT_HEAD", Operation::Revert),
+        ("BISECT_LOG", Operation::Bisect),
+    ];
+    for (state, operation) in states {
+        if git_dir.join(state).try_exists()? {
+            return Ok(Some(operation));
+        }
+    }
+
+    // A sequence of picks or reverts, stopped between two of its commits.
+    match fs_err::read_to_string(git_dir.join("sequencer").join("todo")) {
+        Ok(todo) if todo.starts_with("revert") => Ok(Some(Operation::Revert)),
+        Ok(_) => Ok(Some(Operation::CherryPick)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
 pub async fn get_conflicted_files() -> Result<Vec<String>, Error> {
     let tree = git_cmd("git write-tree")?
         .arg("write-tree")
@@ -221,18 +586,144 @@ async fn parse_merge_msg_for_conflicts() -> Result<Vec<String>, Error> {
     Ok(conflicts)
 }
 
+/// The diff of the working tree against the index.
 pub async fn get_diff() -> Result<Vec<u8>, Error> {
     let output = git_cmd("git diff")?
+        // Only quote paths with control characters, quotes or backslashes, see `header_path`.
+        .arg("-c")
+        .arg("core.quotePath=false")
         .arg("diff")
         .arg("--no-ext-diff") // Disable external diff drivers
         .arg("--no-textconv")
         .arg("--ignore-submodules")
+        // Include the content of binary files, otherwise changing an already modified
+        // binary file again shows up as the same `Binary files differ` line.
+        .arg("--binary")
         .check(true)
         .output()
         .await?;
     Ok(output.stdout)
 }
 
+/// The files deleted from the index.
+///
+/// A hook removing a file with `git rm` leaves nothing to diff against the index, the staged
+/// deletions make it show up as a change too.
+pub async fn get_staged_deletions() -> Result<Vec<String>, Error> {
+    let output = git_cmd("get staged deletions")?
+        .arg("diff")
+        .arg("--cached")
+        .arg("--no-ext-diff")
+        .arg("--name-only")
+        .arg("--diff-filter=D")
+        .arg("-z")
+        .check(true)
+        .output()
+        .await?;
+    Ok(zsplit(&output.stdout))
+}
+
+/// The files whose changes differ between two outputs of [`get_diff`], relative to the
+/// repository root.
+pub fn changed_between(before: &[u8], after: &[u8]) -> Vec<String> {
+    let before = file_diffs(before);
+    let after = file_diffs(after);
+    before
+        .keys()
+        .chain(after.keys())
+        .filter(|path| before.get(*path) != after.get(*path))
+        .unique()
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        .collect()
+}
+
+/// Split the output of `git diff` into the changes of each file, keyed by the path.
+fn file_diffs(diff: &[u8]) -> FxHashMap<Vec<u8>, &[u8]> {
+    const HEADER: &[u8] = b"diff --git ";
+
+    let mut files = FxHashMap::default();
+    let mut current: Option<(Vec<u8>, usize)> = None;
+    let mut offset = 0;
+    for line in diff.split_inclusive(|&byte| byte == b'\n') {
+        if let Some(header) = line.strip_prefix(HEADER) {
+            if let Some((path, start)) = current.take() {
+                files.insert(path, &diff[start..offset]);
+            }
+            let header = header.strip_suffix(b"\n").unwrap_or(header);
+            current = Some((header_path(header), offset));
+        }
+        offset += line.len();
+    }
+    if let Some((path, start)) = current {
+        files.insert(path, &diff[start..]);
+    }
+
+    files
+}
+
+/// The new path of a file from its `a/<path> b/<path>` header in the output of `git diff`.
+///
+/// Git quotes paths with special characters like C strings, e.g. `"a/tab\there" "b/tab\there"`.
+fn header_path(header: &[u8]) -> Vec<u8> {
+    if header.ends_with(b"\"") {
+        // A quote can't appear unescaped inside a quoted path.
+        if let Some(idx) = header.windows(4).rposition(|window| window == b" \"b/") {
+            return unquote(&header[idx + 4..header.len() - 1]);
+        }
+    }
+
+    // Both paths are the same unless the file was renamed, which also works for paths
+    // containing ` b/`.
+    let len = header.len();
+    if header.starts_with(b"a/") && len % 2 == 1 && len >= 5 {
+        let path = &header[len.div_ceil(2) + 2..];
+        if header[2..].starts_with(path) && header[len / 2..].starts_with(b" b/") {
+            return path.to_vec();
+        }
+    }
+    header
+        .windows(3)
+        .rposition(|window| window == b" b/")
+        .map_or(header, |idx| &header[idx + 3..])
+        .to_vec()
+}
+
+/// Undo the C-style quoting git applies to paths with special characters.
+fn unquote(quoted: &[u8]) -> Vec<u8> {
+    let mut path = Vec::with_capacity(quoted.len());
+    let mut idx = 0;
+    while idx < quoted.len() {
+        let byte = quoted[idx];
+        idx += 1;
+        if byte != b'\\' || idx == quoted.len() {
+            path.push(byte);
+            continue;
+        }
+        let escaped = quoted[idx];
+        idx += 1;
+        path.push(match escaped {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            // Bytes outside of ASCII, as three octal digits.
+            b'0'..=b'7' => {
+                let digits = quoted.get(idx..idx + 2).unwrap_or_default();
+                idx += digits.len();
+                digits.iter().fold(escaped - b'0', |value, digit| {
+                    value.wrapping_mul(8).wrapping_add(digit - b'0')
+                })
+            }
+            // `\"` and `\\`.
+            other => other,
+        });
+    }
+    path
+}
+
 /// Create a tree object from the current index.
 ///
 /// The name of the new tree object is printed to standard output.
@@ -246,6 +737,30 @@ pub async fn write_tree() -> Result<String, Error> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// Get the commit checked out in the repository at `path`.
+pub async fn get_head_commit(path: &Path) -> Result<String, Error> {
+    let output = git_cmd("get head commit")?
+        .arg("rev-parse")
+        .arg("HEAD")
+        .current_dir(path)
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// List the files tracked by git in the repository at `path`.
+pub async fn get_tracked_files(path: &Path) -> Result<Vec<String>, Error> {
+    let output = git_cmd("get tracked files")?
+        .arg("ls-files")
+        .arg("-z")
+        .current_dir(path)
+        .check(true)
+        .output()
+        .await?;
+    Ok(zsplit(&output.stdout))
+}
+
 /// Get the path of the top-level directory of the working tree.
 pub async fn get_root() -> Result<PathBuf, Error> {
     let output = git_cmd("get git root")?
@@ -300,17 +815,18 @@ async fn init_repo(url: &str, path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
+async fn shallow_clone(rev: &str, path: &Path, on_progress: &dyn Fn(&str)) -> Result<(), Error> {
     git_cmd("git shallow clone")?
         .current_dir(path)
         .arg("-c")
         .arg("protocol.version=2")
         .arg("fetch")
+        .arg("--progress")
         .arg("origin")
         .arg(rev)
         .arg("--depth=1")
         .check(true)
-        .output()
+        .output_with_stderr_lines(|line| report_fetch_progress(line, on_progress))
         .await?;
 
     git_cmd("git checkout")?
@@ -337,14 +853,15 @@ async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
+async fn full_clone(rev: &str, path: &Path, on_progress: &dyn Fn(&str)) -> Result<(), Error> {
     git_cmd("git full clone")?
         .current_dir(path)
         .arg("fetch")
+        .arg("--progress")
         .arg("origin")
         .arg("--tags")
         .check(true)
-        .output()
+        .output_with_stderr_lines(|line| report_fetch_progress(line, on_progress))
         .await?;
 
     git_cmd("git checkout")?
@@ -368,32 +885,221 @@ async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-pub async fn clone_repo(url: &str, rev: &str, path: &Path) -> Result<(), Error> {
+/// Report a progress line of `git fetch --progress`, like
+/// `remote: Counting objects:  45% (9/20)` or
+/// `Receiving objects:  45% (9/20), 1.00 MiB | 2.00 MiB/s`, as `Receiving objects: 45%`.
+fn report_fetch_progress(line: &str, on_progress: &dyn Fn(&str)) {
+    if let Some(progress) = fetch_progress(line) {
+        on_progress(&progress);
+    }
+}
+
+fn fetch_progress(line: &str) -> Option<String> {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (stage, rest) = line.split_once(':')?;
+    let (percent, _) = rest.trim_start().split_once('%')?;
+    let percent = percent.parse::<u8>().ok()?;
+    Some(format!("{stage}: {percent}%"))
+}
+
+/// Borrow objects from the `reference` repo, like `git clone --reference` does.
+async fn add_alternates(path: &Path, reference: &Path) -> Result<(), Error> {
+    let info_dir = path.join(".git").join("objects").join("info");
+    fs_err::tokio::create_dir_all(&info_dir).await?;
+
+    let objects = std::path::absolute(reference.join(".git").join("objects"))?;
+    fs_err::tokio::write(
+        info_dir.join("alternates"),
+        format!("{}\n", objects.display()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Keep a ref to the checked out commit, so that repos borrowing objects from this one
+/// advertise it when fetching, and only the missing objects are transferred.
+async fn pin_head(path: &Path) -> Result<(), Error> {
+    git_cmd("git update-ref")?
+        .current_dir(path)
+        .arg("update-ref")
+        .arg("refs/prek/head")
+        .arg("HEAD")
+        .check(true)
+        .output()
+        .await?;
+
+    Ok(())
+}
+
+/// Clone the repo at `url` and checkout `rev` into `path`.
+///
+/// If `reference` is given, objects already present in that clone are reused instead of
+/// being fetched again. The reference repo must outlive the new clone.
+///
+/// `on_progress` is called with the progress of fetching the objects, e.g.
+/// `Receiving objects: 45%`.
+pub async fn clone_repo(
+    url: &str,
+    rev: &str,
+    path: &Path,
+    reference: Option<&Path>,
+    on_progress: &dyn Fn(&str),
+) -> Result<(), Error> {
     init_repo(url, path).await?;
 
-    if let Err(err) = shallow_clone(rev, path).await {
+    if let Some(reference) = reference {
+        add_alternates(path, reference).await?;
+    }
+
+    if let Err(err) = shallow_clone(rev, path, on_progress).await {
         warn!(?err, "Failed to shallow clone, falling back to full clone");
-        full_clone(rev, path).await
-    } else {
-        Ok(())
+        full_clone(rev, path, on_progress).await?;
     }
+
+    pin_head(path).await
 }
 
-pub async fn has_hooks_path_set() -> Result<bool> {
-    let output = git_cmd("get git hooks path")?
+/// Get the value of a git config `key`, `None` if it's not set.
+pub async fn get_config(key: &str) -> Result<Option<String>, Error> {
+    let output = git_cmd("get git config")?
         .arg("config")
         .arg("--get")
-        .arg("core.hooksPath")
+        .arg(key)
         .check(false)
         .output()
         .await?;
     if output.status.success() {
-        Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
     } else {
-        Ok(false)
+        Ok(None)
     }
 }
 
+/// The commit being made, for hooks of the `prepare-commit-msg` and `commit-msg` stages.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    /// The commit concludes a merge.
+    pub is_merge: bool,
+    /// The commit amends `HEAD`.
+    pub is_amend: bool,
+    /// The file with the commit message.
+    pub message_file: Option<PathBuf>,
+}
+
+impl CommitInfo {
+    /// Detect a merge or an amend from the state of `git_dir`, and the message `source` and
+    /// commit `object` git passes to `prepare-commit-msg`.
+    ///
+    /// `commit-msg` gets neither, an amend can't be told apart from a new commit there.
+    fn detect(git_dir: &Path, source: Option<&str>, object: Option<&str>) -> (bool, bool) {
+        let is_merge = source == Some("merge") || git_dir.join("MERGE_HEAD").is_file();
+        // `git commit -c <commit>` reuses the message of another commit with the same source.
+        let is_amend = source == Some("commit") && object == Some("HEAD");
+        (is_merge, is_amend)
+    }
+}
+
+/// Gather the [`CommitInfo`] of the commit being made.
+///
+/// The author is read like git does, from `GIT_AUTHOR_NAME` and `GIT_AUTHOR_EMAIL`, then from
+/// the `user.name` and `user.email` config.
+pub async fn commit_info(
+    source: Option<&str>,
+    object: Option<&str>,
+    message_file: Option<&Path>,
+) -> Result<CommitInfo, Error> {
+    let author_name = match EnvVars::var(EnvVars::GIT_AUTHOR_NAME) {
+        Ok(name) => Some(name),
+        Err(_) => get_config("user.name").await?,
+    };
+    let author_email = match EnvVars::var(EnvVars::GIT_AUTHOR_EMAIL) {
+        Ok(email) => Some(email),
+        Err(_) => get_config("user.email").await?,
+    };
+    let (is_merge, is_amend) = CommitInfo::detect(&get_git_dir().await?, source, object);
+
+    Ok(CommitInfo {
+        author_name,
+        author_email,
+        is_merge,
+        is_amend,
+        message_file: message_file.map(Path::to_path_buf),
+    })
+}
+
+pub async fn has_hooks_path_set() -> Result<bool> {
+    Ok(get_config("core.hooksPath")
+        .await?
+        .is_some_and(|path| !path.is_empty()))
+}
+
+/// Get the sizes of the blobs staged in the index for `paths`.
+///
+/// Paths without an entry in the index are omitted from the result.
+pub async fn staged_blob_sizes<T: FromIterator<(String, u64)>>(
+    paths: &[&String],
+) -> Result<T, Error> {
+    let mut job = git_cmd("git cat-file")?
+        .arg("cat-file")
+        .arg("--batch-check=%(objectsize)")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .check(true)
+        .spawn()?;
+
+    let mut stdin = job.stdin.take().expect("Failed to open stdin");
+    let mut input = String::new();
+    for path in paths {
+        input.push(':');
+        input.push_str(path);
+        input.push('\n');
+    }
+    // Write concurrently with reading, so a large output can't block the input.
+    let write = async move {
+        stdin.write_all(input.as_bytes()).await?;
+        drop(stdin);
+        Ok(())
+    };
+    let ((), output) = tokio::try_join!(write, job.wait_with_output())?;
+
+    Ok(paths
+        .iter()
+        .zip(String::from_utf8_lossy(&output.stdout).lines())
+        .filter_map(|(path, line)| {
+            // Missing entries are reported as `:<path> missing`.
+            let size = line.parse::<u64>().ok()?;
+            Some(((*path).clone(), size))
+        })
+        .collect())
+}
+
+/// Get the paths of `paths` that are submodules, recorded as gitlinks in the index.
+pub async fn submodule_files<T: FromIterator<String>>(paths: &[&String]) -> Result<T, Error> {
+    let output = git_cmd("get submodule files")?
+        .arg("ls-files")
+        .arg("--stage")
+        .arg("-z")
+        .arg("--")
+        .args(paths)
+        .check(true)
+        .output()
+        .await?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split_terminator('\0')
+        .filter_map(|line| {
+            // `<mode> <object> <stage>\t<file>`, submodules have the gitlink mode.
+            let (metadata, file) = line.split_once('\t')?;
+            metadata.starts_with("160000 ").then(|| file.to_owned())
+        })
+        .collect())
+}
+
 pub async fn lfs_files<T: FromIterator<String>>(paths: &[&String]) -> Result<T, Error> {
     let mut job = git_cmd("git check-attr")?
         .arg("check-attr")
@@ -426,3 +1132,116 @@ pub async fn lfs_files<T: FromIterator<String>>(paths: &[&String]) -> Result<T,
             .collect(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_merge_and_amend() -> anyhow::Result<()> {
+        let git_dir = tempfile::tempdir()?;
+
+        assert_eq!(
+            CommitInfo::detect(git_dir.path(), None, None),
+            (false, false)
+        );
+        assert_eq!(
+            CommitInfo::detect(git_dir.path(), Some("message"), None),
+            (false, false)
+        );
+        assert_eq!(
+            CommitInfo::detect(git_dir.path(), Some("commit"), Some("HEAD")),
+            (false, true)
+        );
+        // `git commit -c <commit>`
+        assert_eq!(
+            CommitInfo::detect(git_dir.path(), Some("commit"), Some("1a2b3c")),
+            (false, false)
+        );
+        assert_eq!(
+            CommitInfo::detect(git_dir.path(), Some("merge"), None),
+            (true, false)
+        );
+
+        // `commit-msg` gets no source, a merge in progress is found from `MERGE_HEAD`.
+        fs_err::write(git_dir.path().join("MERGE_HEAD"), "1a2b3c\n")?;
+        assert_eq!(
+            CommitInfo::detect(git_dir.path(), None, None),
+            (true, false)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_fetch_progress() {
+        assert_eq!(
+            fetch_progress("remote: Counting objects:  45% (9/20)"),
+            Some("Counting objects: 45%".to_string())
+        );
+        assert_eq!(
+            fetch_progress("Receiving objects: 100% (20/20), 1.00 MiB | 2.00 MiB/s, done."),
+            Some("Receiving objects: 100%".to_string())
+        );
+        assert_eq!(
+            fetch_progress("remote: Enumerating objects: 20, done."),
+            None
+        );
+        assert_eq!(fetch_progress("From https://github.com/j178/prek"), None);
+    }
+
+    #[test]
+    fn changed_between_diffs() {
+        let before = indoc::indoc! {b"
+            diff --git a/unrelated.txt b/unrelated.txt
+            --- a/unrelated.txt
+            +++ b/unrelated.txt
+            @@ -1 +1 @@
+            -old
+            +new
+            diff --git a/with space.txt b/with space.txt
+            --- a/with space.txt
+            +++ b/with space.txt
+            @@ -1 +1 @@
+            -a
+            +b
+        "};
+        let after = indoc::indoc! {b"
+            diff --git a/unrelated.txt b/unrelated.txt
+            --- a/unrelated.txt
+            +++ b/unrelated.txt
+            @@ -1 +1 @@
+            -old
+            +new
+            diff --git a/fixed.txt b/fixed.txt
+            --- a/fixed.txt
+            +++ b/fixed.txt
+            @@ -1 +1 @@
+            -broken
+            +fixed
+            diff --git a/with space.txt b/with space.txt
+            --- a/with space.txt
+            +++ b/with space.txt
+            @@ -1 +1 @@
+            -a
+            +c
+        "};
+
+        let mut changed = changed_between(before, after);
+        changed.sort();
+        assert_eq!(changed, ["fixed.txt", "with space.txt"]);
+        assert!(changed_between(after, after).is_empty());
+    }
+
+    #[test]
+    fn header_paths() {
+        assert_eq!(header_path(b"a/file.txt b/file.txt"), b"file.txt");
+        assert_eq!(header_path(b"a/dir b/file b/dir b/file"), b"dir b/file");
+        assert_eq!(header_path(b"a/old.txt b/new.txt"), b"new.txt");
+        assert_eq!(header_path(br#""a/tab\there" "b/tab\there""#), b"tab\there");
+        assert_eq!(
+            header_path(br#""a/quote\"d \\ b/\303\251" "b/quote\"d \\ b/\303\251""#),
+            "quote\"d \\ b/é".as_bytes()
+        );
+    }
+}
@@ -1,38 +1,75 @@
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, OnceLock};
 
 use anyhow::{Context, Result};
 use clap::ValueEnum;
+use itertools::Itertools;
 use rand::Rng;
 use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tracing::error;
+use tracing::{debug, error};
 use url::Url;
 
 use crate::config::{
-    self, Config, HookOptions, Language, LocalHook, MANIFEST_FILE, ManifestHook, MetaHook,
-    RemoteHook, Stage, read_manifest,
+    self, CheckoutType, Config, HookOptions, HookStdin, Language, LocalHook, MANIFEST_FILE,
+    ManifestHook, MetaHook, RemoteHook, Stage, location_prefix, read_manifest,
 };
+use constants::env_vars::EnvVars;
+
+use crate::languages::env_matches;
 use crate::languages::version::LanguageRequest;
 use crate::store::Store;
+use crate::warn_user_once;
 
 #[derive(Error, Debug)]
 pub(crate) enum Error {
     #[error(transparent)]
     InvalidConfig(#[from] config::Error),
 
-    #[error("Hook `{hook}` is invalid")]
+    #[error("{}Hook `{hook}` is invalid", location_prefix(.location.as_deref()))]
     InvalidHook {
         hook: String,
+        /// Where the hook is defined, as `file:line:column`.
+        location: Option<String>,
         #[source]
         error: anyhow::Error,
     },
 }
 
+/// The languages implemented in this build with `feature`, formatted for error messages.
+fn languages_supporting(feature: fn(Language) -> bool) -> String {
+    Language::ALL
+        .into_iter()
+        .filter(|&language| {
+            Language::supported(language)
+                && language.missing_feature().is_none()
+                && feature(language)
+        })
+        .map(|language| format!("`{language}`"))
+        .join(", ")
+}
+
+impl Error {
+    /// Add where the hook is defined to an invalid hook error.
+    #[must_use]
+    pub(crate) fn at(self, location: String) -> Self {
+        match self {
+            Self::InvalidHook { hook, error, .. } => Self::InvalidHook {
+                hook,
+                location: Some(location),
+                error,
+            },
+            err => err,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum Repo {
     Remote {
@@ -105,6 +142,7 @@ impl Display for Repo {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct HookBuilder {
     repo: Arc<Repo>,
     config: ManifestHook,
@@ -159,10 +197,15 @@ impl HookBuilder {
         options.types.get_or_insert(vec!["file".to_string()]);
         options.types_or.get_or_insert_default();
         options.exclude_types.get_or_insert_default();
+        options.files_case_insensitive.get_or_insert(false);
         options.always_run.get_or_insert(false);
         options.fail_fast.get_or_insert(false);
         options.pass_filenames.get_or_insert(true);
         options.require_serial.get_or_insert(false);
+        options.priority.get_or_insert(0);
+        options
+            .post_checkout_on
+            .get_or_insert_with(|| vec![CheckoutType::Branch]);
         options.verbose.get_or_insert(false);
         options
             .stages
@@ -176,6 +219,9 @@ impl HookBuilder {
         let HookOptions {
             language_version,
             additional_dependencies,
+            stdin,
+            working_dir,
+            minimum_prek_version,
             ..
         } = &self.config.options;
 
@@ -183,27 +229,122 @@ impl HookBuilder {
             .as_ref()
             .map_or(&[][..], |deps| deps.as_slice());
 
+        // Meta hooks are builtin, and `fail` hooks print their entry as a message.
+        if self.config.entry.trim().is_empty()
+            && language != Language::Fail
+            && !matches!(*self.repo, Repo::Meta { .. })
+        {
+            return Err(Error::InvalidHook {
+                hook: self.config.id.clone(),
+                location: None,
+                error: anyhow::anyhow!("Hook `{}` has an empty entry", self.config.id),
+            });
+        }
+
+        if let Some(required) = minimum_prek_version {
+            config::check_prek_version(required).map_err(|err| Error::InvalidHook {
+                hook: self.config.id.clone(),
+                location: None,
+                error: err.into(),
+            })?;
+        }
+
+        if stdin.is_some() && !matches!(language, Language::System | Language::Script) {
+            return Err(Error::InvalidHook {
+                hook: self.config.id.clone(),
+                location: None,
+                error: anyhow::anyhow!(
+                    "Hook specified `stdin` but the language `{language}` does not support it, only `system` and `script` hooks do",
+                ),
+            });
+        }
+
+        if let Some(working_dir) = working_dir {
+            // The repository root is mounted in the container of docker hooks.
+            if matches!(language, Language::Docker | Language::DockerImage) {
+                return Err(Error::InvalidHook {
+                    hook: self.config.id.clone(),
+                    location: None,
+                    error: anyhow::anyhow!(
+                        "Hook specified `working_dir` but the language `{language}` does not support it, docker hooks always run from the repository root",
+                    ),
+                });
+            }
+            // Meta hooks are builtin, they check the configuration of the repository root.
+            if matches!(*self.repo, Repo::Meta { .. }) {
+                return Err(Error::InvalidHook {
+                    hook: self.config.id.clone(),
+                    location: None,
+                    error: anyhow::anyhow!(
+                        "Hook specified `working_dir` but meta hooks do not support it, they always run from the repository root",
+                    ),
+                });
+            }
+            if !Path::new(working_dir)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(Error::InvalidHook {
+                    hook: self.config.id.clone(),
+                    location: None,
+                    error: anyhow::anyhow!(
+                        "Hook specified `working_dir` `{working_dir}` outside of the repository, it must be a relative path inside it",
+                    ),
+                });
+            }
+        }
+
+        let language_version = language_version
+            .as_deref()
+            .filter(|version| *version != "default");
+
+        // The hook is skipped or run as a `system` hook, its environment options are not wrong,
+        // just unused.
+        if !Language::supported(language) {
+            let outcome = if language.supports_system_fallback() {
+                "will be skipped, or run as a `system` hook ignoring it with `--allow-system-fallback`"
+            } else {
+                "will be skipped"
+            };
+            if let Some(language_version) = language_version {
+                warn_user_once!(
+                    "prek does not yet implement `{language}`, hook `{}` specified `language_version` `{language_version}` but {outcome}",
+                    self.config.id,
+                );
+            }
+            if !additional_dependencies.is_empty() {
+                warn_user_once!(
+                    "prek does not yet implement `{language}`, hook `{}` specified `additional_dependencies` `{}` but {outcome}",
+                    self.config.id,
+                    additional_dependencies.join(", "),
+                );
+            }
+            return Ok(());
+        }
+
         if !language.supports_dependency() && !additional_dependencies.is_empty() {
             return Err(Error::InvalidHook {
                 hook: self.config.id.clone(),
+                location: None,
                 error: anyhow::anyhow!(
-                    "Hook specified `additional_dependencies` `{}` but the language `{}` does not support installing dependencies for now",
+                    "Hook specified `additional_dependencies` `{}` but the language `{}` does not support installing dependencies for now, only {} hooks do",
                     additional_dependencies.join(", "),
                     language,
+                    languages_supporting(Language::supports_dependency),
                 ),
             });
         }
 
         if !language.supports_install_env() {
-            if let Some(language_version) = language_version
-                && language_version != "default"
-            {
+            if let Some(language_version) = language_version {
                 return Err(Error::InvalidHook {
                     hook: self.config.id.clone(),
+                    location: None,
                     error: anyhow::anyhow!(
-                        "Hook specified `language_version` `{}` but the language `{}` does not install an environment",
+                        "Hook specified `language_version` `{}` but the language `{}` does not install an environment, only {} hooks accept a `language_version`",
                         language_version,
                         language,
+                        languages_supporting(Language::supports_language_version),
                     ),
                 });
             }
@@ -211,10 +352,12 @@ impl HookBuilder {
             if !additional_dependencies.is_empty() {
                 return Err(Error::InvalidHook {
                     hook: self.config.id.clone(),
+                    location: None,
                     error: anyhow::anyhow!(
-                        "Hook specified `additional_dependencies` `{}` but the language `{}` does not install an environment",
+                        "Hook specified `additional_dependencies` `{}` but the language `{}` does not install an environment, only {} hooks accept `additional_dependencies`",
                         additional_dependencies.join(", "),
                         language,
+                        languages_supporting(Language::supports_dependency),
                     ),
                 });
             }
@@ -223,6 +366,14 @@ impl HookBuilder {
         Ok(())
     }
 
+    /// Return the configuration the hook will be built from, with the default values filled in.
+    pub(crate) fn resolved_config(&self) -> Result<ManifestHook, Error> {
+        self.check()?;
+        let mut builder = self.clone();
+        builder.fill_in_defaults();
+        Ok(builder.config)
+    }
+
     /// Build the hook.
     pub(crate) fn build(mut self) -> Result<Hook, Error> {
         self.check()?;
@@ -233,10 +384,14 @@ impl HookBuilder {
         let language_request = LanguageRequest::parse(self.config.language, &language_version)
             .map_err(|e| Error::InvalidHook {
                 hook: self.config.id.clone(),
+                location: None,
                 error: anyhow::anyhow!(e),
             })?;
 
-        let entry = Entry::new(self.config.id.clone(), self.config.entry);
+        // The entry of `fail` hooks is a message, and meta hooks are run by prek itself.
+        let expand =
+            !matches!(*self.repo, Repo::Meta { .. }) && self.config.language != Language::Fail;
+        let entry = Entry::new(self.config.id.clone(), self.config.entry, expand);
 
         let additional_dependencies = options
             .additional_dependencies
@@ -246,6 +401,7 @@ impl HookBuilder {
 
         Ok(Hook {
             entry,
+            language_version,
             language_request,
             additional_dependencies,
             dependencies: OnceLock::new(),
@@ -257,6 +413,9 @@ impl HookBuilder {
             alias: options.alias.expect("alias not set"),
             files: options.files,
             exclude: options.exclude,
+            files_case_insensitive: options
+                .files_case_insensitive
+                .expect("files_case_insensitive not set"),
             types: options.types.expect("types not set"),
             types_or: options.types_or.expect("types_or not set"),
             exclude_types: options.exclude_types.expect("exclude_types not set"),
@@ -267,7 +426,11 @@ impl HookBuilder {
             description: options.description,
             log_file: options.log_file,
             require_serial: options.require_serial.expect("require_serial not set"),
+            stdin: options.stdin,
+            working_dir: options.working_dir,
+            priority: options.priority.expect("priority not set"),
             stages: options.stages.expect("stages not set"),
+            post_checkout_on: options.post_checkout_on.expect("post_checkout_on not set"),
             verbose: options.verbose.expect("verbose not set"),
             minimum_pre_commit_version: options.minimum_pre_commit_version,
         })
@@ -277,24 +440,120 @@ impl HookBuilder {
 #[derive(Debug, Clone)]
 pub(crate) struct Entry {
     hook: String,
-    entry: String,
+    value: String,
+    /// Whether environment variables are expanded in the entry and the hook `args`.
+    expand: bool,
 }
 
 impl Entry {
-    pub(crate) fn new(hook: String, entry: String) -> Self {
-        Self { hook, entry }
+    pub(crate) fn new(hook: String, entry: String, expand: bool) -> Self {
+        Self {
+            hook,
+            value: entry,
+            expand,
+        }
     }
 
+    /// Split the entry into the command and its arguments, expanding environment variables
+    /// in each of them.
     pub(crate) fn parsed(&self) -> Result<Vec<String>, Error> {
-        shlex::split(&self.entry).ok_or_else(|| Error::InvalidHook {
+        let parsed = self.split()?;
+        if !self.expand {
+            return Ok(parsed);
+        }
+        Ok(parsed
+            .into_iter()
+            .map(|arg| expand_env_vars(&arg).into_owned())
+            .collect())
+    }
+
+    /// Split the entry into the command and its arguments, without expanding environment
+    /// variables.
+    pub(crate) fn split(&self) -> Result<Vec<String>, Error> {
+        shlex::split(&self.value).ok_or_else(|| Error::InvalidHook {
             hook: self.hook.clone(),
-            error: anyhow::anyhow!("Failed to parse entry `{}` as commands", &self.entry),
+            location: None,
+            error: anyhow::anyhow!("Failed to parse entry `{}` as commands", &self.value),
         })
     }
 
+    /// Whether environment variables are expanded in the entry and the hook `args`.
+    pub(crate) fn expands(&self) -> bool {
+        self.expand
+    }
+
     pub(crate) fn entry(&self) -> &str {
-        &self.entry
+        &self.value
+    }
+}
+
+/// A part of an entry or an argument, split around the environment variables to expand.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum EnvSegment<'a> {
+    /// Text to keep as is.
+    Text(&'a str),
+    /// The name of a variable referenced as `${VAR}`.
+    Var(&'a str),
+}
+
+/// Split `value` around its `${VAR}` references. `$${` is a literal `${`.
+///
+/// Only the braced form is a reference, so a `$` passed to a tool, like in a regex or a `$$`,
+/// is kept as is.
+pub(crate) fn env_segments(value: &str) -> Vec<EnvSegment<'_>> {
+    let is_name = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    let mut segments = Vec::new();
+    let mut rest = value;
+    while let Some(pos) = rest.find("${") {
+        let after = &rest[pos + 2..];
+        if rest[..pos].ends_with('$') {
+            segments.push(EnvSegment::Text(&rest[..pos - 1]));
+            segments.push(EnvSegment::Text(&rest[pos..pos + 2]));
+        } else if let Some(end) = after.find('}')
+            && is_name(&after[..end])
+        {
+            segments.push(EnvSegment::Text(&rest[..pos]));
+            segments.push(EnvSegment::Var(&after[..end]));
+            rest = &after[end + 1..];
+            continue;
+        } else {
+            segments.push(EnvSegment::Text(&rest[..pos + 2]));
+        }
+        rest = after;
+    }
+    segments.push(EnvSegment::Text(rest));
+    segments
+}
+
+/// Expand `${VAR}` in `value` from the environment of the process, which includes the
+/// `PRE_COMMIT_*` variables of the current run.
+///
+/// Variables that are not set are left as is.
+fn expand_env_vars(value: &str) -> Cow<'_, str> {
+    if !value.contains("${") {
+        return Cow::Borrowed(value);
+    }
+
+    let mut expanded = String::with_capacity(value.len());
+    for segment in env_segments(value) {
+        match segment {
+            EnvSegment::Text(text) => expanded.push_str(text),
+            EnvSegment::Var(name) => match EnvVars::var(name) {
+                Ok(var) => expanded.push_str(&var),
+                Err(_) => {
+                    expanded.push_str("${");
+                    expanded.push_str(name);
+                    expanded.push('}');
+                }
+            },
+        }
     }
+
+    Cow::Owned(expanded)
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -313,6 +572,7 @@ pub(crate) struct Hook {
     pub alias: String,
     pub files: Option<String>,
     pub exclude: Option<String>,
+    pub files_case_insensitive: bool,
     pub types: Vec<String>,
     pub types_or: Vec<String>,
     pub exclude_types: Vec<String>,
@@ -322,10 +582,17 @@ pub(crate) struct Hook {
     pub fail_fast: bool,
     pub pass_filenames: bool,
     pub description: Option<String>,
+    /// The requested `language_version`, as written in the configuration.
+    pub language_version: String,
     pub language_request: LanguageRequest,
     pub log_file: Option<String>,
     pub require_serial: bool,
+    pub stdin: Option<HookStdin>,
+    /// The directory to run the hook from, relative to the repository root.
+    pub working_dir: Option<String>,
+    pub priority: i64,
     pub stages: Vec<Stage>,
+    pub post_checkout_on: Vec<CheckoutType>,
     pub verbose: bool,
     pub minimum_pre_commit_version: Option<String>,
 }
@@ -358,6 +625,17 @@ impl Hook {
         matches!(&*self.repo, Repo::Remote { .. })
     }
 
+    /// The hook `args`, with environment variables expanded like in the entry.
+    pub(crate) fn expanded_args(&self) -> Vec<String> {
+        if !self.entry.expand {
+            return self.args.clone();
+        }
+        self.args
+            .iter()
+            .map(|arg| expand_env_vars(arg).into_owned())
+            .collect()
+    }
+
     pub(crate) fn is_meta(&self) -> bool {
         matches!(&*self.repo, Repo::Meta { .. })
     }
@@ -460,7 +738,6 @@ impl Hash for InstallInfo {
     }
 }
 
-// TODO: detect collision
 fn random_directory() -> String {
     rand::rng()
         .sample_iter(&rand::distr::Alphanumeric)
@@ -469,10 +746,67 @@ fn random_directory() -> String {
         .collect()
 }
 
+/// Generate an environment directory name that is not used yet in `hooks_dir`.
+fn unique_directory(
+    language: Language,
+    hooks_dir: &Path,
+    mut generate: impl FnMut() -> String,
+) -> String {
+    loop {
+        let env = generate();
+        let path = hooks_dir.join(format!("{}-{env}", language.as_str()));
+        if !path.exists() {
+            return env;
+        }
+        debug!(path = %path.display(), "Environment directory already exists, regenerating");
+    }
+}
+
+/// Derive the environment directory name from the hook's language, dependencies and
+/// requested language version, so the same hook always gets the same directory.
+///
+/// The hash must not change between builds of prek, the store layout would move otherwise.
+fn deterministic_directory(hook: &Hook) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(hook.language.as_str());
+    hasher.update([0]);
+    let mut deps = hook.dependencies().iter().collect::<Vec<_>>();
+    deps.sort_unstable();
+    for dep in deps {
+        hasher.update(dep);
+        hasher.update([0]);
+    }
+    // An empty entry ends the dependencies.
+    hasher.update([0]);
+    hasher.update(&hook.language_version);
+    hex::encode(&hasher.finalize()[..8])
+}
+
 impl InstallInfo {
     pub fn new(language: Language, dependencies: FxHashSet<String>, hooks_dir: &Path) -> Self {
-        let env = random_directory();
+        let env = unique_directory(language, hooks_dir, random_directory);
+        Self::with_env_dir(language, dependencies, hooks_dir, &env)
+    }
 
+    /// Create the install info for a hook.
+    ///
+    /// If `PREK_DETERMINISTIC_ENVS` is set, the environment directory is derived from the hook
+    /// configuration instead of being random, which makes the store layout reproducible.
+    pub fn for_hook(hook: &Hook, hooks_dir: &Path) -> Self {
+        let env = if EnvVars::is_set(EnvVars::PREK_DETERMINISTIC_ENVS) {
+            deterministic_directory(hook)
+        } else {
+            unique_directory(hook.language, hooks_dir, random_directory)
+        };
+        Self::with_env_dir(hook.language, hook.dependencies().clone(), hooks_dir, &env)
+    }
+
+    fn with_env_dir(
+        language: Language,
+        dependencies: FxHashSet<String>,
+        hooks_dir: &Path,
+        env: &str,
+    ) -> Self {
         Self {
             language,
             dependencies,
@@ -506,5 +840,99 @@ impl InstallInfo {
         self.language == hook.language
             && self.dependencies.is_superset(hook.dependencies())
             && hook.language_request.satisfied_by(self)
+            && env_matches(self, hook)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_hook(language_version: &str) -> Hook {
+        let config = ManifestHook {
+            id: "black".to_string(),
+            name: "black".to_string(),
+            entry: "black".to_string(),
+            language: Language::Python,
+            options: HookOptions {
+                language_version: Some(language_version.to_string()),
+                additional_dependencies: Some(vec!["black".to_string(), "click".to_string()]),
+                ..Default::default()
+            },
+        };
+        HookBuilder::new(Arc::new(Repo::local(vec![])), config, 0)
+            .build()
+            .expect("hook should be valid")
+    }
+
+    #[test]
+    fn deterministic_env_directory() {
+        let hooks_dir = Path::new("hooks");
+
+        let first = InstallInfo::with_env_dir(
+            Language::Python,
+            FxHashSet::default(),
+            hooks_dir,
+            &deterministic_directory(&build_hook("3.12")),
+        );
+        let second = InstallInfo::with_env_dir(
+            Language::Python,
+            FxHashSet::default(),
+            hooks_dir,
+            &deterministic_directory(&build_hook("3.12")),
+        );
+        assert_eq!(first.env_path, second.env_path);
+        assert!(first.env_path.starts_with(hooks_dir));
+
+        // A different language version must not share the environment.
+        assert_ne!(
+            deterministic_directory(&build_hook("3.12")),
+            deterministic_directory(&build_hook("3.11"))
+        );
+
+        // The directory must not move between builds of prek.
+        assert_eq!(
+            deterministic_directory(&build_hook("3.12")),
+            "4669c0d9c6e84e11"
+        );
+    }
+
+    #[test]
+    fn env_directory_collision() -> anyhow::Result<()> {
+        let hooks_dir = tempfile::tempdir()?;
+        fs_err::create_dir(hooks_dir.path().join("python-taken"))?;
+
+        let mut candidates = ["taken", "fresh"].into_iter();
+        let env = unique_directory(Language::Python, hooks_dir.path(), || {
+            candidates
+                .next()
+                .expect("ran out of candidates")
+                .to_string()
+        });
+        assert_eq!(env, "fresh");
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_env_vars_in_entry() {
+        let path = EnvVars::var(EnvVars::PATH).unwrap();
+
+        assert_eq!(expand_env_vars("no vars"), "no vars");
+        assert_eq!(expand_env_vars("${PATH}"), path);
+        assert_eq!(
+            expand_env_vars("--path=${PATH}:bin"),
+            format!("--path={path}:bin")
+        );
+        assert_eq!(expand_env_vars("$${PATH} costs $$5"), "${PATH} costs $$5");
+        // Unset variables, unbraced ones and what can't be a variable are kept.
+        assert_eq!(
+            expand_env_vars("$PATH ${PREK_TEST_UNSET_VAR}"),
+            "$PATH ${PREK_TEST_UNSET_VAR}"
+        );
+        assert_eq!(
+            expand_env_vars("^foo$ $1 ${1} ${PATH"),
+            "^foo$ $1 ${1} ${PATH"
+        );
     }
 }
@@ -1,50 +1,124 @@
 use std::borrow::Cow;
-use std::collections::hash_map::DefaultHasher;
 use std::fs;
-use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::UNIX_EPOCH;
 
 use anstream::ColorChoice;
 use anyhow::{Context, Result};
 use fancy_regex::Regex;
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use sha2::{Digest, Sha256};
 use tracing::trace;
 
+use crate::cli::reporter::HookInstallReporter;
+use crate::config::Language;
 use crate::fs::CWD;
-use crate::hook::{Hook, InstallInfo, InstalledHook};
-use crate::languages::LanguageImpl;
+use crate::git;
+use crate::hook::{Hook, InstallInfo, InstalledHook, Repo};
+use crate::languages::{Health, LanguageImpl};
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{build_command, run_by_batch};
 use crate::store::Store;
 
 const PRE_COMMIT_LABEL: &str = "PRE_COMMIT";
+/// The label of images built by prek, used by `prek clean --docker` and `prek gc` to find them.
+pub(crate) const PREK_LABEL: &str = "prek=true";
+
+/// Whether to rebuild the images of docker hooks even if they exist, see `--refresh`.
+static REFRESH_IMAGES: AtomicBool = AtomicBool::new(false);
+
+/// The images built by this process, so `--refresh` rebuilds each image only once.
+static BUILT_IMAGES: LazyLock<Mutex<FxHashSet<String>>> = LazyLock::new(Mutex::default);
+
+/// Rebuild the images of docker hooks instead of reusing existing ones.
+pub(crate) fn refresh_images() {
+    REFRESH_IMAGES.store(true, Ordering::Relaxed);
+}
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Docker;
 
 impl Docker {
-    fn docker_tag(hook: &InstalledHook) -> String {
-        let InstalledHook::Installed { info, .. } = hook else {
-            panic!("Docker tag can only be generated for installed hooks");
-        };
-        let mut hasher = DefaultHasher::new();
-        info.hash(&mut hasher);
-        let digest = hex::encode(hasher.finish().to_le_bytes());
-        format!("prek-{digest}")
+    /// The directory the image of the hook is built from.
+    fn build_context(hook: &Hook) -> &Path {
+        // Local hooks build the `Dockerfile` at the root of the repository, like a repository
+        // of hooks would.
+        hook.repo_path().unwrap_or(CWD.as_path())
+    }
+
+    /// A tag that only changes when the source of the image does.
+    ///
+    /// For remote hooks, this is the repository url, the commit it was cloned at, and the files
+    /// of the build context, so a rev naming a branch doesn't reuse a stale image. For local
+    /// hooks, this is the repository path, the `Dockerfile`, which may not be tracked yet, and
+    /// the files of the build context.
+    async fn docker_tag(hook: &Hook) -> Result<String> {
+        let src = Self::build_context(hook);
+        let mut hasher = Sha256::new();
+        if let Repo::Remote { url, .. } = hook.repo() {
+            hasher.update(url.as_str());
+            hasher.update(git::get_head_commit(src).await?);
+        } else {
+            hasher.update(src.as_os_str().as_encoded_bytes());
+            hasher.update(fs::read(src.join("Dockerfile")).unwrap_or_default());
+        }
+        let hasher = hash_build_context(src, hasher).await?;
+        let digest = hex::encode(&hasher.finalize()[..8]);
+        Ok(format!("prek-{digest}"))
     }
 
-    async fn build_docker_image(hook: &InstalledHook, pull: bool) -> Result<()> {
-        let Some(src) = hook.repo_path() else {
-            anyhow::bail!("Language `docker` cannot work with `local` repository");
+    async fn image_exists(tag: &str) -> Result<bool> {
+        let output = Cmd::new("docker", "inspect docker image")
+            .arg("image")
+            .arg("inspect")
+            .arg(tag)
+            .check(false)
+            .output()
+            .await?;
+        Ok(output.status.success())
+    }
+
+    /// Whether the image `tag` has to be (re)built before running the hook.
+    ///
+    /// The tag changes with the source of the image, so the image is reused as long as it
+    /// exists, unless `--refresh` asks to build it again.
+    async fn needs_build(tag: &str) -> Result<bool> {
+        if REFRESH_IMAGES.load(Ordering::Relaxed) && !BUILT_IMAGES.lock().unwrap().contains(tag) {
+            return Ok(true);
+        }
+        Ok(!Self::image_exists(tag).await?)
+    }
+
+    async fn build_docker_image(hook: &Hook, tag: &str, pull: bool) -> Result<()> {
+        let src = Self::build_context(hook);
+        if hook.is_local() && !src.join("Dockerfile").is_file() {
+            anyhow::bail!(
+                "Local `docker` hook `{}` requires a `Dockerfile` at the repository root",
+                hook.id
+            );
+        }
+
+        let source = match hook.repo() {
+            Repo::Remote { url, .. } => url.to_string(),
+            _ => src.to_string_lossy().to_string(),
         };
 
         let mut cmd = Cmd::new("docker", "build docker image");
 
         let cmd = cmd
             .arg("build")
+            .arg("--quiet")
             .arg("--tag")
-            .arg(Self::docker_tag(hook))
+            .arg(tag)
+            .arg("--label")
+            .arg(PRE_COMMIT_LABEL)
+            .arg("--label")
+            .arg(PREK_LABEL)
             .arg("--label")
-            .arg(PRE_COMMIT_LABEL);
+            .arg(format!("prek.repo={source}"));
 
         // Always attempt to pull all referenced images.
         if pull {
@@ -56,7 +130,32 @@ impl Docker {
         cmd.arg(".");
 
         cmd.current_dir(src).check(true).output().await?;
+        BUILT_IMAGES.lock().unwrap().insert(tag.to_string());
+
+        Ok(())
+    }
+
+    /// Record in the environment of `hook` that it uses the image `tag`, so `prek gc` keeps the
+    /// image.
+    ///
+    /// Hooks of several repositories can share an environment, so there is a record for each
+    /// build context, replaced when its image changes.
+    async fn record_image(hook: &InstalledHook, tag: &str) -> Result<()> {
+        let Some(env) = hook.env_path() else {
+            return Ok(());
+        };
+        let images = env.join("images");
+        let source = Self::build_context(hook).to_string_lossy();
+        let record = images.join(hex::encode(&Sha256::digest(source.as_bytes())[..8]));
+        if fs_err::tokio::read_to_string(&record)
+            .await
+            .is_ok_and(|recorded| recorded == tag)
+        {
+            return Ok(());
+        }
 
+        fs_err::tokio::create_dir_all(&images).await?;
+        fs_err::tokio::write(&record, tag).await?;
         Ok(())
     }
 
@@ -171,21 +270,98 @@ impl Docker {
     }
 }
 
+/// Hash the path, size and modification time of every file git tracks in the build context at
+/// `src`.
+///
+/// The metadata is enough to notice a changed file, without reading the whole build context on
+/// every run.
+async fn hash_build_context(src: &Path, mut hasher: Sha256) -> Result<Sha256> {
+    let files = git::get_tracked_files(src).await?;
+    let src = src.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        for file in files {
+            // Tracked files deleted from the working tree are not part of the build context.
+            let Ok(metadata) = fs::symlink_metadata(src.join(&file)) else {
+                continue;
+            };
+            let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+            hasher.update(file.as_bytes());
+            hasher.update([0]);
+            hasher.update(metadata.len().to_le_bytes());
+            hasher.update(modified.as_nanos().to_le_bytes());
+        }
+        anyhow::Ok(hasher)
+    })
+    .await?
+}
+
+/// The tags of the images used by the `docker` environments of the store.
+pub(crate) fn used_images(store: &Store) -> FxHashSet<String> {
+    store
+        .installed_hooks()
+        .filter(|info| info.language == Language::Docker)
+        .filter_map(|info| fs_err::read_dir(info.env_path.join("images")).ok())
+        .flatten()
+        .flatten()
+        .filter_map(|entry| fs_err::read_to_string(entry.path()).ok())
+        .collect()
+}
+
+/// Remove the images built by prek, except the ones tagged with a tag of `keep`, returning how
+/// many were removed.
+pub(crate) async fn remove_images(keep: &FxHashSet<String>) -> Result<usize> {
+    let output = Cmd::new("docker", "list docker images")
+        .arg("images")
+        .arg("--filter")
+        .arg(format!("label={PREK_LABEL}"))
+        .arg("--format")
+        .arg("{{.Repository}}")
+        .check(true)
+        .output()
+        .await?;
+    let images = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty() && !keep.contains(*tag))
+        .unique()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if images.is_empty() {
+        return Ok(0);
+    }
+
+    Cmd::new("docker", "remove docker images")
+        .arg("rmi")
+        .arg("--force")
+        .args(&images)
+        .check(true)
+        .output()
+        .await?;
+
+    Ok(images.len())
+}
+
 impl LanguageImpl for Docker {
-    async fn install(&self, hook: Arc<Hook>, store: &Store) -> Result<InstalledHook> {
-        let info = InstallInfo::new(
-            hook.language,
-            hook.dependencies().clone(),
-            &store.hooks_dir(),
-        );
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        store: &Store,
+        reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
+        let docker_tag = Docker::docker_tag(&hook).await?;
+        if Docker::needs_build(&docker_tag).await? {
+            let progress = reporter.on_build_start(&hook);
+            Docker::build_docker_image(&hook, &docker_tag, true)
+                .await
+                .context("Failed to build docker image")?;
+            reporter.on_build_complete(progress);
+        }
+
+        let info = InstallInfo::for_hook(&hook, &store.hooks_dir());
         let installed_hook = InstalledHook::Installed {
             hook,
             info: Arc::new(info),
         };
-
-        Docker::build_docker_image(&installed_hook, true)
-            .await
-            .context("Failed to build docker image")?;
         let env = installed_hook
             .env_path()
             .expect("Docker must have env path");
@@ -197,8 +373,29 @@ impl LanguageImpl for Docker {
         Ok(installed_hook)
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self) -> Result<Health> {
+        let output = Cmd::new("docker", "get docker version")
+            .arg("version")
+            .arg("--format")
+            .arg("{{.Server.Version}}")
+            .check(false)
+            .output()
+            .await
+            .context("docker is not installed")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or("unknown error");
+            anyhow::bail!("docker daemon not reachable: {reason}");
+        }
+
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(Health {
+            version: Some(version).filter(|version| !version.is_empty()),
+        })
     }
 
     async fn run(
@@ -206,28 +403,25 @@ impl LanguageImpl for Docker {
         hook: &InstalledHook,
         filenames: &[&String],
         _store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
-        Docker::build_docker_image(hook, false)
-            .await
-            .context("Failed to build docker image")?;
+        let docker_tag = Docker::docker_tag(hook).await?;
+        if Docker::needs_build(&docker_tag).await? {
+            Docker::build_docker_image(hook, &docker_tag, false)
+                .await
+                .context("Failed to build docker image")?;
+        }
+        Docker::record_image(hook, &docker_tag).await?;
 
-        let docker_tag = Docker::docker_tag(hook);
         let entry = hook.entry.parsed()?;
 
         let run = async move |batch: Vec<String>| {
             // docker run [OPTIONS] IMAGE [COMMAND] [ARG...]
             let mut cmd = Docker::docker_run_cmd().await?;
-            let cmd = cmd
-                .arg("--entrypoint")
-                .arg(&entry[0])
-                .arg(&docker_tag)
-                .args(&entry[1..])
-                .args(&hook.args)
-                .args(batch)
-                .check(false);
-
-            let mut output = cmd.output().await?;
-            output.stdout.extend(output.stderr);
+            cmd.arg("--entrypoint").arg(&entry[0]).arg(&docker_tag);
+            let cmd = build_command(&mut cmd, &entry, hook, work_dir, &batch).check(false);
+
+            let output = cmd.combined_output().await?;
             let code = output.status.code().unwrap_or(1);
             anyhow::Ok((code, output.stdout))
         };
@@ -239,7 +433,9 @@ impl LanguageImpl for Docker {
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -1,23 +1,30 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Result;
 
+use crate::cli::reporter::HookInstallReporter;
 use crate::hook::{Hook, InstalledHook};
-use crate::languages::LanguageImpl;
 use crate::languages::docker::Docker;
-use crate::run::run_by_batch;
+use crate::languages::{Health, LanguageImpl};
+use crate::run::{build_command, run_by_batch};
 use crate::store::Store;
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct DockerImage;
 
 impl LanguageImpl for DockerImage {
-    async fn install(&self, hook: Arc<Hook>, _store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        _store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         Ok(InstalledHook::NoNeedInstall(hook))
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self) -> Result<Health> {
+        Docker.check_health().await
     }
 
     async fn run(
@@ -25,18 +32,16 @@ impl LanguageImpl for DockerImage {
         hook: &InstalledHook,
         filenames: &[&String],
         _store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
         let entry = hook.entry.parsed()?;
         let run = async move |batch: Vec<String>| {
             let mut cmd = Docker::docker_run_cmd().await?;
-            let cmd = cmd
-                .args(&entry[..])
-                .args(&hook.args)
-                .args(batch)
-                .check(false);
-
-            let mut output = cmd.output().await?;
-            output.stdout.extend(output.stderr);
+            // The first token of the entry is the image, possibly preceded by options.
+            cmd.arg(&entry[0]);
+            let cmd = build_command(&mut cmd, &entry, hook, work_dir, &batch).check(false);
+
+            let output = cmd.combined_output().await?;
             let code = output.status.code().unwrap_or(1);
             anyhow::Ok((code, output.stdout))
         };
@@ -48,7 +53,9 @@ impl LanguageImpl for DockerImage {
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -0,0 +1,263 @@
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::task::{Context as TaskContext, Poll, ready};
+
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tracing::trace;
+
+use crate::archive;
+use crate::archive::ArchiveExtension;
+use crate::languages::KEEP_TEMP;
+use crate::warn_user;
+
+/// SHA-256 checksums of the toolchain archives prek downloads, as `<sha256>  <filename>` lines.
+///
+/// They are pinned in the binary, so archives are not only checked against checksums served
+/// by the same origin. Generated by `scripts/update-checksums.py`.
+static PINNED_CHECKSUMS: &str = include_str!("checksums.txt");
+
+/// The checksum to verify the archive `filename` with: the pinned one, or else the one
+/// `published` with the release.
+///
+/// Verification is best effort for archives that aren't pinned: if the published checksum
+/// can't be fetched, the archive is installed unverified with a warning.
+pub(crate) async fn expected_checksum(
+    filename: &str,
+    published: impl Future<Output = Result<String>>,
+) -> Option<String> {
+    if let Some(sha256) = pinned_checksum(filename) {
+        return Some(sha256.to_string());
+    }
+    match published.await {
+        Ok(sha256) => {
+            trace!(filename, "No pinned checksum, using the published one");
+            Some(sha256)
+        }
+        Err(err) => {
+            warn_user!("Failed to get the checksum of `{filename}`, it is not verified: {err:#}");
+            None
+        }
+    }
+}
+
+fn pinned_checksum(filename: &str) -> Option<&'static str> {
+    PINNED_CHECKSUMS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once("  "))
+        .find(|(_, name)| *name == filename)
+        .map(|(sha256, _)| sha256)
+}
+
+pub(crate) async fn download_and_extract(
+    client: &reqwest::Client,
+    url: &str,
+    target: &Path,
+    filename: &str,
+    sha256: Option<&str>,
+    scratch: &Path,
+) -> Result<()> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download file from {url}"))?;
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "Failed to download file from {}: {}",
+            url,
+            response.status()
+        );
+    }
+
+    let tarball = response
+        .bytes_stream()
+        .map_err(std::io::Error::other)
+        .into_async_read()
+        .compat();
+
+    trace!(url = %url, "Downloading");
+    extract(tarball, target, filename, sha256, scratch).await
+}
+
+/// A reader computing the SHA-256 digest of the bytes read through it.
+struct HashReader<R> {
+    reader: R,
+    hasher: Sha256,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for HashReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let start = buf.filled().len();
+        let this = &mut *self;
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+        this.hasher.update(&buf.filled()[start..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Extract the archive `filename`, read from `reader`, to `target` through a temporary
+/// directory in `scratch`.
+///
+/// If `sha256` is given, the archive must match it before the extracted files are moved
+/// to `target`.
+///
+/// With `--keep-temp`, the temporary directory is kept when the extraction fails.
+async fn extract(
+    reader: impl AsyncRead + Unpin,
+    target: &Path,
+    filename: &str,
+    sha256: Option<&str>,
+    scratch: &Path,
+) -> Result<()> {
+    let temp_dir = tempfile::tempdir_in(scratch)?;
+    trace!(temp_dir = ?temp_dir.path(), "Extracting");
+
+    let mut reader = HashReader {
+        reader,
+        hasher: Sha256::new(),
+    };
+    let ext = ArchiveExtension::from_path(filename)?;
+    if let Err(err) = archive::unpack(&mut reader, ext, temp_dir.path()).await {
+        if KEEP_TEMP.load(Ordering::Relaxed) {
+            let path = temp_dir.keep();
+            return Err(anyhow::Error::from(err).context(format!(
+                "Failed to extract `{filename}`, the extracted files are kept at `{}`",
+                path.display()
+            )));
+        }
+        return Err(err.into());
+    }
+
+    if let Some(expected) = sha256 {
+        // The archive may end with padding the extraction did not read.
+        tokio::io::copy(&mut reader, &mut tokio::io::sink()).await?;
+        let actual = hex::encode(reader.hasher.finalize());
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!(
+                "Checksum mismatch for `{filename}`: expected `{expected}`, found `{actual}`"
+            );
+        }
+        trace!(sha256 = %actual, "Verified checksum");
+    }
+
+    let extracted = match archive::strip_component(temp_dir.path()) {
+        Ok(top_level) => top_level,
+        Err(archive::Error::NonSingularArchive(_)) => temp_dir.keep(),
+        Err(err) => return Err(err.into()),
+    };
+
+    if target.is_dir() {
+        trace!(target = %target.display(), "Removing existing target");
+        fs_err::tokio::remove_dir_all(&target).await?;
+    }
+
+    trace!(temp_dir = ?extracted, target = %target.display(), "Moving to target");
+    // TODO: retry on Windows
+    fs_err::tokio::rename(extracted, target).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::keep_temp;
+
+    #[tokio::test]
+    async fn keep_temp_on_failure() -> Result<()> {
+        let scratch = tempfile::tempdir()?;
+        let target = scratch.path().join("node");
+        keep_temp();
+
+        let err = extract(
+            &b"not an archive"[..],
+            &target,
+            "node.tar.gz",
+            None,
+            scratch.path(),
+        )
+        .await
+        .unwrap_err();
+        let message = err.to_string();
+        let kept = message
+            .split('`')
+            .nth(3)
+            .expect("the kept directory is reported");
+        assert!(message.starts_with("Failed to extract `node.tar.gz`"));
+        assert!(Path::new(kept).is_dir());
+        assert!(!target.exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch() -> Result<()> {
+        let scratch = tempfile::tempdir()?;
+        let target = scratch.path().join("go");
+
+        let mut builder = tokio_tar::Builder::new(Vec::new());
+        let mut header = tokio_tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "go/VERSION", &b"42"[..])
+            .await?;
+        let tarball = builder.into_inner().await?;
+        let actual = hex::encode(Sha256::digest(&tarball));
+
+        let err = extract(
+            &tarball[..],
+            &target,
+            "go.tar",
+            Some(&"0".repeat(64)),
+            scratch.path(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Checksum mismatch for `go.tar`: expected `{}`, found `{actual}`",
+                "0".repeat(64)
+            )
+        );
+        assert!(!target.exists());
+
+        extract(
+            &tarball[..],
+            &target,
+            "go.tar",
+            Some(&actual),
+            scratch.path(),
+        )
+        .await?;
+        assert_eq!(fs_err::read_to_string(target.join("VERSION"))?, "42");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unpinned_checksum() {
+        let published =
+            expected_checksum("go0.0.0.linux-amd64.tar.gz", async { Ok("0".repeat(64)) }).await;
+        assert_eq!(published, Some("0".repeat(64)));
+
+        // The install goes on unverified when the checksum endpoint is down.
+        let unavailable = expected_checksum("go0.0.0.linux-amd64.tar.gz", async {
+            anyhow::bail!("503 Service Unavailable")
+        })
+        .await;
+        assert_eq!(unavailable, None);
+    }
+}
@@ -1,21 +1,28 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Result;
 
+use crate::cli::reporter::HookInstallReporter;
 use crate::hook::{Hook, InstalledHook};
-use crate::languages::LanguageImpl;
+use crate::languages::{Health, LanguageImpl};
 use crate::store::Store;
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Fail;
 
 impl LanguageImpl for Fail {
-    async fn install(&self, hook: Arc<Hook>, _store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        _store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         Ok(InstalledHook::NoNeedInstall(hook))
     }
 
-    async fn check_health(&self) -> Result<()> {
-        Ok(())
+    async fn check_health(&self) -> Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
@@ -23,6 +30,7 @@ impl LanguageImpl for Fail {
         hook: &InstalledHook,
         filenames: &[&String],
         _store: &Store,
+        _work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
         let mut out = shlex::try_join(hook.entry.parsed()?.iter().map(std::ops::Deref::deref))
             .expect("Failed to join `entry` as command")
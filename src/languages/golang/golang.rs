@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -6,20 +7,26 @@ use anyhow::Context;
 
 use constants::env_vars::EnvVars;
 
+use crate::cli::reporter::HookInstallReporter;
 use crate::hook::{Hook, InstallInfo, InstalledHook};
-use crate::languages::LanguageImpl;
 use crate::languages::golang::GoRequest;
 use crate::languages::golang::installer::GoInstaller;
 use crate::languages::version::LanguageRequest;
+use crate::languages::{Health, LanguageImpl};
 use crate::process::Cmd;
-use crate::run::{prepend_paths, run_by_batch};
+use crate::run::{EnvPath, build_command, run_by_batch};
 use crate::store::{CacheBucket, Store};
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Golang;
 
 impl LanguageImpl for Golang {
-    async fn install(&self, hook: Arc<Hook>, store: &Store) -> anyhow::Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> anyhow::Result<InstalledHook> {
         // 1. Install Go
         let go_dir = store.tools_path(crate::store::ToolBucket::Go);
         let installer = GoInstaller::new(go_dir);
@@ -31,11 +38,7 @@ impl LanguageImpl for Golang {
         };
         let go = installer.install(version).await?;
 
-        let mut info = InstallInfo::new(
-            hook.language,
-            hook.dependencies().clone(),
-            &store.hooks_dir(),
-        );
+        let mut info = InstallInfo::for_hook(&hook, &store.hooks_dir());
         info.with_toolchain(go.bin().to_path_buf())
             .with_language_version(go.version().deref().clone());
 
@@ -55,7 +58,14 @@ impl LanguageImpl for Golang {
             .expect("Go root should exist");
         let go_cache = store.cache_path(CacheBucket::Go);
         // GOPATH used to store downloaded source code (in $GOPATH/pkg/mod)
+        // Build the packages of the hook repository, their binaries provide the hook entry.
         if let Some(repo) = hook.repo_path() {
+            if !repo.join("go.mod").is_file() {
+                anyhow::bail!(
+                    "Hook repository `{}` has no `go.mod`, only Go modules can be built",
+                    hook.repo()
+                );
+            }
             go.cmd("go install")
                 .arg("install")
                 .arg("./...")
@@ -66,19 +76,25 @@ impl LanguageImpl for Golang {
                 .current_dir(repo)
                 .check(true)
                 .output()
-                .await?;
+                .await
+                .context("Failed to build the Go module of the hook repository")?;
         }
         for dep in &hook.additional_dependencies {
-            go.cmd("go install")
-                .arg("install")
-                .arg(dep)
+            let mut cmd = go.cmd("go install");
+            cmd.arg("install")
+                .arg(&*install_target(dep, hook.repo_path().is_some()))
                 .env(EnvVars::GOTOOLCHAIN, "local")
                 .env(EnvVars::GOROOT, go_root)
                 .env(EnvVars::GOBIN, bin_dir(&info.env_path))
-                .env(EnvVars::GOPATH, &go_cache)
-                .check(true)
+                .env(EnvVars::GOPATH, &go_cache);
+            // Resolve dependencies against the module of the hook repository, if any.
+            if let Some(repo) = hook.repo_path() {
+                cmd.current_dir(repo);
+            }
+            cmd.check(true)
                 .output()
-                .await?;
+                .await
+                .with_context(|| format!("Failed to install additional dependency `{dep}`"))?;
         }
 
         Ok(InstalledHook::Installed {
@@ -87,8 +103,8 @@ impl LanguageImpl for Golang {
         })
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
-        todo!()
+    async fn check_health(&self) -> anyhow::Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
@@ -96,8 +112,9 @@ impl LanguageImpl for Golang {
         hook: &InstalledHook,
         filenames: &[&String],
         store: &Store,
+        work_dir: &Path,
     ) -> anyhow::Result<(i32, Vec<u8>)> {
-        let env_dir = hook.env_path().expect("Node must have env path");
+        let env_dir = hook.env_path().expect("Go must have env path");
         let InstalledHook::Installed { hook, info } = hook else {
             unreachable!()
         };
@@ -106,24 +123,26 @@ impl LanguageImpl for Golang {
         let go_root_bin = info.toolchain.parent().expect("Go root should exist");
         let go_root = go_root_bin.parent().expect("Go root should exist");
         let go_bin = bin_dir(env_dir);
-        let new_path = prepend_paths(&[&go_bin, go_root_bin]).context("Failed to join PATH")?;
+        let new_path = EnvPath::new()
+            .prepend(&go_bin)
+            .prepend(go_root_bin)
+            .exclude_store(store)
+            .build()
+            .context("Failed to join PATH")?;
 
         let entry = hook.entry.parsed()?;
         let run = async move |batch: Vec<String>| {
-            let mut output = Cmd::new(&entry[0], "go hook")
-                .args(&entry[1..])
-                .env("PATH", &new_path)
+            let mut cmd = Cmd::new(&entry[0], "go hook");
+            cmd.env("PATH", &new_path)
                 .env(EnvVars::GOTOOLCHAIN, "local")
                 .env(EnvVars::GOROOT, go_root)
                 .env(EnvVars::GOBIN, &go_bin)
-                .env(EnvVars::GOPATH, &go_cache)
-                .args(&hook.args)
-                .args(batch)
+                .env(EnvVars::GOPATH, &go_cache);
+            let output = build_command(&mut cmd, &entry, hook, work_dir, &batch)
                 .check(false)
-                .output()
+                .combined_output()
                 .await?;
 
-            output.stdout.extend(output.stderr);
             let code = output.status.code().unwrap_or(1);
             anyhow::Ok((code, output.stdout))
         };
@@ -134,7 +153,9 @@ impl LanguageImpl for Golang {
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -142,6 +163,18 @@ impl LanguageImpl for Golang {
     }
 }
 
+/// The package to `go install` for an additional dependency.
+///
+/// Outside of a module, `go install` requires a version, so module paths without one
+/// (`golang.org/x/tools/cmd/goimports`) install the latest version.
+fn install_target(dep: &str, in_module: bool) -> Cow<'_, str> {
+    if in_module || dep.contains('@') || dep.starts_with('.') {
+        Cow::Borrowed(dep)
+    } else {
+        Cow::Owned(format!("{dep}@latest"))
+    }
+}
+
 pub(crate) fn bin_dir(env_path: &Path) -> PathBuf {
     env_path.join("bin")
 }
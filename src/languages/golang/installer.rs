@@ -6,12 +6,15 @@ use std::str::FromStr;
 use anyhow::{Context, Result};
 use itertools::Itertools;
 use reqwest::Client;
+use serde::Deserialize;
 use target_lexicon::{Architecture, HOST, OperatingSystem};
 use tracing::{debug, trace, warn};
 
+use constants::env_vars::EnvVars;
+
 use crate::fs::LockedFile;
 use crate::git;
-use crate::languages::download_and_extract;
+use crate::languages::download::{download_and_extract, expected_checksum};
 use crate::languages::golang::GoRequest;
 use crate::languages::golang::golang::bin_dir;
 use crate::languages::golang::version::GoVersion;
@@ -107,11 +110,22 @@ impl GoInstaller {
             return Ok(go);
         }
 
-        if let Some(go) = self.find_system_go(request).await? {
-            trace!(%go, "Using system go");
-            return Ok(go);
-        }
+        let rejected = match self.find_system_go(request).await {
+            Ok(go) => {
+                trace!(%go, "Using system go");
+                return Ok(go);
+            }
+            Err(err) => err,
+        };
 
+        self.resolve_and_download(request).await.with_context(|| {
+            format!(
+                "No Go on `PATH` matches the requested version ({rejected:#}), and installing it failed"
+            )
+        })
+    }
+
+    async fn resolve_and_download(&self, request: &GoRequest) -> Result<GoResult> {
         let resolved_version = self
             .resolve_version(request)
             .await
@@ -206,27 +220,71 @@ impl GoInstaller {
 
         let ext = if cfg!(windows) { "zip" } else { "tar.gz" };
         let filename = format!("go{version}.{os}-{arch}.{ext}");
-        let url = format!("https://go.dev/dl/{filename}");
+        let url = format!("{}/{filename}", download_base_url());
         let target = self.root.join(version.to_string());
-
-        download_and_extract(&self.client, &url, &target, &filename, &self.root)
-            .await
-            .context("Failed to download and extract Go")?;
+        let sha256 = expected_checksum(&filename, self.checksum(&filename)).await;
+
+        download_and_extract(
+            &self.client,
+            &url,
+            &target,
+            &filename,
+            sha256.as_deref(),
+            &self.root,
+        )
+        .await
+        .context("Failed to download and extract Go")?;
 
         Ok(GoResult::from_dir(&target).with_version(version.clone()))
     }
 
-    async fn find_system_go(&self, go_request: &GoRequest) -> Result<Option<GoResult>> {
+    /// Look up the SHA-256 checksum of a Go release archive, as published on `go.dev` or its
+    /// mirror.
+    async fn checksum(&self, filename: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Release {
+            files: Vec<ReleaseFile>,
+        }
+
+        #[derive(Deserialize)]
+        struct ReleaseFile {
+            filename: String,
+            sha256: String,
+        }
+
+        let url = format!("{}/?mode=json&include=all", download_base_url());
+        let releases: Vec<Release> = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        releases
+            .into_iter()
+            .flat_map(|release| release.files)
+            .find(|file| file.filename == filename)
+            .map(|file| file.sha256)
+            .context("No checksum published")
+    }
+
+    /// Find a Go on `PATH` that matches the request, or explain why every one was rejected.
+    async fn find_system_go(&self, go_request: &GoRequest) -> Result<GoResult> {
         let go_paths = match which::which_all("go") {
             Ok(paths) => paths,
             Err(e) => {
                 debug!("No go executables found in PATH: {}", e);
-                return Ok(None);
+                anyhow::bail!("no `go` executable found");
             }
         };
 
+        let mut rejected = Vec::new();
         for go_path in go_paths {
-            match GoResult::from_executable(go_path).fill_version().await {
+            match GoResult::from_executable(go_path.clone())
+                .fill_version()
+                .await
+            {
                 Ok(go) => {
                     // Check if this version matches the request
                     if go_request.matches(&go.version, Some(&go.path)) {
@@ -234,20 +292,42 @@ impl GoInstaller {
                             %go,
                             "Found matching system go"
                         );
-                        return Ok(Some(go));
+                        return Ok(go);
                     }
                     trace!(
                         %go,
                         "System go does not match requested version"
                     );
+                    rejected.push(format!("`{go}` does not match"));
                 }
                 Err(e) => {
                     warn!(?e, "Failed to get version for system go");
+                    rejected.push(format!(
+                        "failed to get the version of `{}`: {e:#}",
+                        go_path.display()
+                    ));
                 }
             }
         }
 
         debug!(?go_request, "No system go matches the requested version");
-        Ok(None)
+        if rejected.is_empty() {
+            anyhow::bail!("no `go` executable found");
+        }
+        anyhow::bail!("{}", rejected.join("; "))
     }
 }
+
+/// The base URL Go release archives are downloaded from, `PREK_GO_DOWNLOAD_MIRROR` or `go.dev`.
+///
+/// A mirror must have the layout of `https://go.dev/dl`, including the JSON list of releases
+/// with their checksums.
+fn download_base_url() -> String {
+    EnvVars::var(EnvVars::PREK_GO_DOWNLOAD_MIRROR)
+        .ok()
+        .filter(|mirror| !mirror.is_empty())
+        .map_or_else(
+            || "https://go.dev/dl".to_string(),
+            |mirror| mirror.trim_end_matches('/').to_string(),
+        )
+}
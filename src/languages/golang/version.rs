@@ -67,7 +67,7 @@ impl FromStr for GoVersion {
 /// `go1.18beta1` or `1.18beta1`
 /// `>= 1.20, < 1.22`
 /// `local/path/to/go`
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub(crate) enum GoRequest {
     Any,
     Major(u64),
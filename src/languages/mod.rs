@@ -1,45 +1,142 @@
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result};
-use futures::TryStreamExt;
-use tokio_util::compat::FuturesAsyncReadCompatExt;
+use itertools::Itertools;
+use rustc_hash::FxHashMap;
 use tracing::trace;
 
-use crate::archive::ArchiveExtension;
+use crate::builtin;
+use crate::cli::reporter::HookInstallReporter;
 use crate::config::Language;
-use crate::hook::{Hook, InstalledHook};
+use crate::git::Git;
+use crate::hook::{Hook, InstallInfo, InstalledHook};
 use crate::store::Store;
-use crate::{archive, builtin};
 
+#[cfg(feature = "docker")]
 mod docker;
+#[cfg(feature = "docker")]
 mod docker_image;
+#[cfg(feature = "download")]
+mod download;
 mod fail;
+#[cfg(feature = "languages-golang")]
 mod golang;
+#[cfg(feature = "languages-node")]
 mod node;
+#[cfg(feature = "languages-python")]
 mod python;
 mod script;
 mod system;
 pub mod version;
 
+#[cfg(feature = "docker")]
+pub(crate) use docker::refresh_images;
+#[cfg(feature = "docker")]
+pub(crate) use docker::{remove_images as remove_docker_images, used_images as used_docker_images};
+
+#[cfg(feature = "languages-golang")]
 static GOLANG: golang::Golang = golang::Golang;
+#[cfg(feature = "languages-python")]
 static PYTHON: python::Python = python::Python;
+#[cfg(feature = "languages-node")]
 static NODE: node::Node = node::Node;
 static SYSTEM: system::System = system::System;
 static FAIL: fail::Fail = fail::Fail;
+#[cfg(feature = "docker")]
 static DOCKER: docker::Docker = docker::Docker;
+#[cfg(feature = "docker")]
 static DOCKER_IMAGE: docker_image::DockerImage = docker_image::DockerImage;
 static SCRIPT: script::Script = script::Script;
 static UNIMPLEMENTED: Unimplemented = Unimplemented;
 
+/// Whether the environment of `info` was installed the way `hook` needs it beyond its language
+/// version and dependencies, like with the package manager of a Node.js hook repository.
+pub(crate) fn env_matches(info: &InstallInfo, hook: &Hook) -> bool {
+    match hook.language {
+        #[cfg(feature = "languages-node")]
+        Language::Node => node::package_manager_matches(info, hook),
+        _ => true,
+    }
+}
+
+/// Whether to keep the temporary directories of failed extractions, see `--keep-temp`.
+static KEEP_TEMP: AtomicBool = AtomicBool::new(false);
+
+/// Keep the temporary directories of failed extractions, for debugging.
+pub(crate) fn keep_temp() {
+    KEEP_TEMP.store(true, Ordering::Relaxed);
+}
+
+/// The runtime a language runs hooks with, as found by [`Language::check_health`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Health {
+    /// The version of the runtime, for languages that depend on one outside of the hook
+    /// environments.
+    pub(crate) version: Option<String>,
+}
+
+/// The health checks of the languages of a run.
+///
+/// The checks are only run when a hook errors, all languages at once, and their results are
+/// kept for the rest of the run.
+pub(crate) struct HealthChecks {
+    languages: Vec<Language>,
+    results: tokio::sync::OnceCell<FxHashMap<Language, Result<Health, String>>>,
+}
+
+impl HealthChecks {
+    pub(crate) fn new(languages: impl IntoIterator<Item = Language>) -> Self {
+        Self {
+            languages: languages.into_iter().unique().collect(),
+            results: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// The health of `language`, or why it is unhealthy.
+    pub(crate) async fn get(&self, language: Language) -> Result<Health, String> {
+        let results = self
+            .results
+            .get_or_init(|| async {
+                let checks = self.languages.iter().map(|&language| async move {
+                    let health = language
+                        .check_health()
+                        .await
+                        .map_err(|err| format!("{err:#}"));
+                    (language, health)
+                });
+                futures::future::join_all(checks)
+                    .await
+                    .into_iter()
+                    .collect()
+            })
+            .await;
+
+        match results.get(&language) {
+            Some(health) => health.clone(),
+            None => language
+                .check_health()
+                .await
+                .map_err(|err| format!("{err:#}")),
+        }
+    }
+}
+
 trait LanguageImpl {
-    async fn install(&self, hook: Arc<Hook>, store: &Store) -> Result<InstalledHook>;
-    async fn check_health(&self) -> Result<()>;
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        store: &Store,
+        reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook>;
+    async fn check_health(&self) -> Result<Health>;
     async fn run(
         &self,
         hook: &InstalledHook,
         filenames: &[&String],
         store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)>;
 }
 
@@ -47,15 +144,38 @@ trait LanguageImpl {
 #[error("Language `{0}` is not implemented yet")]
 struct UnimplementedError(String);
 
+#[derive(thiserror::Error, Debug)]
+#[error(
+    "Language `{language}` is not available in this build of prek, rebuild it with the `{feature}` feature"
+)]
+struct MissingFeatureError {
+    language: Language,
+    feature: &'static str,
+}
+
+/// Fail if `language` is not available in this build of prek.
+fn check_feature(language: Language) -> Result<()> {
+    if let Some(feature) = language.missing_feature() {
+        anyhow::bail!(MissingFeatureError { language, feature });
+    }
+    Ok(())
+}
+
 struct Unimplemented;
 
 impl LanguageImpl for Unimplemented {
-    async fn install(&self, hook: Arc<Hook>, _store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        _store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
+        check_feature(hook.language)?;
         Ok(InstalledHook::NoNeedInstall(hook))
     }
 
-    async fn check_health(&self) -> Result<()> {
-        Ok(())
+    async fn check_health(&self) -> Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
@@ -63,7 +183,9 @@ impl LanguageImpl for Unimplemented {
         hook: &InstalledHook,
         _filenames: &[&String],
         _store: &Store,
+        _work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
+        check_feature(hook.language)?;
         anyhow::bail!(UnimplementedError(format!("{}", hook.language)))
     }
 }
@@ -105,6 +227,32 @@ impl Language {
         )
     }
 
+    /// The cargo feature providing the language, if this build of prek was compiled without it.
+    ///
+    /// Hooks of such languages are still [`supported`](Self::supported), but fail with an error
+    /// naming the feature instead of running.
+    pub fn missing_feature(self) -> Option<&'static str> {
+        match self {
+            #[cfg(not(feature = "languages-python"))]
+            Self::Python => Some("languages-python"),
+            #[cfg(not(feature = "languages-node"))]
+            Self::Node => Some("languages-node"),
+            #[cfg(not(feature = "languages-golang"))]
+            Self::Golang => Some("languages-golang"),
+            #[cfg(not(feature = "docker"))]
+            Self::Docker | Self::DockerImage => Some("docker"),
+            _ => None,
+        }
+    }
+
+    /// Whether a hook of an unsupported language can instead be run as a `system` hook, using
+    /// the tools found on `PATH`.
+    ///
+    /// `pygrep` entries are patterns rather than commands, so they can't be.
+    pub fn supports_system_fallback(self) -> bool {
+        !Self::supported(self) && self != Self::Pygrep
+    }
+
     pub fn supports_install_env(self) -> bool {
         !matches!(
             self,
@@ -140,28 +288,45 @@ impl Language {
         )
     }
 
-    pub async fn install(&self, hook: Arc<Hook>, store: &Store) -> Result<InstalledHook> {
+    pub async fn install(
+        &self,
+        hook: Arc<Hook>,
+        store: &Store,
+        reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         match self {
-            Self::Golang => GOLANG.install(hook, store).await,
-            Self::Python => PYTHON.install(hook, store).await,
-            Self::Node => NODE.install(hook, store).await,
-            Self::System => SYSTEM.install(hook, store).await,
-            Self::Fail => FAIL.install(hook, store).await,
-            Self::Docker => DOCKER.install(hook, store).await,
-            Self::DockerImage => DOCKER_IMAGE.install(hook, store).await,
-            Self::Script => SCRIPT.install(hook, store).await,
-            _ => UNIMPLEMENTED.install(hook, store).await,
+            #[cfg(feature = "languages-golang")]
+            Self::Golang => GOLANG.install(hook, store, reporter).await,
+            #[cfg(feature = "languages-python")]
+            Self::Python => PYTHON.install(hook, store, reporter).await,
+            #[cfg(feature = "languages-node")]
+            Self::Node => NODE.install(hook, store, reporter).await,
+            Self::System => SYSTEM.install(hook, store, reporter).await,
+            Self::Fail => FAIL.install(hook, store, reporter).await,
+            #[cfg(feature = "docker")]
+            Self::Docker => DOCKER.install(hook, store, reporter).await,
+            #[cfg(feature = "docker")]
+            Self::DockerImage => DOCKER_IMAGE.install(hook, store, reporter).await,
+            Self::Script => SCRIPT.install(hook, store, reporter).await,
+            _ => UNIMPLEMENTED.install(hook, store, reporter).await,
         }
     }
 
-    pub async fn check_health(&self) -> Result<()> {
+    /// Check that the runtime of the language is usable, outside of any hook environment.
+    pub async fn check_health(&self) -> Result<Health> {
+        check_feature(*self)?;
         match self {
+            #[cfg(feature = "languages-golang")]
             Self::Golang => GOLANG.check_health().await,
+            #[cfg(feature = "languages-python")]
             Self::Python => PYTHON.check_health().await,
+            #[cfg(feature = "languages-node")]
             Self::Node => NODE.check_health().await,
             Self::System => SYSTEM.check_health().await,
             Self::Fail => FAIL.check_health().await,
+            #[cfg(feature = "docker")]
             Self::Docker => DOCKER.check_health().await,
+            #[cfg(feature = "docker")]
             Self::DockerImage => DOCKER_IMAGE.check_health().await,
             Self::Script => SCRIPT.check_health().await,
             _ => UNIMPLEMENTED.check_health().await,
@@ -173,22 +338,29 @@ impl Language {
         hook: &InstalledHook,
         filenames: &[&String],
         store: &Store,
+        work_dir: &Path,
+        verbose: bool,
     ) -> Result<(i32, Vec<u8>)> {
         // fast path for hooks implemented in Rust
         if builtin::check_fast_path(hook) {
-            return builtin::run_fast_path(hook, filenames).await;
+            return builtin::run_fast_path(&Git, hook, filenames, verbose).await;
         }
 
         match self {
-            Self::Golang => GOLANG.run(hook, filenames, store).await,
-            Self::Python => PYTHON.run(hook, filenames, store).await,
-            Self::Node => NODE.run(hook, filenames, store).await,
-            Self::System => SYSTEM.run(hook, filenames, store).await,
-            Self::Fail => FAIL.run(hook, filenames, store).await,
-            Self::Docker => DOCKER.run(hook, filenames, store).await,
-            Self::DockerImage => DOCKER_IMAGE.run(hook, filenames, store).await,
-            Self::Script => SCRIPT.run(hook, filenames, store).await,
-            _ => UNIMPLEMENTED.run(hook, filenames, store).await,
+            #[cfg(feature = "languages-golang")]
+            Self::Golang => GOLANG.run(hook, filenames, store, work_dir).await,
+            #[cfg(feature = "languages-python")]
+            Self::Python => PYTHON.run(hook, filenames, store, work_dir).await,
+            #[cfg(feature = "languages-node")]
+            Self::Node => NODE.run(hook, filenames, store, work_dir).await,
+            Self::System => SYSTEM.run(hook, filenames, store, work_dir).await,
+            Self::Fail => FAIL.run(hook, filenames, store, work_dir).await,
+            #[cfg(feature = "docker")]
+            Self::Docker => DOCKER.run(hook, filenames, store, work_dir).await,
+            #[cfg(feature = "docker")]
+            Self::DockerImage => DOCKER_IMAGE.run(hook, filenames, store, work_dir).await,
+            Self::Script => SCRIPT.run(hook, filenames, store, work_dir).await,
+            _ => UNIMPLEMENTED.run(hook, filenames, store, work_dir).await,
         }
     }
 }
@@ -263,53 +435,3 @@ async fn create_symlink_or_copy(source: &Path, target: &Path) -> Result<()> {
 
     Ok(())
 }
-
-async fn download_and_extract(
-    client: &reqwest::Client,
-    url: &str,
-    target: &Path,
-    filename: &str,
-    scratch: &Path,
-) -> Result<()> {
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to download file from {url}"))?;
-    if !response.status().is_success() {
-        anyhow::bail!(
-            "Failed to download file from {}: {}",
-            url,
-            response.status()
-        );
-    }
-
-    let tarball = response
-        .bytes_stream()
-        .map_err(std::io::Error::other)
-        .into_async_read()
-        .compat();
-
-    let temp_dir = tempfile::tempdir_in(scratch)?;
-    trace!(url = %url, temp_dir = ?temp_dir.path(), "Downloading");
-
-    let ext = ArchiveExtension::from_path(filename)?;
-    archive::unpack(tarball, ext, temp_dir.path()).await?;
-
-    let extracted = match archive::strip_component(temp_dir.path()) {
-        Ok(top_level) => top_level,
-        Err(archive::Error::NonSingularArchive(_)) => temp_dir.keep(),
-        Err(err) => return Err(err.into()),
-    };
-
-    if target.is_dir() {
-        trace!(target = %target.display(), "Removing existing target");
-        fs_err::tokio::remove_dir_all(&target).await?;
-    }
-
-    trace!(temp_dir = ?extracted, target = %target.display(), "Moving to target");
-    // TODO: retry on Windows
-    fs_err::tokio::rename(extracted, target).await?;
-
-    Ok(())
-}
@@ -11,7 +11,7 @@ use target_lexicon::{Architecture, HOST, OperatingSystem};
 use tracing::{debug, trace, warn};
 
 use crate::fs::LockedFile;
-use crate::languages::download_and_extract;
+use crate::languages::download::{download_and_extract, expected_checksum};
 use crate::languages::node::NodeRequest;
 use crate::languages::node::version::NodeVersion;
 use crate::process::Cmd;
@@ -199,14 +199,39 @@ impl NodeInstaller {
         let filename = format!("node-v{}-{os}-{arch}.{ext}", version.version());
         let url = format!("https://nodejs.org/dist/v{}/{filename}", version.version());
         let target = self.root.join(version.to_string());
-
-        download_and_extract(&self.client, &url, &target, &filename, &self.root)
-            .await
-            .context("Failed to download and extract Node.js")?;
+        let sha256 = expected_checksum(&filename, self.checksum(version, &filename)).await;
+
+        download_and_extract(
+            &self.client,
+            &url,
+            &target,
+            &filename,
+            sha256.as_deref(),
+            &self.root,
+        )
+        .await
+        .context("Failed to download and extract Node.js")?;
 
         Ok(NodeResult::from_dir(&target).with_version(version.clone()))
     }
 
+    /// Look up the SHA-256 checksum of a Node.js release archive, from the `SHASUMS256.txt`
+    /// published with the release.
+    async fn checksum(&self, version: &NodeVersion, filename: &str) -> Result<String> {
+        let url = format!(
+            "https://nodejs.org/dist/v{}/SHASUMS256.txt",
+            version.version()
+        );
+        let response = self.client.get(&url).send().await?.error_for_status()?;
+        let checksums = response.text().await?;
+        checksums
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .find(|(_, name)| *name == filename)
+            .map(|(sha256, _)| sha256.to_string())
+            .context("No checksum published")
+    }
+
     /// Find a suitable system Node.js installation that matches the request.
     async fn find_system_node(&self, node_request: &NodeRequest) -> Result<Option<NodeResult>> {
         let node_paths = match which::which_all("node") {
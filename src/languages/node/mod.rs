@@ -1,7 +1,9 @@
 mod installer;
 #[allow(clippy::module_inception)]
 mod node;
+mod package_manager;
 mod version;
 
 pub(crate) use node::Node;
+pub(crate) use package_manager::package_manager_matches;
 pub(crate) use version::NodeRequest;
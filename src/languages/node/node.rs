@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 use std::env::consts::EXE_EXTENSION;
+use std::ffi::OsStr;
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -8,22 +10,118 @@ use tracing::debug;
 
 use constants::env_vars::EnvVars;
 
+use crate::cli::reporter::HookInstallReporter;
+use crate::fs::copy_dir_all;
 use crate::hook::InstalledHook;
 use crate::hook::{Hook, InstallInfo};
 use crate::languages::node::NodeRequest;
-use crate::languages::node::installer::{NodeInstaller, bin_dir, lib_dir};
+use crate::languages::node::installer::{NodeInstaller, NodeResult, bin_dir, lib_dir};
+use crate::languages::node::package_manager::{
+    EXTRA_KEY_PACKAGE_MANAGER, PackageManager, PackageManagerKind,
+};
 use crate::languages::node::version::EXTRA_KEY_LTS;
 use crate::languages::version::LanguageRequest;
-use crate::languages::{LanguageImpl, create_symlink_or_copy};
+use crate::languages::{Health, LanguageImpl, create_symlink_or_copy};
 use crate::process::Cmd;
-use crate::run::{prepend_paths, run_by_batch};
+use crate::run::{EnvPath, build_command, run_by_batch};
 use crate::store::{Store, ToolBucket};
+use crate::warn_user;
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Node;
 
+/// Install the dependencies of `repo` in place with yarn or pnpm, provisioned by corepack
+/// into `bin_dir`, and return the version of the package manager.
+///
+/// `repo` must be a copy of the hook repository owned by the environment, see [`copy_repo`].
+///
+/// The version pinned by the `packageManager` field of `package.json` is used if there is one.
+async fn install_with_corepack(
+    node: &NodeResult,
+    kind: PackageManagerKind,
+    repo: &Path,
+    bin_dir: &Path,
+    path: &OsStr,
+) -> Result<String> {
+    let corepack = node
+        .npm()
+        .with_file_name("corepack")
+        .with_extension(if cfg!(windows) { "cmd" } else { "" });
+    if !corepack.is_file() {
+        anyhow::bail!(
+            "`corepack` was not found next to `{}`",
+            node.npm().display()
+        );
+    }
+
+    Cmd::new(&corepack, "corepack enable")
+        .arg("enable")
+        .arg("--install-directory")
+        .arg(bin_dir)
+        .arg(kind.as_str())
+        .env("PATH", path)
+        .check(true)
+        .output()
+        .await?;
+
+    let shim = bin_dir
+        .join(kind.as_str())
+        .with_extension(if cfg!(windows) { "cmd" } else { "" });
+
+    let output = Cmd::new(&shim, "get package manager version")
+        .arg("--version")
+        .current_dir(repo)
+        .env("PATH", path)
+        .env(EnvVars::COREPACK_ENABLE_DOWNLOAD_PROMPT, "0")
+        .check(true)
+        .output()
+        .await?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let mut cmd = Cmd::new(&shim, format!("{kind} install"));
+    cmd.arg("install")
+        .current_dir(repo)
+        .env("PATH", path)
+        .env(EnvVars::COREPACK_ENABLE_DOWNLOAD_PROMPT, "0");
+    if kind == PackageManagerKind::Yarn {
+        // Plug'n'Play installs have no `node_modules` to link into the environment.
+        cmd.env(EnvVars::YARN_NODE_LINKER, "node-modules");
+    }
+    cmd.check(true).output().await?;
+
+    Ok(version)
+}
+
+/// Copy the hook repository `repo` to `target`, so yarn or pnpm install it in the environment
+/// rather than in the store clone shared by every environment of the repository.
+async fn copy_repo(repo: &Path, target: &Path) -> Result<()> {
+    let repo = repo.to_path_buf();
+    let target = target.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        fs_err::create_dir_all(&target)?;
+        for entry in fs_err::read_dir(&repo)? {
+            let entry = entry?;
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                copy_dir_all(entry.path(), target.join(entry.file_name()))?;
+            } else {
+                fs_err::copy(entry.path(), target.join(entry.file_name()))?;
+            }
+        }
+        Ok(())
+    })
+    .await?
+}
+
 impl LanguageImpl for Node {
-    async fn install(&self, hook: Arc<Hook>, store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         // 1. Install node
         //   1) Find from `$PREK_HOME/tools/node`
         //   2) Find from system
@@ -42,11 +140,7 @@ impl LanguageImpl for Node {
         };
         let node = installer.install(node_request).await?;
 
-        let mut info = InstallInfo::new(
-            hook.language,
-            hook.dependencies().clone(),
-            &store.hooks_dir(),
-        );
+        let mut info = InstallInfo::for_hook(&hook, &store.hooks_dir());
 
         let lts = serde_json::to_string(&node.version().lts).context("Failed to serialize LTS")?;
         info.with_toolchain(node.node().to_path_buf());
@@ -68,10 +162,53 @@ impl LanguageImpl for Node {
         )
         .await?;
 
+        // `npm` is a script uses `/usr/bin/env node`, we need add `bin_dir` to PATH
+        // so that `npm` can find `node`.
+        let new_path = EnvPath::new()
+            .prepend(&bin_dir)
+            .exclude_store(store)
+            .build()
+            .context("Failed to join PATH")?;
+
         // 3. Install dependencies
-        let deps = if let Some(repo) = hook.repo_path() {
+        let package_manager = match hook.repo_path() {
+            Some(repo) => PackageManager::detect(repo)?,
+            None => PackageManager::default(),
+        };
+        // The requested package manager, not the one used after a fallback, so the environment
+        // matches the hook as long as its repository asks for the same one.
+        info.with_extra(EXTRA_KEY_PACKAGE_MANAGER, &package_manager.to_string());
+
+        // The package to install, the repo, or its copy installed by yarn or pnpm, which only
+        // needs to be linked.
+        let mut package = hook.repo_path().map(Path::to_path_buf);
+        let mut link_repo = false;
+        if let Some(repo) = hook.repo_path()
+            && package_manager.kind != PackageManagerKind::Npm
+        {
+            let copy = info.env_path.join("src");
+            copy_repo(repo, &copy).await?;
+            match install_with_corepack(&node, package_manager.kind, &copy, &bin_dir, &new_path)
+                .await
+            {
+                Ok(version) => {
+                    debug!(%version, "Installed with `{}`", package_manager.kind);
+                    package = Some(copy);
+                    link_repo = true;
+                }
+                Err(err) => {
+                    warn_user!(
+                        "Failed to install hook `{}` with `{package_manager}`, falling back to npm: {err:#}",
+                        hook.id
+                    );
+                    fs_err::tokio::remove_dir_all(&copy).await?;
+                }
+            }
+        }
+
+        let deps = if let Some(package) = package {
             let mut deps = hook.additional_dependencies.clone();
-            deps.insert(repo.to_string_lossy().to_string());
+            deps.insert(package.to_string_lossy().to_string());
             Cow::Owned::<FxHashSet<_>>(deps)
         } else {
             Cow::Borrowed(&hook.additional_dependencies)
@@ -87,11 +224,8 @@ impl LanguageImpl for Node {
             //
             // NOTE: If you want to install the content of a directory like a package from the registry
             // instead of creating a link, you would need to use the --install-links option.
-
-            // `npm` is a script uses `/usr/bin/env node`, we need add `bin_dir` to PATH
-            // so that `npm` can find `node`.
-            let new_path = prepend_paths(&[&bin_dir]).context("Failed to join PATH")?;
-
+            //
+            // A repo installed by yarn or pnpm already has its dependencies, so it's linked.
             Cmd::new(node.npm(), "npm install")
                 .arg("install")
                 .arg("-g")
@@ -99,9 +233,13 @@ impl LanguageImpl for Node {
                 .arg("--no-save")
                 .arg("--no-fund")
                 .arg("--no-audit")
-                .arg("--install-links")
+                .arg(if link_repo {
+                    "--install-links=false"
+                } else {
+                    "--install-links"
+                })
                 .args(&*deps)
-                .env("PATH", new_path)
+                .env("PATH", &new_path)
                 .env(EnvVars::NPM_CONFIG_PREFIX, &info.env_path)
                 .env_remove(EnvVars::NPM_CONFIG_USERCONFIG)
                 .env(EnvVars::NODE_PATH, &lib_dir)
@@ -116,18 +254,24 @@ impl LanguageImpl for Node {
         })
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    // Node.js is installed for the hook environments, nothing outside of them is needed.
+    async fn check_health(&self) -> Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
         &self,
         hook: &InstalledHook,
         filenames: &[&String],
-        _store: &Store,
+        store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
         let env_dir = hook.env_path().expect("Node must have env path");
-        let new_path = prepend_paths(&[&bin_dir(env_dir)]).context("Failed to join PATH")?;
+        let new_path = EnvPath::new()
+            .prepend(bin_dir(env_dir))
+            .exclude_store(store)
+            .build()
+            .context("Failed to join PATH")?;
 
         let entry = hook.entry.parsed()?;
         let run = async move |batch: Vec<String>| {
@@ -145,19 +289,15 @@ impl LanguageImpl for Node {
                 Cmd::new(&entry[0], "node hook")
             };
 
-            let mut output = cmd
-                .args(&entry[1..])
-                .env("PATH", &new_path)
+            cmd.env("PATH", &new_path)
                 .env(EnvVars::NPM_CONFIG_PREFIX, env_dir)
                 .env_remove(EnvVars::NPM_CONFIG_USERCONFIG)
-                .env(EnvVars::NODE_PATH, lib_dir(env_dir))
-                .args(&hook.args)
-                .args(batch)
+                .env(EnvVars::NODE_PATH, lib_dir(env_dir));
+            let output = build_command(&mut cmd, &entry, hook, work_dir, &batch)
                 .check(false)
-                .output()
+                .combined_output()
                 .await?;
 
-            output.stdout.extend(output.stderr);
             let code = output.status.code().unwrap_or(1);
             anyhow::Ok((code, output.stdout))
         };
@@ -169,7 +309,9 @@ impl LanguageImpl for Node {
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -0,0 +1,186 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::hook::{Hook, InstallInfo};
+
+/// The key of the package manager used to install a hook repository in [`crate::hook::InstallInfo`].
+pub(crate) const EXTRA_KEY_PACKAGE_MANAGER: &str = "package_manager";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PackageManagerKind {
+    Npm,
+    Yarn,
+    Pnpm,
+}
+
+impl PackageManagerKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Yarn => "yarn",
+            Self::Pnpm => "pnpm",
+        }
+    }
+}
+
+impl Display for PackageManagerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The package manager a hook repository is meant to be installed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PackageManager {
+    pub(crate) kind: PackageManagerKind,
+    /// The version pinned by the `packageManager` field of `package.json`.
+    pub(crate) version: Option<String>,
+}
+
+impl PackageManager {
+    const NPM: Self = Self {
+        kind: PackageManagerKind::Npm,
+        version: None,
+    };
+
+    /// Detect the package manager of the repository at `repo`.
+    ///
+    /// The `packageManager` field of `package.json` takes precedence over the lockfiles.
+    pub(crate) fn detect(repo: &Path) -> Result<Self> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PackageJson {
+            package_manager: Option<String>,
+        }
+
+        let package_json = repo.join("package.json");
+        if package_json.is_file() {
+            let content = fs_err::read_to_string(&package_json)?;
+            let package: PackageJson = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse `{}`", package_json.display()))?;
+            if let Some(package_manager) = package.package_manager {
+                return Ok(Self::from_field(&package_manager).unwrap_or(Self::NPM));
+            }
+        }
+
+        let kind = if repo.join("pnpm-lock.yaml").is_file() {
+            PackageManagerKind::Pnpm
+        } else if repo.join("yarn.lock").is_file() {
+            PackageManagerKind::Yarn
+        } else {
+            PackageManagerKind::Npm
+        };
+        Ok(Self {
+            kind,
+            version: None,
+        })
+    }
+
+    /// Parse the `packageManager` field, e.g. `yarn@4.1.0+sha512.abc`.
+    fn from_field(field: &str) -> Option<Self> {
+        let (name, version) = field.split_once('@').unwrap_or((field, ""));
+        let kind = match name {
+            "npm" => PackageManagerKind::Npm,
+            "yarn" => PackageManagerKind::Yarn,
+            "pnpm" => PackageManagerKind::Pnpm,
+            _ => return None,
+        };
+        let version = version.split('+').next().unwrap_or_default();
+        Some(Self {
+            kind,
+            version: (!version.is_empty()).then(|| version.to_string()),
+        })
+    }
+}
+
+/// Whether the environment of `info` was installed for the package manager the repository of
+/// `hook` asks for, so that switching lockfiles or `packageManager` gets a new environment.
+pub(crate) fn package_manager_matches(info: &InstallInfo, hook: &Hook) -> bool {
+    let requested = match hook.repo_path() {
+        Some(repo) => PackageManager::detect(repo).unwrap_or_default(),
+        None => PackageManager::default(),
+    };
+    info.get_extra(EXTRA_KEY_PACKAGE_MANAGER)
+        .map_or(requested == PackageManager::NPM, |installed| {
+            *installed == requested.to_string()
+        })
+}
+
+impl Default for PackageManager {
+    fn default() -> Self {
+        Self::NPM
+    }
+}
+
+impl Display for PackageManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{version}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(files: &[(&str, &str)]) -> PackageManager {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            fs_err::write(dir.path().join(name), content).unwrap();
+        }
+        PackageManager::detect(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn detect_from_lockfile() {
+        assert_eq!(detect(&[]), PackageManager::NPM);
+        assert_eq!(
+            detect(&[("package.json", "{}"), ("package-lock.json", "{}")]),
+            PackageManager::NPM
+        );
+        assert_eq!(
+            detect(&[("package.json", "{}"), ("yarn.lock", "")]),
+            PackageManager {
+                kind: PackageManagerKind::Yarn,
+                version: None,
+            }
+        );
+        assert_eq!(
+            detect(&[("package.json", "{}"), ("pnpm-lock.yaml", "")]),
+            PackageManager {
+                kind: PackageManagerKind::Pnpm,
+                version: None,
+            }
+        );
+    }
+
+    #[test]
+    fn detect_from_package_json() {
+        let package_manager = detect(&[
+            (
+                "package.json",
+                r#"{"packageManager": "yarn@4.1.0+sha512.5b7bc055cad63273"}"#,
+            ),
+            ("package-lock.json", "{}"),
+        ]);
+        assert_eq!(
+            package_manager,
+            PackageManager {
+                kind: PackageManagerKind::Yarn,
+                version: Some("4.1.0".to_string()),
+            }
+        );
+        assert_eq!(package_manager.to_string(), "yarn@4.1.0");
+
+        // Unknown package managers are installed with npm.
+        assert_eq!(
+            detect(&[("package.json", r#"{"packageManager": "bun@1.0.0"}"#)]),
+            PackageManager::NPM
+        );
+    }
+}
@@ -135,7 +135,7 @@ impl NodeVersion {
 ///   Or any other semver compatible version requirement.
 /// - `lts/<codename>`: Install the latest version of node with the specified code name.
 /// - `local/path/to/node`: Use the node executable at the specified path.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum NodeRequest {
     Any,
     Major(u64),
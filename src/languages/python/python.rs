@@ -7,15 +7,16 @@ use tracing::debug;
 
 use constants::env_vars::EnvVars;
 
+use crate::cli::reporter::HookInstallReporter;
 use crate::hook::InstalledHook;
 use crate::hook::{Hook, InstallInfo};
-use crate::languages::LanguageImpl;
 use crate::languages::python::PythonRequest;
 use crate::languages::python::uv::Uv;
 use crate::languages::version::LanguageRequest;
+use crate::languages::{Health, LanguageImpl};
 use crate::process;
 use crate::process::Cmd;
-use crate::run::{prepend_paths, run_by_batch};
+use crate::run::{EnvPath, build_command, run_by_batch};
 use crate::store::{Store, ToolBucket};
 
 #[derive(Debug, Copy, Clone)]
@@ -45,15 +46,16 @@ fn to_uv_python_request(request: &LanguageRequest) -> Option<String> {
 }
 
 impl LanguageImpl for Python {
-    async fn install(&self, hook: Arc<Hook>, store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         let uv_dir = store.tools_path(ToolBucket::Uv);
         let uv = Uv::install(&uv_dir).await?;
 
-        let mut info = InstallInfo::new(
-            hook.language,
-            hook.dependencies().clone(),
-            &store.hooks_dir(),
-        );
+        let mut info = InstallInfo::for_hook(&hook, &store.hooks_dir());
 
         debug!(%hook, target = %info.env_path.display(), "Installing environment");
 
@@ -122,34 +124,37 @@ impl LanguageImpl for Python {
         })
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    // The interpreter of a hook comes with its environment.
+    async fn check_health(&self) -> Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
         &self,
         hook: &InstalledHook,
         filenames: &[&String],
-        _store: &Store,
+        store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
         let env_dir = hook.env_path().expect("Python must have env path");
-        let new_path = prepend_paths(&[&bin_dir(env_dir)]).context("Failed to join PATH")?;
+        let new_path = EnvPath::new()
+            .prepend(bin_dir(env_dir))
+            .exclude_store(store)
+            .build()
+            .context("Failed to join PATH")?;
         let entry = hook.entry.parsed()?;
+        let program = entry_program(&entry[0], &bin_dir(env_dir));
 
         let run = async move |batch: Vec<String>| {
-            // TODO: combine stdout and stderr
-            let mut output = Cmd::new(&entry[0], "python hook")
-                .args(&entry[1..])
-                .env("VIRTUAL_ENV", env_dir)
+            let mut cmd = Cmd::new(&program, "python hook");
+            cmd.env("VIRTUAL_ENV", env_dir)
                 .env("PATH", &new_path)
-                .env_remove("PYTHONHOME")
-                .args(&hook.args)
-                .args(batch)
+                .env_remove("PYTHONHOME");
+            let output = build_command(&mut cmd, &entry, hook, work_dir, &batch)
                 .check(false)
-                .output()
+                .combined_output()
                 .await?;
 
-            output.stdout.extend(output.stderr);
             let code = output.status.code().unwrap_or(1);
             anyhow::Ok((code, output.stdout))
         };
@@ -161,7 +166,9 @@ impl LanguageImpl for Python {
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -274,5 +281,56 @@ fn bin_dir(venv: &Path) -> PathBuf {
 }
 
 fn python_exec(venv: &Path) -> PathBuf {
-    bin_dir(venv).join("python").with_extension(EXE_EXTENSION)
+    python_in(&bin_dir(venv))
+}
+
+fn python_in(bin_dir: &Path) -> PathBuf {
+    bin_dir.join("python").with_extension(EXE_EXTENSION)
+}
+
+/// Resolve the program of a hook `entry`.
+///
+/// `python`, `python3` and `python3.Y` refer to the interpreter of the environment, so that
+/// entries like `python -m mypkg.cli` work even where the environment has no such shim.
+fn entry_program(program: &str, bin_dir: &Path) -> PathBuf {
+    let is_python = program.strip_prefix("python").is_some_and(|version| {
+        version.is_empty()
+            || version
+                .split('.')
+                .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    });
+    if is_python {
+        python_in(bin_dir)
+    } else {
+        PathBuf::from(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::consts::EXE_EXTENSION;
+    use std::path::{Path, PathBuf};
+
+    use super::entry_program;
+
+    #[test]
+    fn entry_program_python() {
+        for bin_dir in [Path::new("venv/bin"), Path::new("venv/Scripts")] {
+            let python = bin_dir.join("python").with_extension(EXE_EXTENSION);
+            assert_eq!(entry_program("python", bin_dir), python);
+            assert_eq!(entry_program("python3", bin_dir), python);
+            assert_eq!(entry_program("python3.12", bin_dir), python);
+
+            assert_eq!(entry_program("pythonw", bin_dir), PathBuf::from("pythonw"));
+            assert_eq!(
+                entry_program("python3.", bin_dir),
+                PathBuf::from("python3.")
+            );
+            assert_eq!(entry_program("mypy", bin_dir), PathBuf::from("mypy"));
+            assert_eq!(
+                entry_program("/usr/bin/python3", bin_dir),
+                PathBuf::from("/usr/bin/python3")
+            );
+        }
+    }
 }
@@ -7,7 +7,7 @@ use crate::hook::InstallInfo;
 use crate::languages::version;
 use crate::languages::version::try_into_u64_slice;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum PythonRequest {
     Any,
     Major(u64),
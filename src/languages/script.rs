@@ -1,58 +1,82 @@
+use std::path::Path;
+use std::process::Stdio;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
+use crate::cli::reporter::HookInstallReporter;
+use crate::config::HookStdin;
 use crate::fs::CWD;
 use crate::hook::Hook;
 use crate::hook::InstalledHook;
-use crate::languages::LanguageImpl;
+use crate::languages::{Health, LanguageImpl};
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{EnvPath, build_command, run_by_batch, run_by_file};
 use crate::store::Store;
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Script;
 
 impl LanguageImpl for Script {
-    async fn install(&self, hook: Arc<Hook>, _store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        _store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         Ok(InstalledHook::NoNeedInstall(hook))
     }
 
-    async fn check_health(&self) -> Result<()> {
-        Ok(())
+    async fn check_health(&self) -> Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
         &self,
         hook: &InstalledHook,
         filenames: &[&String],
-        _store: &Store,
+        store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
         let entry = hook.entry.parsed()?;
         let repo_path = hook.repo_path().unwrap_or_else(|| CWD.as_path());
         let cmd = repo_path.join(&entry[0]);
+        let new_path = EnvPath::new()
+            .exclude_store(store)
+            .build()
+            .context("Failed to join PATH")?;
 
-        let run = async move |batch: Vec<String>| {
-            let mut command = Cmd::new(&cmd, "run script command")
-                .args(&entry[1..])
-                .args(&hook.args)
-                .args(batch)
-                .output()
+        let run = async |batch: Vec<String>, stdin: Option<Stdio>| {
+            let mut command = Cmd::new(&cmd, "run script command");
+            command.env("PATH", &new_path);
+            if let Some(stdin) = stdin {
+                command.stdin(stdin);
+            }
+            let output = build_command(&mut command, &entry, hook, work_dir, &batch)
+                .combined_output()
                 .await?;
 
-            command.stdout.extend(command.stderr);
-            let code = command.status.code().unwrap_or(1);
-            anyhow::Ok((code, command.stdout))
+            let code = output.status.code().unwrap_or(1);
+            anyhow::Ok((code, output.stdout))
         };
 
-        let results = run_by_batch(hook, filenames, run).await?;
+        let results = if hook.stdin == Some(HookStdin::Filename) {
+            run_by_file(filenames, work_dir, async |stdin| {
+                run(vec![], Some(stdin)).await
+            })
+            .await?
+        } else {
+            run_by_batch(hook, filenames, async |batch| run(batch, None).await).await?
+        };
 
         // Collect results
         let mut combined_status = 0;
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -1,23 +1,32 @@
+use std::path::Path;
+use std::process::Stdio;
 use std::sync::Arc;
 
 use anyhow::Result;
 
+use crate::cli::reporter::HookInstallReporter;
+use crate::config::HookStdin;
 use crate::hook::{Hook, InstalledHook};
-use crate::languages::LanguageImpl;
+use crate::languages::{Health, LanguageImpl};
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{build_command, run_by_batch, run_by_file};
 use crate::store::Store;
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct System;
 
 impl LanguageImpl for System {
-    async fn install(&self, hook: Arc<Hook>, _store: &Store) -> Result<InstalledHook> {
+    async fn install(
+        &self,
+        hook: Arc<Hook>,
+        _store: &Store,
+        _reporter: &HookInstallReporter,
+    ) -> Result<InstalledHook> {
         Ok(InstalledHook::NoNeedInstall(hook))
     }
 
-    async fn check_health(&self) -> Result<()> {
-        Ok(())
+    async fn check_health(&self) -> Result<Health> {
+        Ok(Health::default())
     }
 
     async fn run(
@@ -25,31 +34,41 @@ impl LanguageImpl for System {
         hook: &InstalledHook,
         filenames: &[&String],
         _store: &Store,
+        work_dir: &Path,
     ) -> Result<(i32, Vec<u8>)> {
         let entry = hook.entry.parsed()?;
 
-        let run = async move |batch: Vec<String>| {
-            let mut output = Cmd::new(&entry[0], "run system command")
-                .args(&entry[1..])
-                .args(&hook.args)
-                .args(batch)
+        let run = async |batch: Vec<String>, stdin: Option<Stdio>| {
+            let mut cmd = Cmd::new(&entry[0], "run system command");
+            if let Some(stdin) = stdin {
+                cmd.stdin(stdin);
+            }
+            let output = build_command(&mut cmd, &entry, hook, work_dir, &batch)
                 .check(false)
-                .output()
+                .combined_output()
                 .await?;
 
-            output.stdout.extend(output.stderr);
             let code = output.status.code().unwrap_or(1);
             anyhow::Ok((code, output.stdout))
         };
 
-        let results = run_by_batch(hook, filenames, run).await?;
+        let results = if hook.stdin == Some(HookStdin::Filename) {
+            run_by_file(filenames, work_dir, async |stdin| {
+                run(vec![], Some(stdin)).await
+            })
+            .await?
+        } else {
+            run_by_batch(hook, filenames, async |batch| run(batch, None).await).await?
+        };
 
         // Collect results
         let mut combined_status = 0;
         let mut combined_output = Vec::new();
 
         for (code, output) in results {
-            combined_status |= code;
+            if combined_status == 0 {
+                combined_status = code;
+            }
             combined_output.extend(output);
         }
 
@@ -2,8 +2,11 @@ use std::str::FromStr;
 
 use crate::config::Language;
 use crate::hook::InstallInfo;
+#[cfg(feature = "languages-golang")]
 use crate::languages::golang::GoRequest;
+#[cfg(feature = "languages-node")]
 use crate::languages::node::NodeRequest;
+#[cfg(feature = "languages-python")]
 use crate::languages::python::PythonRequest;
 
 #[derive(thiserror::Error, Debug)]
@@ -12,11 +15,14 @@ pub enum Error {
     InvalidVersion(String),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum LanguageRequest {
     Any,
+    #[cfg(feature = "languages-python")]
     Python(PythonRequest),
+    #[cfg(feature = "languages-node")]
     Node(NodeRequest),
+    #[cfg(feature = "languages-golang")]
     Golang(GoRequest),
     // TODO: all other languages default to semver for now.
     Semver(SemverRequest),
@@ -39,9 +45,18 @@ impl LanguageRequest {
             return Ok(LanguageRequest::Any);
         }
 
+        // Hooks of languages missing from this build fail before they are installed, so their
+        // requests are not parsed.
+        if lang.missing_feature().is_some() {
+            return Ok(LanguageRequest::Any);
+        }
+
         Ok(match lang {
+            #[cfg(feature = "languages-python")]
             Language::Python => Self::Python(request.parse()?),
+            #[cfg(feature = "languages-node")]
             Language::Node => Self::Node(request.parse()?),
+            #[cfg(feature = "languages-golang")]
             Language::Golang => Self::Golang(request.parse()?),
             _ => Self::Semver(request.parse()?),
         })
@@ -50,15 +65,18 @@ impl LanguageRequest {
     pub fn satisfied_by(&self, install_info: &InstallInfo) -> bool {
         match self {
             LanguageRequest::Any => true,
+            #[cfg(feature = "languages-python")]
             LanguageRequest::Python(req) => req.satisfied_by(install_info),
+            #[cfg(feature = "languages-node")]
             LanguageRequest::Node(req) => req.satisfied_by(install_info),
+            #[cfg(feature = "languages-golang")]
             LanguageRequest::Golang(req) => req.satisfied_by(install_info),
             LanguageRequest::Semver(req) => req.satisfied_by(install_info),
         }
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct SemverRequest(semver::VersionReq);
 
 impl FromStr for SemverRequest {
@@ -7,16 +7,23 @@ use anstream::{ColorChoice, eprintln};
 use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser};
 use clap_complete::CompleteEnv;
+use constants::env_vars::EnvVars;
 use owo_colors::OwoColorize;
 use tracing::{debug, error};
-use tracing_subscriber::EnvFilter;
 use tracing_subscriber::filter::Directive;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
 
 use crate::cleanup::cleanup;
-use crate::cli::{Cli, Command, ExitStatus, SelfCommand, SelfNamespace, SelfUpdateArgs};
+use crate::cli::{
+    Cli, Command, EnvCommand, EnvNamespace, ExitStatus, SelfCommand, SelfNamespace,
+    SelfUninstallArgs, SelfUpdateArgs, SelfVersionArgs,
+};
 use crate::git::get_root;
 use crate::printer::Printer;
 
+#[cfg(feature = "download")]
 mod archive;
 mod builtin;
 mod cleanup;
@@ -52,7 +59,7 @@ pub(crate) enum Level {
     TraceAll,
 }
 
-fn setup_logging(level: Level) -> Result<()> {
+fn setup_logging(level: Level, log_file: Option<&Path>) -> Result<()> {
     let directive = match level {
         Level::Default | Level::Verbose => tracing::level_filters::LevelFilter::OFF.into(),
         Level::Debug => Directive::from_str("prek=debug")?,
@@ -76,10 +83,30 @@ fn setup_logging(level: Level) -> Result<()> {
         .with_target(false)
         .without_time()
         .with_ansi(ansi);
-    tracing_subscriber::fmt::fmt()
-        .with_env_filter(filter)
+    let stderr_layer = tracing_subscriber::fmt::layer()
         .event_format(format)
         .with_writer(anstream::stderr)
+        .with_filter(filter);
+
+    // The log file always gets the debug messages, with timestamps, whatever the verbosity.
+    let file_layer = log_file
+        .map(|path| -> Result<_> {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file `{}`", path.display()))?;
+            Ok(tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_ansi(false)
+                .with_writer(std::sync::Mutex::new(file))
+                .with_filter(EnvFilter::new("prek=debug")))
+        })
+        .transpose()?;
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer)
         .init();
     Ok(())
 }
@@ -93,6 +120,15 @@ fn adjust_relative_paths(cli: &mut Cli, new_cwd: &Path) -> Result<()> {
     }
 
     if let Some(Command::Run(ref mut args) | Command::TryRepo(ref mut args)) = cli.command {
+        if let Some(path) = &mut args.skip_file {
+            *path = std::path::absolute(&*path)?;
+        }
+        // `-` reads the config from stdin.
+        if let Some(path) = &mut args.from_config {
+            if path.as_os_str() != "-" {
+                *path = std::path::absolute(&*path)?;
+            }
+        }
         args.files = args
             .files
             .iter()
@@ -120,31 +156,53 @@ fn adjust_relative_paths(cli: &mut Cli, new_cwd: &Path) -> Result<()> {
             .transpose()?;
     }
 
+    if let Some(Command::NewHook(ref mut args)) = cli.command {
+        args.file = std::path::absolute(&args.file)?;
+    }
+
     Ok(())
 }
 
 async fn run(mut cli: Cli) -> Result<ExitStatus> {
     ColorChoice::write_global(cli.globals.color.into());
 
-    setup_logging(match cli.globals.verbose {
-        0 => Level::Default,
-        1 => Level::Verbose,
-        2 => Level::Debug,
-        3 => Level::Trace,
-        _ => Level::TraceAll,
-    })?;
-
-    let printer = if cli.globals.quiet {
+    setup_logging(
+        match cli.globals.verbose {
+            0 => Level::Default,
+            1 => Level::Verbose,
+            2 => Level::Debug,
+            3 => Level::Trace,
+            _ => Level::TraceAll,
+        },
+        cli.globals.log_file.as_deref(),
+    )?;
+
+    let printer = if cli.globals.silent {
+        Printer::Silent
+    } else if cli.globals.quiet {
         Printer::Quiet
     } else if cli.globals.verbose > 1 {
         Printer::Verbose
-    } else if cli.globals.no_progress {
+    } else if cli.globals.no_progress || EnvVars::is_set(EnvVars::PREK_DETERMINISTIC_OUTPUT) {
         Printer::NoProgress
     } else {
         Printer::Default
     };
 
-    if cli.globals.quiet {
+    if cli.globals.keep_temp {
+        languages::keep_temp();
+    }
+
+    if cli.globals.refresh {
+        #[cfg(feature = "docker")]
+        languages::refresh_images();
+        #[cfg(not(feature = "docker"))]
+        anyhow::bail!(
+            "`--refresh` is not available in this build of prek, rebuild it with the `docker` feature"
+        );
+    }
+
+    if cli.globals.silent {
         warnings::disable();
     } else {
         warnings::enable();
@@ -156,6 +214,7 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
 
     debug!("prek: {}", version::version());
 
+    let invocation_dir = std::env::current_dir()?;
     match get_root().await {
         Ok(root) => {
             debug!("Git root: {}", root.display());
@@ -185,13 +244,15 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
     }
     show_settings!(cli.globals, false);
 
-    match cli.command.unwrap() {
+    let strict = cli.globals.strict;
+    let status = match cli.command.unwrap() {
         Command::Install(args) => {
             show_settings!(args);
 
             cli::install(
                 cli.globals.config,
                 args.hook_types,
+                args.auto_hook_types,
                 args.install_hooks,
                 args.overwrite,
                 args.allow_missing_config,
@@ -204,28 +265,44 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
         Command::Uninstall(args) => {
             show_settings!(args);
 
-            cli::uninstall(cli.globals.config, args.hook_types, printer).await
-        }
-        Command::Run(args) => {
-            show_settings!(args);
-
-            cli::run(
+            cli::uninstall(
                 cli.globals.config,
-                args.hook_id,
-                args.hook_stage,
-                args.from_ref,
-                args.to_ref,
-                args.all_files,
-                args.files,
-                args.directory,
-                args.last_commit,
-                args.show_diff_on_failure,
-                args.extra,
-                cli.globals.verbose > 0,
+                args.hook_types,
+                args.auto_hook_types,
                 printer,
             )
             .await
         }
+        Command::Run(args) => {
+            show_settings!(args);
+
+            let options = cli::RunOptions {
+                config: cli.globals.config,
+                from_config: args.from_config,
+                hook_id: args.hook_id,
+                hook_stages: args.hook_stage,
+                from_ref: args.from_ref,
+                to_ref: args.to_ref,
+                all_files: args.all_files,
+                files: args.files,
+                directories: args.directory,
+                include_untracked: args.include_untracked,
+                last_commit: args.last_commit,
+                show_diff_on_failure: args.show_diff_on_failure,
+                max_output_lines: args.max_output_lines,
+                abort_on_error: args.abort_on_error,
+                propagate_exit_code: args.propagate_exit_code,
+                allow_system_fallback: args.allow_system_fallback,
+                reinstall_stale_envs: args.reinstall_stale_envs,
+                no_install: args.no_install,
+                fail_fast_first: args.fail_fast_first,
+                skip_file: args.skip_file,
+                paths: args.paths,
+                extra_args: args.extra,
+                verbose: cli.globals.verbose > 0,
+            };
+            cli::run(options, &invocation_dir, printer).await
+        }
         Command::HookImpl(args) => {
             show_settings!(args);
 
@@ -239,18 +316,52 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
             )
             .await
         }
-        Command::Clean => cli::clean(printer),
+        Command::Clean(args) => cli::clean(args.docker, printer).await,
+        Command::GC => cli::gc(printer).await,
         Command::ValidateConfig(args) => {
             show_settings!(args);
 
-            Ok(cli::validate_configs(args.configs))
+            cli::validate_configs(
+                args.configs,
+                args.output_format,
+                cli.globals.strict,
+                printer,
+            )
         }
         Command::ValidateManifest(args) => {
             show_settings!(args);
 
             Ok(cli::validate_manifest(args.manifests))
         }
-        Command::SampleConfig(args) => cli::sample_config(args.file, printer),
+        Command::SampleConfig(args) => {
+            cli::sample_config(args.file, args.format, args.force, args.append, printer)
+        }
+        Command::NewHook(args) => {
+            show_settings!(args);
+
+            cli::new_hook(
+                &args.file,
+                args.id,
+                args.name,
+                args.language,
+                args.entry,
+                args.types,
+                printer,
+            )
+        }
+        Command::ConfigSchema => cli::config_schema(printer),
+        Command::Env(EnvNamespace {
+            command: EnvCommand::Info(args),
+        }) => {
+            show_settings!(args);
+
+            cli::env_info(cli.globals.config, &args.hook_id, args.json, printer).await
+        }
+        Command::ExportHooks(args) => {
+            show_settings!(args);
+
+            cli::export_hooks(cli.globals.config, args.format, args.hook_stage, printer).await
+        }
         Command::Self_(SelfNamespace {
             command:
                 SelfCommand::Update(SelfUpdateArgs {
@@ -258,6 +369,17 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
                     token,
                 }),
         }) => cli::self_update(target_version, token, printer).await,
+        Command::Self_(SelfNamespace {
+            command: SelfCommand::Version(SelfVersionArgs { json }),
+        }) => cli::self_version(json, printer),
+        Command::Self_(SelfNamespace {
+            command:
+                SelfCommand::Uninstall(SelfUninstallArgs {
+                    yes,
+                    all_repos,
+                    purge_cache,
+                }),
+        }) => cli::self_uninstall(yes, all_repos, purge_cache, printer).await,
         Command::GenerateShellCompletion(args) => {
             show_settings!(args);
 
@@ -282,24 +404,44 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
             .await
         }
         _ => {
-            writeln!(printer.stderr(), "Command not implemented yet")?;
+            writeln!(printer.stderr_important(), "Command not implemented yet")?;
             Ok(ExitStatus::Failure)
         }
+    }?;
+
+    let warnings = warnings::emitted();
+    if strict && !warnings.is_empty() {
+        writeln!(
+            printer.stderr_important(),
+            "\n{}: {} promoted to errors by `--strict`:",
+            "error".red().bold(),
+            if warnings.len() == 1 {
+                "1 warning was".to_string()
+            } else {
+                format!("{} warnings were", warnings.len())
+            }
+        )?;
+        for warning in &warnings {
+            writeln!(printer.stderr_important(), "  - {warning}")?;
+        }
+        if matches!(status, ExitStatus::Success) {
+            return Ok(ExitStatus::Failure);
+        }
     }
+
+    Ok(status)
 }
 
 fn main() -> ExitCode {
     CompleteEnv::with_factory(Cli::command).complete();
 
+    // Also handles SIGTERM and SIGHUP, which CI runners send to cancel a job, so the stashed
+    // work tree changes are restored.
     ctrlc::set_handler(move || {
+        process::interrupt_hooks();
         cleanup();
 
-        #[allow(clippy::exit, clippy::cast_possible_wrap)]
-        std::process::exit(if cfg!(windows) {
-            0xC000_013A_u32 as i32
-        } else {
-            130
-        });
+        ExitStatus::Interrupted.exit_process();
     })
     .expect("Error setting Ctrl-C handler");
 
@@ -319,7 +461,12 @@ fn main() -> ExitCode {
         _profiler_guard = Some(());
     }
 
-    let runtime = tokio::runtime::Builder::new_current_thread()
+    // Environment variables are process-global, clear the inherited git ones before there
+    // are other threads that could read them.
+    git::clear_git_env();
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(run::worker_threads())
         .enable_all()
         .build()
         .expect("Failed to create tokio runtime");
@@ -27,8 +27,10 @@ use indicatif::ProgressDrawTarget;
 pub enum Printer {
     /// A printer that prints to standard streams (e.g., stdout).
     Default,
-    /// A printer that suppresses all output.
+    /// A printer that suppresses all output, except warnings and errors.
     Quiet,
+    /// A printer that suppresses all output.
+    Silent,
     /// A printer that prints all output, including debug messages.
     Verbose,
     /// A printer that prints to standard streams, excluding all progress outputs
@@ -41,6 +43,7 @@ impl Printer {
         match self {
             Self::Default => ProgressDrawTarget::stderr(),
             Self::Quiet => ProgressDrawTarget::hidden(),
+            Self::Silent => ProgressDrawTarget::hidden(),
             // Confusingly, hide the progress bar when in verbose mode.
             // Otherwise, it gets interleaved with debug messages.
             Self::Verbose => ProgressDrawTarget::hidden(),
@@ -53,6 +56,7 @@ impl Printer {
         match self {
             Self::Default => Stdout::Enabled,
             Self::Quiet => Stdout::Disabled,
+            Self::Silent => Stdout::Disabled,
             Self::Verbose => Stdout::Enabled,
             Self::NoProgress => Stdout::Enabled,
         }
@@ -63,6 +67,18 @@ impl Printer {
         match self {
             Self::Default => Stderr::Enabled,
             Self::Quiet => Stderr::Disabled,
+            Self::Silent => Stderr::Disabled,
+            Self::Verbose => Stderr::Enabled,
+            Self::NoProgress => Stderr::Enabled,
+        }
+    }
+
+    /// Return the [`Stderr`] for failures and errors, which are shown in quiet mode too.
+    pub fn stderr_important(self) -> Stderr {
+        match self {
+            Self::Default => Stderr::Enabled,
+            Self::Quiet => Stderr::Enabled,
+            Self::Silent => Stderr::Disabled,
             Self::Verbose => Stderr::Enabled,
             Self::NoProgress => Stderr::Enabled,
         }
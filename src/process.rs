@@ -26,7 +26,9 @@
 
 /// Adapt [axoprocess] to use [`tokio::process::Process`] instead of [`std::process::Command`].
 use std::fmt::Display;
+use std::io::Read;
 use std::process::Output;
+use std::sync::{Mutex, PoisonError};
 use std::{
     ffi::OsStr,
     path::Path,
@@ -36,12 +38,67 @@ use std::{
 use miette::Diagnostic;
 use owo_colors::OwoColorize;
 use thiserror::Error;
+use tokio::io::AsyncReadExt;
 use tracing::trace;
 
 use crate::git::GIT;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The process groups of the running hooks, see [`interrupt_hooks`].
+static PROCESS_GROUPS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Keeps a process group in [`PROCESS_GROUPS`] while its leader is running.
+struct ProcessGroupGuard(u32);
+
+impl ProcessGroupGuard {
+    fn register(id: u32) -> Self {
+        PROCESS_GROUPS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(id);
+        Self(id)
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        PROCESS_GROUPS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|&id| id != self.0);
+    }
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GenerateConsoleCtrlEvent(ctrl_event: u32, process_group_id: u32) -> i32;
+}
+
+/// Forward an interrupt to the running hooks.
+///
+/// Hooks run in their own process group, so that they can be interrupted as a whole, with
+/// their own children. The interrupt of the terminal doesn't reach them, it must be forwarded.
+/// On Windows, `CTRL_BREAK_EVENT` is sent because `CTRL_C_EVENT` is ignored by new process
+/// groups.
+pub fn interrupt_hooks() {
+    let groups = PROCESS_GROUPS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for &id in groups.iter() {
+        #[cfg(unix)]
+        if let Ok(pgid) = libc::pid_t::try_from(id) {
+            unsafe { libc::killpg(pgid, libc::SIGINT) };
+        }
+        #[cfg(windows)]
+        {
+            const CTRL_BREAK_EVENT: u32 = 1;
+            unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, id) };
+        }
+    }
+}
+
 /// An error from executing a Command
 #[derive(Debug, Error, Diagnostic)]
 pub enum Error {
@@ -106,6 +163,8 @@ pub struct Cmd {
     pub inner: tokio::process::Command,
     summary: String,
     check_status: bool,
+    /// Whether stdin was set, [`Cmd::combined_output`] closes it otherwise.
+    stdin: bool,
 }
 
 /// Constructors
@@ -117,6 +176,7 @@ impl Cmd {
             summary: summary.into(),
             inner,
             check_status: true,
+            stdin: false,
         }
     }
 }
@@ -180,6 +240,125 @@ impl Cmd {
         Ok(output)
     }
 
+    /// Like [`Cmd::output`][], but with stdout and stderr redirected into the same pipe.
+    ///
+    /// The combined stream preserves the order in which the command wrote to both
+    /// streams, and is returned as `stdout` (`stderr` is always empty).
+    ///
+    /// This is how hooks are run, so the command gets its own process group, which is
+    /// interrupted by [`interrupt_hooks`].
+    pub async fn combined_output(&mut self) -> Result<Output> {
+        let summary = self.summary.clone();
+        let exec_error = |cause| Error::Exec {
+            summary: summary.clone(),
+            cause,
+        };
+
+        let (mut reader, writer) = std::io::pipe().map_err(exec_error)?;
+        let writer_clone = writer.try_clone().map_err(exec_error)?;
+        if !self.stdin {
+            self.inner.stdin(Stdio::null());
+        }
+        self.inner.stdout(writer).stderr(writer_clone);
+
+        #[cfg(unix)]
+        self.inner.process_group(0);
+        #[cfg(windows)]
+        {
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+            self.inner.creation_flags(CREATE_NEW_PROCESS_GROUP);
+        }
+
+        let child = self.spawn();
+        // Drop the write ends held by the command, so reading stops once the child exits.
+        self.inner.stdout(Stdio::null()).stderr(Stdio::null());
+        let mut child = child?;
+        let _group = child.id().map(ProcessGroupGuard::register);
+
+        let read = tokio::task::spawn_blocking(move || {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).map(|_| buf)
+        });
+        let status = child.wait().await.map_err(exec_error)?;
+        let stdout = read
+            .await
+            .expect("Failed to join the reader task")
+            .map_err(exec_error)?;
+
+        let output = Output {
+            status,
+            stdout,
+            stderr: Vec::new(),
+        };
+        self.maybe_check_output(&output)?;
+        Ok(output)
+    }
+
+    /// Like [`Cmd::output`][], but calls `on_line` with each line the command writes to stderr
+    /// while it runs.
+    ///
+    /// Lines are also split on `\r`, which progress meters like the one of `git` use to redraw
+    /// the current line. The command is killed if the returned future is dropped, e.g. by a
+    /// timeout.
+    pub async fn output_with_stderr_lines(
+        &mut self,
+        mut on_line: impl FnMut(&str),
+    ) -> Result<Output> {
+        let summary = self.summary.clone();
+        let exec_error = |cause| Error::Exec {
+            summary: summary.clone(),
+            cause,
+        };
+
+        self.inner
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = self.spawn()?;
+        let mut stdout_pipe = child.stdout.take().expect("stdout is piped");
+        let mut stderr_pipe = child.stderr.take().expect("stderr is piped");
+
+        let read_stdout = async {
+            let mut stdout = Vec::new();
+            stdout_pipe.read_to_end(&mut stdout).await.map(|_| stdout)
+        };
+        let read_stderr = async {
+            let mut stderr = Vec::new();
+            let mut chunk = [0; 4096];
+            // Where the current, unfinished line starts.
+            let mut start = 0;
+            loop {
+                let read = stderr_pipe.read(&mut chunk).await?;
+                if read == 0 {
+                    break;
+                }
+                stderr.extend_from_slice(&chunk[..read]);
+                while let Some(end) = stderr[start..]
+                    .iter()
+                    .position(|&b| b == b'\n' || b == b'\r')
+                {
+                    let line = String::from_utf8_lossy(&stderr[start..start + end]);
+                    let line = line.trim();
+                    if !line.is_empty() {
+                        on_line(line);
+                    }
+                    start += end + 1;
+                }
+            }
+            Ok::<_, std::io::Error>(stderr)
+        };
+        let (stdout, stderr) = tokio::try_join!(read_stdout, read_stderr).map_err(exec_error)?;
+        let status = child.wait().await.map_err(exec_error)?;
+
+        let output = Output {
+            status,
+            stdout,
+            stderr,
+        };
+        self.maybe_check_output(&output)?;
+        Ok(output)
+    }
+
     /// Equivalent to [`std::process::Command::status`][]
     /// but logged, with the error wrapped, and status checked (by default)
     pub async fn status(&mut self) -> Result<ExitStatus> {
@@ -253,6 +432,7 @@ impl Cmd {
     /// Forwards to [`std::process::Command::stdin`][]
     pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
         self.inner.stdin(cfg);
+        self.stdin = true;
         self
     }
 
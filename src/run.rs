@@ -1,63 +1,109 @@
 use std::cmp::max;
 use std::ffi::OsString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::LazyLock;
 
 use futures::StreamExt;
+use rustc_hash::FxHashSet;
 use tracing::trace;
 
 use constants::env_vars::EnvVars;
 
 use crate::hook::Hook;
+use crate::process::Cmd;
+use crate::store::Store;
+use crate::warn_user;
 
 pub(crate) static CONCURRENCY: LazyLock<usize> = LazyLock::new(|| {
     if EnvVars::is_set(EnvVars::PREK_NO_CONCURRENCY) {
-        1
-    } else {
-        std::thread::available_parallelism()
-            .map(std::num::NonZero::get)
-            .unwrap_or(1)
+        return 1;
     }
+    if let Ok(concurrency) = EnvVars::var(EnvVars::PREK_CONCURRENCY) {
+        if let Ok(concurrency) = concurrency.parse::<std::num::NonZero<usize>>() {
+            return concurrency.get();
+        }
+        warn_user!(
+            "Ignoring invalid `{}` value: `{concurrency}`",
+            EnvVars::PREK_CONCURRENCY
+        );
+    }
+    std::thread::available_parallelism()
+        .map(std::num::NonZero::get)
+        .unwrap_or(1)
 });
 
+/// The number of worker threads of the async runtime.
+///
+/// Follows the configured concurrency, and defaults to the number of CPUs capped to 8. This
+/// runs before logging and warnings are set up, invalid values are reported by [`CONCURRENCY`].
+pub(crate) fn worker_threads() -> usize {
+    if EnvVars::is_set(EnvVars::PREK_NO_CONCURRENCY) {
+        return 1;
+    }
+    if let Ok(concurrency) = EnvVars::var(EnvVars::PREK_CONCURRENCY)
+        && let Ok(concurrency) = concurrency.parse::<std::num::NonZero<usize>>()
+    {
+        return concurrency.get();
+    }
+    std::thread::available_parallelism()
+        .map(std::num::NonZero::get)
+        .unwrap_or(1)
+        .min(8)
+}
+
 fn target_concurrency(serial: bool) -> usize {
     if serial { 1 } else { *CONCURRENCY }
 }
 
+/// The maximum length of the command line of a hook, like `pre-commit`.
+///
+/// On unix, this is `ARG_MAX` minus the size of the environment and some headroom, kept within
+/// 4 KiB and 128 KiB. On Windows, this is the limit of `CreateProcess` minus some headroom.
+static MAX_CLI_LENGTH: LazyLock<usize> = LazyLock::new(|| {
+    #[cfg(unix)]
+    {
+        let arg_max = usize::try_from(unsafe { libc::sysconf(libc::_SC_ARG_MAX) }).unwrap_or(0);
+        // Each variable is passed as `KEY=VALUE\0`, along with a pointer to it.
+        let env_size = std::env::vars_os()
+            .map(|(key, value)| key.len() + value.len() + 2 + size_of::<usize>())
+            .sum::<usize>();
+        arg_max
+            .saturating_sub(env_size)
+            .saturating_sub(2048)
+            .clamp(1 << 12, 1 << 17)
+    }
+    #[cfg(not(unix))]
+    {
+        (1 << 15) - 2048 // UNICODE_STRING max - headroom
+    }
+});
+
 /// Iterator that yields partitions of filenames that fit within the maximum command line length.
+///
+/// A filename longer than the limit on its own is still passed, in a batch of its own.
 struct Partitions<'a> {
-    hook: &'a Hook,
     filenames: &'a [&'a String],
-    concurrency: usize,
     current_index: usize,
     command_length: usize,
     max_per_batch: usize,
     max_cli_length: usize,
 }
 
-// TODO: do a more accurate calculation
 impl<'a> Partitions<'a> {
-    fn new(hook: &'a Hook, filenames: &'a [&'a String], concurrency: usize) -> Self {
+    fn new(hook: &Hook, filenames: &'a [&'a String], concurrency: usize) -> Self {
         let max_per_batch = max(4, filenames.len().div_ceil(concurrency));
-        // TODO: subtract the env size
-        let max_cli_length = if cfg!(unix) {
-            1 << 12
-        } else {
-            (1 << 15) - 2048 // UNICODE_STRING max - headroom
-        };
 
-        let command_length = hook.entry.entry().len()
-            + hook.args.iter().map(String::len).sum::<usize>()
-            + hook.args.len();
+        let args = hook.expanded_args();
+        let command_length =
+            hook.entry.entry().len() + args.iter().map(String::len).sum::<usize>() + args.len();
 
         Self {
-            hook,
             filenames,
-            concurrency,
             current_index: 0,
             command_length,
             max_per_batch,
-            max_cli_length,
+            max_cli_length: *MAX_CLI_LENGTH,
         }
     }
 }
@@ -83,8 +129,9 @@ impl<'a> Iterator for Partitions<'a> {
             let filename = self.filenames[self.current_index];
             let length = filename.len() + 1;
 
-            if current_length + length > self.max_cli_length
-                || self.current_index - start_index >= self.max_per_batch
+            if self.current_index > start_index
+                && (current_length + length > self.max_cli_length
+                    || self.current_index - start_index >= self.max_per_batch)
             {
                 break;
             }
@@ -93,14 +140,18 @@ impl<'a> Iterator for Partitions<'a> {
             self.current_index += 1;
         }
 
-        if self.current_index == start_index {
-            None
-        } else {
-            Some(&self.filenames[start_index..self.current_index])
-        }
+        Some(&self.filenames[start_index..self.current_index])
     }
 }
 
+/// Whether [`run_by_batch`] runs the hook more than once to pass all the filenames.
+pub(crate) fn is_partitioned(hook: &Hook, filenames: &[&String]) -> bool {
+    let concurrency = target_concurrency(hook.require_serial);
+    Partitions::new(hook, filenames, concurrency)
+        .nth(1)
+        .is_some()
+}
+
 pub(crate) async fn run_by_batch<T, F>(
     hook: &Hook,
     filenames: &[&String],
@@ -137,13 +188,248 @@ where
     Ok(results)
 }
 
-pub(crate) fn prepend_paths(paths: &[&Path]) -> Result<OsString, std::env::JoinPathsError> {
-    std::env::join_paths(
-        paths.iter().map(|p| p.to_path_buf()).chain(
-            EnvVars::var_os(EnvVars::PATH)
-                .as_ref()
-                .iter()
-                .flat_map(std::env::split_paths),
-        ),
-    )
+/// Run a hook with `stdin: filename` once per file, one file at a time, with the content of
+/// the file on stdin.
+///
+/// The filenames are relative to `work_dir`, the directory the hook is run from.
+pub(crate) async fn run_by_file<T, F>(
+    filenames: &[&String],
+    work_dir: &Path,
+    run: F,
+) -> anyhow::Result<Vec<T>>
+where
+    F: AsyncFn(Stdio) -> anyhow::Result<T>,
+{
+    let mut results = Vec::with_capacity(filenames.len());
+    for filename in filenames {
+        let (file, _) = fs_err::File::open(work_dir.join(filename))?.into_parts();
+        results.push(run(Stdio::from(file)).await?);
+    }
+
+    Ok(results)
+}
+
+/// Add the arguments of a hook run to `cmd`, which runs the first token of the entry from
+/// `work_dir`.
+///
+/// Like `pre-commit`, the arguments are always the rest of the entry, then the hook `args`,
+/// then the filenames.
+pub(crate) fn build_command<'a>(
+    cmd: &'a mut Cmd,
+    entry: &[String],
+    hook: &Hook,
+    work_dir: &Path,
+    filenames: &[String],
+) -> &'a mut Cmd {
+    cmd.current_dir(work_dir)
+        .args(&entry[1..])
+        .args(hook.expanded_args())
+        .args(filenames)
+}
+
+/// Builder for the `PATH` environment variable passed to hook processes.
+///
+/// Directories are added in the order they are prepended, followed by the inherited `PATH`.
+/// Duplicate entries are removed, keeping the first occurrence. When a store is given,
+/// inherited entries pointing into the store are dropped, so that a stale environment from a
+/// parent hook invocation can't shadow the current hook's executables.
+pub(crate) struct EnvPath {
+    prepend: Vec<PathBuf>,
+    inherited: Option<OsString>,
+    store: Option<PathBuf>,
+    case_insensitive: bool,
+}
+
+impl EnvPath {
+    /// Create a builder inheriting the current `PATH`.
+    pub(crate) fn new() -> Self {
+        Self {
+            prepend: Vec::new(),
+            inherited: EnvVars::var_os(EnvVars::PATH),
+            store: None,
+            case_insensitive: cfg!(windows),
+        }
+    }
+
+    /// Prepend a directory, after the ones already prepended.
+    #[must_use]
+    pub(crate) fn prepend(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.prepend.push(dir.into());
+        self
+    }
+
+    /// Drop inherited entries that point into the store.
+    #[must_use]
+    pub(crate) fn exclude_store(mut self, store: &Store) -> Self {
+        self.store = Some(store.path().to_path_buf());
+        self
+    }
+
+    /// Normalize a path for comparison.
+    fn key(&self, path: &Path) -> PathBuf {
+        let path = dunce::simplified(path);
+        if self.case_insensitive {
+            Path::new(&path.to_string_lossy().to_lowercase())
+                .components()
+                .collect()
+        } else {
+            path.components().collect()
+        }
+    }
+
+    /// Return the list of directories, in order.
+    fn entries(&self) -> Vec<PathBuf> {
+        let store = self.store.as_deref().map(|store| self.key(store));
+        let inherited = self
+            .inherited
+            .as_ref()
+            .into_iter()
+            .flat_map(std::env::split_paths)
+            .filter(|path| {
+                store
+                    .as_ref()
+                    .is_none_or(|store| !self.key(path).starts_with(store))
+            });
+
+        let mut seen = FxHashSet::default();
+        self.prepend
+            .iter()
+            .cloned()
+            .chain(inherited)
+            .filter(|path| !path.as_os_str().is_empty() && seen.insert(self.key(path)))
+            .collect()
+    }
+
+    /// Join the directories into a `PATH` value.
+    pub(crate) fn build(&self) -> Result<OsString, std::env::JoinPathsError> {
+        std::env::join_paths(self.entries())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_path(inherited: &[&str]) -> EnvPath {
+        EnvPath {
+            prepend: Vec::new(),
+            inherited: Some(std::env::join_paths(inherited).unwrap()),
+            store: None,
+            case_insensitive: false,
+        }
+    }
+
+    #[test]
+    fn prepend_in_order() {
+        let entries = env_path(&["/usr/bin", "/bin"])
+            .prepend("/env/bin")
+            .prepend("/toolchain/bin")
+            .entries();
+        assert_eq!(
+            entries,
+            ["/env/bin", "/toolchain/bin", "/usr/bin", "/bin"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn deduplicate() {
+        let entries = env_path(&["/usr/bin", "/env/bin/", "", "/usr/bin", "/bin"])
+            .prepend("/env/bin")
+            .entries();
+        assert_eq!(entries, ["/env/bin", "/usr/bin", "/bin"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn exclude_store() {
+        let mut builder = env_path(&[
+            "/cache/prek/hooks/python-stale/bin",
+            "/usr/bin",
+            "/cache/prek-other/bin",
+            "/cache/prek/tools/node/bin",
+        ])
+        .prepend("/cache/prek/hooks/python-current/bin");
+        builder.store = Some(PathBuf::from("/cache/prek"));
+
+        assert_eq!(
+            builder.entries(),
+            [
+                "/cache/prek/hooks/python-current/bin",
+                "/usr/bin",
+                "/cache/prek-other/bin",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let mut builder =
+            env_path(&["/Cache/Prek/hooks/node-stale/bin", "/USR/BIN"]).prepend("/usr/bin");
+        builder.store = Some(PathBuf::from("/cache/prek"));
+        builder.case_insensitive = true;
+
+        assert_eq!(builder.entries(), [PathBuf::from("/usr/bin")]);
+    }
+
+    #[test]
+    fn no_inherited_path() {
+        let mut builder = env_path(&[]).prepend("/env/bin");
+        builder.inherited = None;
+        assert_eq!(builder.entries(), [PathBuf::from("/env/bin")]);
+    }
+
+    fn partitions<'a>(
+        filenames: &'a [&'a String],
+        max_per_batch: usize,
+        max_cli_length: usize,
+    ) -> Vec<&'a [&'a String]> {
+        Partitions {
+            filenames,
+            current_index: 0,
+            command_length: 10,
+            max_per_batch,
+            max_cli_length,
+        }
+        .collect()
+    }
+
+    #[test]
+    fn partitions_within_cli_length() {
+        let filenames = (0..10_000)
+            .map(|i| format!("src/some/deeply/nested/directory/file_{i:05}.rs"))
+            .collect::<Vec<_>>();
+        let filenames = filenames.iter().collect::<Vec<_>>();
+
+        let batches = partitions(&filenames, usize::MAX, 1 << 12);
+        assert!(batches.len() > 1);
+        for batch in &batches {
+            let length = 10 + 1 + batch.iter().map(|f| f.len() + 1).sum::<usize>();
+            assert!(length <= 1 << 12, "batch of {length} bytes");
+        }
+        assert_eq!(batches.concat(), filenames);
+
+        // Also split by the number of files per batch.
+        let batches = partitions(&filenames, 2_500, usize::MAX);
+        assert_eq!(batches.len(), 4);
+        assert_eq!(batches.concat(), filenames);
+    }
+
+    #[test]
+    fn partitions_with_long_filename() {
+        let long = "a".repeat(1 << 13);
+        let short = "b".to_string();
+        let filenames = vec![&short, &long, &short];
+
+        let batches = partitions(&filenames, usize::MAX, 1 << 12);
+        assert_eq!(
+            batches,
+            [&[&short][..], &[&long][..], &[&short][..]].to_vec()
+        );
+    }
+
+    #[test]
+    fn partitions_without_filenames() {
+        let batches = partitions(&[], 4, 1 << 12);
+        assert_eq!(batches, [&[][..]].to_vec());
+    }
 }
@@ -2,18 +2,42 @@ use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
+use std::time::Duration;
 
 use anyhow::Result;
 use etcetera::BaseStrategy;
+use serde::Deserialize;
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
+use url::Url;
 
 use constants::env_vars::EnvVars;
 
 use crate::config::RemoteRepo;
-use crate::fs::LockedFile;
+use crate::fs::{LockedFile, Simplified};
 use crate::git::clone_repo;
 use crate::hook::InstallInfo;
+use crate::warn_user;
+
+/// How long to wait for the store lock before telling the user why we are stuck.
+const LOCK_WAIT_MESSAGE_DELAY: Duration = Duration::from_secs(1);
+
+fn lock_timeout() -> Duration {
+    if let Ok(timeout) = EnvVars::var(EnvVars::PREK_LOCK_TIMEOUT)
+        && let Ok(timeout) = timeout.parse::<u64>()
+    {
+        return Duration::from_secs(timeout);
+    }
+    Duration::from_secs(10 * 60)
+}
+
+/// How long cloning a repo may take, from `PREK_CLONE_TIMEOUT` in seconds, unlimited by default.
+fn clone_timeout() -> Option<Duration> {
+    EnvVars::var(EnvVars::PREK_CLONE_TIMEOUT)
+        .ok()
+        .and_then(|timeout| timeout.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -29,6 +53,8 @@ pub enum Error {
     Git(#[from] crate::git::Error),
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
+    #[error("Cloning timed out after {0}s, set `PREK_CLONE_TIMEOUT` to wait longer")]
+    CloneTimeout(u64),
 }
 
 static STORE_HOME: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
@@ -82,11 +108,32 @@ impl Store {
     }
 
     /// Clone a remote repo into the store.
-    pub(crate) async fn clone_repo(&self, repo: &RemoteRepo) -> Result<PathBuf, Error> {
+    ///
+    /// If the same repo is already cloned at another revision, the new clone borrows
+    /// objects from it through git alternates. Cloned repos are never removed individually
+    /// (only `prek clean` removes the whole store), so the borrowed objects stay available,
+    /// unless the store is moved. A clone whose borrowed objects are gone is cloned again
+    /// without borrowing.
+    ///
+    /// `on_progress` is called with the progress of the clone, see [`clone_repo`].
+    pub(crate) async fn clone_repo(
+        &self,
+        repo: &RemoteRepo,
+        on_progress: &dyn Fn(&str),
+    ) -> Result<PathBuf, Error> {
         // Check if the repo is already cloned.
         let target = self.repo_path(repo);
+        let mut reuse_objects = true;
         if target.join(".prek-repo.json").try_exists()? {
-            return Ok(target);
+            if borrowed_objects_exist(&target) {
+                return Ok(target);
+            }
+            // The store was moved, or the clone the objects were borrowed from is gone.
+            warn!(
+                target = %target.display(),
+                "Objects borrowed by the clone are missing, cloning it again"
+            );
+            reuse_objects = false;
         }
 
         fs_err::tokio::create_dir_all(self.repos_dir()).await?;
@@ -98,7 +145,28 @@ impl Store {
             %repo,
             "Cloning repo",
         );
-        clone_repo(repo.repo.as_str(), &repo.rev, temp.path()).await?;
+        let reference = if reuse_objects {
+            self.find_reference(repo)
+        } else {
+            None
+        };
+        if let Some(reference) = &reference {
+            debug!(reference = %reference.display(), "Reusing objects from an existing clone");
+        }
+        let clone = clone_repo(
+            repo.repo.as_str(),
+            &repo.rev,
+            temp.path(),
+            reference.as_deref(),
+            on_progress,
+        );
+        if let Some(timeout) = clone_timeout() {
+            tokio::time::timeout(timeout, clone)
+                .await
+                .map_err(|_| Error::CloneTimeout(timeout.as_secs()))??;
+        } else {
+            clone.await?;
+        }
 
         // TODO: add windows retry
         fs_err::tokio::remove_dir_all(&target).await.ok();
@@ -110,6 +178,42 @@ impl Store {
         Ok(target)
     }
 
+    /// The path of a remote repo in the store, if it is already cloned.
+    pub(crate) fn cloned_repo(&self, repo: &RemoteRepo) -> Option<PathBuf> {
+        let target = self.repo_path(repo);
+        target.join(".prek-repo.json").is_file().then_some(target)
+    }
+
+    /// Find an existing clone of the same repo url to borrow objects from.
+    ///
+    /// Only clones that own their objects are considered, so that alternates never chain.
+    fn find_reference(&self, repo: &RemoteRepo) -> Option<PathBuf> {
+        #[derive(Deserialize)]
+        struct ClonedRepo {
+            repo: Url,
+        }
+
+        fs_err::read_dir(self.repos_dir())
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| {
+                let Ok(file) = fs_err::File::open(path.join(".prek-repo.json")) else {
+                    return false;
+                };
+                let Ok(cloned) = serde_json::from_reader::<_, ClonedRepo>(file) else {
+                    return false;
+                };
+                cloned.repo == repo.repo
+                    && !path
+                        .join(".git")
+                        .join("objects")
+                        .join("info")
+                        .join("alternates")
+                        .exists()
+            })
+    }
+
     /// Returns installed hooks in the store.
     pub(crate) fn installed_hooks(&self) -> impl Iterator<Item = InstallInfo> {
         fs_err::read_dir(self.hooks_dir())
@@ -129,8 +233,32 @@ impl Store {
         LockedFile::acquire_blocking(self.path.join(".lock"), "store")
     }
 
+    /// Lock the store, waiting at most `PREK_LOCK_TIMEOUT` seconds (10 minutes by default) for
+    /// another process to release it.
     pub(crate) async fn lock_async(&self) -> Result<LockedFile, std::io::Error> {
-        LockedFile::acquire(self.path.join(".lock"), "store").await
+        let lock = LockedFile::acquire(self.path.join(".lock"), "store");
+        tokio::pin!(lock);
+
+        let timeout = lock_timeout();
+        let wait_message_delay = LOCK_WAIT_MESSAGE_DELAY.min(timeout);
+        tokio::select! {
+            lock = &mut lock => return lock,
+            () = tokio::time::sleep(wait_message_delay) => {}
+        }
+
+        warn_user!("Waiting for another prek process to release the store lock...");
+        match tokio::time::timeout(timeout - wait_message_delay, lock).await {
+            Ok(lock) => lock,
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!(
+                    "Timed out after {}s waiting for the store lock at `{}`, set `{}` to wait longer",
+                    timeout.as_secs(),
+                    self.path.join(".lock").user_display(),
+                    EnvVars::PREK_LOCK_TIMEOUT,
+                ),
+            )),
+        }
     }
 
     /// Returns the path to the cloned repo.
@@ -153,6 +281,10 @@ impl Store {
         self.path.join("patches")
     }
 
+    pub(crate) fn logs_dir(&self) -> PathBuf {
+        self.path.join("logs")
+    }
+
     /// The path to the tool directory in the store.
     pub(crate) fn tools_path(&self, tool: ToolBucket) -> PathBuf {
         self.path.join("tools").join(tool.as_str())
@@ -197,7 +329,27 @@ impl CacheBucket {
     }
 }
 
+/// Whether the objects the clone at `path` borrows through git alternates still exist.
+///
+/// The alternates file holds the absolute path of the objects of another clone, which is
+/// stale once the store is moved.
+fn borrowed_objects_exist(path: &Path) -> bool {
+    let Ok(alternates) = fs_err::read_to_string(
+        path.join(".git")
+            .join("objects")
+            .join("info")
+            .join("alternates"),
+    ) else {
+        return true;
+    };
+    alternates
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .all(|objects| Path::new(objects).is_dir())
+}
+
 /// Convert a u64 to a hex string.
-fn to_hex(num: u64) -> String {
+pub(crate) fn to_hex(num: u64) -> String {
     hex::encode(num.to_le_bytes())
 }
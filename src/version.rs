@@ -45,6 +45,14 @@ pub struct VersionInfo {
     ///
     /// `None` if not built from a git repo or if retrieval failed.
     commit_info: Option<CommitInfo>,
+    /// The target triple prek was built for, such as "x86_64-unknown-linux-gnu".
+    target: String,
+}
+
+impl VersionInfo {
+    pub(crate) fn target(&self) -> &str {
+        &self.target
+    }
 }
 
 impl fmt::Display for VersionInfo {
@@ -92,8 +100,12 @@ pub fn version() -> VersionInfo {
             .map_or(0, |value| value.parse::<u32>().unwrap_or(0)),
     });
 
+    // The target is set by `build.rs`
+    let target = env!("PREK_BUILD_TARGET").to_string();
+
     VersionInfo {
         version,
         commit_info,
+        target,
     }
 }
@@ -43,6 +43,24 @@ pub fn disable() {
     ENABLED.store(false, std::sync::atomic::Ordering::SeqCst);
 }
 
+/// The warnings emitted so far, whether they were shown or not.
+static EMITTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Record a warning, for `--strict` to fail the run.
+pub fn record(message: &str) {
+    if let Ok(mut emitted) = EMITTED.lock() {
+        emitted.push(message.to_string());
+    }
+}
+
+/// Return the warnings emitted so far.
+pub fn emitted() -> Vec<String> {
+    EMITTED
+        .lock()
+        .map(|emitted| emitted.clone())
+        .unwrap_or_default()
+}
+
 /// Warn a user, if warnings are enabled.
 #[macro_export]
 macro_rules! warn_user {
@@ -50,8 +68,9 @@ macro_rules! warn_user {
         use $crate::warnings::anstream::eprintln;
         use $crate::warnings::owo_colors::OwoColorize;
 
+        let message = format!("{}", format_args!($($arg)*));
+        $crate::warnings::record(&message);
         if $crate::warnings::ENABLED.load(std::sync::atomic::Ordering::SeqCst) {
-            let message = format!("{}", format_args!($($arg)*));
             let formatted = message.bold();
             eprintln!("{}{} {formatted}", "warning".yellow().bold(), ":".bold());
         }
@@ -68,10 +87,11 @@ macro_rules! warn_user_once {
         use $crate::warnings::anstream::eprintln;
         use $crate::warnings::owo_colors::OwoColorize;
 
-        if $crate::warnings::ENABLED.load(std::sync::atomic::Ordering::SeqCst) {
-            if let Ok(mut states) = $crate::warnings::WARNINGS.lock() {
-                let message = format!("{}", format_args!($($arg)*));
-                if states.insert(message.clone()) {
+        if let Ok(mut states) = $crate::warnings::WARNINGS.lock() {
+            let message = format!("{}", format_args!($($arg)*));
+            if states.insert(message.clone()) {
+                $crate::warnings::record(&message);
+                if $crate::warnings::ENABLED.load(std::sync::atomic::Ordering::SeqCst) {
                     eprintln!("{}{} {}", "warning".yellow().bold(), ":".bold(), message.bold());
                 }
             }
@@ -10,7 +10,10 @@ use rustc_hash::FxHashMap;
 use thiserror::Error;
 use tracing::{debug, error};
 
-use crate::config::{self, ALTER_CONFIG_FILE, CONFIG_FILE, Config, ManifestHook, read_config};
+use crate::config::{
+    self, ALTER_CONFIG_FILE, CONFIG_FILE, Config, Locations, ManifestHook, TOML_CONFIG_FILE,
+    location_prefix, parse_config, read_config_content,
+};
 use crate::fs::{CWD, Simplified};
 use crate::hook::{self, Hook, HookBuilder, Repo};
 use crate::store::Store;
@@ -27,9 +30,11 @@ pub(crate) enum Error {
     #[error("Hook `{hook}` not present in repo `{repo}`")]
     HookNotFound { hook: String, repo: String },
 
-    #[error("Failed to initialize repo `{repo}`")]
+    #[error("{}Failed to initialize repo `{repo}`", location_prefix(.location.as_deref()))]
     Store {
         repo: String,
+        /// Where the repo is defined, as `file:line:column`.
+        location: Option<String>,
         #[source]
         error: Box<store::Error>,
     },
@@ -37,6 +42,8 @@ pub(crate) enum Error {
 
 pub(crate) trait HookInitReporter {
     fn on_clone_start(&self, repo: &str) -> usize;
+    /// Report the progress of the clone `id` of `repo`, e.g. `Receiving objects: 45%`.
+    fn on_clone_progress(&self, id: usize, repo: &str, progress: &str);
     fn on_clone_complete(&self, id: usize);
     fn on_complete(&self);
 }
@@ -44,11 +51,14 @@ pub(crate) trait HookInitReporter {
 pub(crate) struct Project {
     config_path: PathBuf,
     config: Config,
+    locations: Locations,
     repos: Vec<Arc<Repo>>,
 }
 
 impl Project {
     /// Find the configuration file in the given path or the current working directory.
+    ///
+    /// The YAML names are preferred over `.pre-commit-config.toml`.
     pub(crate) fn find_config_file(config: Option<PathBuf>) -> Result<PathBuf, Error> {
         if let Some(config) = config {
             if config.exists() {
@@ -59,20 +69,20 @@ impl Project {
             )));
         }
 
-        let main = CWD.join(CONFIG_FILE);
-        let alternate = CWD.join(ALTER_CONFIG_FILE);
-        if main.exists() && alternate.exists() {
+        let found = [CONFIG_FILE, ALTER_CONFIG_FILE, TOML_CONFIG_FILE]
+            .into_iter()
+            .map(|file| CWD.join(file))
+            .filter(|path| path.exists())
+            .collect::<Vec<_>>();
+        if let [main, alternate, ..] = found.as_slice() {
             warn_user!(
                 "Both {main} and {alternate} exist, using {main}",
                 main = main.display(),
                 alternate = alternate.display()
             );
         }
-        if main.exists() {
-            return Ok(main);
-        }
-        if alternate.exists() {
-            return Ok(alternate);
+        if let Some(config) = found.into_iter().next() {
+            return Ok(config);
         }
 
         Err(Error::InvalidConfig(config::Error::NotFound(
@@ -92,11 +102,24 @@ impl Project {
             path = %config_path.display(),
             "Loading project configuration"
         );
-        let config = read_config(&config_path)?;
+        // The config is read once, it may come from stdin.
+        let content = read_config_content(&config_path)?;
+        let config = parse_config(&config_path, &content)?;
+        let locations = Locations::find(&config, &content);
+        for warning in locations.check_config(&config_path, &content) {
+            let location = match (warning.line, warning.column) {
+                (Some(line), Some(column)) => {
+                    format!("{}:{line}:{column}", config_path.user_display())
+                }
+                _ => config_path.user_display().to_string(),
+            };
+            warn_user!("{location}: {}", warning.message);
+        }
         let size = config.repos.len();
         Ok(Self {
             config,
             config_path,
+            locations,
             repos: Vec::with_capacity(size),
         })
     }
@@ -105,11 +128,29 @@ impl Project {
         &self.config
     }
 
+    /// Where the repo at `repo_idx` is defined, as `file:line:column`.
+    pub(crate) fn repo_location(&self, repo_idx: usize) -> Option<String> {
+        let location = self.locations.repo(repo_idx)?;
+        Some(format!("{}:{location}", self.config_path.user_display()))
+    }
+
+    /// Where the hook at `hook_idx` of the repo at `repo_idx` is defined, as `file:line:column`.
+    pub(crate) fn hook_location(&self, repo_idx: usize, hook_idx: usize) -> Option<String> {
+        let location = self.locations.hook(repo_idx, hook_idx)?;
+        Some(format!("{}:{location}", self.config_path.user_display()))
+    }
+
+    /// The repositories of the config, in order, once initialized by [`Project::init_repos`].
+    pub(crate) fn repos(&self) -> &[Arc<Repo>] {
+        &self.repos
+    }
+
     pub(crate) fn config_file(&self) -> &Path {
         &self.config_path
     }
 
-    async fn init_repos(
+    /// Clone the remote repositories if needed, and load the hooks of all repositories.
+    pub(crate) async fn init_repos(
         &mut self,
         store: &Store,
         reporter: Option<&dyn HookInitReporter>,
@@ -117,40 +158,60 @@ impl Project {
         let remote_repos = Rc::new(Mutex::new(FxHashMap::default()));
         let mut seen = HashSet::new();
 
-        // Prepare remote repos in parallel.
-        let remotes_iter = self.config.repos.iter().filter_map(|repo| match repo {
-            // Deduplicate remote repos.
-            config::Repo::Remote(repo) if seen.insert(repo) => Some(repo),
-            _ => None,
-        });
-        let mut tasks = futures::stream::iter(remotes_iter)
-            .map(async |repo_config| {
-                let remote_repos = remote_repos.clone();
-
-                let progress = reporter
-                    .map(|reporter| (reporter, reporter.on_clone_start(&format!("{repo_config}"))));
-
-                let path = store
-                    .clone_repo(repo_config)
-                    .await
-                    .map_err(|e| Error::Store {
-                        repo: format!("{}", repo_config.repo),
-                        error: Box::new(e),
-                    })?;
-
-                if let Some((reporter, progress)) = progress {
-                    reporter.on_clone_complete(progress);
-                }
+        // Deduplicate remote repos, and group revisions of the same repo together.
+        let mut groups: Vec<Vec<&config::RemoteRepo>> = Vec::new();
+        let mut group_index = FxHashMap::default();
+        let mut locations = FxHashMap::default();
+        for (repo_idx, repo) in self.config.repos.iter().enumerate() {
+            if let config::Repo::Remote(repo) = repo
+                && seen.insert(repo)
+            {
+                locations.insert(repo, self.repo_location(repo_idx));
+                let idx = *group_index.entry(&repo.repo).or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+                groups[idx].push(repo);
+            }
+        }
+
+        // Prepare different repos in parallel. Revisions of the same repo are cloned one
+        // after another, so that later clones can reuse objects from the first one.
+        let mut tasks = futures::stream::iter(groups)
+            .map(async |group| {
+                for repo_config in group {
+                    let name = format!("{repo_config}");
+                    let progress =
+                        reporter.map(|reporter| (reporter, reporter.on_clone_start(&name)));
+                    let on_progress = |line: &str| {
+                        if let Some((reporter, id)) = progress {
+                            reporter.on_clone_progress(id, &name, line);
+                        }
+                    };
+
+                    let path = store
+                        .clone_repo(repo_config, &on_progress)
+                        .await
+                        .map_err(|e| Error::Store {
+                            repo: format!("{}", repo_config.repo),
+                            location: locations[repo_config].clone(),
+                            error: Box::new(e),
+                        })?;
+
+                    if let Some((reporter, progress)) = progress {
+                        reporter.on_clone_complete(progress);
+                    }
 
-                let repo = Arc::new(Repo::remote(
-                    repo_config.repo.clone(),
-                    repo_config.rev.clone(),
-                    path,
-                )?);
-                remote_repos
-                    .lock()
-                    .unwrap()
-                    .insert(repo_config, repo.clone());
+                    let repo = Arc::new(Repo::remote(
+                        repo_config.repo.clone(),
+                        repo_config.rev.clone(),
+                        path,
+                    )?);
+                    remote_repos
+                        .lock()
+                        .unwrap()
+                        .insert(repo_config, repo.clone());
+                }
 
                 Ok::<(), Error>(())
             })
@@ -193,8 +254,13 @@ impl Project {
         self.init_repos(store, reporter).await?;
 
         let mut hooks = Vec::new();
+        // The index of the hook in the configuration file, duplicates included.
+        let mut idx = 0;
 
-        for (repo_config, repo) in zip_eq(self.config.repos.iter(), self.repos.iter()) {
+        for (repo_idx, (repo_config, repo)) in
+            zip_eq(self.config.repos.iter(), self.repos.iter()).enumerate()
+        {
+            let mut builders = Vec::new();
             match repo_config {
                 config::Repo::Remote(repo_config) => {
                     for hook_config in &repo_config.hooks {
@@ -206,37 +272,53 @@ impl Project {
                             });
                         };
 
-                        let repo = Arc::clone(repo);
-                        let mut builder = HookBuilder::new(repo, hook.clone(), hooks.len());
+                        let mut builder = HookBuilder::new(Arc::clone(repo), hook.clone(), idx);
                         builder.update(hook_config);
-                        builder.combine(&self.config);
-
-                        let hook = builder.build()?;
-                        hooks.push(hook);
+                        builders.push(builder);
+                        idx += 1;
                     }
                 }
                 config::Repo::Local(repo_config) => {
                     for hook_config in &repo_config.hooks {
-                        let repo = Arc::clone(repo);
-                        let mut builder = HookBuilder::new(repo, hook_config.clone(), hooks.len());
-                        builder.combine(&self.config);
-
-                        let hook = builder.build()?;
-                        hooks.push(hook);
+                        let builder = HookBuilder::new(Arc::clone(repo), hook_config.clone(), idx);
+                        builders.push(builder);
+                        idx += 1;
                     }
                 }
                 config::Repo::Meta(repo_config) => {
                     for hook_config in &repo_config.hooks {
-                        let repo = Arc::clone(repo);
                         let hook_config = ManifestHook::from(hook_config.clone());
-                        let mut builder = HookBuilder::new(repo, hook_config, hooks.len());
-                        builder.combine(&self.config);
-
-                        let hook = builder.build()?;
-                        hooks.push(hook);
+                        let builder = HookBuilder::new(Arc::clone(repo), hook_config, idx);
+                        builders.push(builder);
+                        idx += 1;
                     }
                 }
             }
+
+            // Hooks listed twice in the same repo with the same resolved configuration are
+            // usually a copy-paste mistake, running them again would only waste time.
+            let mut resolved: Vec<(usize, ManifestHook)> = Vec::with_capacity(builders.len());
+            for (position, mut builder) in builders.into_iter().enumerate() {
+                let located = |err: hook::Error| match self.hook_location(repo_idx, position) {
+                    Some(location) => err.at(location),
+                    None => err,
+                };
+                builder.combine(&self.config);
+                let config = builder.resolved_config().map_err(located)?;
+                if let Some((first, _)) = resolved.iter().find(|(_, c)| *c == config) {
+                    warn_user!(
+                        "Hook `{}` is listed as hooks #{} and #{} of repo `{}` with identical configuration, running it only once",
+                        config.id,
+                        first + 1,
+                        position + 1,
+                        repo,
+                    );
+                    continue;
+                }
+                resolved.push((position, config));
+
+                hooks.push(builder.build().map_err(located)?);
+            }
         }
 
         reporter.map(HookInitReporter::on_complete);
@@ -38,7 +38,7 @@ fn end_of_file_fixer_hook() -> Result<()> {
     context.git_add(".");
 
     // First run: hooks should fail and fix the files
-    cmd_snapshot!(context.filters(), context.run(), @r#"
+    cmd_snapshot!(context.filters(), context.run().env("PREK_CONCURRENCY", "1"), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
@@ -47,10 +47,10 @@ fn end_of_file_fixer_hook() -> Result<()> {
     - exit code: 1
     - files were modified by this hook
       Fixing multiple_crlf.txt
+      Fixing multiple_lf.txt
+      Fixing no_newline.txt
       Fixing only_newlines.txt
       Fixing only_win_newlines.txt
-      Fixing no_newline.txt
-      Fixing multiple_lf.txt
 
     ----- stderr -----
     "#);
@@ -182,5 +182,387 @@ fn check_added_large_files_hook() -> Result<()> {
     ----- stderr -----
     "#);
 
+    context.git_commit("Add lfs file");
+
+    // Sizes are read from the index, not the working tree.
+    let partial = cwd.child("partial.txt");
+    partial.write_str("Hello World\n")?;
+    context.git_add("partial.txt");
+    partial.write_binary(&[0; 2048])?;
+
+    let shrunk = cwd.child("shrunk.txt");
+    shrunk.write_binary(&[0; 2048])?;
+    context.git_add("shrunk.txt");
+    shrunk.write_str("Hello World\n")?;
+
+    // Staged but deleted from the working tree.
+    let deleted = cwd.child("deleted.txt");
+    deleted.write_binary(&[0; 2048])?;
+    context.git_add("deleted.txt");
+    fs_err::remove_file(&deleted)?;
+
+    // Fourth run: only the file whose staged version is large fails
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check for added large files..............................................Failed
+    - hook id: check-added-large-files
+    - exit code: 1
+      shrunk.txt (2 KB) exceeds 1 KB
+
+    ----- stderr -----
+    "#);
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"/\d+-\d+.patch", "/[TIME]-[PID].patch")])
+        .collect();
+
+    // Fifth run: the same holds for a default run on the staged files
+    cmd_snapshot!(filters.clone(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check for added large files..............................................Failed
+    - hook id: check-added-large-files
+    - exit code: 1
+      deleted.txt (2 KB) exceeds 1 KB
+      shrunk.txt (2 KB) exceeds 1 KB
+
+    ----- stderr -----
+    Non-staged changes detected, saving to `[HOME]/patches/[TIME]-[PID].patch`
+
+    Restored working tree changes from `[HOME]/patches/[TIME]-[PID].patch`
+    "#);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: check-added-large-files
+                args: ['--maxkb=1', '--enforce-all']
+    "});
+    // Tracked but not staged, so the working tree is checked.
+    cwd.child("README.md").write_binary(&[0; 2048])?;
+
+    // Sixth run: staged files use their staged size, other files their size on disk
+    cmd_snapshot!(filters, context.run().arg("--all-files"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check for added large files..............................................Failed
+    - hook id: check-added-large-files
+    - exit code: 1
+      README.md (2 KB) exceeds 1 KB
+      shrunk.txt (2 KB) exceeds 1 KB
+      large_file.txt (2 KB) exceeds 1 KB
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Files are fixed in parallel, while the output keeps the order of the files.
+#[test]
+fn trailing_whitespace_parallel() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+    "});
+
+    let cwd = context.work_dir();
+    for i in 0..20 {
+        // Every third file is already clean.
+        let content = if i % 3 == 0 {
+            "Hello World\n".repeat(1000)
+        } else {
+            "Hello World  \n".repeat(1000)
+        };
+        cwd.child(format!("file_{i:02}.txt")).write_str(&content)?;
+    }
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("PREK_CONCURRENCY", "4"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trim trailing whitespace.................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+    - files were modified by this hook
+      Fixing file_08.txt
+      Fixing file_16.txt
+      Fixing file_01.txt
+      Fixing file_11.txt
+      Fixing file_17.txt
+      Fixing file_05.txt
+      Fixing file_02.txt
+      Fixing file_04.txt
+      Fixing file_13.txt
+      Fixing file_07.txt
+      Fixing file_10.txt
+      Fixing file_19.txt
+      Fixing file_14.txt
+
+    ----- stderr -----
+    "#);
+
+    for i in 0..20 {
+        assert_eq!(
+            context.read(format!("file_{i:02}.txt")),
+            "Hello World\n".repeat(1000)
+        );
+    }
+
+    Ok(())
+}
+
+/// The builtin fixers give the same results with any concurrency, on many files.
+#[test]
+fn builtin_fixers_concurrency() -> Result<()> {
+    const FILES: usize = 3000;
+
+    let setup = || -> Result<TestContext> {
+        let context = TestContext::new();
+        context.init_project();
+
+        // Files are passed in sorted order, so the outputs can be compared line by line.
+        context.write_pre_commit_config(indoc::indoc! {r"
+            repos:
+              - repo: https://github.com/pre-commit/pre-commit-hooks
+                rev: v5.0.0
+                hooks:
+                  - id: trailing-whitespace
+                    require_serial: true
+                  - id: end-of-file-fixer
+                    require_serial: true
+                  - id: check-added-large-files
+                    args: [--maxkb=1]
+                    require_serial: true
+        "});
+
+        let cwd = context.work_dir();
+        for i in 0..FILES {
+            let content = match i % 5 {
+                0 => "Hello World\n".to_string(),
+                1 => "Hello World  \n".repeat(3),
+                2 => "No trailing newline".to_string(),
+                3 => "Too many newlines\n\n\n".to_string(),
+                _ if i % 500 == 4 => "Large file\n".repeat(200),
+                _ => String::new(),
+            };
+            cwd.child(format!("file_{i:04}.txt")).write_str(&content)?;
+        }
+        context.git_add(".");
+
+        Ok(context)
+    };
+
+    let sequential = setup()?;
+    let concurrent = setup()?;
+
+    let expected = sequential.run().env("PREK_CONCURRENCY", "1").output()?;
+    let actual = concurrent.run().env("PREK_CONCURRENCY", "8").output()?;
+
+    assert_eq!(actual.status.code(), Some(1));
+    assert_eq!(actual.status.code(), expected.status.code());
+    assert_eq!(
+        String::from_utf8_lossy(&actual.stdout),
+        String::from_utf8_lossy(&expected.stdout)
+    );
+    for i in 0..FILES {
+        let file = format!("file_{i:04}.txt");
+        assert_eq!(concurrent.read(&file), sequential.read(&file), "{file}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn check_vcs_permalinks_hook() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: check-vcs-permalinks
+                args: ['--additional-github-domain', 'git.example.com']
+    "});
+
+    let cwd = context.work_dir();
+    cwd.child("ok.md").write_str(indoc::indoc! {r"
+        See https://github.com/owner/repo/blob/0a1b2c3d4e5f/src/lib.rs#L10
+        And https://github.com/owner/repo/blob/main/README.md
+    "})?;
+    cwd.child("branch.md").write_str(indoc::indoc! {r"
+        # Links
+        See https://github.com/owner/repo/blob/main/src/lib.rs#L10
+        Also https://git.example.com/owner/repo/blob/dev/a.py#L1-L3
+    "})?;
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check vcs permalinks.....................................................Failed
+    - hook id: check-vcs-permalinks
+    - exit code: 1
+      branch.md:2:5: https://github.com/owner/repo/blob/main/src/lib.rs#L10
+      branch.md:3:6: https://git.example.com/owner/repo/blob/dev/a.py#L1
+
+      Non-permanent github link detected.
+      On any page on github press [y] to load a permalink.
+
+    ----- stderr -----
+    "#);
+
+    // With `--verbose`, the lines around each link are shown.
+    cmd_snapshot!(context.filters(), context.run().arg("--verbose").arg("--files").arg("branch.md"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    check vcs permalinks.....................................................Failed
+    - hook id: check-vcs-permalinks
+    - duration: [TIME]
+    - exit code: 1
+      branch.md:2:5: https://github.com/owner/repo/blob/main/src/lib.rs#L10
+          1 | # Links
+        > 2 | See https://github.com/owner/repo/blob/main/src/lib.rs#L10
+          3 | Also https://git.example.com/owner/repo/blob/dev/a.py#L1-L3
+      branch.md:3:6: https://git.example.com/owner/repo/blob/dev/a.py#L1
+          1 | # Links
+          2 | See https://github.com/owner/repo/blob/main/src/lib.rs#L10
+        > 3 | Also https://git.example.com/owner/repo/blob/dev/a.py#L1-L3
+
+      Non-permanent github link detected.
+      On any page on github press [y] to load a permalink.
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+#[test]
+fn forbid_submodules_hook() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: forbid-submodules
+    "});
+
+    let cwd = context.work_dir();
+    cwd.child("file.txt").write_str("Hello World\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    forbid submodules....................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+
+    // A nested repository is added as a gitlink entry.
+    let sub = cwd.child("sub");
+    sub.create_dir_all()?;
+    context.init_repo(&sub);
+    context.git_add("sub");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    forbid submodules........................................................Failed
+    - hook id: forbid-submodules
+    - exit code: 1
+      submodules are not allowed in this repository
+
+      sub
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Hooks with an overridden `entry` or `args` the builtin implementation doesn't support run in
+/// their own environment.
+#[test]
+fn builtin_fallback() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // `--no-markdown-linebreak-ext` is only supported by the upstream implementation.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+                args: [--no-markdown-linebreak-ext]
+    "});
+    context.work_dir().child("a.md").write_str("line  \n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trim trailing whitespace.................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+    - files were modified by this hook
+      Fixing a.md
+
+    ----- stderr -----
+    "#);
+    assert_snapshot!(context.read("a.md"), @"line\n");
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+                entry: python -c 'print("not the builtin")'
+                pass_filenames: false
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trim trailing whitespace.................................................Passed
+    - hook id: trailing-whitespace
+    - duration: [TIME]
+      not the builtin
+
+    ----- stderr -----
+    "#);
+
     Ok(())
 }
@@ -1,5 +1,7 @@
+use std::time::{Duration, SystemTime};
+
 use assert_fs::assert::PathAssert;
-use assert_fs::fixture::{PathChild, PathCreateDir};
+use assert_fs::fixture::{FileTouch, PathChild, PathCreateDir};
 
 use crate::common::{TestContext, cmd_snapshot};
 
@@ -25,3 +27,33 @@ fn clean() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// `gc` removes the hook output logs older than a week.
+#[test]
+fn gc_old_logs() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    let logs = context.home_dir().child("logs");
+    let old = logs.child("old.log");
+    old.touch()?;
+    std::fs::File::options()
+        .write(true)
+        .open(&old)?
+        .set_modified(SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60))?;
+    let recent = logs.child("recent.log");
+    recent.touch()?;
+
+    cmd_snapshot!(context.filters(), context.command().arg("gc"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Removed 1 old hook output logs
+
+    ----- stderr -----
+    "#);
+
+    old.assert(predicates::path::missing());
+    recent.assert(predicates::path::exists());
+
+    Ok(())
+}
@@ -118,7 +118,7 @@ impl TestContext {
         let mut cmd = Command::new(bin);
         cmd.current_dir(self.work_dir());
         cmd.env(EnvVars::PREK_HOME, &**self.home_dir());
-        cmd.env(EnvVars::PREK_INTERNAL__SORT_FILENAMES, "1");
+        cmd.env(EnvVars::PREK_DETERMINISTIC_OUTPUT, "1");
         cmd
     }
 
@@ -170,6 +170,30 @@ impl TestContext {
         command
     }
 
+    pub fn new_hook(&self) -> Command {
+        let mut command = self.command();
+        command.arg("new-hook");
+        command
+    }
+
+    pub fn config_schema(&self) -> Command {
+        let mut command = self.command();
+        command.arg("config-schema");
+        command
+    }
+
+    pub fn env_info(&self) -> Command {
+        let mut command = self.command();
+        command.arg("env").arg("info");
+        command
+    }
+
+    pub fn export_hooks(&self) -> Command {
+        let mut command = self.command();
+        command.arg("export-hooks");
+        command
+    }
+
     /// Standard snapshot filters _plus_ those for this test context.
     pub fn filters(&self) -> Vec<(&str, &str)> {
         // Put test context snapshots before the default filters
@@ -219,6 +243,28 @@ impl TestContext {
             .success();
     }
 
+    /// Initialize a git repository in `dir`, e.g. a repository of hooks, and commit all its
+    /// files.
+    #[allow(clippy::unused_self)]
+    pub fn init_repo(&self, dir: &Path) {
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=Prek Test",
+                    "-c",
+                    "user.email=test@prek.dev",
+                ])
+                .args(args)
+                .current_dir(dir)
+                .assert()
+                .success();
+        };
+        git(&["init", "--initial-branch=master"]);
+        git(&["add", "."]);
+        git(&["commit", "--allow-empty", "-m", "Initial commit"]);
+    }
+
     /// Run `git add`.
     pub fn git_add(&self, path: impl AsRef<OsStr>) {
         Command::new("git")
@@ -296,8 +342,6 @@ pub const INSTA_FILTERS: &[(&str, &str)] = &[
         r"Caused by: .* \(os error 2\)",
         "Caused by: No such file or directory (os error 2)",
     ),
-    // Time seconds
-    (r"(\d+\.)?\d+(ms|s)", "[TIME]"),
 ];
 
 #[allow(unused_macros)]
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use assert_cmd::assert::OutputAssertExt;
+
+use crate::common::TestContext;
+
+mod common;
+
+#[test]
+fn config_schema() -> Result<()> {
+    let context = TestContext::new();
+
+    let output = context.config_schema().assert().success();
+    let schema: serde_json::Value = serde_json::from_slice(&output.get_output().stdout)?;
+
+    assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+    assert_eq!(schema["required"], serde_json::json!(["repos"]));
+    assert!(schema["properties"]["repos"].is_object());
+    assert!(schema["properties"]["default_stages"].is_object());
+
+    let stages = schema["definitions"]["stage"]["enum"].as_array().unwrap();
+    assert!(stages.contains(&"pre-commit".into()));
+    assert!(stages.contains(&"manual".into()));
+
+    let hook = &schema["definitions"]["local_hook"];
+    assert_eq!(
+        hook["required"],
+        serde_json::json!(["id", "name", "entry", "language"])
+    );
+    for key in ["stages", "files", "exclude", "args", "pass_filenames"] {
+        assert!(hook["properties"][key].is_object(), "missing `{key}`");
+    }
+
+    let languages = schema["definitions"]["language"]["enum"]
+        .as_array()
+        .unwrap();
+    assert!(languages.contains(&"python".into()));
+
+    Ok(())
+}
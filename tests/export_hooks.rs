@@ -0,0 +1,152 @@
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn export_hooks() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        exclude: ^vendor/
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+          - repo: local
+            hooks:
+              - id: lint
+                name: Lint text files
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:])"
+                args: [--strict]
+                files: '(?i)\.TXT$'
+                exclude: ^docs/
+              - id: check
+                name: check
+                language: script
+                entry: check.sh --all --config=${HOME}/.checkrc
+                types: [python]
+                pass_filenames: false
+                always_run: true
+              - id: no-rej
+                name: no .rej files
+                language: fail
+                entry: "Found .rej files:"
+                files: '[.]rej$'
+              - id: format
+                name: format
+                language: python
+                entry: ruff format
+                additional_dependencies: [ruff]
+    "#});
+
+    // The output has no paths, and the default filters would rewrite the backslashes in it.
+    let filters: Vec<(&str, &str)> = vec![];
+
+    cmd_snapshot!(filters.clone(), context.export_hooks(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #!/bin/sh
+    # Generated by `prek export-hooks` from `.pre-commit-config.yaml`.
+    # File patterns are matched with `grep -E` and file types are not checked,
+    # review the commands before relying on them.
+
+    cd "$(git rev-parse --show-toplevel)" || exit 1
+    status=0
+
+    # identity
+    prek run identity --all-files || status=1
+
+    # lint
+    files=$(git ls-files | grep -vE -- '^vendor/' | grep -iE -- '\.TXT$' | grep -vE -- '^docs/' || true)
+    if [ -n "$files" ]; then
+      printf '%s\n' "$files" | tr '\n' '\0' | xargs -0 python3 -c 'import sys; print(sys.argv[1:])' --strict || status=1
+    fi
+
+    # check
+    # Only files of types `python` should be checked.
+    ./check.sh --all --config="${HOME}"/.checkrc || status=1
+
+    # no-rej
+    files=$(git ls-files | grep -vE -- '^vendor/' | grep -E -- '[.]rej$' || true)
+    if [ -n "$files" ]; then
+      printf '%s\n' 'Found .rej files:' "$files"
+      status=1
+    fi
+
+    # TODO: `format` is a `python` hook, install its toolchain and run:
+    #   ruff format
+
+    exit $status
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(filters, context.export_hooks().arg("--format").arg("github-actions"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    # Generated by `prek export-hooks` from `.pre-commit-config.yaml`.
+    # File patterns are matched with `grep -E` and file types are not checked,
+    # review the commands before relying on them.
+
+    - name: "identity"
+      run: |
+        prek run identity --all-files
+
+    - name: "Lint text files"
+      run: |
+        files=$(git ls-files | grep -vE -- '^vendor/' | grep -iE -- '\.TXT$' | grep -vE -- '^docs/' || true)
+        if [ -n "$files" ]; then
+          printf '%s\n' "$files" | tr '\n' '\0' | xargs -0 python3 -c 'import sys; print(sys.argv[1:])' --strict
+        fi
+
+    - name: "check"
+      run: |
+        # Only files of types `python` should be checked.
+        ./check.sh --all --config="${HOME}"/.checkrc
+
+    - name: "no .rej files"
+      run: |
+        files=$(git ls-files | grep -vE -- '^vendor/' | grep -E -- '[.]rej$' || true)
+        if [ -n "$files" ]; then
+          printf '%s\n' 'Found .rej files:' "$files"
+          exit 1
+        fi
+
+    # TODO: `format` is a `python` hook, install its toolchain and run:
+    #   ruff format
+
+    ----- stderr -----
+    "#);
+}
+
+#[test]
+fn export_hooks_untranslatable_pattern() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo
+                files: '(?<!test_)\w+\.py$'
+    "});
+
+    cmd_snapshot!(context.filters(), context.export_hooks(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to export hook `lint`
+      caused by: Failed to translate the `files` pattern `(?<!test_)\w+\.py$` for `grep -E`
+      caused by: Lookarounds have no `grep -E` equivalent
+    "#);
+}
@@ -1,5 +1,7 @@
 use std::process::Command;
 
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
 use common::TestContext;
 use indoc::indoc;
 
@@ -56,3 +58,303 @@ fn hook_impl() {
       .pre-commit-config.yaml
     "#);
 }
+
+#[test]
+fn hook_impl_disabled() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: fail
+             name: fail
+             language: fail
+             entry: always fail
+             always_run: true
+    "});
+
+    context.git_add(".");
+    context.configure_git_author();
+    context.install().assert().success();
+
+    let commit = || {
+        let mut commit = Command::new("git");
+        commit
+            .arg("commit")
+            .arg("--allow-empty")
+            .arg("--quiet")
+            .current_dir(context.work_dir())
+            .arg("-m")
+            .arg("Initial commit");
+        commit
+    };
+
+    // Disable all hooks.
+    cmd_snapshot!(context.filters(), commit().env("PREK_DISABLE", "1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    prek disabled via PREK_DISABLE
+    ");
+
+    // Disable only `pre-push` hooks, `pre-commit` still runs.
+    cmd_snapshot!(context.filters(), commit().env("PREK_DISABLE", "pre-push"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      always fail
+    ");
+
+    // Disable all hooks with the marker file.
+    context
+        .work_dir()
+        .child(".git/prek-disabled")
+        .write_str("")?;
+    cmd_snapshot!(context.filters(), commit(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    prek disabled via .git/prek-disabled
+    ");
+
+    // `PREK_DISABLE` takes precedence over the marker file.
+    cmd_snapshot!(context.filters(), commit().env("PREK_DISABLE", "0"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      always fail
+    ");
+
+    Ok(())
+}
+
+/// A commit with no staged files skips the hooks, unless one of them is `always_run`.
+#[test]
+fn hook_impl_nothing_staged() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: fail
+             name: fail
+             language: fail
+             entry: always fail
+             files: \.py$
+    "});
+
+    context.git_add(".");
+    context.configure_git_author();
+    context.git_commit("Initial commit");
+    context.install().assert().success();
+
+    let commit = || {
+        let mut commit = Command::new("git");
+        commit
+            .arg("commit")
+            .arg("--allow-empty")
+            .arg("--quiet")
+            .current_dir(context.work_dir())
+            .arg("-m")
+            .arg("Empty commit");
+        commit
+    };
+
+    // The hooks are not run at all, so there is no "no files to check" status either.
+    cmd_snapshot!(context.filters(), commit(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    // Files not matching any hook still go through `run`.
+    context.work_dir().child("README.md").write_str("Hello")?;
+    context.git_add("README.md");
+    cmd_snapshot!(context.filters(), commit(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    fail.................................................(no files to check)Skipped
+    ");
+
+    // An `always_run` hook runs without staged files.
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: fail
+             name: fail
+             language: fail
+             entry: always fail
+             always_run: true
+    "});
+    context.git_add(".");
+    Command::new("git")
+        .arg("commit")
+        .arg("--no-verify")
+        .arg("-m")
+        .arg("Always run")
+        .current_dir(context.work_dir())
+        .assert()
+        .success();
+    cmd_snapshot!(context.filters(), commit(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      always fail
+    ");
+
+    Ok(())
+}
+
+/// A missing config fails the hook, unless `--skip-on-missing-config` is set.
+#[test]
+fn hook_impl_missing_config() {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let hook_impl = || {
+        let mut command = context.command();
+        command
+            .arg("hook-impl")
+            .arg("--hook-type=pre-commit")
+            .arg("--hook-dir")
+            .arg(".git/hooks");
+        command
+    };
+
+    cmd_snapshot!(context.filters(), hook_impl(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Config file not found: .pre-commit-config.yaml
+    - To temporarily silence this, run `PREK_ALLOW_NO_CONFIG=1 git ...`
+    - To permanently silence this, install hooks with the `--allow-missing-config` flag
+    - To uninstall hooks, run `prek uninstall`
+    ");
+
+    cmd_snapshot!(context.filters(), hook_impl().arg("--config=.pre-commit-config.yaml"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Config file not found: .pre-commit-config.yaml
+    - To temporarily silence this, run `PREK_ALLOW_NO_CONFIG=1 git ...`
+    - To permanently silence this, install hooks with the `--allow-missing-config` flag
+    - To uninstall hooks, run `prek uninstall`
+    ");
+
+    cmd_snapshot!(context.filters(), hook_impl().arg("--skip-on-missing-config"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), hook_impl().env("PREK_ALLOW_NO_CONFIG", "1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+}
+
+/// `post-checkout` hooks run for branch checkouts only, unless configured for file checkouts.
+#[test]
+fn hook_impl_post_checkout_type() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc! {r"
+        repos:
+        - repo: local
+          hooks:
+           - id: default
+             name: default
+             language: system
+             entry: echo default
+             stages: [post-checkout]
+             always_run: true
+           - id: any-checkout
+             name: any-checkout
+             language: system
+             entry: echo any-checkout
+             stages: [post-checkout]
+             post_checkout_on: [branch, file]
+             always_run: true
+    "});
+    context.git_add(".");
+    context.configure_git_author();
+    context.git_commit("Initial commit");
+
+    let hook_impl = |flag: &str| {
+        let mut command = context.command();
+        command
+            .arg("hook-impl")
+            .arg("--hook-type=post-checkout")
+            .arg("--hook-dir")
+            .arg(".git/hooks")
+            .arg("--")
+            .arg("HEAD")
+            .arg("HEAD")
+            .arg(flag);
+        command
+    };
+
+    // File checkout.
+    cmd_snapshot!(context.filters(), hook_impl("0"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    default..................................................(file checkout)Skipped
+    any-checkout.............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    // Branch checkout.
+    cmd_snapshot!(context.filters(), hook_impl("1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    default..................................................................Passed
+    any-checkout.............................................................Passed
+
+    ----- stderr -----
+    ");
+}
@@ -277,6 +277,15 @@ fn uninstall() -> anyhow::Result<()> {
     .git/hooks/pre-commit does not exist, skipping.
     "#);
 
+    // Informational messages are silenced by `--quiet`.
+    cmd_snapshot!(context.filters(), context.uninstall().arg("--quiet"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "#);
+
     // Uninstall `pre-commit` hook.
     context.install().assert().success();
     cmd_snapshot!(context.filters(), context.uninstall(), @r#"
@@ -341,6 +350,205 @@ fn uninstall() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Without `--hook-type`, the hook types of `default_install_hook_types` are installed.
+#[test]
+fn default_install_hook_types() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        default_install_hook_types: [pre-commit, pre-push]
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo lint
+    "});
+
+    cmd_snapshot!(context.filters(), context.install(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prek installed at .git/hooks/pre-commit
+    prek installed at .git/hooks/pre-push
+
+    ----- stderr -----
+    "#);
+    context
+        .work_dir()
+        .child(".git/hooks/pre-commit")
+        .assert(predicates::path::exists());
+    context
+        .work_dir()
+        .child(".git/hooks/pre-push")
+        .assert(predicates::path::exists());
+
+    // An explicit `--hook-type` takes precedence over the config.
+    cmd_snapshot!(context.filters(), context.install().arg("--hook-type").arg("commit-msg"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prek installed at .git/hooks/commit-msg
+
+    ----- stderr -----
+    "#);
+}
+
+/// Installing a hook type that no hook runs at warns about it.
+#[test]
+fn install_unused_hook_type() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo lint
+                stages: [pre-commit]
+    "});
+
+    cmd_snapshot!(context.filters(), context.install().arg("--hook-type").arg("pre-commit").arg("--hook-type").arg("pre-push"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prek installed at .git/hooks/pre-commit
+    prek installed at .git/hooks/pre-push
+
+    ----- stderr -----
+    warning: No hook runs at `pre-push`, the installed `pre-push` hook will do nothing
+    "#);
+}
+
+/// `--auto-hook-types` picks the hook types from the stages of the hooks.
+#[test]
+fn auto_hook_types() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo lint
+              - id: push-check
+                name: push-check
+                language: system
+                entry: echo push
+                stages: [pre-push]
+              - id: manual-check
+                name: manual-check
+                language: system
+                entry: echo manual
+                stages: [manual]
+    "});
+
+    cmd_snapshot!(context.filters(), context.install().arg("--auto-hook-types"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Using pre-commit for `lint`
+    Using pre-push for `push-check`
+    prek installed at .git/hooks/pre-commit
+    prek installed at .git/hooks/pre-push
+
+    ----- stderr -----
+    "#);
+    context
+        .work_dir()
+        .child(".git/hooks/pre-push")
+        .assert(predicates::path::exists());
+
+    cmd_snapshot!(context.filters(), context.uninstall().arg("--auto-hook-types"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Using pre-commit for `lint`
+    Using pre-push for `push-check`
+    Uninstalled pre-commit
+    Uninstalled pre-push
+
+    ----- stderr -----
+    "#);
+    context
+        .work_dir()
+        .child(".git/hooks/pre-push")
+        .assert(predicates::path::missing());
+
+    // Only the hook types of `default_install_hook_types` are considered.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        default_install_hook_types: [pre-push]
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo lint
+    "});
+
+    cmd_snapshot!(context.filters(), context.install().arg("--auto-hook-types"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    No hook runs at a hook type to install, using the defaults
+    prek installed at .git/hooks/pre-push
+
+    ----- stderr -----
+    "#);
+}
+
+/// Remote hooks without `stages` in the config run at the stages of their manifest.
+#[test]
+fn auto_hook_types_manifest_stages() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.home_dir().child("msg-hooks");
+    repo.child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: check-message
+          name: check-message
+          language: system
+          entry: echo
+          stages: [commit-msg]
+    "})?;
+    context.init_repo(&repo);
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: check-message
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo lint
+    ", repo.display()});
+
+    cmd_snapshot!(context.filters(), context.install().arg("--auto-hook-types"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Using commit-msg for `check-message`
+    Using pre-commit for `lint`
+    prek installed at .git/hooks/commit-msg
+    prek installed at .git/hooks/pre-commit
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
 #[test]
 fn init_template_dir() {
     let context = TestContext::new();
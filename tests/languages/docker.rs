@@ -1,3 +1,8 @@
+use std::process::Command;
+
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
 use crate::common::{TestContext, cmd_snapshot};
 
 /// GitHub Action only has docker for linux hosted runners.
@@ -31,3 +36,183 @@ fn docker() {
     ----- stderr -----
     "#);
 }
+
+/// A `local` docker hook builds the `Dockerfile` of the repository and runs in it, the image is
+/// rebuilt when the build context changes.
+#[test]
+fn local_dockerfile() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: from-dockerfile
+                name: from-dockerfile
+                language: docker
+                entry: cat /message
+                pass_filenames: false
+                always_run: true
+                verbose: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to install hook `from-dockerfile`
+      caused by: Failed to build docker image
+      caused by: Local `docker` hook `from-dockerfile` requires a `Dockerfile` at the repository root
+    "#);
+
+    context
+        .work_dir()
+        .child("Dockerfile")
+        .write_str(indoc::indoc! {r#"
+        FROM alpine:3.20
+        RUN echo "Built from the local Dockerfile" > /message
+    "#})?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    from-dockerfile..........................................................Passed
+    - hook id: from-dockerfile
+    - duration: [TIME]
+      Built from the local Dockerfile
+
+    ----- stderr -----
+    "#);
+
+    // The image is reused while the build context doesn't change.
+    let output = context.run().arg("-vvv").output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("build --quiet --tag prek-"), "{stderr}");
+
+    context
+        .work_dir()
+        .child("Dockerfile")
+        .write_str(indoc::indoc! {r#"
+        FROM alpine:3.20
+        RUN echo "Rebuilt from the local Dockerfile" > /message
+    "#})?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    from-dockerfile..........................................................Passed
+    - hook id: from-dockerfile
+    - duration: [TIME]
+      Rebuilt from the local Dockerfile
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// The image of a remote repository is tagged by its source, and reused by later runs unless
+/// `--refresh` is given.
+#[test]
+fn reuse_image() -> anyhow::Result<()> {
+    let last_tagged = || -> anyhow::Result<String> {
+        let images = Command::new("docker")
+            .args([
+                "images",
+                "--filter",
+                "label=prek.repo=https://github.com/prek-test-repos/docker-hooks",
+                "--format",
+                "{{.Repository}}",
+            ])
+            .output()?;
+        let tag = String::from_utf8(images.stdout)?.trim().to_string();
+        assert!(tag.starts_with("prek-"), "unexpected tag `{tag}`");
+
+        let inspect = Command::new("docker")
+            .args([
+                "image",
+                "inspect",
+                "--format",
+                "{{.Metadata.LastTagTime}}",
+                &tag,
+            ])
+            .output()?;
+        Ok(String::from_utf8(inspect.stdout)?)
+    };
+
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: https://github.com/prek-test-repos/docker-hooks
+            rev: master
+            hooks:
+              - id: hello-world
+                entry: "echo Hello, world!"
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    context.run().assert().success();
+    let built = last_tagged()?;
+
+    context.run().assert().success();
+    assert_eq!(last_tagged()?, built, "the image was rebuilt");
+
+    // `--refresh` builds it again.
+    let output = context.run().arg("--refresh").arg("-vvv").output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("build --quiet --tag prek-"), "{stderr}");
+
+    Ok(())
+}
+
+/// A hook erroring because the docker daemon is down reports the state of the daemon.
+#[test]
+fn daemon_not_reachable() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: from-dockerfile
+                name: from-dockerfile
+                language: docker
+                entry: echo
+                pass_filenames: false
+                always_run: true
+    "});
+    context
+        .work_dir()
+        .child("Dockerfile")
+        .write_str("FROM alpine:3.20\n")?;
+    context.git_add(".");
+
+    context.run().assert().success();
+
+    let output = context
+        .run()
+        .env("DOCKER_HOST", "unix:///nonexistent/docker.sock")
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(
+        stdout.contains("runtime: docker daemon not reachable: "),
+        "{stdout}"
+    );
+
+    Ok(())
+}
@@ -37,7 +37,10 @@ fn docker_image() -> Result<()> {
     let filters = context
         .filters()
         .into_iter()
-        .chain([(r"\d\d?:\d\d(AM|PM)", "[TIME]")])
+        .chain([
+            (r"\d\d?:\d\d(AM|PM)", "[TIME]"),
+            (r"completed in (\d+\.)?\d+(ms|s)", "completed in [TIME]"),
+        ])
         .collect::<Vec<_>>();
 
     cmd_snapshot!(filters, context.run(), @r#"
@@ -1,7 +1,8 @@
+use assert_cmd::assert::OutputAssertExt;
 use assert_fs::assert::PathAssert;
-use assert_fs::fixture::PathChild;
+use assert_fs::fixture::{ChildPath, FileWriteStr, PathChild, PathCreateDir};
 
-use crate::common::{TestContext, cmd_snapshot};
+use crate::common::{TestContext, cmd_snapshot, remove_bin_from_path};
 
 // We use `setup-go` action to install go1.24.5 in CI, so 1.23.11 should be downloaded by prek.
 #[test]
@@ -164,6 +165,37 @@ fn additional_dependencies() {
     "#);
 }
 
+/// Module paths without a version install the latest version into the hook environment.
+#[test]
+fn additional_dependencies_without_version() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: golang
+                name: golang
+                language: golang
+                entry: gofumpt -h
+                additional_dependencies: ["mvdan.cc/gofumpt"]
+                always_run: true
+                pass_filenames: false
+    "#});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    golang...................................................................Passed
+
+    ----- stderr -----
+    "#);
+}
+
 /// Test a remote go hook.
 #[test]
 fn remote_hook() {
@@ -192,3 +224,156 @@ fn remote_hook() {
     ----- stderr -----
     "#);
 }
+
+/// Create a git repository of Go hooks in `dir`, with a `main` package at its root.
+fn go_hook_repo(context: &TestContext, dir: &ChildPath, go_mod: bool) -> anyhow::Result<()> {
+    dir.child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: greet
+          name: greet
+          language: golang
+          entry: greet
+    "})?;
+    dir.child("main.go").write_str(indoc::indoc! {r#"
+        package main
+
+        import (
+            "fmt"
+            "os"
+        )
+
+        func main() {
+            fmt.Println("Hello from", len(os.Args)-1, "files")
+        }
+    "#})?;
+    if go_mod {
+        dir.child("go.mod")
+            .write_str("module example.com/greet\n\ngo 1.23\n")?;
+    }
+
+    context.init_repo(dir);
+
+    Ok(())
+}
+
+/// A remote repository shipping a `main` package is built into the hook environment.
+#[test]
+fn build_from_repo() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.home_dir().child("greet-hooks");
+    go_hook_repo(&context, &repo, true)?;
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: greet
+                verbose: true
+    ", repo.display()});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    greet....................................................................Passed
+    - hook id: greet
+    - duration: [TIME]
+      Hello from 1 files
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Only Go modules can be built.
+#[test]
+fn build_from_repo_without_go_mod() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.home_dir().child("greet-hooks");
+    go_hook_repo(&context, &repo, false)?;
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: greet
+    ", repo.display()});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to install hook `greet`
+      caused by: Hook repository `file://[HOME]/greet-hooks@master` has no `go.mod`, only Go modules can be built
+    "#);
+
+    Ok(())
+}
+
+/// A system Go that doesn't match the request is rejected, then Go is downloaded from
+/// `PREK_GO_DOWNLOAD_MIRROR`, and a failed download reports both causes.
+#[cfg(unix)]
+#[test]
+fn download_mirror() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: golang
+                name: golang
+                language: golang
+                entry: go version
+                language_version: '1.23.11'
+                pass_filenames: false
+                always_run: true
+    "});
+    context.git_add(".");
+
+    // Replace any system Go with one of another version.
+    let fake_bin = context.home_dir().child("fake-bin");
+    fake_bin.create_dir_all()?;
+    let fake_go = fake_bin.child("go");
+    fake_go.write_str("#!/bin/sh\necho 'go version go1.22.0 linux/amd64'\n")?;
+    fs_err::set_permissions(&fake_go, std::fs::Permissions::from_mode(0o755))?;
+    let path = remove_bin_from_path("go")?;
+    let path = std::env::join_paths(
+        std::iter::once(fake_bin.to_path_buf()).chain(std::env::split_paths(&path)),
+    )?;
+
+    // Nothing listens on the discard port.
+    let output = context
+        .run()
+        .env("PATH", path)
+        .env("PREK_GO_DOWNLOAD_MIRROR", "http://127.0.0.1:9/golang/")
+        .assert()
+        .code(2);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains(&format!(
+            "caused by: No Go on `PATH` matches the requested version (`{}@1.22.0` does not match), and installing it failed",
+            fake_go.display()
+        )),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("http://127.0.0.1:9/golang/?mode=json&include=all"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
@@ -6,9 +6,12 @@ mod docker;
 #[cfg(all(feature = "docker", target_os = "linux"))]
 mod docker_image;
 mod fail;
+#[cfg(feature = "languages-golang")]
 mod golang;
+#[cfg(feature = "languages-node")]
 mod node;
 mod pygrep;
+#[cfg(feature = "languages-python")]
 mod python;
 mod script;
 mod unimplemented;
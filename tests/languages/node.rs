@@ -158,6 +158,57 @@ fn additional_dependencies() {
     "###);
 }
 
+/// Hooks with the same node version and dependencies share one environment.
+#[test]
+fn reuse_env() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: cowsay
+                name: cowsay
+                language: node
+                language_version: '18.20.8'
+                entry: cowsay Hello
+                additional_dependencies: ["cowsay"]
+                always_run: true
+                pass_filenames: false
+              - id: cowthink
+                name: cowthink
+                language: node
+                language_version: '18'
+                entry: cowthink Hello
+                additional_dependencies: ["cowsay"]
+                always_run: true
+                pass_filenames: false
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    cowsay...................................................................Passed
+    cowthink.................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    let envs = context
+        .home_dir()
+        .child("hooks")
+        .read_dir()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("node-"))
+        .count();
+    assert_eq!(envs, 1);
+
+    Ok(())
+}
+
 /// Test `https://github.com/thlorenz/doctoc` works correctly with prek.
 /// Previously, prek did not install its dependencies correctly.
 #[test]
@@ -202,3 +253,90 @@ fn doctoc() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// A hook repository with a yarn lockfile is installed with yarn in the hook environment,
+/// leaving the store clone shared by the environments untouched.
+#[test]
+fn yarn_lockfile() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.home_dir().child("hello-hooks");
+    repo.child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: hello
+          name: hello
+          language: node
+          entry: hello
+          pass_filenames: false
+          always_run: true
+    "})?;
+    repo.child("package.json").write_str(indoc::indoc! {r#"
+        {
+          "name": "hello",
+          "version": "1.0.0",
+          "bin": { "hello": "hello.js" },
+          "packageManager": "yarn@1.22.22"
+        }
+    "#})?;
+    repo.child("yarn.lock").write_str(indoc::indoc! {r"
+        # THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+        # yarn lockfile v1
+
+
+    "})?;
+    repo.child("hello.js")
+        .write_str("#!/usr/bin/env node\nconsole.log('Hello from yarn');\n")?;
+
+    context.init_repo(&repo);
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: hello
+                verbose: true
+    ", repo.display()});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    hello....................................................................Passed
+    - hook id: hello
+    - duration: [TIME]
+      Hello from yarn
+
+    ----- stderr -----
+    "#);
+
+    // Yarn installed the copy of the repository in the environment, not the clone.
+    for clone in context.home_dir().child("repos").read_dir()?.flatten() {
+        assert!(!clone.path().join("node_modules").exists());
+    }
+
+    // The environment is reused as long as the repository asks for the same package manager.
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    hello....................................................................Passed
+    - hook id: hello
+    - duration: [TIME]
+      Hello from yarn
+
+    ----- stderr -----
+    "#);
+    let envs = context
+        .home_dir()
+        .child("hooks")
+        .read_dir()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("node-"))
+        .count();
+    assert_eq!(envs, 1);
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
+use assert_cmd::assert::OutputAssertExt;
 use assert_fs::assert::PathAssert;
-use assert_fs::fixture::PathChild;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use constants::env_vars::EnvVars;
 
 use crate::common::{TestContext, cmd_snapshot};
 
@@ -152,7 +155,7 @@ fn invalid_version() {
     ----- stdout -----
 
     ----- stderr -----
-    error: Hook `local` is invalid
+    error: .pre-commit-config.yaml:4:9: Hook `local` is invalid
       caused by: Invalid `language_version` value: `invalid-version`
     "#);
 }
@@ -241,3 +244,254 @@ fn additional_dependencies() {
     ----- stderr -----
     "#);
 }
+
+/// `python -m` entries run with the interpreter of the hook environment.
+#[test]
+fn module_entry() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: json-tool
+                name: json-tool
+                language: python
+                entry: python -m json.tool --sort-keys
+                files: \.json$
+                verbose: true
+              - id: prefix
+                name: prefix
+                language: python
+                entry: python3 -c 'import sys; print(sys.prefix == sys.base_prefix)'
+                pass_filenames: false
+                always_run: true
+                verbose: true
+    "});
+    context
+        .work_dir()
+        .child("data.json")
+        .write_str(r#"{"b": 1, "a": 2}"#)?;
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    json-tool................................................................Passed
+    - hook id: json-tool
+    - duration: [TIME]
+      {
+          "a": 2,
+          "b": 1
+      }
+    prefix...................................................................Passed
+    - hook id: prefix
+    - duration: [TIME]
+      False
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// `env info` shows what would be installed for a hook, then the environment it runs in.
+#[test]
+fn env_info() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: greet
+                name: greet
+                language: python
+                entry: python -c 'print("hi")'
+                pass_filenames: false
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.env_info().arg("greet"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    greet (local)
+      language: python
+      environment: not installed
+      would install: language version `default`
+      command: python -c 'print("hi")'
+
+    ----- stderr -----
+    "#);
+
+    context.run().assert().success();
+
+    let output = context.env_info().arg("greet").arg("--json").output()?;
+    assert!(output.status.success());
+    let infos: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let info = &infos[0];
+    assert_eq!(info["id"], "greet");
+    assert_eq!(info["installed"], true);
+    let env_path = std::path::Path::new(info["env_path"].as_str().unwrap());
+    assert!(env_path.join(".prek-hook.json").is_file());
+    assert!(info["language_version"].as_str().unwrap().starts_with("3."));
+    assert!(info["size"].as_u64().unwrap() > 0);
+    assert!(info["installed_at"].is_u64());
+    assert_eq!(info["command"], r#"python -c 'print("hi")'"#);
+
+    cmd_snapshot!(context.filters(), context.env_info().arg("missing"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    No hook found for id `missing`
+    ");
+
+    Ok(())
+}
+
+/// A hook reuses an environment with dependencies it no longer asks for, logging it without
+/// a warning that would fail `--strict`, unless `--reinstall-stale-envs` installs a fresh one.
+#[test]
+fn stale_dependencies() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: python
+                entry: pyecho Hello
+                additional_dependencies: ["pyecho-cli", "six"]
+                always_run: true
+                pass_filenames: false
+    "#});
+    context.git_add(".");
+    context.run().assert().success();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: python
+                entry: pyecho Hello
+                additional_dependencies: ["pyecho-cli"]
+                always_run: true
+                pass_filenames: false
+    "#});
+    context.git_add(".");
+
+    let output = context.run().arg("--strict").output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(!stderr.contains("which also has"), "{stderr}");
+
+    let output = context.run().arg("-vv").output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.contains("which also has `six` installed"),
+        "{stderr}"
+    );
+
+    let output = context
+        .run()
+        .arg("-vv")
+        .arg("--reinstall-stale-envs")
+        .output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(!stderr.contains("which also has"), "{stderr}");
+
+    // The fresh environment is preferred from now on.
+    let output = context.run().arg("-vv").output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(!stderr.contains("which also has"), "{stderr}");
+
+    Ok(())
+}
+
+/// Installing environments prints a summary of the new and the reused environments.
+#[test]
+fn install_summary() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fresh
+                name: fresh
+                language: python
+                language_version: '3'
+                entry: python -c 'print(1)'
+              - id: cached
+                name: cached
+                language: python
+                entry: python -c 'print(1)'
+                additional_dependencies: ["seeded-dep"]
+              - id: system
+                name: system
+                language: system
+                entry: echo
+    "#});
+    context.git_add(".");
+
+    // An environment installed by a previous run, which only `cached` can use.
+    let seeded = context.home_dir().child("hooks").child("python-seeded");
+    seeded.child(".prek-hook.json").write_str(
+        &serde_json::json!({
+            "language": "python",
+            "language_version": "2.99.0",
+            "dependencies": ["seeded-dep"],
+            "env_path": seeded.path(),
+            "toolchain": "",
+            "extra": {},
+        })
+        .to_string(),
+    )?;
+
+    // The summary is part of the progress output, which `-vv` keeps under the deterministic
+    // output, with a placeholder for the duration.
+    let output = context.install_hooks().arg("-vv").output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.lines().any(
+            |line| line.starts_with("Installed 1 environment (python 3.")
+                && line.ends_with(": 1) in [TIME]; reused 1 cached environment")
+        ),
+        "{stderr}"
+    );
+
+    let filters = context.filters();
+
+    // Nothing to install, nothing to report.
+    cmd_snapshot!(filters.clone(), context.install_hooks().env_remove(EnvVars::PREK_DETERMINISTIC_OUTPUT), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(filters, context.install_hooks().arg("--no-progress"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
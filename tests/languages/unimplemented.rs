@@ -25,3 +25,31 @@ fn unimplemented_language() {
     ----- stderr -----
     "#);
 }
+
+#[cfg(not(feature = "languages-python"))]
+#[test]
+fn missing_language_feature() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+            - id: python-hook
+              name: python-hook
+              language: python
+              entry: python -c 'print(1)'
+    "});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to install hook `python-hook`
+      caused by: Language `python` is not available in this build of prek, rebuild it with the `languages-python` feature
+    ");
+}
@@ -37,7 +37,7 @@ fn meta_hooks() -> anyhow::Result<()> {
     "});
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run(), @r#"
+    cmd_snapshot!(context.filters(), context.run().env("PREK_CONCURRENCY", "1"), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
@@ -52,11 +52,11 @@ fn meta_hooks() -> anyhow::Result<()> {
     identity.................................................................Passed
     - hook id: identity
     - duration: [TIME]
-      file.txt
       .pre-commit-config.yaml
-      valid.json
+      file.txt
       invalid.json
       main.py
+      valid.json
     match no files.......................................(no files to check)Skipped
     useless exclude..........................................................Passed
 
@@ -0,0 +1,145 @@
+use assert_fs::fixture::{FileWriteStr, PathChild};
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn new_hook_creates_manifest() {
+    let context = TestContext::new();
+
+    cmd_snapshot!(context.filters(), context.new_hook()
+        .arg("--id").arg("check-json")
+        .arg("--language").arg("python")
+        .arg("--entry").arg("check-json")
+        .arg("--types").arg("json"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Added hook `check-json` to `.pre-commit-hooks.yaml`
+    hint: Try it by copying it to a `repo: local` repo of your config, then run `prek run check-json --verbose --all-files`
+
+    ----- stderr -----
+    ");
+    insta::assert_snapshot!(context.read(".pre-commit-hooks.yaml"), @r"
+    - id: check-json
+      name: check-json
+      entry: check-json
+      language: python
+      types:
+      - json
+    ");
+
+    cmd_snapshot!(context.filters(), context.validate_manifest().arg(".pre-commit-hooks.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn new_hook_appends_to_manifest() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    let manifest = indoc::indoc! {r"
+        # Hooks provided by this repository.
+        -   id: lint
+            name: Lint
+            entry: lint.sh
+            language: script
+    "};
+    context
+        .work_dir()
+        .child(".pre-commit-hooks.yaml")
+        .write_str(manifest)?;
+
+    cmd_snapshot!(context.filters(), context.new_hook()
+        .arg("--id").arg("format")
+        .arg("--name").arg("Format")
+        .arg("--language").arg("system")
+        .arg("--entry").arg("fmt --check")
+        .arg("--types").arg("python,pyi"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Appended hook `format` to `.pre-commit-hooks.yaml`
+    hint: Try it by copying it to a `repo: local` repo of your config, then run `prek run format --verbose --all-files`
+
+    ----- stderr -----
+    ");
+    insta::assert_snapshot!(context.read(".pre-commit-hooks.yaml"), @r"
+    # Hooks provided by this repository.
+    -   id: lint
+        name: Lint
+        entry: lint.sh
+        language: script
+    - id: format
+      name: Format
+      entry: fmt --check
+      language: system
+      types:
+      - python
+      - pyi
+    ");
+
+    // An existing id is not overwritten.
+    cmd_snapshot!(context.filters(), context.new_hook()
+        .arg("--id").arg("lint")
+        .arg("--language").arg("system")
+        .arg("--entry").arg("lint"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Hook `lint` already exists in `.pre-commit-hooks.yaml`
+    ");
+
+    // Invalid hooks are rejected before writing.
+    cmd_snapshot!(context.filters(), context.new_hook()
+        .arg("--id").arg("bad")
+        .arg("--language").arg("cobol")
+        .arg("--entry").arg("bad"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Invalid hook `bad`
+      caused by: Failed to parse `.pre-commit-hooks.yaml`
+      caused by: [2]: Unknown language: `cobol` at line 13 column 3
+    ");
+    assert!(!context.read(".pre-commit-hooks.yaml").contains("bad"));
+
+    Ok(())
+}
+
+/// `--file` is relative to the directory prek is run from, not to the repository root.
+#[test]
+fn new_hook_in_subdirectory() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let child = context.work_dir().child("hooks");
+    child.create_dir_all()?;
+
+    let output = context
+        .new_hook()
+        .current_dir(&child)
+        .arg("--id")
+        .arg("lint")
+        .arg("--language")
+        .arg("system")
+        .arg("--entry")
+        .arg("lint")
+        .arg("--file")
+        .arg("manifest.yaml")
+        .output()?;
+    assert!(output.status.success(), "{output:?}");
+    assert!(context.read("hooks/manifest.yaml").contains("id: lint"));
+    assert!(!context.work_dir().join("manifest.yaml").exists());
+
+    Ok(())
+}
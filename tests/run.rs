@@ -3,6 +3,7 @@ use std::process::Command;
 use anyhow::Result;
 use assert_cmd::assert::OutputAssertExt;
 use assert_fs::prelude::*;
+use constants::env_vars::EnvVars;
 use insta::assert_snapshot;
 
 use crate::common::{TestContext, cmd_snapshot};
@@ -33,7 +34,8 @@ fn run_basic() -> Result<()> {
 
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run(), @r#"
+    // With a single invocation per hook, the files are passed in sorted order.
+    cmd_snapshot!(context.filters(), context.run().env(EnvVars::PREK_CONCURRENCY, "1"), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
@@ -46,9 +48,9 @@ fn run_basic() -> Result<()> {
     - hook id: end-of-file-fixer
     - exit code: 1
     - files were modified by this hook
-      Fixing valid.json
       Fixing invalid.json
       Fixing main.py
+      Fixing valid.json
     check json...............................................................Passed
 
     ----- stderr -----
@@ -103,8 +105,8 @@ fn invalid_config() {
             hooks:
               - id: trailing-whitespace
                 name: trailing-whitespace
-                language: dotnet
-                additional_dependencies: ["dotnet@6"]
+                language: script
+                additional_dependencies: ["shellcheck"]
                 entry: echo Hello, world!
     "#});
     context.git_add(".");
@@ -115,8 +117,38 @@ fn invalid_config() {
     ----- stdout -----
 
     ----- stderr -----
-    error: Hook `trailing-whitespace` is invalid
-      caused by: Hook specified `additional_dependencies` `dotnet@6` but the language `dotnet` does not support installing dependencies for now
+    error: .pre-commit-config.yaml:4:9: Hook `trailing-whitespace` is invalid
+      caused by: Hook specified `additional_dependencies` `shellcheck` but the language `script` does not support installing dependencies for now, only `golang`, `node`, `python` hooks do
+    "#);
+
+    // Languages not implemented yet are skipped, their options are not an error.
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: dotnet
+                additional_dependencies: ["dotnet@6"]
+                entry: echo Hello, world!
+              - id: rubocop
+                name: rubocop
+                language: ruby
+                language_version: '3.2'
+                entry: rubocop
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trailing-whitespace..................................(unimplemented yet)Skipped
+    rubocop..............................................(unimplemented yet)Skipped
+
+    ----- stderr -----
+    warning: prek does not yet implement `dotnet`, hook `trailing-whitespace` specified `additional_dependencies` `dotnet@6` but will be skipped, or run as a `system` hook ignoring it with `--allow-system-fallback`
+    warning: prek does not yet implement `ruby`, hook `rubocop` specified `language_version` `3.2` but will be skipped, or run as a `system` hook ignoring it with `--allow-system-fallback`
     "#);
 
     context.write_pre_commit_config(indoc::indoc! {r"
@@ -137,8 +169,8 @@ fn invalid_config() {
     ----- stdout -----
 
     ----- stderr -----
-    error: Hook `trailing-whitespace` is invalid
-      caused by: Hook specified `language_version` `6` but the language `fail` does not install an environment
+    error: .pre-commit-config.yaml:4:9: Hook `trailing-whitespace` is invalid
+      caused by: Hook specified `language_version` `6` but the language `fail` does not install an environment, only `golang`, `node`, `python` hooks accept a `language_version`
     "#);
 }
 
@@ -189,6 +221,87 @@ fn same_repo() -> Result<()> {
     Ok(())
 }
 
+/// Different revisions of the same repo share objects with the first clone, as long as they
+/// exist.
+#[test]
+fn same_repo_shares_objects() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    let hook_repo = assert_fs::TempDir::new()?;
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(&hook_repo)
+            .env("GIT_AUTHOR_NAME", "Prek Test")
+            .env("GIT_AUTHOR_EMAIL", "test@prek.dev")
+            .env("GIT_COMMITTER_NAME", "Prek Test")
+            .env("GIT_COMMITTER_EMAIL", "test@prek.dev")
+            .assert()
+            .success();
+    };
+    git(&["init", "--initial-branch=master"]);
+    hook_repo
+        .child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+            - id: hello
+              name: hello
+              language: system
+              entry: echo hello
+        "})?;
+    git(&["add", "."]);
+    git(&["commit", "-m", "v1"]);
+    git(&["tag", "v1"]);
+    hook_repo.child("README.md").write_str("Hello, world!\n")?;
+    git(&["add", "."]);
+    git(&["commit", "-m", "v2"]);
+    git(&["tag", "v2"]);
+
+    let url = url::Url::from_directory_path(hook_repo.path()).expect("valid path");
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: {url}
+            rev: v1
+            hooks:
+              - id: hello
+          - repo: {url}
+            rev: v2
+            hooks:
+              - id: hello
+    "});
+    context.work_dir().child("file.txt").write_str("Hello")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    hello....................................................................Passed
+    hello....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    let alternates = || -> Result<Vec<_>> {
+        Ok(fs_err::read_dir(context.home_dir().join("repos"))?
+            .flatten()
+            .map(|entry| entry.path().join(".git/objects/info/alternates"))
+            .filter(|path| path.exists())
+            .collect())
+    };
+    let borrowing = alternates()?;
+    assert_eq!(borrowing.len(), 1);
+
+    // Objects borrowed from a path that is gone, as after moving the store, are cloned again.
+    let moved = context.home_dir().join("moved").join("objects");
+    fs_err::write(&borrowing[0], format!("{}\n", moved.display()))?;
+    context.run().assert().success();
+    assert!(alternates()?.is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn local() {
     let context = TestContext::new();
@@ -217,6 +330,52 @@ fn local() {
     "#);
 }
 
+/// Run in a freshly initialized repository without any commits.
+#[test]
+fn empty_repo() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: system
+                entry: echo Hello, world!
+    "});
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    local................................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--last-commit"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    local................................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--from-ref").arg("HEAD").arg("--to-ref").arg("HEAD"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    local................................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+}
+
 #[test]
 fn invalid_hook_id() {
     let context = TestContext::new();
@@ -244,6 +403,40 @@ fn invalid_hook_id() {
     "#);
 }
 
+/// Hook ids starting with `-` can be selected after `--`.
+#[test]
+fn dash_prefixed_hook_id() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: -weird-hook-id
+                name: weird
+                language: system
+                entry: echo
+                always_run: true
+              - id: other
+                name: other
+                language: system
+                entry: echo
+                always_run: true
+    "});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--").arg("-weird-hook-id"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    weird....................................................................Passed
+
+    ----- stderr -----
+    "#);
+}
+
 /// `.pre-commit-config.yaml` is not staged.
 #[test]
 fn config_not_staged() -> Result<()> {
@@ -384,7 +577,7 @@ fn skips() {
     trailing-whitespace......................................................Failed
     - hook id: trailing-whitespace
     - exit code: 1
-    fix end of files........................................................Skipped
+    fix end of files......................................(skipped via SKIP)Skipped
     check json...............................................................Failed
     - hook id: check-json
     - exit code: 1
@@ -396,14 +589,123 @@ fn skips() {
     success: false
     exit_code: 1
     ----- stdout -----
-    trailing-whitespace.....................................................Skipped
-    fix end of files........................................................Skipped
+    trailing-whitespace...................................(skipped via SKIP)Skipped
+    fix end of files......................................(skipped via SKIP)Skipped
+    check json...............................................................Failed
+    - hook id: check-json
+    - exit code: 1
+
+    ----- stderr -----
+    "#);
+}
+
+/// Skips hooks listed in the skip file, in addition to the `SKIP` environment variable.
+#[test]
+fn skip_file() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c "exit(1)"
+              - id: end-of-file-fixer
+                name: fix end of files
+                language: system
+                entry: python3 -c "exit(1)"
+              - id: check-json
+                name: check json
+                language: system
+                entry: python3 -c "exit(1)"
+    "#});
+    context.git_add(".");
+
+    let cwd = context.work_dir();
+    cwd.child(".prek-skip")
+        .write_str("# Skipped locally\ntrailing-whitespace\n\n")?;
+    cwd.child("ci-skip.txt").write_str("check-json\n")?;
+
+    // `.prek-skip` is read by default.
+    cmd_snapshot!(context.filters(), context.run().env("SKIP", "end-of-file-fixer"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trailing-whitespace...................................(skipped via SKIP)Skipped
+    fix end of files......................................(skipped via SKIP)Skipped
     check json...............................................................Failed
     - hook id: check-json
     - exit code: 1
 
     ----- stderr -----
     "#);
+
+    // `--skip-file` replaces it, relative to the current directory.
+    cwd.child("sub").create_dir_all()?;
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("sub")).arg("--skip-file").arg("../ci-skip.txt"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trailing-whitespace......................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+    fix end of files.........................................................Failed
+    - hook id: end-of-file-fixer
+    - exit code: 1
+    check json............................................(skipped via SKIP)Skipped
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Expand `${VAR}` environment variables in the hook `entry` and `args`, and leave other `$`
+/// as is.
+#[test]
+fn expand_env_vars() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo entry=${PREK_TEST_VAR} $PREK_TEST_VAR
+                args: ["args=${PREK_TEST_VAR}", "price=$$5", "literal=$${PREK_TEST_VAR}"]
+                pass_filenames: false
+                verbose: true
+              - id: fail
+                name: fail
+                language: fail
+                entry: "fail: ${PREK_TEST_VAR}"
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("PREK_TEST_VAR", "expanded"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      entry=expanded $PREK_TEST_VAR args=expanded price=$$5 literal=${PREK_TEST_VAR}
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      fail: '${PREK_TEST_VAR}'
+
+      .pre-commit-config.yaml
+
+    ----- stderr -----
+    "#);
 }
 
 /// Run hooks with matched `stage`.
@@ -466,6 +768,89 @@ fn stage() {
     "#);
 }
 
+/// `minimum_prek_version` of the config and of hooks is checked against the running prek, and
+/// can be a partial version.
+#[test]
+fn minimum_prek_version() {
+    let context = TestContext::new();
+    context.init_project();
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(
+            r"current version `\d+\.\d+\.\d+[^`]*`",
+            "current version `[VERSION]`",
+        )])
+        .collect();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        minimum_prek_version: '999'
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(filters.clone(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Required minimum prek version `999` is greater than current version `[VERSION]`, run `prek self update` to upgrade
+    "#);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        minimum_prek_version: 0.0.1
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                minimum_prek_version: 999.0.0
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(filters.clone(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: .pre-commit-config.yaml:5:9: Hook `echo` is invalid
+      caused by: Required minimum prek version `999.0.0` is greater than current version `[VERSION]`, run `prek self update` to upgrade
+    "#);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        minimum_prek_version: '0.0'
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                minimum_prek_version: 0.0.1
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(filters, context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+}
+
 /// Test global `files`, `exclude`, and hook level `files`, `exclude`.
 #[test]
 fn files_and_exclude() -> Result<()> {
@@ -563,17 +948,123 @@ fn files_and_exclude() -> Result<()> {
     Ok(())
 }
 
-/// Test selecting files by type, `types`, `types_or`, and `exclude_types`.
+/// `files` and `exclude` can be lists of patterns, matching any of them.
 #[test]
-fn file_types() -> Result<()> {
+fn files_and_exclude_lists() -> Result<()> {
     let context = TestContext::new();
 
     context.init_project();
 
     let cwd = context.work_dir();
-    cwd.child("file.txt").write_str("Hello, world!  ")?;
-    cwd.child("json.json").write_str("{}\n  ")?;
-    cwd.child("main.py").write_str(r#"print "abc"  "#)?;
+    cwd.child("file.txt").write_str("Hello, world!\n")?;
+    cwd.child("valid.json").write_str("{}\n")?;
+    cwd.child("invalid.json").write_str("{}")?;
+    cwd.child("main.py").write_str("print('abc')\n")?;
+    cwd.child("docs/index.md").write_str("# Docs\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        exclude: [^docs/]
+        repos:
+          - repo: local
+            hooks:
+              - id: list-files
+                name: list files
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1:]); exit(1)'
+                files:
+                  - \.json$
+                  - ^main\.py$
+                  - \.md$
+                exclude: [^invalid]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    list files...............................................................Failed
+    - hook id: list-files
+    - exit code: 1
+      ['main.py', 'valid.json']
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// `files_case_insensitive` matches `files` and `exclude` ignoring case, like an inline `(?i)`.
+#[test]
+fn files_case_insensitive() -> Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let cwd = context.work_dir();
+    cwd.child("README.MD").write_str("# Readme\n")?;
+    cwd.child("notes.md").write_str("# Notes\n")?;
+    cwd.child("Docs/Guide.Md").write_str("# Guide\n")?;
+    cwd.child("main.py").write_str("print('abc')\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: case-insensitive
+                name: case insensitive
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1:]); exit(1)'
+                files: \.md$
+                exclude: ^docs/
+                files_case_insensitive: true
+              - id: case-sensitive
+                name: case sensitive
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1:]); exit(1)'
+                files: \.md$
+              - id: inline-flag
+                name: inline flag
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1:]); exit(1)'
+                files: (?i)^readme
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    case insensitive.........................................................Failed
+    - hook id: case-insensitive
+    - exit code: 1
+      ['README.MD', 'notes.md']
+    case sensitive...........................................................Failed
+    - hook id: case-sensitive
+    - exit code: 1
+      ['notes.md']
+    inline flag..............................................................Failed
+    - hook id: inline-flag
+    - exit code: 1
+      ['README.MD']
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Test selecting files by type, `types`, `types_or`, and `exclude_types`.
+#[test]
+fn file_types() -> Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let cwd = context.work_dir();
+    cwd.child("file.txt").write_str("Hello, world!  ")?;
+    cwd.child("json.json").write_str("{}\n  ")?;
+    cwd.child("main.py").write_str(r#"print "abc"  "#)?;
 
     context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
@@ -620,11 +1111,11 @@ fn file_types() -> Result<()> {
     trailing-whitespace......................................................Failed
     - hook id: trailing-whitespace
     - exit code: 1
-      ['main.py', 'json.json']
+      ['json.json', 'main.py']
     trailing-whitespace......................................................Failed
     - hook id: trailing-whitespace
     - exit code: 1
-      ['file.txt', '.pre-commit-config.yaml', 'main.py']
+      ['.pre-commit-config.yaml', 'file.txt', 'main.py']
     trailing-whitespace..................................(no files to check)Skipped
 
     ----- stderr -----
@@ -682,51 +1173,73 @@ fn fail_fast() {
       Fixing files
 
     ----- stderr -----
+    warning: Hook `trailing-whitespace` is listed as hooks #3 and #4 of repo `local` with identical configuration, running it only once
     "#);
 }
 
-/// Run from a subdirectory. File arguments should be fixed to be relative to the root.
+/// A `fail_fast` hook skipped via `SKIP` doesn't stop the run, only its failure does.
 #[test]
-fn subdirectory() -> Result<()> {
+fn fail_fast_skipped() {
     let context = TestContext::new();
     context.init_project();
 
-    let cwd = context.work_dir();
-    let child = cwd.child("foo/bar/baz");
-    child.create_dir_all()?;
-    child.child("file.txt").write_str("Hello, world!\n")?;
-
-    context.write_pre_commit_config(indoc::indoc! {r"
+    context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
           - repo: local
             hooks:
-              - id: trailing-whitespace
-                name: trailing-whitespace
+              - id: fail-fast
+                name: fail-fast
                 language: system
-                entry: python3 -c 'import sys; print(sys.argv[1]); exit(1)'
+                entry: python3 -c 'print("fail fast"); exit(1)'
                 always_run: true
-    "});
-
+                fail_fast: true
+              - id: failing
+                name: failing
+                language: system
+                entry: python3 -c 'print("failing"); exit(1)'
+                always_run: true
+              - id: passing
+                name: passing
+                language: system
+                entry: python3 -c 'print("passing")'
+                always_run: true
+                verbose: true
+    "#});
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--files").arg("file.txt"), @r#"
+    cmd_snapshot!(context.filters(), context.run().env("SKIP", "fail-fast"), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
-    trailing-whitespace......................................................Failed
-    - hook id: trailing-whitespace
+    fail-fast.............................................(skipped via SKIP)Skipped
+    failing..................................................................Failed
+    - hook id: failing
     - exit code: 1
-      foo/bar/baz/file.txt
+      failing
+    passing..................................................................Passed
+    - hook id: passing
+    - duration: [TIME]
+      passing
 
     ----- stderr -----
     "#);
 
-    Ok(())
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail-fast................................................................Failed
+    - hook id: fail-fast
+    - exit code: 1
+      fail fast
+
+    ----- stderr -----
+    "#);
 }
 
-/// Test hook `log_file` option.
+/// `--quiet` only prints the failures, `--silent` prints nothing.
 #[test]
-fn log_file() {
+fn quiet_and_silent() {
     let context = TestContext::new();
     context.init_project();
 
@@ -734,253 +1247,242 @@ fn log_file() {
         repos:
           - repo: local
             hooks:
-              - id: trailing-whitespace
-                name: trailing-whitespace
+              - id: passing
+                name: passing
                 language: system
-                entry: python3 -c 'print("Fixing files"); exit(1)'
+                entry: python3 -c 'print("all good")'
+                pass_filenames: false
+                always_run: true
+                verbose: true
+              - id: skipped
+                name: skipped
+                language: system
+                entry: python3 -c 'exit(1)'
+                files: \.rs$
+              - id: failing
+                name: failing
+                language: system
+                entry: python3 -c 'print("something is wrong"); exit(1)'
+                pass_filenames: false
                 always_run: true
-                log_file: log.txt
     "#});
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run(), @r#"
+    cmd_snapshot!(context.filters(), context.run().arg("--quiet"), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
-    trailing-whitespace......................................................Failed
-    - hook id: trailing-whitespace
+    failing..................................................................Failed
+    - hook id: failing
     - exit code: 1
+      something is wrong
 
     ----- stderr -----
     "#);
 
-    let log = context.read("log.txt");
-    assert_eq!(log, "Fixing files");
+    cmd_snapshot!(context.filters(), context.run().arg("--silent"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    "#);
 }
 
-/// Pass pre-commit environment variables to the hook.
+/// `--quiet` prints hooks that could not be run, like failing ones.
 #[test]
-fn pass_env_vars() {
+fn quiet_errored() {
     let context = TestContext::new();
-
     context.init_project();
 
     context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
           - repo: local
             hooks:
-              - id: env-vars
-                name: Pass environment
+              - id: broken
+                name: broken
                 language: system
-                entry: python3 -c "import os, sys; print(os.getenv('PRE_COMMIT')); sys.exit(1)"
+                entry: echo "unterminated
+                pass_filenames: false
+                always_run: true
+              - id: healthy
+                name: healthy
+                language: system
+                entry: echo healthy
+                pass_filenames: false
                 always_run: true
+                verbose: true
     "#});
+    context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run(), @r###"
+    cmd_snapshot!(context.filters(), context.run().arg("--quiet"), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
-    Pass environment.........................................................Failed
-    - hook id: env-vars
-    - exit code: 1
-      1
+    broken...................................................................Errored
+    - hook id: broken
+      error: Failed to run hook `broken`
+        caused by: Hook `broken` is invalid
+        caused by: Failed to parse entry `echo "unterminated` as commands
 
     ----- stderr -----
-    "###);
+    "#);
 }
 
+/// `PRE_COMMIT_ALL_FILES` is set for hooks with `--all-files`.
 #[test]
-fn staged_files_only() -> Result<()> {
+fn all_files_env_var() {
     let context = TestContext::new();
     context.init_project();
+
     context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
           - repo: local
             hooks:
-              - id: trailing-whitespace
-                name: trailing-whitespace
+              - id: all-files
+                name: all-files
                 language: system
-                entry: python3 -c 'print(open("file.txt", "rt").read())'
+                entry: python3 -c 'import os; print(os.environ.get("PRE_COMMIT_ALL_FILES"))'
+                pass_filenames: false
+                always_run: true
                 verbose: true
-                types: [text]
-   "#});
-
-    context
-        .work_dir()
-        .child("file.txt")
-        .write_str("Hello, world!")?;
+    "#});
     context.git_add(".");
 
-    // Non-staged files should be stashed and restored.
-    context
-        .work_dir()
-        .child("file.txt")
-        .write_str("Hello world again!")?;
-
-    let filters: Vec<_> = context
-        .filters()
-        .into_iter()
-        .chain([(r"/\d+-\d+.patch", "/[TIME]-[PID].patch")])
-        .collect();
-
-    cmd_snapshot!(filters, context.run(), @r#"
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    trailing-whitespace......................................................Passed
-    - hook id: trailing-whitespace
+    all-files................................................................Passed
+    - hook id: all-files
     - duration: [TIME]
-      Hello, world!
+      1
 
     ----- stderr -----
-    Non-staged changes detected, saving to `[HOME]/patches/[TIME]-[PID].patch`
-
-    Restored working tree changes from `[HOME]/patches/[TIME]-[PID].patch`
     "#);
 
-    let content = context.read("file.txt");
-    assert_snapshot!(content, @"Hello world again!");
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    all-files................................................................Passed
+    - hook id: all-files
+    - duration: [TIME]
+      None
 
-    Ok(())
+    ----- stderr -----
+    "#);
 }
 
-#[cfg(unix)]
+/// Hooks deleting files, from the working tree or from the index, modify the files.
 #[test]
-fn restore_on_interrupt() -> Result<()> {
+fn hook_deletes_files() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
-    // The hook will sleep for 3 seconds.
+    context.configure_git_author();
+
     context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
           - repo: local
             hooks:
-              - id: trailing-whitespace
-                name: trailing-whitespace
+              - id: delete-file
+                name: delete-file
                 language: system
-                entry: python3 -c 'import time; open("out.txt", "wt").write(open("file.txt", "rt").read()); time.sleep(10)'
-                verbose: true
-                types: [text]
-   "#});
-
-    context
-        .work_dir()
-        .child("file.txt")
-        .write_str("Hello, world!")?;
+                entry: python3 -c 'import os; os.remove("a.txt")'
+                pass_filenames: false
+                always_run: true
+              - id: git-rm
+                name: git-rm
+                language: system
+                entry: git rm -q b.txt
+                pass_filenames: false
+                always_run: true
+    "#});
+    let cwd = context.work_dir();
+    cwd.child("a.txt").write_str("a")?;
+    cwd.child("b.txt").write_str("b")?;
     context.git_add(".");
+    context.git_commit("Initial commit");
 
-    // Non-staged files should be stashed and restored.
-    context
-        .work_dir()
-        .child("file.txt")
-        .write_str("Hello world again!")?;
-
-    let mut child = context.run().spawn()?;
-    let child_id = child.id();
-
-    // Send an interrupt signal to the process.
-    let handle = std::thread::spawn(move || {
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        #[allow(clippy::cast_possible_wrap)]
-        unsafe {
-            libc::kill(child_id as i32, libc::SIGINT)
-        };
-    });
-
-    handle.join().unwrap();
-    child.wait()?;
-
-    let content = context.read("out.txt");
-    assert_snapshot!(content, @"Hello, world!");
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    delete-file..............................................................Failed
+    - hook id: delete-file
+    - files were modified by this hook
+    git-rm...................................................................Failed
+    - hook id: git-rm
+    - files were modified by this hook
 
-    let content = context.read("file.txt");
-    assert_snapshot!(content, @"Hello world again!");
+    ----- stderr -----
+    "#);
 
     Ok(())
 }
 
-/// When in merge conflict, runs on files that have conflicts fixed.
+/// `--fail-fast-first` runs the `fail_fast` hooks before the others.
 #[test]
-fn merge_conflicts() -> Result<()> {
+fn fail_fast_first() {
     let context = TestContext::new();
     context.init_project();
 
-    // Create a merge conflict.
-    let cwd = context.work_dir();
-    cwd.child("file.txt").write_str("Hello, world!")?;
-    context.git_add(".");
-    context.configure_git_author();
-    context.git_commit("Initial commit");
-
-    Command::new("git")
-        .arg("checkout")
-        .arg("-b")
-        .arg("feature")
-        .current_dir(cwd)
-        .assert()
-        .success();
-    cwd.child("file.txt").write_str("Hello, world again!")?;
-    context.git_add(".");
-    context.git_commit("Feature commit");
-
-    Command::new("git")
-        .arg("checkout")
-        .arg("master")
-        .current_dir(cwd)
-        .assert()
-        .success();
-    cwd.child("file.txt")
-        .write_str("Hello, world from master!")?;
-    context.git_add(".");
-    context.git_commit("Master commit");
-
-    Command::new("git")
-        .arg("merge")
-        .arg("feature")
-        .current_dir(cwd)
-        .assert()
-        .code(1);
-
-    context.write_pre_commit_config(indoc::indoc! {r"
+    context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
           - repo: local
             hooks:
-              - id: trailing-whitespace
-                name: trailing-whitespace
+              - id: slow-check
+                name: slow-check
                 language: system
-                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
-                verbose: true
-    "});
+                entry: python3 -c 'print("slow check failed"); exit(1)'
+                always_run: true
+              - id: quick-check
+                name: quick-check
+                language: system
+                entry: python3 -c 'print("quick check failed"); exit(1)'
+                always_run: true
+                fail_fast: true
+              - id: other-quick-check
+                name: other-quick-check
+                language: system
+                entry: python3 -c 'print("other quick check passed")'
+                always_run: true
+                fail_fast: true
+    "#});
+    context.git_add(".");
 
-    // Abort on merge conflicts.
     cmd_snapshot!(context.filters(), context.run(), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
+    slow-check...............................................................Failed
+    - hook id: slow-check
+    - exit code: 1
+      slow check failed
+    quick-check..............................................................Failed
+    - hook id: quick-check
+    - exit code: 1
+      quick check failed
 
     ----- stderr -----
-    You have unmerged paths. Resolve them before running prek.
     "#);
 
-    // Fix the conflict and run again.
-    context.git_add(".");
-    cmd_snapshot!(context.filters(), context.run(), @r#"
-    success: true
-    exit_code: 0
+    cmd_snapshot!(context.filters(), context.run().arg("--fail-fast-first"), @r#"
+    success: false
+    exit_code: 1
     ----- stdout -----
-    trailing-whitespace......................................................Passed
-    - hook id: trailing-whitespace
-    - duration: [TIME]
-      ['.pre-commit-config.yaml', 'file.txt']
+    quick-check..............................................................Failed
+    - hook id: quick-check
+    - exit code: 1
+      quick check failed
 
     ----- stderr -----
     "#);
-
-    Ok(())
 }
 
-/// Local python hook with no additional dependencies.
+/// Hooks with a higher `priority` run first, and `fail_fast` follows the new order.
 #[test]
-fn local_python_hook() {
+fn priority() {
     let context = TestContext::new();
     context.init_project();
 
@@ -988,264 +1490,421 @@ fn local_python_hook() {
         repos:
           - repo: local
             hooks:
-              - id: local-python-hook
-                name: local-python-hook
-                language: python
-                entry: python3 -c 'import sys; print("Hello, world!"); sys.exit(1)'
+              - id: slow-check
+                name: slow-check
+                language: system
+                entry: python3 -c 'print("slow check passed")'
+                always_run: true
+                verbose: true
+              - id: syntax-check
+                name: syntax-check
+                language: system
+                entry: python3 -c 'print("syntax check failed"); exit(1)'
+                always_run: true
+                fail_fast: true
+                priority: 10
     "#});
-
     context.git_add(".");
 
     cmd_snapshot!(context.filters(), context.run(), @r#"
     success: false
     exit_code: 1
     ----- stdout -----
-    local-python-hook........................................................Failed
-    - hook id: local-python-hook
+    syntax-check.............................................................Failed
+    - hook id: syntax-check
     - exit code: 1
-      Hello, world!
+      syntax check failed
 
     ----- stderr -----
     "#);
 }
 
-/// Supports reading `pre-commit-config.yml` as well.
+/// Run from a subdirectory. File arguments should be fixed to be relative to the root.
 #[test]
-fn alternate_config_file() {
+fn subdirectory() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
 
-    context.write_pre_commit_config(indoc::indoc! {r#"
+    let cwd = context.work_dir();
+    let child = cwd.child("foo/bar/baz");
+    child.create_dir_all()?;
+    child.child("file.txt").write_str("Hello, world!\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
         repos:
           - repo: local
             hooks:
-              - id: local-python-hook
-                name: local-python-hook
-                language: python
-                entry: python3 -c 'import sys; print("Hello, world!")'
-    "#});
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1]); exit(1)'
+                always_run: true
+    "});
 
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run().arg("-v"), @r#"
-    success: true
-    exit_code: 0
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--files").arg("file.txt"), @r#"
+    success: false
+    exit_code: 1
     ----- stdout -----
-    local-python-hook........................................................Passed
-    - hook id: local-python-hook
-    - duration: [TIME]
-      Hello, world!
+    trailing-whitespace......................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      foo/bar/baz/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // Absolute paths are made relative to the repository root too.
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--files").arg(child.child("file.txt").path()), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trailing-whitespace......................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+      foo/bar/baz/file.txt
 
     ----- stderr -----
     "#);
+
+    Ok(())
 }
 
-/// Invalid `entry`
+/// `--paths` rewrites the filenames passed to hooks.
 #[test]
-fn invalid_entry() {
+fn hook_paths() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
 
-    context.write_pre_commit_config(indoc::indoc! {r#"
+    let cwd = context.work_dir();
+    let child = cwd.child("foo/bar");
+    child.create_dir_all()?;
+    child.child("file.txt").write_str("Hello, world!\n")?;
+    cwd.child("top.txt").write_str("Hello, world!\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
         repos:
           - repo: local
             hooks:
-              - id: entry
-                name: entry
-                language: python
-                entry: '"'
-    "#});
+              - id: paths
+                name: paths
+                language: system
+                entry: python3 -c 'import os, sys; print(sorted((f, os.path.isfile(f)) for f in sys.argv[1:]))'
+                verbose: true
+    "});
 
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run(), @r#"
-    success: false
-    exit_code: 2
+    // Relative to the repository root by default.
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
     ----- stdout -----
-    entry....................................................................
+    paths....................................................................Passed
+    - hook id: paths
+    - duration: [TIME]
+      [('.pre-commit-config.yaml', True), ('foo/bar/file.txt', True), ('top.txt', True)]
+
     ----- stderr -----
-    error: Failed to run hook `entry`
-      caused by: Hook `entry` is invalid
-      caused by: Failed to parse entry `"` as commands
     "#);
-}
 
-/// Initialize a repo that does not exist.
-#[test]
-fn init_nonexistent_repo() {
-    let context = TestContext::new();
-    context.init_project();
-    context.write_pre_commit_config(indoc::indoc! {r"
-        repos:
-          - repo: https://notexistentatallnevergonnahappen.com/nonexistent/repo
-            rev: v1.0.0
-            hooks:
-              - id: nonexistent
-                name: nonexistent
-        "});
-    context.git_add(".");
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--all-files").arg("--paths").arg("cwd"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    paths....................................................................Passed
+    - hook id: paths
+    - duration: [TIME]
+      [('../../.pre-commit-config.yaml', True), ('../../top.txt', True), ('file.txt', True)]
 
-    let filters = context
-        .filters()
-        .into_iter()
-        .chain([(r"exit code: ", "exit status: "),
-            // Normalize Git error message to handle environment-specific variations
-            (
-                r"fatal: unable to access 'https://notexistentatallnevergonnahappen\.com/nonexistent/repo/':.*",
-                r"fatal: unable to access 'https://notexistentatallnevergonnahappen.com/nonexistent/repo/': [error]"
-            ),
-        ])
-        .collect::<Vec<_>>();
+    ----- stderr -----
+    "#);
 
-    cmd_snapshot!(filters, context.run(), @r"
-    success: false
-    exit_code: 2
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--all-files").arg("--paths").arg("absolute"), @r#"
+    success: true
+    exit_code: 0
     ----- stdout -----
+    paths....................................................................Passed
+    - hook id: paths
+    - duration: [TIME]
+      [('[TEMP_DIR]/.pre-commit-config.yaml', True), ('[TEMP_DIR]/foo/bar/file.txt', True), ('[TEMP_DIR]/top.txt', True)]
 
     ----- stderr -----
-    error: Failed to initialize repo `https://notexistentatallnevergonnahappen.com/nonexistent/repo`
-      caused by: command `git full clone` exited with an error:
-
-    [status]
-    exit status: 128
+    "#);
 
-    [stderr]
-    fatal: unable to access 'https://notexistentatallnevergonnahappen.com/nonexistent/repo/': [error]
-    ");
+    Ok(())
 }
 
-/// Test hooks that specifies `types: [directory]`.
+/// A hook with `working_dir` runs from there, with the filenames relative to it.
 #[test]
-fn types_directory() -> Result<()> {
+fn hook_working_dir() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
+
+    let cwd = context.work_dir();
+    let app = cwd.child("packages/app");
+    app.child("src").create_dir_all()?;
+    app.child("src/main.py").write_str("print('Hello')\n")?;
+    app.child("README.md").write_str("# App\n")?;
+    cwd.child("top.txt").write_str("Hello, world!\n")?;
+
     context.write_pre_commit_config(indoc::indoc! {r"
         repos:
           - repo: local
             hooks:
-              - id: directory
-                name: directory
+              - id: app
+                name: app
                 language: system
-                entry: echo
-                types: [directory]
-        "});
-    context.work_dir().child("dir").create_dir_all()?;
-    context
-        .work_dir()
-        .child("dir/file.txt")
-        .write_str("Hello, world!")?;
+                entry: python3 -c 'import os, sys; print(os.path.basename(os.getcwd()), sorted((f, os.path.isfile(f)) for f in sys.argv[1:]))'
+                files: ^packages/app/
+                working_dir: packages/app
+                verbose: true
+              - id: root
+                name: root
+                language: system
+                entry: python3 -c 'import os, sys; print(sorted((f, os.path.isfile(f)) for f in sys.argv[1:]))'
+                files: ^top
+                verbose: true
+    "});
+
     context.git_add(".");
 
-    cmd_snapshot!(context.filters(), context.run(), @r#"
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory............................................(no files to check)Skipped
+    app......................................................................Passed
+    - hook id: app
+    - duration: [TIME]
+      app [('README.md', True), ('src/main.py', True)]
+    root.....................................................................Passed
+    - hook id: root
+    - duration: [TIME]
+      [('top.txt', True)]
 
     ----- stderr -----
     "#);
 
-    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("dir"), @r#"
+    // The working directory doesn't depend on where prek is run from.
+    cmd_snapshot!(context.filters(), context.run().current_dir(&app).arg("--all-files").arg("--paths").arg("cwd"), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory................................................................Passed
+    app......................................................................Passed
+    - hook id: app
+    - duration: [TIME]
+      app [('README.md', True), ('src/main.py', True)]
+    root.....................................................................Passed
+    - hook id: root
+    - duration: [TIME]
+      [('../../top.txt', True)]
 
     ----- stderr -----
     "#);
 
-    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
-    success: true
-    exit_code: 0
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: app
+                name: app
+                language: system
+                entry: echo
+                working_dir: ../app
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
     ----- stdout -----
-    directory............................................(no files to check)Skipped
 
     ----- stderr -----
+    error: .pre-commit-config.yaml:4:9: Hook `app` is invalid
+      caused by: Hook specified `working_dir` `../app` outside of the repository, it must be a relative path inside it
     "#);
 
-    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("non-exist-files"), @r#"
-    success: true
-    exit_code: 0
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+                working_dir: packages/app
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
     ----- stdout -----
-    directory............................................(no files to check)Skipped
 
     ----- stderr -----
-    warning: This file does not exist, it will be ignored: `non-exist-files`
+    error: .pre-commit-config.yaml:4:9: Hook `identity` is invalid
+      caused by: Hook specified `working_dir` but meta hooks do not support it, they always run from the repository root
     "#);
+
     Ok(())
 }
 
+/// Hooks can read the staged files from `PREK_STAGED_FILES`, whatever their own filters and the
+/// files of the run.
 #[test]
-fn run_last_commit() -> Result<()> {
+fn staged_files_list() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
-    context.configure_git_author();
 
     let cwd = context.work_dir();
-    context.write_pre_commit_config(indoc::indoc! {r"
+    cwd.child("a.txt").write_str("a")?;
+    cwd.child("b.py").write_str("b")?;
+    cwd.child("sub/c.md").write_str("c")?;
+    cwd.child("unstaged.txt").write_str("unstaged")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
         repos:
-          - repo: https://github.com/pre-commit/pre-commit-hooks
-            rev: v5.0.0
+          - repo: local
             hooks:
-              - id: trailing-whitespace
-              - id: end-of-file-fixer
-    "});
+              - id: staged-files
+                name: staged-files
+                language: system
+                entry: python3 -c 'import os, sys; print(sorted(sys.argv[1:]), sorted(open(os.environ["PREK_STAGED_FILES"]).read().split("\0")[:-1]))'
+                files: \.py$
+                verbose: true
+    "#});
+    context.git_add(".pre-commit-config.yaml");
+    context.git_add("a.txt");
+    context.git_add("b.py");
+    context.git_add("sub/c.md");
 
-    // Create initial files and make first commit
-    cwd.child("file1.txt").write_str("Hello, world!\n")?;
-    cwd.child("file2.txt")
-        .write_str("Initial content with trailing spaces   \n")?; // This has issues but won't be in last commit
-    context.git_add(".");
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    staged-files.............................................................Passed
+    - hook id: staged-files
+    - duration: [TIME]
+      ['b.py'] ['.pre-commit-config.yaml', 'a.txt', 'b.py', 'sub/c.md']
+
+    ----- stderr -----
+    "#);
+
+    // Only the staged files are listed, not all the files of the run.
+    context.configure_git_author();
     context.git_commit("Initial commit");
+    cwd.child("d.py").write_str("d")?;
+    context.git_add("d.py");
 
-    // Modify files and make second commit with trailing whitespace
-    cwd.child("file1.txt").write_str("Hello, world!   \n")?; // trailing whitespace
-    cwd.child("file3.txt").write_str("New file")?; // missing newline
-    // Note: file2.txt is NOT modified in this commit, so it should be filtered out by --last-commit
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    staged-files.............................................................Passed
+    - hook id: staged-files
+    - duration: [TIME]
+      ['b.py', 'd.py'] ['d.py']
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// The output of a hook run in batches is in a stable order, however the batches finish.
+#[test]
+fn batch_output_order() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    for i in 0..16 {
+        context
+            .work_dir()
+            .child(format!("file{i:02}.txt"))
+            .write_str("")?;
+    }
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: batches
+                name: batches
+                language: system
+                entry: python3 -c "import random, sys, time; time.sleep(random.random() / 5); print(*sys.argv[1:])"
+                verbose: true
+    "#});
     context.git_add(".");
-    context.git_commit("Second commit with issues");
 
-    // Run with --last-commit should only check files from the last commit
-    // This should only process file1.txt and file3.txt, NOT file2.txt
-    cmd_snapshot!(context.filters(), context.run().arg("--last-commit"), @r#"
-    success: false
-    exit_code: 1
+    let run = || {
+        let mut command = context.run();
+        command
+            .arg("--all-files")
+            .env(EnvVars::PREK_CONCURRENCY, "4");
+        command
+    };
+
+    cmd_snapshot!(context.filters(), run(), @r#"
+    success: true
+    exit_code: 0
     ----- stdout -----
-    trim trailing whitespace.................................................Failed
-    - hook id: trailing-whitespace
-    - exit code: 1
-    - files were modified by this hook
-      Fixing file1.txt
-    fix end of files.........................................................Failed
-    - hook id: end-of-file-fixer
-    - exit code: 1
-    - files were modified by this hook
-      Fixing file3.txt
+    batches..................................................................Passed
+    - hook id: batches
+    - duration: [TIME]
+      file00.txt file08.txt file15.txt file01.txt file11.txt
+      file09.txt file05.txt file02.txt file04.txt file13.txt
+      file07.txt .pre-commit-config.yaml file06.txt file12.txt file10.txt
+      file14.txt file03.txt
 
     ----- stderr -----
     "#);
 
-    // Now reset the files to their problematic state for comparison
-    cwd.child("file1.txt").write_str("Hello, world!   \n")?; // trailing whitespace
-    cwd.child("file3.txt").write_str("New file")?; // missing newline
+    let output = || -> Result<Vec<String>> {
+        let stdout = String::from_utf8(run().output()?.stdout)?;
+        Ok(stdout
+            .lines()
+            .filter(|line| !line.contains("- duration:"))
+            .map(ToString::to_string)
+            .collect())
+    };
+    let first = output()?;
+    for _ in 0..3 {
+        assert_eq!(output()?, first);
+    }
+
+    Ok(())
+}
+
+/// Hooks run in a single invocation, like `require_serial` hooks, get the files in sorted order.
+#[test]
+fn require_serial_sorted_files() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    for name in ["d.txt", "b.txt", "e.txt", "a.txt", "c/a.txt", "c.txt"] {
+        context.work_dir().child(name).write_str("")?;
+    }
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: argv
+                name: argv
+                language: system
+                entry: python3 -c "import sys; print(*sys.argv[1:])"
+                files: \.txt$
+                require_serial: true
+                verbose: true
+    "#});
+    context.git_add(".");
 
-    // Run with --all-files should check ALL files including file2.txt
-    // This demonstrates that file2.txt was indeed filtered out in the previous test
     cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
-    success: false
-    exit_code: 1
+    success: true
+    exit_code: 0
     ----- stdout -----
-    trim trailing whitespace.................................................Failed
-    - hook id: trailing-whitespace
-    - exit code: 1
-    - files were modified by this hook
-      Fixing file1.txt
-      Fixing file2.txt
-    fix end of files.........................................................Failed
-    - hook id: end-of-file-fixer
-    - exit code: 1
-    - files were modified by this hook
-      Fixing file3.txt
+    argv.....................................................................Passed
+    - hook id: argv
+    - duration: [TIME]
+      a.txt b.txt c.txt c/a.txt d.txt e.txt
 
     ----- stderr -----
     "#);
@@ -1253,114 +1912,2488 @@ fn run_last_commit() -> Result<()> {
     Ok(())
 }
 
-/// Test `prek run --directory` flags.
+/// Files split across several invocations of a hook are still shuffled, to balance the batches.
 #[test]
-fn run_directory() -> Result<()> {
+fn partitioned_files_shuffled() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let files = (0..10).map(|i| format!("file{i}.txt")).collect::<Vec<_>>();
+    for file in &files {
+        context.work_dir().child(file).write_str("")?;
+    }
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: argv
+                name: argv
+                language: system
+                entry: python3 -c "import sys; print(*sys.argv[1:])"
+                files: \.txt$
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    let output = context
+        .run()
+        .arg("--all-files")
+        .env(EnvVars::PREK_CONCURRENCY, "2")
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let batches = stdout
+        .lines()
+        .filter(|line| line.starts_with("  "))
+        .collect::<Vec<_>>();
+    assert_eq!(batches.len(), 2, "{stdout}");
+
+    let passed = batches
+        .iter()
+        .flat_map(|line| line.split_whitespace())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_ne!(passed, files, "{stdout}");
+
+    let mut passed = passed;
+    passed.sort();
+    assert_eq!(passed, files);
+
+    Ok(())
+}
+
+/// `--include-untracked` skips files ignored by `.gitignore`, nested `.gitignore` and `.git/info/exclude`.
+#[test]
+fn include_untracked() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
+
     context.write_pre_commit_config(indoc::indoc! {r"
         repos:
           - repo: local
             hooks:
-              - id: directory
-                name: directory
+              - id: echo
+                name: echo
                 language: system
                 entry: echo
                 verbose: true
     "});
 
     let cwd = context.work_dir();
-    cwd.child("dir1").create_dir_all()?;
-    cwd.child("dir1/file.txt").write_str("Hello, world!")?;
-    cwd.child("dir2").create_dir_all()?;
-    cwd.child("dir2/file.txt").write_str("Hello, world!")?;
+    cwd.child("tracked.txt").write_str("Hello")?;
     context.git_add(".");
 
-    // one `--directory`
-    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1"), @r#"
+    cwd.child(".gitignore").write_str("*.log\n")?;
+    cwd.child("untracked.txt").write_str("Hello")?;
+    cwd.child("debug.log").write_str("Hello")?;
+    cwd.child("secret.txt").write_str("Hello")?;
+    cwd.child(".git/info/exclude").write_str("secret.txt\n")?;
+    cwd.child("sub/.gitignore").write_str("build/\n")?;
+    cwd.child("sub/main.txt").write_str("Hello")?;
+    cwd.child("sub/build/out.txt").write_str("Hello")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory................................................................Passed
-    - hook id: directory
+    echo.....................................................................Passed
+    - hook id: echo
     - duration: [TIME]
-      dir1/file.txt
+      .pre-commit-config.yaml tracked.txt
 
     ----- stderr -----
     "#);
 
-    // repeated `--directory`
-    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--directory").arg("dir1"), @r#"
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").arg("--include-untracked").env(EnvVars::PREK_CONCURRENCY, "1"), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory................................................................Passed
-    - hook id: directory
+    echo.....................................................................Passed
+    - hook id: echo
     - duration: [TIME]
-      dir1/file.txt
+      .gitignore .pre-commit-config.yaml sub/.gitignore sub/main.txt tracked.txt untracked.txt
 
     ----- stderr -----
     "#);
 
-    // multiple `--directory`
-    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--directory").arg("dir2"), @r#"
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("sub").arg("--include-untracked"), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory................................................................Passed
-    - hook id: directory
+    echo.....................................................................Passed
+    - hook id: echo
     - duration: [TIME]
-      dir2/file.txt dir1/file.txt
+      sub/.gitignore sub/main.txt
 
     ----- stderr -----
     "#);
 
-    // non-existing directory
-    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("non-existing-dir"), @r#"
-    success: true
-    exit_code: 0
-    ----- stdout -----
-    directory............................................(no files to check)Skipped
+    Ok(())
+}
 
-    ----- stderr -----
-    "#);
+/// Interleaved stdout and stderr of a hook keep their original order.
+#[test]
+fn interleaved_output() {
+    let context = TestContext::new();
+    context.init_project();
 
-    // `--directory` with `--files`
-    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--files").arg("dir1/file.txt"), @r#"
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: interleaved
+                name: interleaved
+                language: system
+                entry: python3 -c "import sys; print('out1', flush=True); print('err1', file=sys.stderr, flush=True); print('out2', flush=True); print('err2', file=sys.stderr, flush=True)"
+                pass_filenames: false
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory................................................................Passed
-    - hook id: directory
+    interleaved..............................................................Passed
+    - hook id: interleaved
     - duration: [TIME]
-      dir1/file.txt
+      out1
+      err1
+      out2
+      err2
 
     ----- stderr -----
     "#);
-    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--files").arg("dir2/file.txt"), @r#"
+}
+
+/// Multiline verbose regex patterns with comments, like `pre-commit` supports.
+#[test]
+fn verbose_regex() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        exclude: |
+          (?x)^(
+              third_party/.*|
+              generated/.*  # codegen
+          )$
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                files: |
+                  (?x)
+                  \.txt$  # text files only
+                verbose: true
+    "});
+
+    let cwd = context.work_dir();
+    cwd.child("main.txt").write_str("Hello")?;
+    cwd.child("main.py").write_str("Hello")?;
+    cwd.child("third_party/lib.txt").write_str("Hello")?;
+    cwd.child("generated/api.txt").write_str("Hello")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
-    directory................................................................Passed
-    - hook id: directory
+    echo.....................................................................Passed
+    - hook id: echo
     - duration: [TIME]
-      dir2/file.txt dir1/file.txt
+      main.txt
 
     ----- stderr -----
     "#);
 
-    // run `--directory` inside a subdirectory
-    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("dir1")).arg("--directory").arg("."), @r#"
-    success: true
-    exit_code: 0
+    // Invalid patterns are reported with the pattern and where it comes from.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                exclude: |
+                  (?x)^(
+                      generated/.*  # codegen
+                  $
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
     ----- stdout -----
-    directory................................................................Passed
-    - hook id: directory
-    - duration: [TIME]
-      dir1/file.txt
 
     ----- stderr -----
+    error: Invalid `exclude` regex of hook `echo` in `.pre-commit-config.yaml`:
+        (?x)^(
+            generated/.*  # codegen
+        $
+      caused by: Parsing error at position 37: Opening parenthesis without closing parenthesis
     "#);
 
     Ok(())
 }
+
+/// Test hook `log_file` option.
+#[test]
+fn log_file() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'print("Fixing files"); exit(1)'
+                always_run: true
+                log_file: log.txt
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trailing-whitespace......................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+
+    ----- stderr -----
+    "#);
+
+    let log = context.read("log.txt");
+    assert_eq!(log, "Fixing files");
+}
+
+/// Pass pre-commit environment variables to the hook.
+#[test]
+fn pass_env_vars() {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: env-vars
+                name: Pass environment
+                language: system
+                entry: python3 -c "import os, sys; print(os.getenv('PRE_COMMIT')); sys.exit(1)"
+                always_run: true
+    "#});
+
+    cmd_snapshot!(context.filters(), context.run(), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Pass environment.........................................................Failed
+    - hook id: env-vars
+    - exit code: 1
+      1
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn staged_files_only() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'print(open("file.txt", "rt").read())'
+                verbose: true
+                types: [text]
+   "#});
+
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("Hello, world!")?;
+    context.git_add(".");
+
+    // Non-staged files should be stashed and restored.
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("Hello world again!")?;
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"/\d+-\d+.patch", "/[TIME]-[PID].patch")])
+        .collect();
+
+    cmd_snapshot!(filters, context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trailing-whitespace......................................................Passed
+    - hook id: trailing-whitespace
+    - duration: [TIME]
+      Hello, world!
+
+    ----- stderr -----
+    Non-staged changes detected, saving to `[HOME]/patches/[TIME]-[PID].patch`
+
+    Restored working tree changes from `[HOME]/patches/[TIME]-[PID].patch`
+    "#);
+
+    let content = context.read("file.txt");
+    assert_snapshot!(content, @"Hello world again!");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn restore_on_interrupt() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    // The hook will sleep for 3 seconds.
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'import time; open("out.txt", "wt").write(open("file.txt", "rt").read()); time.sleep(10)'
+                verbose: true
+                types: [text]
+   "#});
+
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("Hello, world!")?;
+    context.git_add(".");
+
+    // Non-staged files should be stashed and restored.
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("Hello world again!")?;
+
+    let mut child = context.run().spawn()?;
+    let child_id = child.id();
+
+    // Send an interrupt signal to the process.
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        #[allow(clippy::cast_possible_wrap)]
+        unsafe {
+            libc::kill(child_id as i32, libc::SIGINT)
+        };
+    });
+
+    handle.join().unwrap();
+    child.wait()?;
+
+    let content = context.read("out.txt");
+    assert_snapshot!(content, @"Hello, world!");
+
+    let content = context.read("file.txt");
+    assert_snapshot!(content, @"Hello world again!");
+
+    Ok(())
+}
+
+/// Like `restore_on_interrupt`, for the SIGTERM sent by CI runners when a job is cancelled.
+#[cfg(unix)]
+#[test]
+fn restore_on_terminate() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'import time; open("out.txt", "wt").write(open("file.txt", "rt").read()); time.sleep(10)'
+                verbose: true
+                types: [text]
+   "#});
+
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("Hello, world!")?;
+    context.git_add(".");
+
+    // Non-staged files should be stashed and restored.
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("Hello world again!")?;
+
+    let mut child = context.run().spawn()?;
+    let child_id = child.id();
+
+    // Wait for the hook to start, then terminate the process.
+    let out = context.work_dir().child("out.txt");
+    let start = std::time::Instant::now();
+    while !out.exists() && start.elapsed() < std::time::Duration::from_secs(10) {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    #[allow(clippy::cast_possible_wrap)]
+    unsafe {
+        libc::kill(child_id as i32, libc::SIGTERM)
+    };
+
+    let status = child.wait()?;
+    assert!(!status.success());
+
+    let content = context.read("out.txt");
+    assert_snapshot!(content, @"Hello, world!");
+
+    let content = context.read("file.txt");
+    assert_snapshot!(content, @"Hello world again!");
+
+    Ok(())
+}
+
+/// Hooks run in their own process group, the interrupt is forwarded to them.
+#[cfg(unix)]
+#[test]
+fn forward_interrupt_to_hooks() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: wait
+                name: wait
+                language: system
+                entry: python3 -c 'import signal, sys, time; signal.signal(signal.SIGINT, lambda *_:(open("interrupted.txt", "w").write("interrupted"), sys.exit(1))); open("ready.txt", "w").close(); time.sleep(10)'
+                pass_filenames: false
+                always_run: true
+   "#});
+    context.git_add(".");
+
+    let mut child = context.run().spawn()?;
+    let child_id = child.id();
+
+    let wait_for = |name: &str| {
+        let file = context.work_dir().child(name);
+        let start = std::time::Instant::now();
+        while !file.exists() && start.elapsed() < std::time::Duration::from_secs(10) {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    };
+
+    // Only prek is signaled, not the process group of the hook.
+    wait_for("ready.txt");
+    #[allow(clippy::cast_possible_wrap)]
+    unsafe {
+        libc::kill(child_id as i32, libc::SIGINT)
+    };
+
+    let status = child.wait()?;
+    assert_eq!(status.code(), Some(130));
+
+    wait_for("interrupted.txt");
+    let content = context.read("interrupted.txt");
+    assert_snapshot!(content, @"interrupted");
+
+    Ok(())
+}
+
+/// When in merge conflict, runs on files that have conflicts fixed.
+#[test]
+fn merge_conflicts() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // Create a merge conflict.
+    let cwd = context.work_dir();
+    cwd.child("file.txt").write_str("Hello, world!")?;
+    context.git_add(".");
+    context.configure_git_author();
+    context.git_commit("Initial commit");
+
+    Command::new("git")
+        .arg("checkout")
+        .arg("-b")
+        .arg("feature")
+        .current_dir(cwd)
+        .assert()
+        .success();
+    cwd.child("file.txt").write_str("Hello, world again!")?;
+    context.git_add(".");
+    context.git_commit("Feature commit");
+
+    Command::new("git")
+        .arg("checkout")
+        .arg("master")
+        .current_dir(cwd)
+        .assert()
+        .success();
+    cwd.child("file.txt")
+        .write_str("Hello, world from master!")?;
+    context.git_add(".");
+    context.git_commit("Master commit");
+
+    Command::new("git")
+        .arg("merge")
+        .arg("feature")
+        .current_dir(cwd)
+        .assert()
+        .code(1);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                verbose: true
+    "});
+
+    // Abort on merge conflicts.
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    You have unmerged paths. Resolve them before running prek.
+    "#);
+
+    // Fix the conflict and run again.
+    context.git_add(".");
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trailing-whitespace......................................................Passed
+    - hook id: trailing-whitespace
+    - duration: [TIME]
+      ['.pre-commit-config.yaml', 'file.txt']
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Local python hook with no additional dependencies.
+#[test]
+fn local_python_hook() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local-python-hook
+                name: local-python-hook
+                language: python
+                entry: python3 -c 'import sys; print("Hello, world!"); sys.exit(1)'
+    "#});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    local-python-hook........................................................Failed
+    - hook id: local-python-hook
+    - exit code: 1
+      Hello, world!
+
+    ----- stderr -----
+    "#);
+}
+
+/// Supports reading `pre-commit-config.yml` as well.
+#[test]
+fn alternate_config_file() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local-python-hook
+                name: local-python-hook
+                language: python
+                entry: python3 -c 'import sys; print("Hello, world!")'
+    "#});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("-v"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    local-python-hook........................................................Passed
+    - hook id: local-python-hook
+    - duration: [TIME]
+      Hello, world!
+
+    ----- stderr -----
+    "#);
+}
+
+/// Supports reading `.pre-commit-config.toml`, after the YAML names.
+#[test]
+fn toml_config_file() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.work_dir();
+    cwd.child(".pre-commit-config.toml")
+        .write_str(indoc::indoc! {r#"
+        [[repos]]
+        repo = "local"
+
+        [[repos.hooks]]
+        id = "echo"
+        name = "echo"
+        language = "system"
+        entry = "echo toml"
+        pass_filenames = false
+        verbose = true
+    "#})?;
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      toml
+
+    ----- stderr -----
+    "#);
+
+    // The YAML config wins.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo yaml
+                pass_filenames: false
+                verbose: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      yaml
+
+    ----- stderr -----
+    warning: Both [TEMP_DIR]/.pre-commit-config.yaml and [TEMP_DIR]/.pre-commit-config.toml exist, using [TEMP_DIR]/.pre-commit-config.yaml
+    "#);
+
+    Ok(())
+}
+
+/// Invalid `entry`
+#[test]
+fn invalid_entry() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: entry
+                name: entry
+                language: python
+                entry: '"'
+    "#});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    entry....................................................................Errored
+    - hook id: entry
+      error: Failed to run hook `entry`
+        caused by: Hook `entry` is invalid
+        caused by: Failed to parse entry `"` as commands
+
+    ----- stderr -----
+    "#);
+}
+
+/// Empty `entry`
+#[test]
+fn empty_entry() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: entry
+                name: entry
+                language: system
+                entry: ' '
+    "});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: .pre-commit-config.yaml:4:9: Hook `entry` is invalid
+      caused by: Hook `entry` has an empty entry
+    "#);
+}
+
+/// Initialize a repo that does not exist.
+#[test]
+fn init_nonexistent_repo() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://notexistentatallnevergonnahappen.com/nonexistent/repo
+            rev: v1.0.0
+            hooks:
+              - id: nonexistent
+                name: nonexistent
+        "});
+    context.git_add(".");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r"exit code: ", "exit status: "),
+            // Normalize Git error message to handle environment-specific variations
+            (
+                r"fatal: unable to access 'https://notexistentatallnevergonnahappen\.com/nonexistent/repo/':.*",
+                r"fatal: unable to access 'https://notexistentatallnevergonnahappen.com/nonexistent/repo/': [error]"
+            ),
+        ])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters, context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: .pre-commit-config.yaml:2:5: Failed to initialize repo `https://notexistentatallnevergonnahappen.com/nonexistent/repo`
+      caused by: command `git full clone` exited with an error:
+
+    [status]
+    exit status: 128
+
+    [stderr]
+    fatal: unable to access 'https://notexistentatallnevergonnahappen.com/nonexistent/repo/': [error]
+    ");
+}
+
+/// `PREK_CLONE_TIMEOUT` stops a clone that takes too long.
+#[cfg(unix)]
+#[test]
+fn clone_timeout() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.home_dir().child("hooks-repo");
+    repo.child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+            - id: echo
+              name: echo
+              entry: echo
+              language: system
+        "})?;
+    context.init_repo(&repo);
+
+    // A `git` that hangs when fetching.
+    let output = Command::new("git").arg("--exec-path").output()?;
+    let exec_path = String::from_utf8(output.stdout)?;
+    let bin = context.home_dir().child("bin");
+    bin.create_dir_all()?;
+    bin.child("git").write_str(&indoc::formatdoc! {r#"
+        #!/bin/sh
+        for arg in "$@"; do
+            if [ "$arg" = fetch ]; then exec sleep 60; fi
+        done
+        exec "{}/git" "$@"
+    "#, exec_path.trim()})?;
+    fs_err::set_permissions(bin.child("git"), std::fs::Permissions::from_mode(0o755))?;
+    let path = std::env::join_paths(std::iter::once(bin.to_path_buf()).chain(
+        std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default()),
+    ))?;
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+    ", repo.display()});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("PATH", path).env("PREK_CLONE_TIMEOUT", "1"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: .pre-commit-config.yaml:2:5: Failed to initialize repo `file://[HOME]/hooks-repo`
+      caused by: Cloning timed out after 1s, set `PREK_CLONE_TIMEOUT` to wait longer
+    ");
+
+    Ok(())
+}
+
+/// Test hooks that specifies `types: [directory]`.
+#[test]
+fn types_directory() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: directory
+                name: directory
+                language: system
+                entry: echo
+                types: [directory]
+        "});
+    context.work_dir().child("dir").create_dir_all()?;
+    context
+        .work_dir()
+        .child("dir/file.txt")
+        .write_str("Hello, world!")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory............................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("dir"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory............................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("non-exist-files"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory............................................(no files to check)Skipped
+
+    ----- stderr -----
+    warning: This file does not exist, it will be ignored: `non-exist-files`
+    "#);
+    Ok(())
+}
+
+#[test]
+fn run_last_commit() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    let cwd = context.work_dir();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+              - id: end-of-file-fixer
+    "});
+
+    // Create initial files and make first commit
+    cwd.child("file1.txt").write_str("Hello, world!\n")?;
+    cwd.child("file2.txt")
+        .write_str("Initial content with trailing spaces   \n")?; // This has issues but won't be in last commit
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    // Modify files and make second commit with trailing whitespace
+    cwd.child("file1.txt").write_str("Hello, world!   \n")?; // trailing whitespace
+    cwd.child("file3.txt").write_str("New file")?; // missing newline
+    // Note: file2.txt is NOT modified in this commit, so it should be filtered out by --last-commit
+    context.git_add(".");
+    context.git_commit("Second commit with issues");
+
+    // Run with --last-commit should only check files from the last commit
+    // This should only process file1.txt and file3.txt, NOT file2.txt
+    cmd_snapshot!(context.filters(), context.run().arg("--last-commit"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trim trailing whitespace.................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+    - files were modified by this hook
+      Fixing file1.txt
+    fix end of files.........................................................Failed
+    - hook id: end-of-file-fixer
+    - exit code: 1
+    - files were modified by this hook
+      Fixing file3.txt
+
+    ----- stderr -----
+    "#);
+
+    // Now reset the files to their problematic state for comparison
+    cwd.child("file1.txt").write_str("Hello, world!   \n")?; // trailing whitespace
+    cwd.child("file3.txt").write_str("New file")?; // missing newline
+
+    // Run with --all-files should check ALL files including file2.txt
+    // This demonstrates that file2.txt was indeed filtered out in the previous test
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    trim trailing whitespace.................................................Failed
+    - hook id: trailing-whitespace
+    - exit code: 1
+    - files were modified by this hook
+      Fixing file1.txt
+      Fixing file2.txt
+    fix end of files.........................................................Failed
+    - hook id: end-of-file-fixer
+    - exit code: 1
+    - files were modified by this hook
+      Fixing file3.txt
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Test `prek run --directory` flags.
+#[test]
+fn run_directory() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: directory
+                name: directory
+                language: system
+                entry: echo
+                verbose: true
+    "});
+
+    let cwd = context.work_dir();
+    cwd.child("dir1").create_dir_all()?;
+    cwd.child("dir1/file.txt").write_str("Hello, world!")?;
+    cwd.child("dir2").create_dir_all()?;
+    cwd.child("dir2/file.txt").write_str("Hello, world!")?;
+    context.git_add(".");
+
+    // one `--directory`
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // repeated `--directory`
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--directory").arg("dir1"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // multiple `--directory`
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--directory").arg("dir2"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt dir2/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // non-existing directory
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("non-existing-dir"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory............................................(no files to check)Skipped
+
+    ----- stderr -----
+    warning: This directory does not exist, it will be ignored: `non-existing-dir`
+    "#);
+
+    // trailing slash
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1/"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // directory outside the repository
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("dir1")).arg("--directory").arg("../.."), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Directory `..` is outside the repository
+    "#);
+
+    // absolute directory from another subdirectory
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("dir2")).arg("--directory").arg(cwd.join("dir1")), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // absolute file outside the repository
+    let outside = context.home_dir().child("outside.txt");
+    outside.write_str("Hello, world!")?;
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("dir1")).arg("--files").arg(outside.path()), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory............................................(no files to check)Skipped
+
+    ----- stderr -----
+    warning: This file is outside the repository, it will be ignored: `../home/outside.txt`
+    "#);
+
+    // `--directory` with `--files`
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--files").arg("dir1/file.txt"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+    cmd_snapshot!(context.filters(), context.run().arg("--directory").arg("dir1").arg("--files").arg("dir2/file.txt"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt dir2/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // `--all-files` scoped to a directory
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").arg("--directory").arg("dir1"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+
+    // run `--directory` inside a subdirectory
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("dir1")).arg("--directory").arg("."), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    directory................................................................Passed
+    - hook id: directory
+    - duration: [TIME]
+      dir1/file.txt
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Long hook output is truncated, with the full output saved to a log file.
+#[test]
+fn max_output_lines() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        default_max_output_lines: 4
+        repos:
+          - repo: local
+            hooks:
+              - id: long-output
+                name: long-output
+                language: system
+                entry: python3 -c "[print(f'line {i}') for i in range(10)]"
+                pass_filenames: false
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"long-output-\d+-\d+.log", "long-output-[TIME]-[PID].log")])
+        .collect();
+
+    cmd_snapshot!(filters.clone(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    long-output..............................................................Passed
+    - hook id: long-output
+    - duration: [TIME]
+      line 0
+      line 1
+      ... 6 lines truncated (full output: [HOME]/logs/long-output-[TIME]-[PID].log)
+      line 8
+      line 9
+
+    ----- stderr -----
+    "#);
+
+    // The command line takes precedence over the config.
+    cmd_snapshot!(filters, context.run().arg("--max-output-lines").arg("3"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    long-output..............................................................Passed
+    - hook id: long-output
+    - duration: [TIME]
+      line 0
+      ... 7 lines truncated (full output: [HOME]/logs/long-output-[TIME]-[PID].log)
+      line 8
+      line 9
+
+    ----- stderr -----
+    "#);
+
+    let logs = fs_err::read_dir(context.home_dir().join("logs"))?
+        .map(|entry| fs_err::read_to_string(entry?.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let expected = (0..10)
+        .map(|i| format!("line {i}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(logs, vec![expected.clone(), expected]);
+
+    Ok(())
+}
+
+/// A `commit-msg` hook that rewrites the message fails, like a hook modifying files.
+#[test]
+fn commit_msg_modified() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: ticket-prefix
+                name: ticket-prefix
+                language: system
+                entry: python3 -c "import sys; p = sys.argv[1]; m = open(p).read(); m.startswith('PREK-1') or open(p, 'w').write('PREK-1 ' + m)"
+                stages: [commit-msg]
+    "#});
+    context.git_add(".");
+
+    let message = context.work_dir().child(".git/COMMIT_EDITMSG");
+    message.write_str("Add a feature\n")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("commit-msg").arg("--commit-msg-filename").arg(".git/COMMIT_EDITMSG").arg("--verbose"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    ticket-prefix............................................................Failed
+    - hook id: ticket-prefix
+    - duration: [TIME]
+    - commit message was modified by this hook
+
+    ----- stderr -----
+    "#);
+    message.assert("PREK-1 Add a feature\n");
+
+    // The message is already prefixed, nothing changes.
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("commit-msg").arg("--commit-msg-filename").arg(".git/COMMIT_EDITMSG"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    ticket-prefix............................................................Passed
+
+    ----- stderr -----
+    "#);
+    message.assert("PREK-1 Add a feature\n");
+
+    Ok(())
+}
+
+/// Colors follow `--color`, for our own output as well as the output of hooks.
+#[test]
+fn color_choice() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: color
+                name: color
+                language: system
+                entry: python3 -c "import os; print('\x1b[31mFORCE_COLOR=' + os.environ.get('FORCE_COLOR', '') + '\x1b[0m')"
+                pass_filenames: false
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    // Captured output is not a terminal, so `auto` disables colors.
+    let output = context
+        .run()
+        .env_remove(EnvVars::FORCE_COLOR)
+        .env_remove(EnvVars::PREK_COLOR)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(!stdout.contains('\x1b'), "{stdout}");
+    assert!(stdout.contains("FORCE_COLOR=\n"), "{stdout}");
+
+    // `always` also forces colors in hooks.
+    let output = context
+        .run()
+        .arg("--color")
+        .arg("always")
+        .env_remove(EnvVars::FORCE_COLOR)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(stdout.contains("\x1b[31mFORCE_COLOR=1\x1b[0m"), "{stdout}");
+}
+
+/// A hook that can't be run is reported as errored, and the other hooks still run.
+#[test]
+fn errored_hook() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: broken
+                name: broken
+                language: system
+                entry: echo "unterminated
+                pass_filenames: false
+                always_run: true
+              - id: healthy
+                name: healthy
+                language: system
+                entry: echo healthy
+                pass_filenames: false
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    broken...................................................................Errored
+    - hook id: broken
+      error: Failed to run hook `broken`
+        caused by: Hook `broken` is invalid
+        caused by: Failed to parse entry `echo "unterminated` as commands
+    healthy..................................................................Passed
+    - hook id: healthy
+    - duration: [TIME]
+      healthy
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--abort-on-error"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    broken...................................................................
+    ----- stderr -----
+    error: Failed to run hook `broken`
+      caused by: Hook `broken` is invalid
+      caused by: Failed to parse entry `echo "unterminated` as commands
+    "#);
+}
+
+/// Like `pre-commit`, the flags of the entry are passed first, then `args`, then the files,
+/// whatever the language of the hook.
+#[test]
+fn args_order() -> Result<()> {
+    let mut cases = vec![
+        ("system", r#"python3 -c "import sys; print(sys.argv[1:])""#),
+        ("python", r#"python -c "import sys; print(sys.argv[1:])""#),
+    ];
+    // Scripts can't be run on Windows yet.
+    if cfg!(unix) {
+        cases.push(("script", "./argv.py"));
+    }
+
+    for (language, entry) in cases {
+        let context = TestContext::new();
+        context.init_project();
+
+        context.write_pre_commit_config(&indoc::formatdoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: argv
+                    name: argv
+                    language: {language}
+                    entry: {entry} --from-entry
+                    args: [--from-args, --fix]
+                    files: \.txt$
+                    verbose: true
+        "});
+        let script = context.work_dir().child("argv.py");
+        script.write_str("#!/usr/bin/env python3\nimport sys\nprint(sys.argv[1:])\n")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs_err::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+        }
+        context.work_dir().child("a.txt").write_str("a")?;
+        context.git_add(".");
+
+        cmd_snapshot!(context.filters(), context.run(), @r#"
+        success: true
+        exit_code: 0
+        ----- stdout -----
+        argv.....................................................................Passed
+        - hook id: argv
+        - duration: [TIME]
+          ['--from-entry', '--from-args', '--fix', 'a.txt']
+
+        ----- stderr -----
+        "#);
+    }
+
+    Ok(())
+}
+
+/// Hooks of unsupported languages can be run as `system` hooks with `--allow-system-fallback`.
+#[test]
+fn allow_system_fallback() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: perl-hook
+                name: perl-hook
+                language: perl
+                entry: perl -e 'print "@ARGV\n"'
+                files: \.txt$
+                verbose: true
+    "#});
+    context.work_dir().child("a.txt").write_str("a")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    perl-hook............................................(unimplemented yet)Skipped
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--allow-system-fallback"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    perl-hook................................................................Passed
+    - hook id: perl-hook
+    - duration: [TIME]
+      a.txt
+
+    ----- stderr -----
+    warning: Language `perl` is not supported yet, running hook `perl-hook` as a `system` hook
+    "#);
+
+    Ok(())
+}
+
+/// Hooks listed twice in a repo with identical configuration are only run once.
+#[test]
+fn duplicate_hooks() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // The second hook spells out a default, it's still identical once resolved.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                files: \.txt$
+                verbose: true
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                files: \.txt$
+                verbose: true
+                pass_filenames: true
+    "});
+    context.work_dir().child("a.txt").write_str("a")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      a.txt
+
+    ----- stderr -----
+    warning: Hook `echo` is listed as hooks #1 and #2 of repo `local` with identical configuration, running it only once
+    "#);
+
+    // Hooks with different arguments are both run.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                files: \.txt$
+                verbose: true
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                args: [--flag]
+                files: \.txt$
+                verbose: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      a.txt
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      --flag a.txt
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Wait for the store lock held by another process, and give up after `PREK_LOCK_TIMEOUT`.
+#[test]
+fn store_lock_timeout() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+
+    fs_err::create_dir_all(context.home_dir())?;
+    let lock = fs_err::File::create(context.home_dir().join(".lock"))?;
+    lock.file().lock()?;
+
+    cmd_snapshot!(context.filters(), context.run().env(EnvVars::PREK_LOCK_TIMEOUT, "2"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: Waiting for another prek process to release the store lock...
+    error: Timed out after 2s waiting for the store lock at `[HOME]/.lock`, set `PREK_LOCK_TIMEOUT` to wait longer
+    "#);
+
+    lock.file().unlock()?;
+    cmd_snapshot!(context.filters(), context.run().env(EnvVars::PREK_LOCK_TIMEOUT, "2"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Warnings fail the run with `--strict`, even if all hooks passed.
+#[test]
+fn strict() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                stages: [commit]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    warning: .pre-commit-config.yaml:4:9: Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--strict"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    warning: .pre-commit-config.yaml:4:9: Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead
+
+    error: 1 warning was promoted to errors by `--strict`:
+      - .pre-commit-config.yaml:4:9: Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().env(EnvVars::PREK_STRICT, "1"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    warning: .pre-commit-config.yaml:4:9: Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead
+
+    error: 1 warning was promoted to errors by `--strict`:
+      - .pre-commit-config.yaml:4:9: Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead
+    "#);
+}
+
+/// `--hook-stage` can be repeated to run the hooks of several stages.
+#[test]
+fn multiple_hook_stages() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: commit
+                name: commit
+                language: system
+                entry: echo
+                stages: [pre-commit]
+              - id: push
+                name: push
+                language: system
+                entry: echo
+                stages: [pre-push]
+              - id: manual
+                name: manual
+                language: system
+                entry: echo
+                stages: [manual]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-commit").arg("--hook-stage").arg("pre-push"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    commit...................................................................Passed
+    push.....................................................................Passed
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-commit").arg("--hook-stage").arg("commit-msg").arg("--commit-msg-filename").arg("MSG"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: `--hook-stage commit-msg` can't be combined with `--hook-stage pre-commit`, they run on different files
+    "#);
+}
+
+/// With `PREK_DETERMINISTIC_OUTPUT`, the output is the same on every run.
+#[test]
+fn deterministic_output() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                require_serial: true
+                verbose: true
+    "});
+    for name in ["b.txt", "a.txt", "c/d.txt", "c/a.txt"] {
+        context.work_dir().child(name).write_str("content\n")?;
+    }
+    context.git_add(".");
+
+    let first = context.run().arg("-v").output()?;
+    let second = context.run().arg("-v").output()?;
+    assert!(first.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    assert_eq!(first.stderr, second.stderr);
+
+    let stdout = String::from_utf8(first.stdout)?;
+    assert!(stdout.contains("- duration: [TIME]"));
+    // No progress or spinner output.
+    assert!(!stdout.contains(['\r', '\x1b']));
+    assert!(!String::from_utf8(first.stderr)?.contains(['\r', '\x1b']));
+
+    assert_snapshot!(stdout, @r"
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      .pre-commit-config.yaml
+      a.txt
+      b.txt
+      c/a.txt
+      c/d.txt
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      .pre-commit-config.yaml a.txt b.txt c/a.txt c/d.txt
+    ");
+
+    Ok(())
+}
+
+/// Files matched by `.prekignore` are excluded from all hooks.
+#[test]
+fn ignore_file() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                verbose: true
+                require_serial: true
+    "});
+    context
+        .work_dir()
+        .child(".prekignore")
+        .write_str(indoc::indoc! {r"
+        # Python files, except one
+        *.py
+        !important.py
+        # Directories named `generated`, at any depth
+        generated/
+    "})?;
+    for name in [
+        "a.py",
+        "important.py",
+        "b.txt",
+        "generated/c.txt",
+        "src/generated/d.txt",
+        "src/e.txt",
+    ] {
+        context.work_dir().child(name).write_str("content\n")?;
+    }
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      .pre-commit-config.yaml .prekignore b.txt important.py src/e.txt
+
+    ----- stderr -----
+    "#);
+
+    // The `exclude` of the config still applies, and the file can be disabled.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        use_ignore_file: false
+        exclude: ^src/
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                verbose: true
+                require_serial: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      .pre-commit-config.yaml .prekignore a.py b.txt generated/c.txt important.py
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// A hook with `stdin: filename` reads the content of the files on stdin, one at a time.
+#[test]
+fn stdin_filename() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: upper
+                name: upper
+                language: system
+                entry: sh -c 'tr a-z A-Z; echo "$# arguments"' --
+                files: \.txt$
+                stdin: filename
+                verbose: true
+    "#});
+    context
+        .work_dir()
+        .child("file.txt")
+        .write_str("hello\nworld\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    upper....................................................................Passed
+    - hook id: upper
+    - duration: [TIME]
+      HELLO
+      WORLD
+      0 arguments
+
+    ----- stderr -----
+    "#);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: upper
+                name: upper
+                language: python
+                entry: upper
+                stdin: filename
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: .pre-commit-config.yaml:4:9: Hook `upper` is invalid
+      caused by: Hook specified `stdin` but the language `python` does not support it, only `system` and `script` hooks do
+    "#);
+
+    Ok(())
+}
+
+/// `--show-diff-on-failure` only shows the changes of the files modified by hooks, including
+/// files with paths git quotes.
+#[test]
+fn show_diff_on_failure() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: system
+                entry: sh -c 'echo fixed > fixé.txt'
+                pass_filenames: false
+                always_run: true
+    "#});
+    context.work_dir().child("fixé.txt").write_str("broken\n")?;
+    context
+        .work_dir()
+        .child("unrelated.txt")
+        .write_str("committed\n")?;
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    context
+        .work_dir()
+        .child("unrelated.txt")
+        .write_str("unstaged\n")?;
+
+    let output = context
+        .run()
+        .arg("--all-files")
+        .arg("--show-diff-on-failure")
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("All changes made by hooks:"), "{stdout}");
+    assert!(stdout.contains("-broken\n+fixed"), "{stdout}");
+    assert!(!stdout.contains("unrelated.txt"), "{stdout}");
+
+    Ok(())
+}
+
+/// Changes to binary files count as modifications, and are summarized by `--show-diff-on-failure`.
+#[test]
+fn binary_file_modified() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: optimizer
+                name: optimizer
+                language: system
+                entry: sh -c 'for f; do printf "\\000\\001" >> "$f"; done' --
+                files: \.bin$
+    "#});
+    context
+        .work_dir()
+        .child("image.bin")
+        .write_binary(b"\x89PNG\0\0\0")?;
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    // The file already has unstaged changes, the hook modifies it again.
+    context
+        .work_dir()
+        .child("image.bin")
+        .write_binary(b"\x89PNG\0\0\0\0")?;
+
+    let output = context
+        .run()
+        .arg("--all-files")
+        .arg("--show-diff-on-failure")
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(
+        stdout.contains("- files were modified by this hook"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("All changes made by hooks:"), "{stdout}");
+    assert!(
+        stdout.contains("Binary files a/image.bin and b/image.bin differ"),
+        "{stdout}"
+    );
+
+    Ok(())
+}
+
+/// Untracked files reach the hooks of a staged run only with `--include-untracked`, and changes
+/// made to them count as modifications.
+#[test]
+fn include_untracked_staged() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                files: \.txt$
+                verbose: true
+    "});
+    let cwd = context.work_dir();
+    cwd.child("staged.txt").write_str("Hello")?;
+    context.git_add(".");
+    cwd.child(".gitignore").write_str("*.log\n")?;
+    cwd.child("untracked.txt").write_str("Hello")?;
+    cwd.child("debug.log").write_str("Hello")?;
+
+    let output = context.run().output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("staged.txt"), "{stdout}");
+    assert!(!stdout.contains("untracked.txt"), "{stdout}");
+
+    let output = context.run().arg("--include-untracked").output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("staged.txt"), "{stdout}");
+    assert!(stdout.contains("untracked.txt"), "{stdout}");
+    assert!(!stdout.contains("debug.log"), "{stdout}");
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: system
+                entry: sh -c 'for f; do echo fixed > "$f"; done' --
+                files: ^untracked\.txt$
+    "#});
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    fixer................................................(no files to check)Skipped
+
+    ----- stderr -----
+    "#);
+
+    let output = context.run().arg("--include-untracked").output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("files were modified by this hook"),
+        "{stdout}"
+    );
+    assert_eq!(
+        fs_err::read_to_string(cwd.join("untracked.txt"))?,
+        "fixed\n"
+    );
+
+    Ok(())
+}
+
+/// `--from-config -` reads the configuration from stdin, which doesn't need to be staged.
+#[test]
+fn from_config_stdin() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.work_dir().child("file.txt").write_str("Hello")?;
+    context.git_add(".");
+    // An unstaged config of the repository is not read, and doesn't stop the run.
+    context
+        .work_dir()
+        .child(".pre-commit-config.yaml")
+        .write_str("repos: []\n")?;
+
+    let config = indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                verbose: true
+    "};
+
+    let output = assert_cmd::Command::from_std(context.run())
+        .arg("--from-config")
+        .arg("-")
+        .write_stdin(config)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(stdout.contains("echo....."), "{stdout}");
+    assert!(stdout.contains("file.txt"), "{stdout}");
+
+    // A config file is relative to the directory prek is run from.
+    let child = context.work_dir().child("sub");
+    child.create_dir_all()?;
+    child.child("config.yaml").write_str(config)?;
+    let output = context
+        .run()
+        .current_dir(&child)
+        .arg("--from-config")
+        .arg("config.yaml")
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("echo....."), "{stdout}");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--from-config").arg("-").arg("--config").arg(".pre-commit-config.yaml"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: `--from-config` can't be combined with `--config`
+    "#);
+
+    Ok(())
+}
+
+/// Hooks run from a paused interactive rebase don't stash the unstaged changes.
+#[test]
+fn run_during_rebase() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: cat
+                name: cat
+                language: system
+                entry: cat
+                files: \.txt$
+                verbose: true
+    "});
+    let cwd = context.work_dir();
+    cwd.child("file.txt").write_str("first\n")?;
+    context.git_add(".");
+    context.git_commit("Initial commit");
+    cwd.child("file.txt").write_str("second\n")?;
+    context.git_add(".");
+    context.git_commit("Second commit");
+
+    // Stop at the last commit to edit it.
+    Command::new("git")
+        .arg("rebase")
+        .arg("--interactive")
+        .arg("HEAD~1")
+        .env("GIT_SEQUENCE_EDITOR", "sed -i.bak -e 's/^pick/edit/'")
+        .current_dir(cwd)
+        .assert()
+        .success();
+
+    cwd.child("file.txt").write_str("staged\n")?;
+    context.git_add("file.txt");
+    cwd.child("file.txt").write_str("unstaged\n")?;
+
+    // Not a warning, so `--strict` runs pass during a rebase.
+    cmd_snapshot!(context.filters(), context.run().arg("--strict"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    cat......................................................................Passed
+    - hook id: cat
+    - duration: [TIME]
+      unstaged
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(context.read("file.txt"), "unstaged\n");
+
+    Ok(())
+}
+
+/// A single hook selected by its id propagates its exit code with `--propagate-exit-code`.
+#[test]
+fn single_hook_exit_code() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: exit-3
+                name: exit-3
+                language: system
+                entry: python3 -c 'import sys; sys.exit(3)'
+              - id: exit-4
+                name: exit-4
+                language: system
+                entry: python3 -c 'import sys; sys.exit(4)'
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("exit-3").arg("--propagate-exit-code"), @r"
+    success: false
+    exit_code: 3
+    ----- stdout -----
+    exit-3...................................................................Failed
+    - hook id: exit-3
+    - exit code: 3
+
+    ----- stderr -----
+    ");
+
+    // Without `--propagate-exit-code`, a failure exits with 1.
+    cmd_snapshot!(context.filters(), context.run().arg("exit-3"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    exit-3...................................................................Failed
+    - hook id: exit-3
+    - exit code: 3
+
+    ----- stderr -----
+    ");
+
+    // With several hooks, any failure exits with 1.
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    exit-3...................................................................Failed
+    - hook id: exit-3
+    - exit code: 3
+    exit-4...................................................................Failed
+    - hook id: exit-4
+    - exit code: 4
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--no-install` lists the hooks without an installed environment, and runs nothing.
+#[test]
+fn no_install() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: cached
+                name: cached
+                language: python
+                entry: python3 -c 'print("cached")'
+    "#});
+    context.git_add(".");
+
+    // Install the environment of the first hook.
+    context.run().assert().success();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: cached
+                name: cached
+                language: python
+                entry: python3 -c 'print("cached")'
+              - id: uncached
+                name: uncached
+                language: python
+                entry: python3 -c 'print("uncached")'
+                additional_dependencies: ["pyyaml", "click"]
+              - id: system
+                name: system
+                language: system
+                entry: echo
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--no-install"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    No environment is installed for these hooks, and `--no-install` is set:
+    - uncached: language `python`, language_version `default`, dependencies [click, pyyaml]
+    ");
+
+    // The environment variable works as well, and a run with every environment installed
+    // goes on.
+    cmd_snapshot!(context.filters(), context.run().arg("cached").env("PREK_NO_INSTALL", "1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    cached...................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A hook running prek must not run the hooks again.
+#[test]
+fn recursive_invocation() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let prek = assert_cmd::cargo::cargo_bin("prek")
+        .display()
+        .to_string()
+        .replace('\\', "/");
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: recursive
+                name: recursive
+                language: system
+                entry: '{prek}' run --all-files
+                pass_filenames: false
+                always_run: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    recursive................................................................Failed
+    - hook id: recursive
+    - exit code: 2
+      error: prek is already running the `pre-commit` hooks of this repository, refusing to run them from within a hook (does a hook run `prek` or `git commit`?)
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A hook can run prek in another repository, e.g. for a fixture of a test suite.
+#[test]
+fn nested_invocation_other_repo() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let other = context.home_dir().child("other");
+    other.create_dir_all()?;
+    Command::new("git")
+        .arg("init")
+        .current_dir(&other)
+        .assert()
+        .success();
+    other
+        .child(".pre-commit-config.yaml")
+        .write_str(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: inner
+                name: inner
+                language: system
+                entry: echo inner
+                pass_filenames: false
+                always_run: true
+    "})?;
+
+    let prek = assert_cmd::cargo::cargo_bin("prek")
+        .display()
+        .to_string()
+        .replace('\\', "/");
+    context.write_pre_commit_config(&indoc::formatdoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: outer
+                name: outer
+                language: system
+                entry: sh -c 'cd "$0" && "$1" run --all-files' '{}' '{prek}'
+                pass_filenames: false
+                always_run: true
+    "#, other.display().to_string().replace('\\', "/")});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--verbose"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    outer....................................................................Passed
+    - hook id: outer
+    - duration: [TIME]
+      inner....................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--log-file` gets the debug logs even at the default verbosity.
+#[test]
+fn log_file() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+
+    let log = context.home_dir().child("prek.log");
+    cmd_snapshot!(context.filters(), context.run().arg("--log-file").arg(log.path()), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    let content = fs_err::read_to_string(&log)?;
+    assert!(content.contains(" DEBUG prek: "), "{content}");
+    assert!(content.contains(" DEBUG Git root: "), "{content}");
+
+    // Also set by `PREK_LOG_FILE`, appending to the file.
+    context
+        .run()
+        .env(EnvVars::PREK_LOG_FILE, log.path())
+        .assert()
+        .success();
+    let appended = fs_err::read_to_string(&log)?;
+    assert!(appended.starts_with(&content));
+    assert!(appended.len() > content.len());
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use assert_fs::fixture::{FileTouch, PathChild};
+
 use crate::common::{TestContext, cmd_snapshot};
 
 mod common;
@@ -68,3 +70,89 @@ fn sample_config() {
           - id: check-added-large-files
     "##);
 }
+
+#[test]
+fn sample_config_existing_file() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    let config = indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "};
+    context.write_pre_commit_config(config);
+
+    cmd_snapshot!(context.filters(), context.sample_config().arg("-f"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    File `.pre-commit-config.yaml` already exists, refusing to overwrite it.
+    hint: Use `--force` to overwrite it, or `--append` to add the sample repos to it.
+    "#);
+    assert_eq!(context.read(".pre-commit-config.yaml"), config);
+
+    cmd_snapshot!(context.filters(), context.sample_config().arg("-f").arg("--append"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Appended sample repos to `.pre-commit-config.yaml`
+
+    ----- stderr -----
+    "#);
+    insta::assert_snapshot!(context.read(".pre-commit-config.yaml"), @r#"
+    repos:
+      - repo: local
+        hooks:
+          - id: echo
+            name: echo
+            language: system
+            entry: echo
+      - repo: 'https://github.com/pre-commit/pre-commit-hooks'
+        rev: v5.0.0
+        hooks:
+          - id: trailing-whitespace
+          - id: end-of-file-fixer
+          - id: check-yaml
+          - id: check-added-large-files
+    "#);
+
+    cmd_snapshot!(context.filters(), context.sample_config().arg("-f").arg("--force"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Written to `.pre-commit-config.yaml`
+
+    ----- stderr -----
+    "#);
+    insta::assert_snapshot!(context.read(".pre-commit-config.yaml"), @r#"
+    # See https://pre-commit.com for more information
+    # See https://pre-commit.com/hooks.html for more hooks
+    repos:
+      - repo: 'https://github.com/pre-commit/pre-commit-hooks'
+        rev: v5.0.0
+        hooks:
+          - id: trailing-whitespace
+          - id: end-of-file-fixer
+          - id: check-yaml
+          - id: check-added-large-files
+    "#);
+
+    // An empty file is overwritten.
+    context.work_dir().child("empty.yaml").touch()?;
+    cmd_snapshot!(context.filters(), context.sample_config().arg("-f").arg("empty.yaml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Written to `empty.yaml`
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
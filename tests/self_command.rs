@@ -0,0 +1,142 @@
+use std::process::Command;
+
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::assert::PathAssert;
+use assert_fs::fixture::{FileWriteStr, PathChild, PathCreateDir};
+
+use constants::env_vars::EnvVars;
+
+use crate::common::{TestContext, cmd_snapshot};
+
+mod common;
+
+#[test]
+fn self_version() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([
+            (r"prek \d+\.\d+\.\d+.*", "prek [VERSION]"),
+            (r"target: .+", "target: [TARGET]"),
+        ])
+        .collect();
+
+    cmd_snapshot!(filters, context.command().arg("self").arg("version"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prek [VERSION]
+    target: [TARGET]
+
+    ----- stderr -----
+    "#);
+
+    let output = context
+        .command()
+        .arg("self")
+        .arg("version")
+        .arg("--json")
+        .output()?;
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    assert!(json["target"].is_string());
+
+    Ok(())
+}
+
+#[test]
+fn self_uninstall() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // Uninstall a copy of the binary, so the one under test is kept.
+    let bin_dir = context.work_dir().child("bin");
+    bin_dir.create_dir_all()?;
+    let prek = bin_dir.child(format!("prek{}", std::env::consts::EXE_SUFFIX));
+    fs_err::copy(assert_cmd::cargo::cargo_bin("prek"), &prek)?;
+    let command = |dir: &std::path::Path| {
+        let mut command = Command::new(&*prek);
+        command.current_dir(dir);
+        command.env(EnvVars::PREK_HOME, &**context.home_dir());
+        command
+    };
+
+    // A hook installed by the copy, replacing an existing hook.
+    let hooks = context.work_dir().child(".git/hooks");
+    hooks
+        .child("pre-commit")
+        .write_str("#!/bin/sh\necho legacy\n")?;
+    command(context.work_dir())
+        .arg("install")
+        .assert()
+        .success();
+    // A hook installed by another prek binary is kept.
+    context
+        .install()
+        .arg("--hook-type")
+        .arg("pre-push")
+        .assert()
+        .success();
+
+    let other = context.work_dir().child("other");
+    other.create_dir_all()?;
+    Command::new("git")
+        .arg("init")
+        .current_dir(&other)
+        .assert()
+        .success();
+    command(&other).arg("install").assert().success();
+
+    context.home_dir().child("repos").create_dir_all()?;
+
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"prek\.exe", "prek")])
+        .collect();
+
+    // Without a terminal to confirm on, `--yes` is required.
+    cmd_snapshot!(filters.clone(), command(context.work_dir()).arg("self").arg("uninstall"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    The following will be removed:
+      bin/prek
+      .git/hooks/pre-commit
+
+    ----- stderr -----
+    Refusing to uninstall without confirmation, pass `--yes` to proceed
+    "#);
+    prek.assert(predicates::path::exists());
+
+    cmd_snapshot!(filters, command(context.work_dir()).arg("self").arg("uninstall").arg("--yes").arg("--all-repos").arg("other").arg("--purge-cache"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    The following will be removed:
+      bin/prek
+      .git/hooks/pre-commit
+      other/.git/hooks/pre-commit
+      [HOME]/
+    Removed hook .git/hooks/pre-commit
+    Restored previous hook to .git/hooks/pre-commit
+    Removed hook other/.git/hooks/pre-commit
+    Cleaned `[HOME]/`
+    Uninstalled prek
+
+    ----- stderr -----
+    "#);
+
+    prek.assert(predicates::path::missing());
+    hooks.child("pre-commit").assert("#!/bin/sh\necho legacy\n");
+    hooks.child("pre-push").assert(predicates::path::exists());
+    other
+        .child(".git/hooks/pre-commit")
+        .assert(predicates::path::missing());
+    context.home_dir().assert(predicates::path::missing());
+
+    Ok(())
+}
@@ -46,7 +46,7 @@ fn validate_config() -> anyhow::Result<()> {
     // Validate multiple files.
     cmd_snapshot!(context.filters(), context.validate_config().arg(".pre-commit-config.yaml").arg("config-1.yaml"), @r#"
     success: false
-    exit_code: 1
+    exit_code: 2
     ----- stdout -----
 
     ----- stderr -----
@@ -57,6 +57,298 @@ fn validate_config() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn validate_config_directory_json() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context
+        .work_dir()
+        .child("configs/good/.pre-commit-config.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: echo
+                    name: echo
+                    entry: echo
+                    language: system
+                    stages: [commit]
+            unknown_key: true
+        "})?;
+    context
+        .work_dir()
+        .child("configs/nested/bad/.pre-commit-config.yml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: https://github.com/pre-commit/pre-commit-hooks
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("configs").arg("--output-format").arg("json"), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    [
+      {
+        "file": "configs/good/.pre-commit-config.yaml",
+        "errors": [],
+        "warnings": [
+          {
+            "message": "Unexpected key `unknown_key`",
+            "line": 9,
+            "column": 1
+          },
+          {
+            "message": "Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead",
+            "line": 4,
+            "column": 9
+          }
+        ]
+      },
+      {
+        "file": "configs/nested/bad/.pre-commit-config.yml",
+        "errors": [
+          {
+            "message": "repos: Invalid remote repo: missing field `rev` at line 2 column 3",
+            "line": 2,
+            "column": 3
+          }
+        ],
+        "warnings": []
+      }
+    ]
+
+    ----- stderr -----
+    "#);
+
+    // Warnings alone don't fail the validation unless `--strict` is used.
+    cmd_snapshot!(context.filters(), context.validate_config().arg("configs/good").arg("--strict"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: configs/good/.pre-commit-config.yaml:9:1: Unexpected key `unknown_key`
+    warning: configs/good/.pre-commit-config.yaml:4:9: Hook `echo` uses deprecated stage `commit`, use `pre-commit` instead
+    "#);
+
+    Ok(())
+}
+
+/// Report hooks that don't run at any installed hook type, and stages no hook runs at.
+#[test]
+fn validate_config_stages() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context
+        .work_dir()
+        .child("mismatch.yaml")
+        .write_str(indoc::indoc! {r"
+            default_install_hook_types: [pre-commit, commit-msg]
+            default_stages: [pre-push]
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    entry: echo
+                    language: system
+                    stages: [pre-commit]
+                  - id: push
+                    name: push
+                    entry: echo
+                    language: system
+                  - id: manual
+                    name: manual
+                    entry: echo
+                    language: system
+                    stages: [manual]
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("mismatch.yaml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: mismatch.yaml:11:9: Hook `push` runs at `pre-push`, but only `pre-commit, commit-msg` hooks are installed
+    warning: mismatch.yaml:1:1: `default_install_hook_types` contains `commit-msg`, but no hook runs at it
+    "#);
+
+    // `default_stages` only applies to hooks that don't set their stages.
+    context
+        .work_dir()
+        .child("default_stages.yaml")
+        .write_str(indoc::indoc! {r"
+            default_stages: [pre-commit, pre-push]
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    entry: echo
+                    language: system
+                    stages: [pre-commit]
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("default_stages.yaml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: default_stages.yaml:1:1: `default_stages` contains `pre-push`, but no hook runs at it
+    "#);
+
+    // Remote hooks run at the stages of their manifest unless the configuration sets them.
+    context
+        .work_dir()
+        .child("remote.yaml")
+        .write_str(indoc::indoc! {r"
+            default_install_hook_types: [pre-push]
+            repos:
+              - repo: https://github.com/pre-commit/pre-commit-hooks
+                rev: v5.0.0
+                hooks:
+                  - id: trailing-whitespace
+                  - id: end-of-file-fixer
+                    stages: [pre-commit]
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("remote.yaml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: remote.yaml:7:9: Hook `end-of-file-fixer` runs at `pre-commit`, but only `pre-push` hooks are installed
+    "#);
+
+    // TOML configurations get the same locations.
+    context
+        .work_dir()
+        .child("mismatch.toml")
+        .write_str(indoc::indoc! {r#"
+            default_stages = ["pre-push"]
+            unknown_key = 1
+
+            [[repos]]
+            repo = "local"
+
+            [[repos.hooks]]
+            id = "lint"
+            name = "lint"
+            entry = "echo"
+            language = "system"
+            stages = ["commit"]
+        "#})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("mismatch.toml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: mismatch.toml:2:1: Unexpected key `unknown_key`
+    warning: mismatch.toml:7:1: Hook `lint` uses deprecated stage `commit`, use `pre-commit` instead
+    warning: mismatch.toml:1:1: `default_stages` contains `pre-push`, but no hook runs at it
+    "#);
+
+    Ok(())
+}
+
+/// Lines of `.prekignore` that can't be parsed are reported, unless the file is not used.
+#[test]
+fn validate_config_ignore_file() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context
+        .work_dir()
+        .child(".prekignore")
+        .write_str("*.log\nfoo[\n")?;
+    context
+        .work_dir()
+        .child(".pre-commit-config.yaml")
+        .write_str(indoc::indoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    entry: echo
+                    language: system
+        "})?;
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r"invalid and ignored: .*", "invalid and ignored: [ERROR]")])
+        .collect::<Vec<_>>();
+    cmd_snapshot!(filters, context.validate_config().arg(".pre-commit-config.yaml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: .pre-commit-config.yaml: `.prekignore` line 2 is invalid and ignored: [ERROR]
+    "#);
+
+    context
+        .work_dir()
+        .child(".pre-commit-config.yaml")
+        .write_str(indoc::indoc! {r"
+            use_ignore_file: false
+            repos:
+              - repo: local
+                hooks:
+                  - id: lint
+                    name: lint
+                    entry: echo
+                    language: system
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg(".pre-commit-config.yaml"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// `-` validates a configuration read from stdin.
+#[test]
+fn validate_config_stdin() {
+    let context = TestContext::new();
+
+    assert_cmd::Command::from_std(context.validate_config())
+        .arg("-")
+        .write_stdin(indoc::indoc! {r"
+            repos:
+              - repo: local
+                hooks:
+                  - id: echo
+                    name: echo
+                    language: system
+                    entry: echo
+        "})
+        .assert()
+        .success();
+
+    let output = assert_cmd::Command::from_std(context.validate_config())
+        .arg("-")
+        .write_stdin(indoc::indoc! {r"
+            repos:
+              - repo: https://github.com/pre-commit/pre-commit-hooks
+        "})
+        .assert()
+        .code(2);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(stderr.contains("Failed to parse `-`"), "{stderr}");
+    assert!(stderr.contains("missing field `rev`"), "{stderr}");
+}
+
 #[test]
 fn validate_manifest() -> anyhow::Result<()> {
     let context = TestContext::new();